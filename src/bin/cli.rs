@@ -68,6 +68,10 @@ async fn cli_mode(
             let response = client.ping(msg.map(|s| s.into())).await?;
             println!("{}", String::from_utf8_lossy(response.as_ref()));
         }
+        LojaSubcommand::Latency => {
+            let latency = client.latency().await?;
+            println!("{:.3} ms", latency.as_secs_f64() * 1000.0);
+        }
         LojaSubcommand::Get { key } => {
             let response = client.get(&key).await?;
             if let Some(value) = response {
@@ -123,6 +127,8 @@ enum LojaSubcommand {
         /// Message to ping
         msg: Option<String>,
     },
+    /// Measure the round-trip time of a ping to the server.
+    Latency,
     /// Get the value of key.
     Get {
         /// Name of key to get.
@@ -1,7 +1,7 @@
 use anyhow::Context;
-use clap::{command, Parser};
+use clap::Parser;
 use loja::{server, DEFAULT_HOST, DEFAULT_PORT};
-use tokio::net::TcpListener;
+use std::time::Duration;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
 
@@ -10,14 +10,50 @@ async fn main() -> anyhow::Result<()> {
     setup_logging();
 
     let cli = LojaServerCli::parse();
-    let addr = std::net::SocketAddr::new(cli.host, cli.port);
+    let hosts = if cli.bind.is_empty() {
+        vec![cli.host]
+    } else {
+        cli.bind
+    };
 
-    let listener = TcpListener::bind(&addr)
-        .await
-        .context("failed to bind tcp listener")?;
-    info!("listening on {addr}");
+    let mut listeners = vec![];
+    for host in hosts {
+        let addr = std::net::SocketAddr::new(host, cli.port);
+        let options = server::BindOptions {
+            backlog: cli.tcp_backlog,
+            reuse_addr: cli.reuse_addr,
+        };
+        let listener = server::bind(addr, options)
+            .with_context(|| format!("failed to bind tcp listener to {addr}"))?;
+        info!("listening on {addr}");
+        listeners.push(listener);
+    }
 
-    server::run(listener, tokio::signal::ctrl_c()).await;
+    let config = server::ServerConfig {
+        idle_timeout: cli.idle_timeout.map(Duration::from_secs),
+        notify_keyspace_events: cli.notify_keyspace_events,
+        maxmemory: cli.maxmemory,
+        maxmemory_policy: cli.maxmemory_policy,
+        requirepass: cli.requirepass,
+        slowlog_log_slower_than: cli.slowlog_log_slower_than.map(Duration::from_micros),
+        slowlog_max_len: cli.slowlog_max_len,
+        latency_monitor_threshold: cli.latency_monitor_threshold.map(Duration::from_millis),
+        access_log: cli.access_log,
+        pubsub_channel_capacity: cli.pubsub_channel_capacity,
+        max_frame_size: cli.max_frame_size,
+        max_protocol_errors: cli.max_protocol_errors,
+        max_subscriptions: cli.max_subscriptions,
+        active_expiration: !cli.lazy_expiration,
+        output_buffer_limit: server::OutputBufferLimit {
+            hard: cli.output_buffer_hard_limit,
+            soft: cli
+                .output_buffer_soft_limit
+                .map(|soft| (soft, Duration::from_secs(cli.output_buffer_soft_seconds))),
+        },
+        disabled_commands: cli.disable_command.into_iter().collect(),
+    };
+
+    server::run_multi(listeners, config, tokio::signal::ctrl_c()).await;
 
     Ok(())
 }
@@ -32,9 +68,88 @@ struct LojaServerCli {
     #[arg(short, long, default_value = DEFAULT_HOST)]
     /// Host to bind to.
     host: std::net::IpAddr,
+    #[arg(long)]
+    /// Address to bind to, instead of `host`. May be given several times to
+    /// accept connections on all of them.
+    bind: Vec<std::net::IpAddr>,
     #[arg(short, long, default_value_t = DEFAULT_PORT)]
     /// Port to bind to.
     port: u16,
+    #[arg(long, default_value_t = server::DEFAULT_TCP_BACKLOG)]
+    /// Maximum number of connections waiting to be accepted.
+    tcp_backlog: u32,
+    #[arg(long, default_value_t = cfg!(unix), action = clap::ArgAction::Set)]
+    /// Set `SO_REUSEADDR` before binding, so the server can restart right
+    /// away on the same port. Enabled by default on Unix.
+    reuse_addr: bool,
+    #[arg(long)]
+    /// Close connections idle for more than this many seconds.
+    idle_timeout: Option<u64>,
+    #[arg(long)]
+    /// Publish keyspace notifications when keys are modified or expire.
+    notify_keyspace_events: bool,
+    #[arg(long)]
+    /// Memory budget in bytes, unbounded if unset.
+    maxmemory: Option<usize>,
+    #[arg(long, default_value = "noeviction")]
+    /// Eviction policy once the memory budget is reached: noeviction,
+    /// allkeys-lru, allkeys-lfu or allkeys-random.
+    maxmemory_policy: server::EvictionPolicy,
+    #[arg(long)]
+    /// Require clients to authenticate with this password.
+    requirepass: Option<String>,
+    #[arg(long)]
+    /// Log commands taking longer than this many microseconds to the slow
+    /// log, disabled if unset.
+    slowlog_log_slower_than: Option<u64>,
+    #[arg(long, default_value_t = server::DEFAULT_SLOWLOG_MAX_LEN)]
+    /// Maximum number of entries kept in the slow log.
+    slowlog_max_len: usize,
+    #[arg(long)]
+    /// Record latencies of at least this many milliseconds, reported by
+    /// `LATENCY`, disabled if unset.
+    latency_monitor_threshold: Option<u64>,
+    #[arg(long)]
+    /// Log every applied command with the `loja::access` target.
+    access_log: bool,
+    #[arg(
+        long,
+        default_value_t = server::DEFAULT_PUBSUB_CHANNEL_CAPACITY,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    /// Number of messages buffered per pub/sub channel, slower subscribers
+    /// miss messages.
+    pubsub_channel_capacity: usize,
+    #[arg(long, default_value_t = server::DEFAULT_MAX_FRAME_SIZE)]
+    /// Maximum size in bytes of a frame received from a client.
+    max_frame_size: usize,
+    #[arg(long, default_value_t = server::DEFAULT_MAX_PROTOCOL_ERRORS)]
+    /// Close connections sending this many invalid commands in a row.
+    max_protocol_errors: usize,
+    #[arg(long)]
+    /// Maximum number of channels a connection may subscribe to, unbounded
+    /// if unset.
+    max_subscriptions: Option<usize>,
+    #[arg(long)]
+    /// Only remove expired keys once accessed, without a background task.
+    lazy_expiration: bool,
+    #[arg(long)]
+    /// Close connections with more than this many bytes of replies not yet
+    /// read by the client, unbounded if unset.
+    output_buffer_hard_limit: Option<usize>,
+    #[arg(long)]
+    /// Close connections with more than this many bytes of replies not yet
+    /// read by the client for `output-buffer-soft-seconds`, unbounded if
+    /// unset.
+    output_buffer_soft_limit: Option<usize>,
+    #[arg(long, default_value_t = 60)]
+    /// How long, in seconds, a connection may stay over the soft output
+    /// buffer limit.
+    output_buffer_soft_seconds: u64,
+    #[arg(long)]
+    /// Reply to this command as unknown instead of applying it. May be given
+    /// several times.
+    disable_command: Vec<String>,
 }
 
 fn setup_logging() {
@@ -1,11 +1,27 @@
 //! A Redis client implementation over a [`TcpStream`].
 use crate::{
-    cmd::{Command, GetCmd, PingCmd, SetCmd},
+    clients::{Pipeline, Transaction},
+    cmd::{
+        AuthCmd, BLPopCmd, BRPopCmd, BitCountCmd, BitFieldCmd, BitFieldOp, Command, CopyCmd,
+        DecrByCmd, DecrCmd, DumpCmd, ExpireCmd, ExpireCondition, GetBitCmd, GetCmd, GetExCmd,
+        GetExOption, GetSetCmd, HExpireCmd, HGetCmd, HIncrByCmd, HKeysCmd, HMGetCmd, HPersistCmd,
+        HRandFieldCmd, HScanCmd, HSetCmd, HTtlCmd, HValsCmd, IncrByCmd, IncrByFloatCmd, IncrCmd,
+        InfoCmd, LIndexCmd, LLenCmd, LMPopCmd, LPosCmd, LPushCmd, LRemCmd, LSetCmd, LTrimCmd,
+        LatencyCmd, LexBound, ListDirection, LolwutCmd, MemoryUsageCmd, ObjectCmd, PfAddCmd,
+        PfCountCmd, PingCmd, RPopLPushCmd, RPushCmd, RestoreCmd, SAddCmd, SDiffCmd, SDiffStoreCmd,
+        SInterCardCmd, SInterCmd, SInterStoreCmd, SMoveCmd, SScanCmd, SUnionCmd, SUnionStoreCmd,
+        ScoreDirection, ScoredMember, SetBitCmd, SetCmd, SlowLogCmd, TimeCmd, WaitCmd, ZAddCmd,
+        ZCardCmd, ZCountCmd, ZMPopCmd, ZRandMemberCmd, ZRangeByLexCmd, ZRangeByScoreCmd, ZRangeCmd,
+        ZRankCmd, ZRemCmd, ZRevRankCmd, ZScanCmd, ZScoreCmd,
+    },
     Connection, Error, Frame, Result,
 };
 use bytes::Bytes;
-use std::time::Duration;
-use tokio::net::{TcpStream, ToSocketAddrs};
+use std::{ops::Bound, time::Duration};
+use tokio::{
+    net::{TcpStream, ToSocketAddrs},
+    time::Instant,
+};
 use tracing::debug;
 
 /// Established connection with a Redis server.
@@ -61,6 +77,16 @@ impl Client<TcpStream> {
         }
     }
 
+    /// Measure the round-trip time of a `PING` to the server.
+    ///
+    /// This is a quick way to check the server is up and responsive.
+    #[tracing::instrument(skip(self))]
+    pub async fn latency(&mut self) -> Result<Duration> {
+        let start = Instant::now();
+        self.ping(None).await?;
+        Ok(start.elapsed())
+    }
+
     /// Get the value of key.
     ///
     /// If the key does not exist `None` is returned.
@@ -105,6 +131,22 @@ impl Client<TcpStream> {
         self.set_cmd(SetCmd::new(key, val, Some(expire))).await
     }
 
+    /// Set `key` to hold the given `value`, returning the value it previously
+    /// held, if any.
+    ///
+    /// Any previous time to live associated with the key is discarded.
+    #[tracing::instrument(skip(self))]
+    pub async fn getset(&mut self, key: &str, val: Bytes) -> Result<Option<Bytes>> {
+        let frame = GetSetCmd::new(key, val).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::BulkString(val) => Ok(Some(val)),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
     /// The core `SET` logic, used by both `set` and `set_expires.
     async fn set_cmd(&mut self, cmd: SetCmd) -> Result<()> {
         let frame = cmd.into_frame()?;
@@ -119,21 +161,1252 @@ impl Client<TcpStream> {
         }
     }
 
-    async fn read_response(&mut self) -> Result<Frame> {
-        let response = self.connection.read_frame().await?;
-        debug!(?response);
-        match response {
-            Some(Frame::SimpleError(msg)) => Err(Error::Response(msg)),
-            Some(frame) => Ok(frame),
-            None => {
-                // Receiving `None` indicates the connection has been closed by the server
-                // without sending a frame. This is unexpected and treated as an `Error::Io`.
-                Err(std::io::Error::new(
-                    std::io::ErrorKind::ConnectionReset,
-                    "connection reset by server",
-                )
-                .into())
-            }
+    /// Increment the integer value stored at `key` by one.
+    ///
+    /// If the key does not exist, it is set to `0` before performing the
+    /// operation. Returns the value of the key after the increment.
+    #[tracing::instrument(skip(self))]
+    pub async fn incr(&mut self, key: &str) -> Result<i64> {
+        let frame = IncrCmd::new(key).into_frame()?;
+        self.signed_integer_cmd(frame).await
+    }
+
+    /// Decrement the integer value stored at `key` by one.
+    ///
+    /// Returns like [`Client::incr`].
+    #[tracing::instrument(skip(self))]
+    pub async fn decr(&mut self, key: &str) -> Result<i64> {
+        let frame = DecrCmd::new(key).into_frame()?;
+        self.signed_integer_cmd(frame).await
+    }
+
+    /// Increment the integer value stored at `key` by `increment`.
+    ///
+    /// Returns like [`Client::incr`].
+    #[tracing::instrument(skip(self))]
+    pub async fn incr_by(&mut self, key: &str, increment: i64) -> Result<i64> {
+        let frame = IncrByCmd::new(key, increment).into_frame()?;
+        self.signed_integer_cmd(frame).await
+    }
+
+    /// Decrement the integer value stored at `key` by `decrement`.
+    ///
+    /// Returns like [`Client::incr`].
+    #[tracing::instrument(skip(self))]
+    pub async fn decr_by(&mut self, key: &str, decrement: i64) -> Result<i64> {
+        let frame = DecrByCmd::new(key, decrement).into_frame()?;
+        self.signed_integer_cmd(frame).await
+    }
+
+    /// Increment the float value stored at `key` by `increment`.
+    ///
+    /// If the key does not exist, it is set to `0` before performing the
+    /// operation. Returns the value of the key after the increment.
+    #[tracing::instrument(skip(self))]
+    pub async fn incr_by_float(&mut self, key: &str, increment: f64) -> Result<f64> {
+        let frame = IncrByFloatCmd::new(key, increment).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::BulkString(val) => parse_float(&val),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Wait for previous writes to be acknowledged by `numreplicas` replicas.
+    ///
+    /// Returns the number of replicas that acknowledged the writes. As the
+    /// server is single-node, this is always `0`.
+    #[tracing::instrument(skip(self))]
+    pub async fn wait(&mut self, numreplicas: u64, timeout: Duration) -> Result<u64> {
+        let frame = WaitCmd::new(numreplicas, timeout).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(val) => Ok(val.try_into()?),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Authenticate the connection with the server password.
+    ///
+    /// The `username` defaults to `default`, the only user of the server.
+    #[tracing::instrument(skip(self, password))]
+    pub async fn auth(&mut self, username: Option<String>, password: &str) -> Result<()> {
+        let frame = AuthCmd::new(username, password).into_frame()?;
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::SimpleString(val) if val == "OK" => Ok(()),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Copy the value stored at `source` to `destination`, along with its
+    /// time to live.
+    ///
+    /// Returns `true` if the value was copied, which does not happen if
+    /// `destination` already exists, unless `replace` is set.
+    #[tracing::instrument(skip(self))]
+    pub async fn copy(&mut self, source: &str, destination: &str, replace: bool) -> Result<bool> {
+        let frame = CopyCmd::new(source, destination, None, replace).into_frame()?;
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Serialize the value stored at `key`, to be recreated with
+    /// [`Client::restore`].
+    ///
+    /// Returns `None` if the key does not exist.
+    #[tracing::instrument(skip(self))]
+    pub async fn dump(&mut self, key: &str) -> Result<Option<Bytes>> {
+        let frame = DumpCmd::new(key).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::BulkString(payload) => Ok(Some(payload)),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Recreate `key` from a `payload` produced by [`Client::dump`], expiring
+    /// after `ttl` if set.
+    ///
+    /// Fails if `key` already exists, unless `replace` is set.
+    #[tracing::instrument(skip(self, payload))]
+    pub async fn restore(
+        &mut self,
+        key: &str,
+        ttl: Option<Duration>,
+        payload: Bytes,
+        replace: bool,
+    ) -> Result<()> {
+        let frame = RestoreCmd::new(key, ttl, payload)
+            .replace(replace)
+            .into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::SimpleString(val) if val == "OK" => Ok(()),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Set the bit at `offset` in the string stored at `key`, growing the
+    /// string as needed.
+    ///
+    /// Returns the previous value of the bit.
+    #[tracing::instrument(skip(self))]
+    pub async fn setbit(&mut self, key: &str, offset: u64, bit: bool) -> Result<bool> {
+        let frame = SetBitCmd::new(key, offset, bit).into_frame()?;
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Get the bit at `offset` in the string stored at `key`.
+    #[tracing::instrument(skip(self))]
+    pub async fn getbit(&mut self, key: &str, offset: u64) -> Result<bool> {
+        let frame = GetBitCmd::new(key, offset).into_frame()?;
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Count the bits set in the string stored at `key`, restricted to the
+    /// inclusive byte `range` if given.
+    #[tracing::instrument(skip(self))]
+    pub async fn bitcount(&mut self, key: &str, range: Option<(i64, i64)>) -> Result<u64> {
+        let mut cmd = BitCountCmd::new(key);
+        if let Some((start, end)) = range {
+            cmd = cmd.range(start, end);
+        }
+        self.integer_cmd(cmd.into_frame()?).await
+    }
+
+    /// Apply the `BITFIELD` operations `ops` to the string stored at `key`.
+    ///
+    /// Returns the result of every operation, in order.
+    #[tracing::instrument(skip(self))]
+    pub async fn bitfield(&mut self, key: &str, ops: Vec<BitFieldOp>) -> Result<Vec<i64>> {
+        let frame = BitFieldCmd::new(key, ops).into_frame()?;
+        self.integers_cmd(frame).await
+    }
+
+    /// Add `elements` to the HyperLogLog stored at `key`.
+    ///
+    /// Returns `true` if the HyperLogLog was created or its estimate may have
+    /// changed.
+    #[tracing::instrument(skip(self))]
+    pub async fn pfadd(&mut self, key: &str, elements: Vec<Bytes>) -> Result<bool> {
+        let frame = PfAddCmd::new(key, elements).into_frame()?;
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Estimate the number of distinct elements added to the union of the
+    /// HyperLogLogs stored at `keys`.
+    #[tracing::instrument(skip(self))]
+    pub async fn pfcount(&mut self, keys: Vec<String>) -> Result<u64> {
+        let frame = PfCountCmd::new(keys).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Get the approximate number of bytes used to store `key` and its value.
+    ///
+    /// Returns `None` if the key does not exist.
+    #[tracing::instrument(skip(self))]
+    pub async fn memory_usage(&mut self, key: &str) -> Result<Option<u64>> {
+        let frame = MemoryUsageCmd::new(key).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(val) => Ok(Some(val.try_into()?)),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get information and statistics about the server.
+    ///
+    /// If `sections` is empty, the default sections are returned.
+    #[tracing::instrument(skip(self))]
+    pub async fn info(&mut self, sections: Vec<String>) -> Result<String> {
+        let frame = InfoCmd::new(sections).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::BulkString(val) => Ok(String::from_utf8(val.to_vec())?),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get the number of seconds since `key` was last accessed.
+    ///
+    /// Returns `None` if the key does not exist.
+    #[tracing::instrument(skip(self))]
+    pub async fn object_idletime(&mut self, key: &str) -> Result<Option<u64>> {
+        let frame = ObjectCmd::idle_time(key).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(val) => Ok(Some(val.try_into()?)),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get the access frequency counter of `key`, which the `allkeys-lfu`
+    /// eviction policy evicts the lowest of.
+    ///
+    /// Returns `None` if the key does not exist.
+    #[tracing::instrument(skip(self))]
+    pub async fn object_freq(&mut self, key: &str) -> Result<Option<u64>> {
+        let frame = ObjectCmd::freq(key).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(val) => Ok(Some(val.try_into()?)),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get the server time, as the Unix time in seconds and the microseconds
+    /// elapsed in the current second.
+    #[tracing::instrument(skip(self))]
+    pub async fn time(&mut self) -> Result<(u64, u64)> {
+        let frame = TimeCmd::new().into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(reply) => match <[Frame; 2]>::try_from(reply) {
+                Ok([Frame::BulkString(secs), Frame::BulkString(micros)]) => {
+                    let parse = |val: &[u8]| {
+                        atoi::atoi(val)
+                            .ok_or_else(|| Error::Response("invalid time in reply".to_string()))
+                    };
+                    Ok((parse(&secs)?, parse(&micros)?))
+                }
+                Ok(reply) => Err(Error::Response(format!("unexpected frame: {reply:?}"))),
+                Err(reply) => Err(Error::Response(format!("unexpected frame: {reply:?}"))),
+            },
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get the server banner, which includes the server version.
+    #[tracing::instrument(skip(self))]
+    pub async fn lolwut(&mut self) -> Result<String> {
+        let frame = LolwutCmd::new(None).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::BulkString(val) => Ok(String::from_utf8(val.to_vec())?),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get the number of entries in the slow log.
+    #[tracing::instrument(skip(self))]
+    pub async fn slowlog_len(&mut self) -> Result<u64> {
+        let frame = SlowLogCmd::len().into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Remove every entry from the slow log.
+    #[tracing::instrument(skip(self))]
+    pub async fn slowlog_reset(&mut self) -> Result<()> {
+        let frame = SlowLogCmd::reset().into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::SimpleString(val) if val == "OK" => Ok(()),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Remove the latency samples of `events`, or of every event if it is
+    /// empty.
+    ///
+    /// Returns the number of events which had samples.
+    #[tracing::instrument(skip(self))]
+    pub async fn latency_reset(&mut self, events: Vec<String>) -> Result<u64> {
+        let frame = LatencyCmd::reset(events).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Set a time to live on `key`, in seconds, if `condition` is met.
+    ///
+    /// Returns `true` if the time to live was set, or `false` if the key does
+    /// not exist or the condition is not met.
+    #[tracing::instrument(skip(self))]
+    pub async fn expire(
+        &mut self,
+        key: &str,
+        seconds: i64,
+        condition: Option<ExpireCondition>,
+    ) -> Result<bool> {
+        let frame = ExpireCmd::new(key, seconds, condition).into_frame()?;
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Get the value of `key`, and update its time to live according to
+    /// `option`.
+    ///
+    /// Without an option, this behaves like [`Client::get`].
+    #[tracing::instrument(skip(self))]
+    pub async fn getex(&mut self, key: &str, option: Option<GetExOption>) -> Result<Option<Bytes>> {
+        let frame = GetExCmd::new(key, option).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::BulkString(val) => Ok(Some(val)),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Insert `values` at the head of the list stored at `key`.
+    ///
+    /// Returns the length of the list after the push.
+    #[tracing::instrument(skip(self))]
+    pub async fn lpush(&mut self, key: &str, values: Vec<Bytes>) -> Result<u64> {
+        let frame = LPushCmd::new(key, values).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Insert `values` at the tail of the list stored at `key`.
+    ///
+    /// Returns the length of the list after the push.
+    #[tracing::instrument(skip(self))]
+    pub async fn rpush(&mut self, key: &str, values: Vec<Bytes>) -> Result<u64> {
+        let frame = RPushCmd::new(key, values).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Get the length of the list stored at `key`.
+    #[tracing::instrument(skip(self))]
+    pub async fn llen(&mut self, key: &str) -> Result<u64> {
+        let frame = LLenCmd::new(key).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Get the element at `index` in the list stored at `key`.
+    ///
+    /// Negative indices count from the tail of the list. If the key does not
+    /// exist or the index is out of range `None` is returned.
+    #[tracing::instrument(skip(self))]
+    pub async fn lindex(&mut self, key: &str, index: i64) -> Result<Option<Bytes>> {
+        let frame = LIndexCmd::new(key, index).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::BulkString(val) => Ok(Some(val)),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Set the element at `index` in the list stored at `key` to `value`.
+    #[tracing::instrument(skip(self))]
+    pub async fn lset(&mut self, key: &str, index: i64, value: Bytes) -> Result<()> {
+        let frame = LSetCmd::new(key, index, value).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::SimpleString(val) if val == "OK" => Ok(()),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get the indices of the elements equal to `element` in the list stored
+    /// at `key`.
+    ///
+    /// `rank` selects the first match to report, a negative one searching from
+    /// the tail. Up to `count` indices are returned, zero meaning every match.
+    #[tracing::instrument(skip(self))]
+    pub async fn lpos(
+        &mut self,
+        key: &str,
+        element: Bytes,
+        rank: i64,
+        count: u64,
+    ) -> Result<Vec<u64>> {
+        let frame = LPosCmd::new(key, element)
+            .rank(rank)
+            .count(count)
+            .into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Integer(index) => Ok(index.try_into()?),
+                    frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+                })
+                .collect(),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Remove up to `count` occurrences of `value` from the list stored at
+    /// `key`.
+    ///
+    /// A positive `count` removes from head to tail, a negative one from tail
+    /// to head, and zero removes every occurrence. Returns the number of
+    /// removed elements.
+    #[tracing::instrument(skip(self))]
+    pub async fn lrem(&mut self, key: &str, count: i64, value: Bytes) -> Result<u64> {
+        let frame = LRemCmd::new(key, count, value).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Trim the list stored at `key` to the inclusive range from `start` to
+    /// `stop`.
+    #[tracing::instrument(skip(self))]
+    pub async fn ltrim(&mut self, key: &str, start: i64, stop: i64) -> Result<()> {
+        let frame = LTrimCmd::new(key, start, stop).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::SimpleString(val) if val == "OK" => Ok(()),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
         }
     }
+
+    /// Atomically move the last element of the list stored at `source` to the
+    /// head of the list stored at `destination`.
+    ///
+    /// Returns the moved element, or `None` if `source` does not exist.
+    #[tracing::instrument(skip(self))]
+    pub async fn rpoplpush(&mut self, source: &str, destination: &str) -> Result<Option<Bytes>> {
+        let frame = RPopLPushCmd::new(source, destination).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::BulkString(val) => Ok(Some(val)),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Pop an element from the head of the first non empty list stored at one
+    /// of `keys`, blocking until one is pushed for up to `timeout`.
+    ///
+    /// A zero `timeout` blocks forever. Returns the key the element was popped
+    /// from along with the element, or `None` if the timeout was reached.
+    #[tracing::instrument(skip(self))]
+    pub async fn blpop(
+        &mut self,
+        keys: Vec<String>,
+        timeout: Duration,
+    ) -> Result<Option<(String, Bytes)>> {
+        let frame = BLPopCmd::new(keys, timeout).into_frame()?;
+        self.bpop_cmd(frame).await
+    }
+
+    /// Pop an element from the tail of the first non empty list stored at one
+    /// of `keys`, blocking until one is pushed for up to `timeout`.
+    ///
+    /// Returns like [`Client::blpop`].
+    #[tracing::instrument(skip(self))]
+    pub async fn brpop(
+        &mut self,
+        keys: Vec<String>,
+        timeout: Duration,
+    ) -> Result<Option<(String, Bytes)>> {
+        let frame = BRPopCmd::new(keys, timeout).into_frame()?;
+        self.bpop_cmd(frame).await
+    }
+
+    /// Send a blocking pop request, and read back the key and the popped
+    /// element.
+    async fn bpop_cmd(&mut self, frame: Frame) -> Result<Option<(String, Bytes)>> {
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(entries) => match entries.as_slice() {
+                [Frame::BulkString(key), Frame::BulkString(value)] => {
+                    let key = String::from_utf8(key.to_vec())
+                        .map_err(|_| Error::Response("key is not valid UTF-8".to_string()))?;
+                    Ok(Some((key, value.clone())))
+                }
+                _ => Err(Error::Response(format!("unexpected frames: {entries:?}"))),
+            },
+            Frame::NullArray => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Pop up to `count` elements from the `direction` end of the first non
+    /// empty list stored at one of `keys`.
+    ///
+    /// Returns the key along with the popped elements, or `None` if every
+    /// list is empty.
+    #[tracing::instrument(skip(self))]
+    pub async fn lmpop(
+        &mut self,
+        keys: Vec<String>,
+        direction: ListDirection,
+        count: u64,
+    ) -> Result<Option<(String, Vec<Bytes>)>> {
+        let frame = LMPopCmd::new(keys, direction).count(count).into_frame()?;
+        let Some((key, popped)) = self.mpop_cmd(frame).await? else {
+            return Ok(None);
+        };
+        let values = popped
+            .into_iter()
+            .map(|value| match value {
+                Frame::BulkString(value) => Ok(value),
+                frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+            })
+            .collect::<Result<_>>()?;
+        Ok(Some((key, values)))
+    }
+
+    /// Pop up to `count` members, with the lowest or highest scores
+    /// depending on `direction`, from the first non empty sorted set stored
+    /// at one of `keys`.
+    ///
+    /// Returns the key along with the popped members and their scores, or
+    /// `None` if every sorted set is empty.
+    #[tracing::instrument(skip(self))]
+    pub async fn zmpop(
+        &mut self,
+        keys: Vec<String>,
+        direction: ScoreDirection,
+        count: u64,
+    ) -> Result<Option<(String, Vec<ScoredMember>)>> {
+        let frame = ZMPopCmd::new(keys, direction).count(count).into_frame()?;
+        let Some((key, popped)) = self.mpop_cmd(frame).await? else {
+            return Ok(None);
+        };
+        let members = popped
+            .into_iter()
+            .map(|pair| match pair {
+                Frame::Array(pair) => match pair.as_slice() {
+                    [Frame::BulkString(member), Frame::BulkString(score)] => {
+                        Ok((member.clone(), parse_float(score)?))
+                    }
+                    _ => Err(Error::Response(format!("unexpected frames: {pair:?}"))),
+                },
+                frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+            })
+            .collect::<Result<_>>()?;
+        Ok(Some((key, members)))
+    }
+
+    /// Send a multi-key pop request, and read back the key and the frames of
+    /// the popped items.
+    async fn mpop_cmd(&mut self, frame: Frame) -> Result<Option<(String, Vec<Frame>)>> {
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(entries) => match <[Frame; 2]>::try_from(entries) {
+                Ok([Frame::BulkString(key), Frame::Array(popped)]) => {
+                    let key = String::from_utf8(key.to_vec())
+                        .map_err(|_| Error::Response("key is not valid UTF-8".to_string()))?;
+                    Ok(Some((key, popped)))
+                }
+                Ok(entries) => Err(Error::Response(format!("unexpected frames: {entries:?}"))),
+                Err(entries) => Err(Error::Response(format!("unexpected frames: {entries:?}"))),
+            },
+            Frame::NullArray => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Add `members` to the set stored at `key`.
+    ///
+    /// Returns the number of members that were added, not counting the ones
+    /// already in the set.
+    #[tracing::instrument(skip(self))]
+    pub async fn sadd(&mut self, key: &str, members: Vec<Bytes>) -> Result<u64> {
+        let frame = SAddCmd::new(key, members).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Get the members of the intersection of the sets stored at `keys`.
+    #[tracing::instrument(skip(self))]
+    pub async fn sinter(&mut self, keys: Vec<String>) -> Result<Vec<Bytes>> {
+        let frame = SInterCmd::new(keys).into_frame()?;
+        self.members_cmd(frame).await
+    }
+
+    /// Get the number of members of the intersection of the sets stored at
+    /// `keys`.
+    ///
+    /// With a `limit`, counting stops once `limit` members are found.
+    #[tracing::instrument(skip(self))]
+    pub async fn sintercard(&mut self, keys: Vec<String>, limit: Option<u64>) -> Result<u64> {
+        let mut cmd = SInterCardCmd::new(keys);
+        if let Some(limit) = limit {
+            cmd = cmd.limit(limit);
+        }
+        self.integer_cmd(cmd.into_frame()?).await
+    }
+
+    /// Get the members of the union of the sets stored at `keys`.
+    #[tracing::instrument(skip(self))]
+    pub async fn sunion(&mut self, keys: Vec<String>) -> Result<Vec<Bytes>> {
+        let frame = SUnionCmd::new(keys).into_frame()?;
+        self.members_cmd(frame).await
+    }
+
+    /// Get the members of the first set stored at `keys` which are not in any
+    /// of the following sets.
+    #[tracing::instrument(skip(self))]
+    pub async fn sdiff(&mut self, keys: Vec<String>) -> Result<Vec<Bytes>> {
+        let frame = SDiffCmd::new(keys).into_frame()?;
+        self.members_cmd(frame).await
+    }
+
+    /// The core logic of commands replying with a non negative integer.
+    async fn integer_cmd(&mut self, frame: Frame) -> Result<u64> {
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(val) => Ok(val.try_into()?),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Atomically move `member` from the set stored at `source` to the set
+    /// stored at `destination`.
+    ///
+    /// Returns `true` if the member was moved, or `false` if it is not a
+    /// member of `source`.
+    #[tracing::instrument(skip(self))]
+    pub async fn smove(&mut self, source: &str, destination: &str, member: Bytes) -> Result<bool> {
+        let frame = SMoveCmd::new(source, destination, member).into_frame()?;
+        Ok(self.integer_cmd(frame).await? == 1)
+    }
+
+    /// Store the intersection of the sets stored at `keys` at `destination`.
+    ///
+    /// Returns the number of members of the result. An empty result deletes
+    /// `destination`.
+    #[tracing::instrument(skip(self))]
+    pub async fn sinterstore(&mut self, destination: &str, keys: Vec<String>) -> Result<u64> {
+        let frame = SInterStoreCmd::new(destination, keys).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Store the union of the sets stored at `keys` at `destination`.
+    ///
+    /// Returns like [`Client::sinterstore`].
+    #[tracing::instrument(skip(self))]
+    pub async fn sunionstore(&mut self, destination: &str, keys: Vec<String>) -> Result<u64> {
+        let frame = SUnionStoreCmd::new(destination, keys).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Store the members of the first set stored at `keys` which are not in
+    /// any of the following sets at `destination`.
+    ///
+    /// Returns like [`Client::sinterstore`].
+    #[tracing::instrument(skip(self))]
+    pub async fn sdiffstore(&mut self, destination: &str, keys: Vec<String>) -> Result<u64> {
+        let frame = SDiffStoreCmd::new(destination, keys).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// The core logic of commands replying with a possibly negative integer.
+    async fn signed_integer_cmd(&mut self, frame: Frame) -> Result<i64> {
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(val) => Ok(val),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// The core logic of commands replying with an array of integers, such
+    /// as `bitfield` or `httl`.
+    async fn integers_cmd(&mut self, frame: Frame) -> Result<Vec<i64>> {
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(values) => values
+                .into_iter()
+                .map(|value| match value {
+                    Frame::Integer(val) => Ok(val),
+                    frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+                })
+                .collect(),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// The core logic of commands replying with an array of bulk strings,
+    /// such as `sinter` or `zrange`.
+    async fn members_cmd(&mut self, frame: Frame) -> Result<Vec<Bytes>> {
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(members) => members
+                .into_iter()
+                .map(|member| match member {
+                    Frame::BulkString(val) => Ok(val),
+                    frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+                })
+                .collect(),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Add `members` with their scores to the sorted set stored at `key`.
+    ///
+    /// Returns the number of members that were added, not counting the ones
+    /// whose score was updated.
+    #[tracing::instrument(skip(self))]
+    pub async fn zadd(&mut self, key: &str, members: Vec<(f64, Bytes)>) -> Result<u64> {
+        let frame = ZAddCmd::new(key, members).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Get the score of `member` in the sorted set stored at `key`.
+    ///
+    /// If the key or the member do not exist `None` is returned.
+    #[tracing::instrument(skip(self))]
+    pub async fn zscore(&mut self, key: &str, member: Bytes) -> Result<Option<f64>> {
+        let frame = ZScoreCmd::new(key, member).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::BulkString(val) => parse_float(&val).map(Some),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get the rank of `member` in the sorted set stored at `key`, ranking the
+    /// members from the lowest score.
+    ///
+    /// If the key or the member do not exist `None` is returned.
+    #[tracing::instrument(skip(self))]
+    pub async fn zrank(&mut self, key: &str, member: Bytes) -> Result<Option<u64>> {
+        let frame = ZRankCmd::new(key, member).into_frame()?;
+        self.rank_cmd(frame).await
+    }
+
+    /// Get the rank of `member` in the sorted set stored at `key`, ranking the
+    /// members from the highest score.
+    ///
+    /// If the key or the member do not exist `None` is returned.
+    #[tracing::instrument(skip(self))]
+    pub async fn zrevrank(&mut self, key: &str, member: Bytes) -> Result<Option<u64>> {
+        let frame = ZRevRankCmd::new(key, member).into_frame()?;
+        self.rank_cmd(frame).await
+    }
+
+    async fn rank_cmd(&mut self, frame: Frame) -> Result<Option<u64>> {
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(rank) => Ok(Some(rank as u64)),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get the number of members of the sorted set stored at `key`.
+    #[tracing::instrument(skip(self))]
+    pub async fn zcard(&mut self, key: &str) -> Result<u64> {
+        let frame = ZCardCmd::new(key).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Count the members of the sorted set stored at `key` whose score is
+    /// between `min` and `max`.
+    #[tracing::instrument(skip(self))]
+    pub async fn zcount(&mut self, key: &str, min: Bound<f64>, max: Bound<f64>) -> Result<u64> {
+        let frame = ZCountCmd::new(key, min, max).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Remove `members` from the sorted set stored at `key`.
+    ///
+    /// Returns the number of members that were removed.
+    #[tracing::instrument(skip(self))]
+    pub async fn zrem(&mut self, key: &str, members: Vec<Bytes>) -> Result<u64> {
+        let frame = ZRemCmd::new(key, members).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Get the members of the sorted set stored at `key` whose rank is between
+    /// `start` and `stop`, inclusive.
+    #[tracing::instrument(skip(self))]
+    pub async fn zrange(&mut self, key: &str, start: i64, stop: i64) -> Result<Vec<Bytes>> {
+        let frame = ZRangeCmd::new(key, start, stop, false).into_frame()?;
+        self.members_cmd(frame).await
+    }
+
+    /// Get the members of the sorted set stored at `key` whose rank is between
+    /// `start` and `stop`, inclusive, along with their scores.
+    #[tracing::instrument(skip(self))]
+    pub async fn zrange_with_scores(
+        &mut self,
+        key: &str,
+        start: i64,
+        stop: i64,
+    ) -> Result<Vec<(Bytes, f64)>> {
+        let frame = ZRangeCmd::new(key, start, stop, true).into_frame()?;
+        let reply = self.members_cmd(frame).await?;
+        reply
+            .chunks(2)
+            .map(|pair| match pair {
+                [member, score] => Ok((member.clone(), parse_float(score)?)),
+                _ => Err(Error::Response("missing score in reply".to_string())),
+            })
+            .collect()
+    }
+
+    /// Get a random member of the sorted set stored at `key`.
+    ///
+    /// If the key does not exist `None` is returned.
+    #[tracing::instrument(skip(self))]
+    pub async fn zrandmember(&mut self, key: &str) -> Result<Option<Bytes>> {
+        let frame = ZRandMemberCmd::new(key).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::BulkString(val) => Ok(Some(val)),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get `count` random members of the sorted set stored at `key`.
+    ///
+    /// `count` is like for [`Client::hrandfield_count`].
+    #[tracing::instrument(skip(self))]
+    pub async fn zrandmember_count(&mut self, key: &str, count: i64) -> Result<Vec<Bytes>> {
+        let frame = ZRandMemberCmd::new(key).count(count).into_frame()?;
+        self.members_cmd(frame).await
+    }
+
+    /// Get `count` random members of the sorted set stored at `key`, along
+    /// with their scores.
+    ///
+    /// `count` is like for [`Client::hrandfield_count`].
+    #[tracing::instrument(skip(self))]
+    pub async fn zrandmember_with_scores(
+        &mut self,
+        key: &str,
+        count: i64,
+    ) -> Result<Vec<(Bytes, f64)>> {
+        let frame = ZRandMemberCmd::new(key)
+            .count(count)
+            .with_scores()
+            .into_frame()?;
+        let reply = self.members_cmd(frame).await?;
+        reply
+            .chunks(2)
+            .map(|pair| match pair {
+                [member, score] => Ok((member.clone(), parse_float(score)?)),
+                _ => Err(Error::Response("missing score in reply".to_string())),
+            })
+            .collect()
+    }
+
+    /// Get the members of the sorted set stored at `key` whose score is
+    /// between `min` and `max`.
+    ///
+    /// `limit` restricts the reply to `count` members after skipping `offset`
+    /// ones.
+    #[tracing::instrument(skip(self))]
+    pub async fn zrangebyscore(
+        &mut self,
+        key: &str,
+        min: Bound<f64>,
+        max: Bound<f64>,
+        limit: Option<(i64, i64)>,
+    ) -> Result<Vec<Bytes>> {
+        let mut cmd = ZRangeByScoreCmd::new(key, min, max, false);
+        if let Some((offset, count)) = limit {
+            cmd = cmd.limit(offset, count);
+        }
+        self.members_cmd(cmd.into_frame()?).await
+    }
+
+    /// Get the members of the sorted set stored at `key` which are between
+    /// `min` and `max` lexicographically.
+    ///
+    /// `limit` is like for [`Client::zrangebyscore`].
+    #[tracing::instrument(skip(self))]
+    pub async fn zrangebylex(
+        &mut self,
+        key: &str,
+        min: LexBound,
+        max: LexBound,
+        limit: Option<(i64, i64)>,
+    ) -> Result<Vec<Bytes>> {
+        let mut cmd = ZRangeByLexCmd::new(key, min, max);
+        if let Some((offset, count)) = limit {
+            cmd = cmd.limit(offset, count);
+        }
+        self.members_cmd(cmd.into_frame()?).await
+    }
+
+    /// Set `fields` to their values in the hash stored at `key`.
+    ///
+    /// Returns the number of fields that were added.
+    #[tracing::instrument(skip(self))]
+    pub async fn hset(&mut self, key: &str, fields: Vec<(Bytes, Bytes)>) -> Result<u64> {
+        let frame = HSetCmd::new(key, fields).into_frame()?;
+        self.integer_cmd(frame).await
+    }
+
+    /// Get the value of `field` in the hash stored at `key`.
+    #[tracing::instrument(skip(self))]
+    pub async fn hget(&mut self, key: &str, field: Bytes) -> Result<Option<Bytes>> {
+        let frame = HGetCmd::new(key, field).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::BulkString(val) => Ok(Some(val)),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Increment the integer stored at `field` in the hash stored at `key` by
+    /// `increment`.
+    ///
+    /// Returns the value of the field after the increment.
+    #[tracing::instrument(skip(self))]
+    pub async fn hincr_by(&mut self, key: &str, field: Bytes, increment: i64) -> Result<i64> {
+        let frame = HIncrByCmd::new(key, field, increment).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(val) => Ok(val),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get the values of `fields` in the hash stored at `key`.
+    ///
+    /// Fields that do not exist are `None`.
+    #[tracing::instrument(skip(self))]
+    pub async fn hmget(&mut self, key: &str, fields: Vec<Bytes>) -> Result<Vec<Option<Bytes>>> {
+        let frame = HMGetCmd::new(key, fields).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(values) => values
+                .into_iter()
+                .map(|value| match value {
+                    Frame::BulkString(val) => Ok(Some(val)),
+                    Frame::NullBulkString => Ok(None),
+                    frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+                })
+                .collect(),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get the fields of the hash stored at `key`.
+    #[tracing::instrument(skip(self))]
+    pub async fn hkeys(&mut self, key: &str) -> Result<Vec<Bytes>> {
+        let frame = HKeysCmd::new(key).into_frame()?;
+        self.members_cmd(frame).await
+    }
+
+    /// Get a random field of the hash stored at `key`.
+    ///
+    /// If the key does not exist `None` is returned.
+    #[tracing::instrument(skip(self))]
+    pub async fn hrandfield(&mut self, key: &str) -> Result<Option<Bytes>> {
+        let frame = HRandFieldCmd::new(key).into_frame()?;
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::BulkString(val) => Ok(Some(val)),
+            Frame::NullBulkString => Ok(None),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Get `count` random fields of the hash stored at `key`.
+    ///
+    /// A positive `count` gets distinct fields, at most every field of the
+    /// hash, while a negative one gets `-count` fields which may repeat.
+    #[tracing::instrument(skip(self))]
+    pub async fn hrandfield_count(&mut self, key: &str, count: i64) -> Result<Vec<Bytes>> {
+        let frame = HRandFieldCmd::new(key).count(count).into_frame()?;
+        self.members_cmd(frame).await
+    }
+
+    /// Get `count` random fields of the hash stored at `key`, along with their
+    /// values.
+    ///
+    /// `count` is like for [`Client::hrandfield_count`].
+    #[tracing::instrument(skip(self))]
+    pub async fn hrandfield_with_values(
+        &mut self,
+        key: &str,
+        count: i64,
+    ) -> Result<Vec<(Bytes, Bytes)>> {
+        let frame = HRandFieldCmd::new(key)
+            .count(count)
+            .with_values()
+            .into_frame()?;
+        let reply = self.members_cmd(frame).await?;
+        reply
+            .chunks(2)
+            .map(|pair| match pair {
+                [field, value] => Ok((field.clone(), value.clone())),
+                _ => Err(Error::Response("missing value in reply".to_string())),
+            })
+            .collect()
+    }
+
+    /// Get the values of the hash stored at `key`.
+    #[tracing::instrument(skip(self))]
+    pub async fn hvals(&mut self, key: &str) -> Result<Vec<Bytes>> {
+        let frame = HValsCmd::new(key).into_frame()?;
+        self.members_cmd(frame).await
+    }
+
+    /// Set a time to live on `fields` of the hash stored at `key`, in
+    /// seconds, if `condition` is met.
+    ///
+    /// Returns, for each field, `-2` if it does not exist, `0` if the
+    /// condition is not met, `1` if the time to live was set, or `2` if the
+    /// field was deleted as the time to live is not positive.
+    #[tracing::instrument(skip(self))]
+    pub async fn hexpire(
+        &mut self,
+        key: &str,
+        seconds: i64,
+        condition: Option<ExpireCondition>,
+        fields: Vec<Bytes>,
+    ) -> Result<Vec<i64>> {
+        let frame = HExpireCmd::new(key, seconds, condition, fields).into_frame()?;
+        self.integers_cmd(frame).await
+    }
+
+    /// Get the remaining time to live of `fields` of the hash stored at `key`,
+    /// in seconds.
+    ///
+    /// Returns, for each field, `-2` if it does not exist, `-1` if it has no
+    /// time to live, or the time to live otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn httl(&mut self, key: &str, fields: Vec<Bytes>) -> Result<Vec<i64>> {
+        let frame = HTtlCmd::new(key, fields).into_frame()?;
+        self.integers_cmd(frame).await
+    }
+
+    /// Remove the time to live of `fields` of the hash stored at `key`.
+    ///
+    /// Returns, for each field, `-2` if it does not exist, `-1` if it has no
+    /// time to live, or `1` if its time to live was removed.
+    #[tracing::instrument(skip(self))]
+    pub async fn hpersist(&mut self, key: &str, fields: Vec<Bytes>) -> Result<Vec<i64>> {
+        let frame = HPersistCmd::new(key, fields).into_frame()?;
+        self.integers_cmd(frame).await
+    }
+
+    /// Iterate the fields of the hash stored at `key`, along with their
+    /// values, starting at `cursor`.
+    ///
+    /// Returns the cursor to continue from, which is `0` once the iteration is
+    /// complete, and the visited fields matching `pattern`.
+    #[tracing::instrument(skip(self))]
+    pub async fn hscan(
+        &mut self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<String>,
+        count: Option<u64>,
+    ) -> Result<(u64, Vec<(Bytes, Bytes)>)> {
+        let frame = HScanCmd::new(key, cursor, pattern, count).into_frame()?;
+        let (cursor, page) = self.scan_cmd(frame).await?;
+        let fields = page
+            .chunks(2)
+            .map(|pair| match pair {
+                [field, value] => Ok((field.clone(), value.clone())),
+                _ => Err(Error::Response("missing value in reply".to_string())),
+            })
+            .collect::<Result<_>>()?;
+        Ok((cursor, fields))
+    }
+
+    /// Iterate the members of the set stored at `key`, starting at `cursor`.
+    ///
+    /// Returns the cursor to continue from, which is `0` once the iteration is
+    /// complete, and the visited members matching `pattern`.
+    #[tracing::instrument(skip(self))]
+    pub async fn sscan(
+        &mut self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<String>,
+        count: Option<u64>,
+    ) -> Result<(u64, Vec<Bytes>)> {
+        let frame = SScanCmd::new(key, cursor, pattern, count).into_frame()?;
+        self.scan_cmd(frame).await
+    }
+
+    /// Iterate the members of the sorted set stored at `key`, along with their
+    /// scores, starting at `cursor`.
+    ///
+    /// Returns the cursor to continue from, which is `0` once the iteration is
+    /// complete, and the visited members matching `pattern`.
+    #[tracing::instrument(skip(self))]
+    pub async fn zscan(
+        &mut self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<String>,
+        count: Option<u64>,
+    ) -> Result<(u64, Vec<(Bytes, f64)>)> {
+        let frame = ZScanCmd::new(key, cursor, pattern, count).into_frame()?;
+        let (cursor, page) = self.scan_cmd(frame).await?;
+        let members = page
+            .chunks(2)
+            .map(|pair| match pair {
+                [member, score] => Ok((member.clone(), parse_float(score)?)),
+                _ => Err(Error::Response("missing score in reply".to_string())),
+            })
+            .collect::<Result<_>>()?;
+        Ok((cursor, members))
+    }
+
+    /// The core logic of the scan commands, replying with the next cursor and
+    /// a page of bulk strings.
+    async fn scan_cmd(&mut self, frame: Frame) -> Result<(u64, Vec<Bytes>)> {
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(reply) => match <[Frame; 2]>::try_from(reply) {
+                Ok([Frame::BulkString(cursor), Frame::Array(page)]) => {
+                    let cursor = atoi::atoi(&cursor)
+                        .ok_or_else(|| Error::Response("invalid cursor in reply".to_string()))?;
+                    let page = page
+                        .into_iter()
+                        .map(|item| match item {
+                            Frame::BulkString(val) => Ok(val),
+                            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+                        })
+                        .collect::<Result<_>>()?;
+                    Ok((cursor, page))
+                }
+                Ok(reply) => Err(Error::Response(format!("unexpected frame: {reply:?}"))),
+                Err(reply) => Err(Error::Response(format!("unexpected frame: {reply:?}"))),
+            },
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+
+    /// Start a batch of requests, sent in a single round trip.
+    pub fn pipeline(&mut self) -> Pipeline<'_> {
+        Pipeline::new(self)
+    }
+
+    /// Start a transaction, whose requests are applied atomically.
+    pub fn transaction(&mut self) -> Transaction<'_> {
+        Transaction::new(self)
+    }
+
+    /// Send a command made of `args`, the command name followed by its
+    /// arguments, and return the raw response.
+    ///
+    /// This reaches commands without a dedicated method. Error replies are
+    /// returned as [`Error::Response`].
+    #[tracing::instrument(skip(self))]
+    pub async fn send_command(&mut self, args: &[Bytes]) -> Result<Frame> {
+        let frame = Frame::Array(args.iter().cloned().map(Frame::BulkString).collect());
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        self.read_response().await
+    }
+
+    /// Write every request with a single flush, then read their responses.
+    ///
+    /// Error replies are kept as frames, at the position of their request.
+    pub(crate) async fn send_batch(&mut self, requests: &[Frame]) -> Result<Vec<Frame>> {
+        debug!(?requests);
+        self.connection.write_frames(requests).await?;
+
+        let mut responses = Vec::with_capacity(requests.len());
+        for _ in requests {
+            match self.connection.read_frame().await? {
+                Some(frame) => responses.push(frame),
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::ConnectionReset,
+                        "connection reset by server",
+                    )
+                    .into())
+                }
+            }
+        }
+        debug!(?responses);
+        Ok(responses)
+    }
+
+    async fn read_response(&mut self) -> Result<Frame> {
+        let response = self.connection.read_frame().await?;
+        debug!(?response);
+        match response {
+            Some(Frame::SimpleError(msg)) => Err(Error::Response(msg)),
+            Some(frame) => Ok(frame),
+            None => {
+                // Receiving `None` indicates the connection has been closed by the server
+                // without sending a frame. This is unexpected and treated as an `Error::Io`.
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::ConnectionReset,
+                    "connection reset by server",
+                )
+                .into())
+            }
+        }
+    }
+}
+
+/// Parse a float reply, rendered as a string by the server.
+fn parse_float(val: &Bytes) -> Result<f64> {
+    std::str::from_utf8(val)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| Error::Response(format!("invalid float reply: {val:?}")))
 }
@@ -1,3 +1,57 @@
-//! Clients module. Currently only contains a simple [`Client`] struct.
+//! Clients module. Contains a simple [`Client`] struct, and the [`Pipeline`]
+//! and [`Transaction`] batching its requests.
+
+/// Implement the methods queuing requests on a batch of requests, which has a
+/// `queue` method taking a command.
+macro_rules! queue_methods {
+    () => {
+        /// Queue a `PING`.
+        pub fn ping(self, msg: Option<::bytes::Bytes>) -> Self {
+            self.queue($crate::cmd::PingCmd::new(msg))
+        }
+
+        /// Queue getting the value of `key`.
+        pub fn get(self, key: &str) -> Self {
+            self.queue($crate::cmd::GetCmd::new(key))
+        }
+
+        /// Queue setting `key` to hold `val`.
+        pub fn set(self, key: &str, val: ::bytes::Bytes) -> Self {
+            self.queue($crate::cmd::SetCmd::new(key, val, None))
+        }
+
+        /// Queue pushing `values` to the head of the list stored at `key`.
+        pub fn lpush(self, key: &str, values: Vec<::bytes::Bytes>) -> Self {
+            self.queue($crate::cmd::LPushCmd::new(key, values))
+        }
+
+        /// Queue pushing `values` to the tail of the list stored at `key`.
+        pub fn rpush(self, key: &str, values: Vec<::bytes::Bytes>) -> Self {
+            self.queue($crate::cmd::RPushCmd::new(key, values))
+        }
+
+        /// Queue adding `members` to the set stored at `key`.
+        pub fn sadd(self, key: &str, members: Vec<::bytes::Bytes>) -> Self {
+            self.queue($crate::cmd::SAddCmd::new(key, members))
+        }
+
+        /// Queue setting `fields` in the hash stored at `key`.
+        pub fn hset(self, key: &str, fields: Vec<(::bytes::Bytes, ::bytes::Bytes)>) -> Self {
+            self.queue($crate::cmd::HSetCmd::new(key, fields))
+        }
+
+        /// Queue getting `field` of the hash stored at `key`.
+        pub fn hget(self, key: &str, field: ::bytes::Bytes) -> Self {
+            self.queue($crate::cmd::HGetCmd::new(key, field))
+        }
+    };
+}
+
 pub mod client;
 pub use client::Client;
+
+pub mod pipeline;
+pub use pipeline::Pipeline;
+
+pub mod transaction;
+pub use transaction::Transaction;
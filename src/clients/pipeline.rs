@@ -0,0 +1,54 @@
+//! Batching of several requests into a single round trip.
+use crate::{cmd::Command, Client, Error, Frame, Result};
+use tokio::net::TcpStream;
+
+/// A batch of requests, sent together once [`Pipeline::execute`] is called.
+///
+/// Created with [`Client::pipeline`]. Requests are written with a single
+/// flush, then every response is read back in order. As the server replies
+/// to each request on its own, an error replied to one request does not
+/// prevent the following ones from being applied.
+#[derive(Debug)]
+pub struct Pipeline<'a> {
+    /// The client the requests are sent through.
+    client: &'a mut Client<TcpStream>,
+    /// Frames of the queued requests.
+    requests: Vec<Frame>,
+    /// The first error encoding a queued request, reported by `execute`.
+    error: Option<Error>,
+}
+
+impl<'a> Pipeline<'a> {
+    pub(crate) fn new(client: &'a mut Client<TcpStream>) -> Self {
+        Self {
+            client,
+            requests: vec![],
+            error: None,
+        }
+    }
+
+    /// Queue the request of `cmd`.
+    fn queue(mut self, cmd: impl Command) -> Self {
+        match cmd.into_frame() {
+            Ok(frame) => self.requests.push(frame),
+            Err(err) => {
+                self.error.get_or_insert(err);
+            }
+        }
+        self
+    }
+
+    queue_methods!();
+
+    /// Send the queued requests, and read back their responses.
+    ///
+    /// The responses are returned in the order the requests were queued in.
+    /// Error replies are returned as [`Frame::SimpleError`] at the position of
+    /// the failed request, instead of failing the whole batch.
+    pub async fn execute(self) -> Result<Vec<Frame>> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        self.client.send_batch(&self.requests).await
+    }
+}
@@ -0,0 +1,79 @@
+//! Transactions applying several requests atomically.
+use crate::{
+    cmd::{Command, ExecCmd, MultiCmd},
+    Client, Error, Frame, Result,
+};
+use tokio::net::TcpStream;
+
+/// A batch of requests, applied atomically once [`Transaction::exec`] is
+/// called.
+///
+/// Created with [`Client::transaction`]. The requests are wrapped in `MULTI`
+/// and `EXEC`, and sent in a single round trip. If any request fails to be
+/// queued by the server, none of them is applied.
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    /// The client the requests are sent through.
+    client: &'a mut Client<TcpStream>,
+    /// Frames of the queued requests.
+    requests: Vec<Frame>,
+    /// The first error encoding a queued request, reported by `exec`.
+    error: Option<Error>,
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) fn new(client: &'a mut Client<TcpStream>) -> Self {
+        Self {
+            client,
+            requests: vec![],
+            error: None,
+        }
+    }
+
+    /// Queue the request of `cmd`.
+    fn queue(mut self, cmd: impl Command) -> Self {
+        match cmd.into_frame() {
+            Ok(frame) => self.requests.push(frame),
+            Err(err) => {
+                self.error.get_or_insert(err);
+            }
+        }
+        self
+    }
+
+    queue_methods!();
+
+    /// Apply the queued requests atomically, and read back their responses.
+    ///
+    /// The responses are returned in the order the requests were queued in,
+    /// with error replies as [`Frame::SimpleError`] at the position of the
+    /// failed request. If the server discarded the transaction because a
+    /// request failed to be queued, [`Error::ExecAbort`] is returned.
+    pub async fn exec(self) -> Result<Vec<Frame>> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let mut requests = Vec::with_capacity(self.requests.len() + 2);
+        requests.push(MultiCmd::new().into_frame()?);
+        requests.extend(self.requests);
+        requests.push(ExecCmd::new().into_frame()?);
+
+        let mut responses = self.client.send_batch(&requests).await?;
+        let reply = responses.pop();
+        // `MULTI` replies with `OK`, then every queued request with `QUEUED`.
+        // Requests which failed to be queued abort the transaction, which is
+        // reported by the reply to `EXEC`.
+        for response in responses {
+            match response {
+                Frame::SimpleString(_) | Frame::SimpleError(_) => {}
+                frame => return Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+            }
+        }
+        match reply {
+            Some(Frame::Array(results)) => Ok(results),
+            Some(Frame::SimpleError(msg)) if msg.starts_with("EXECABORT") => Err(Error::ExecAbort),
+            Some(Frame::SimpleError(msg)) => Err(Error::Response(msg)),
+            frame => Err(Error::Response(format!("unexpected frame: {frame:?}"))),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Implementation of the `AUTH` command.
+use crate::{
+    parse::Parse, server::ServerState, Connection, ConnectionStream, Error, Frame, Result,
+};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Authenticate the connection with the password configured on the server.
+///
+/// Until a connection is authenticated, every other command is rejected with a
+/// `NOAUTH` error. The only user is `default`, so giving a `username` is
+/// optional.
+#[derive(PartialEq, Eq)]
+pub struct AuthCmd {
+    /// The user to authenticate as.
+    username: Option<String>,
+    /// The password of the user.
+    password: String,
+}
+
+impl AuthCmd {
+    /// Creates a new [`AuthCmd`] command.
+    pub fn new(username: Option<String>, password: impl ToString) -> Self {
+        Self {
+            username,
+            password: password.to_string(),
+        }
+    }
+
+    /// Get the username.
+    pub fn username(&self) -> Option<&str> {
+        self.username.as_deref()
+    }
+
+    /// Parse an [`AuthCmd`] instance from a received frame.
+    ///
+    /// The `AUTH` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// AUTH [username] password
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Self> {
+        let first = parse.next_string()?;
+        match parse.next_string() {
+            Ok(password) => Ok(Self::new(Some(first), password)),
+            Err(Error::EndOfStream) => Ok(Self::new(None, first)),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Apply the `AUTH` command, checking the credentials against the
+    /// password of `server`.
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn apply<S: ConnectionStream>(
+        self,
+        server: &ServerState,
+        dst: &mut Connection<S>,
+    ) -> Result<()> {
+        server.authenticate(self.username.as_deref(), &self.password)?;
+        let response = Frame::SimpleString("OK".to_string());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Creates the request frame of the command.
+    pub(crate) fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("auth"))?;
+        if let Some(username) = self.username {
+            frame.push_bulk(Bytes::from(username))?;
+        }
+        frame.push_bulk(Bytes::from(self.password))?;
+        Ok(frame)
+    }
+}
+
+// The password is never printed, so it does not end up in the logs.
+impl std::fmt::Debug for AuthCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthCmd")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+impl Display for AuthCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.username {
+            Some(username) => write!(f, "AUTH {username} <redacted>"),
+            None => write!(f, "AUTH <redacted>"),
+        }
+    }
+}
@@ -0,0 +1,750 @@
+//! Implementation of the `SETBIT`, `GETBIT`, `BITCOUNT` and `BITFIELD`
+//! commands.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::{Bytes, BytesMut};
+use std::fmt::Display;
+use tracing::debug;
+
+/// Bit offsets are limited to strings of 512MB, like Redis does.
+const MAX_BIT_OFFSET: u64 = 512 * 1024 * 1024 * 8;
+
+/// Parse a bit offset, which must be within the maximum string length.
+fn parse_offset(parse: &mut Parse) -> Result<u64> {
+    parse
+        .next_int_signed()
+        .ok()
+        .and_then(|offset| u64::try_from(offset).ok())
+        .filter(|offset| *offset < MAX_BIT_OFFSET)
+        .ok_or(Error::BitOffsetOutOfRange)
+}
+
+/// Set the bit at `offset` in the string stored at `key`.
+///
+/// Bits are numbered from the most significant bit of the first byte. The
+/// string is grown with zero bytes as needed, and created if the key does not
+/// exist. Replies with the previous value of the bit.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SetBitCmd {
+    key: String,
+    offset: u64,
+    bit: bool,
+}
+
+impl SetBitCmd {
+    /// Creates a new [`SetBitCmd`] command.
+    pub fn new(key: impl ToString, offset: u64, bit: bool) -> Self {
+        Self {
+            key: key.to_string(),
+            offset,
+            bit,
+        }
+    }
+
+    /// Returns a reference to the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the offset of the bit.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Get the value the bit is set to.
+    pub fn bit(&self) -> bool {
+        self.bit
+    }
+}
+
+impl Command for SetBitCmd {
+    /// Parse a [`SetBitCmd`] instance from a received frame.
+    ///
+    /// The `SETBIT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SETBIT key offset value
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let offset = parse_offset(parse)?;
+        let bit = match parse.next_int_signed() {
+            Ok(0) => false,
+            Ok(1) => true,
+            _ => return Err(Error::BitOutOfRange),
+        };
+        Ok(Self { key, offset, bit })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let prev = db.setbit(&self.key, self.offset as usize, self.bit)?;
+        let response = Frame::Integer(prev as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("setbit"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_int(self.offset as i64)?;
+        frame.push_int(self.bit as i64)?;
+        Ok(frame)
+    }
+}
+
+/// Get the bit at `offset` in the string stored at `key`.
+///
+/// Bits past the end of the string, or of a missing key, are `0`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetBitCmd {
+    key: String,
+    offset: u64,
+}
+
+impl GetBitCmd {
+    /// Creates a new [`GetBitCmd`] command.
+    pub fn new(key: impl ToString, offset: u64) -> Self {
+        Self {
+            key: key.to_string(),
+            offset,
+        }
+    }
+
+    /// Returns a reference to the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the offset of the bit.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl Command for GetBitCmd {
+    /// Parse a [`GetBitCmd`] instance from a received frame.
+    ///
+    /// The `GETBIT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// GETBIT key offset
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let offset = parse_offset(parse)?;
+        Ok(Self { key, offset })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let bit = db.getbit(&self.key, self.offset as usize)?;
+        let response = Frame::Integer(bit as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("getbit"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_int(self.offset as i64)?;
+        Ok(frame)
+    }
+}
+
+/// Count the bits set in the string stored at `key`.
+///
+/// The count can be restricted to an inclusive range of bytes, whose negative
+/// offsets count from the end of the string. A missing key counts as an empty
+/// string.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BitCountCmd {
+    key: String,
+    range: Option<(i64, i64)>,
+}
+
+impl BitCountCmd {
+    /// Creates a new [`BitCountCmd`] command, counting the bits of the whole
+    /// string.
+    pub fn new(key: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+            range: None,
+        }
+    }
+
+    /// Restrict the count to the bytes from `start` to `end`, inclusive.
+    pub fn range(mut self, start: i64, end: i64) -> Self {
+        self.range = Some((start, end));
+        self
+    }
+
+    /// Returns a reference to the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Command for BitCountCmd {
+    /// Parse a [`BitCountCmd`] instance from a received frame.
+    ///
+    /// The `BITCOUNT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// BITCOUNT key [start end]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let range = match parse.next_int_signed() {
+            Ok(start) => match parse.next_int_signed() {
+                Ok(end) => Some((start, end)),
+                Err(Error::EndOfStream) => return Err(Error::Syntax),
+                Err(err) => return Err(err),
+            },
+            Err(Error::EndOfStream) => None,
+            Err(err) => return Err(err),
+        };
+        if parse.remaining() > 0 {
+            return Err(Error::Syntax);
+        }
+        Ok(Self { key, range })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let count = db.bitcount(&self.key, self.range)?;
+        let response = Frame::Integer(count as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("bitcount"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        if let Some((start, end)) = self.range {
+            frame.push_int(start)?;
+            frame.push_int(end)?;
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for BitCountCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "BITCOUNT {}", self.key)?;
+        if let Some((start, end)) = self.range {
+            write!(f, " {start} {end}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Type of a `BITFIELD` field, a signed or unsigned integer of a given number
+/// of bits.
+///
+/// Signed fields are up to 64 bits wide, and unsigned ones up to 63 bits, so
+/// their values fit in an `i64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitFieldType {
+    /// A two's complement signed integer, like `i8`.
+    Signed(u8),
+    /// An unsigned integer, like `u8`.
+    Unsigned(u8),
+}
+
+impl BitFieldType {
+    /// Parse a type such as `i16` or `u8`.
+    fn parse(s: &str) -> Result<Self> {
+        let bits = s.get(1..).and_then(|bits| bits.parse().ok());
+        match (s.as_bytes().first(), bits) {
+            (Some(b'i' | b'I'), Some(bits @ 1..=64)) => Ok(Self::Signed(bits)),
+            (Some(b'u' | b'U'), Some(bits @ 1..=63)) => Ok(Self::Unsigned(bits)),
+            _ => Err(Error::InvalidBitFieldType),
+        }
+    }
+
+    /// Get the number of bits of the field.
+    pub fn bits(self) -> u8 {
+        match self {
+            Self::Signed(bits) | Self::Unsigned(bits) => bits,
+        }
+    }
+
+    /// Interpret the low bits of `raw` as a value of this type.
+    fn decode(self, raw: u64) -> i64 {
+        let shift = 64 - u32::from(self.bits());
+        match self {
+            Self::Signed(_) => ((raw << shift) as i64) >> shift,
+            Self::Unsigned(_) => ((raw << shift) >> shift) as i64,
+        }
+    }
+}
+
+impl Display for BitFieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Signed(bits) => write!(f, "i{bits}"),
+            Self::Unsigned(bits) => write!(f, "u{bits}"),
+        }
+    }
+}
+
+/// An operation of a `BITFIELD` command on a single field.
+///
+/// Values which do not fit in the field wrap around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitFieldOp {
+    /// Get the value of the field.
+    Get {
+        /// The type of the field.
+        ty: BitFieldType,
+        /// The bit the field starts at.
+        offset: u64,
+    },
+    /// Set the field to `value`, replying with its previous value.
+    Set {
+        /// The type of the field.
+        ty: BitFieldType,
+        /// The bit the field starts at.
+        offset: u64,
+        /// The value to set.
+        value: i64,
+    },
+    /// Increment the field by `increment`, replying with its new value.
+    IncrBy {
+        /// The type of the field.
+        ty: BitFieldType,
+        /// The bit the field starts at.
+        offset: u64,
+        /// The amount to add, which may be negative.
+        increment: i64,
+    },
+}
+
+impl BitFieldOp {
+    /// Get the type and offset of the field.
+    fn field(&self) -> (BitFieldType, u64) {
+        match *self {
+            Self::Get { ty, offset }
+            | Self::Set { ty, offset, .. }
+            | Self::IncrBy { ty, offset, .. } => (ty, offset),
+        }
+    }
+
+    /// Returns `true` if the operation only reads its field.
+    pub(crate) fn is_get(&self) -> bool {
+        matches!(self, Self::Get { .. })
+    }
+
+    /// Read the current value of the field from `data`.
+    pub(crate) fn read(&self, data: &[u8]) -> i64 {
+        let (ty, offset) = self.field();
+        ty.decode(read_bits(data, offset, ty.bits()))
+    }
+
+    /// Length the string has to grow to for the operation to write its
+    /// field, `0` if it only reads it.
+    pub(crate) fn written_len(&self) -> usize {
+        if self.is_get() {
+            return 0;
+        }
+        let (ty, offset) = self.field();
+        (offset + u64::from(ty.bits())).div_ceil(8) as usize
+    }
+
+    /// Apply the operation to `data`, returning the value to reply with.
+    pub(crate) fn apply(&self, data: &mut BytesMut) -> i64 {
+        let (ty, offset) = self.field();
+        let current = self.read(data);
+        let (stored, reply) = match *self {
+            Self::Get { .. } => return current,
+            Self::Set { value, .. } => (ty.decode(value as u64), current),
+            Self::IncrBy { increment, .. } => {
+                let value = ty.decode(current.wrapping_add(increment) as u64);
+                (value, value)
+            }
+        };
+        write_bits(data, offset, ty.bits(), stored as u64);
+        reply
+    }
+}
+
+/// Read the `bits` wide field starting at bit `offset` of `data`, from its
+/// most significant bit. Bits past the end of `data` are `0`.
+fn read_bits(data: &[u8], offset: u64, bits: u8) -> u64 {
+    (offset..offset + u64::from(bits)).fold(0, |raw, pos| {
+        let bit = data
+            .get((pos / 8) as usize)
+            .is_some_and(|byte| byte & (0x80 >> (pos % 8)) != 0);
+        raw << 1 | u64::from(bit)
+    })
+}
+
+/// Write the low `bits` of `raw` to the field starting at bit `offset` of
+/// `data`, growing it with zero bytes as needed.
+fn write_bits(data: &mut BytesMut, offset: u64, bits: u8, raw: u64) {
+    let end = (offset + u64::from(bits)).div_ceil(8) as usize;
+    if data.len() < end {
+        data.resize(end, 0);
+    }
+    for (i, pos) in (offset..offset + u64::from(bits)).enumerate() {
+        let (byte, mask) = ((pos / 8) as usize, 0x80 >> (pos % 8));
+        if raw >> (u32::from(bits) - 1 - i as u32) & 1 == 1 {
+            data[byte] |= mask;
+        } else {
+            data[byte] &= !mask;
+        }
+    }
+}
+
+/// Parse the offset of a field of type `ty`.
+///
+/// An offset prefixed with `#` is multiplied by the width of the field, so
+/// `#2` is the third field of that type.
+fn parse_field_offset(parse: &mut Parse, ty: BitFieldType) -> Result<u64> {
+    let offset = parse.next_string()?;
+    let (multiplier, offset) = match offset.strip_prefix('#') {
+        Some(offset) => (u64::from(ty.bits()), offset),
+        None => (1, offset.as_str()),
+    };
+    offset
+        .parse::<u64>()
+        .ok()
+        .and_then(|offset| offset.checked_mul(multiplier))
+        .filter(|offset| offset + u64::from(ty.bits()) <= MAX_BIT_OFFSET)
+        .ok_or(Error::BitOffsetOutOfRange)
+}
+
+/// Get, set or increment integer fields of arbitrary width at arbitrary bit
+/// offsets in the string stored at `key`.
+///
+/// The string is grown with zero bytes as needed, and created if the key does
+/// not exist, unless only `GET` operations are given. Replies with an array
+/// holding the result of every operation, in order.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BitFieldCmd {
+    key: String,
+    ops: Vec<BitFieldOp>,
+}
+
+impl BitFieldCmd {
+    /// Creates a new [`BitFieldCmd`] command.
+    pub fn new(key: impl ToString, ops: Vec<BitFieldOp>) -> Self {
+        Self {
+            key: key.to_string(),
+            ops,
+        }
+    }
+
+    /// Returns a reference to the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the operations.
+    pub fn ops(&self) -> &[BitFieldOp] {
+        &self.ops
+    }
+}
+
+impl Command for BitFieldCmd {
+    /// Parse a [`BitFieldCmd`] instance from a received frame.
+    ///
+    /// The `BITFIELD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// BITFIELD key [GET type offset | SET type offset value |
+    ///   INCRBY type offset increment ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let mut ops = vec![];
+        loop {
+            let op = match parse.next_string() {
+                Ok(op) => op.to_uppercase(),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            };
+            if !matches!(op.as_str(), "GET" | "SET" | "INCRBY") {
+                return Err(Error::Syntax);
+            }
+            let ty = BitFieldType::parse(&parse.next_string()?)?;
+            let offset = parse_field_offset(parse, ty)?;
+            ops.push(match op.as_str() {
+                "GET" => BitFieldOp::Get { ty, offset },
+                "SET" => BitFieldOp::Set {
+                    ty,
+                    offset,
+                    value: parse.next_int_signed()?,
+                },
+                _ => BitFieldOp::IncrBy {
+                    ty,
+                    offset,
+                    increment: parse.next_int_signed()?,
+                },
+            });
+        }
+        Ok(Self { key, ops })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let values = db.bitfield(&self.key, &self.ops)?;
+        let response = Frame::Array(values.into_iter().map(Frame::Integer).collect());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("bitfield"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        for op in self.ops {
+            let (name, argument) = match op {
+                BitFieldOp::Get { .. } => ("get", None),
+                BitFieldOp::Set { value, .. } => ("set", Some(value)),
+                BitFieldOp::IncrBy { increment, .. } => ("incrby", Some(increment)),
+            };
+            let (ty, offset) = op.field();
+            frame.push_bulk(Bytes::from(name))?;
+            frame.push_bulk(Bytes::from(ty.to_string()))?;
+            frame.push_int(offset as i64)?;
+            if let Some(argument) = argument {
+                frame.push_int(argument)?;
+            }
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for BitFieldCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "BITFIELD {}", self.key)?;
+        for op in &self.ops {
+            match op {
+                BitFieldOp::Get { ty, offset } => write!(f, " GET {ty} {offset}")?,
+                BitFieldOp::Set { ty, offset, value } => write!(f, " SET {ty} {offset} {value}")?,
+                BitFieldOp::IncrBy {
+                    ty,
+                    offset,
+                    increment,
+                } => write!(f, " INCRBY {ty} {offset} {increment}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::ListEnd, CommandVariant, Connection, Db};
+
+    #[tokio::test]
+    async fn test_bit_operations() {
+        let db = Db::default();
+
+        // Setting a bit past the end grows the string with zero bytes.
+        assert!(!db.setbit("foo", 17, true).unwrap());
+        assert_eq!(
+            db.get("foo").unwrap(),
+            Some(Bytes::from_static(&[0, 0, 0x40]))
+        );
+        assert!(db.getbit("foo", 17).unwrap());
+        assert!(!db.getbit("foo", 16).unwrap());
+        assert!(!db.getbit("foo", 1000).unwrap());
+        assert!(db.setbit("foo", 17, false).unwrap());
+
+        // "foobar" has 26 bits set, 6 in "o" and 6 in "ba".
+        db.set("bar".to_string(), Bytes::from("foobar"), None)
+            .unwrap();
+        let stream = tokio_test::io::Builder::new()
+            .write(b":26\r\n")
+            .write(b":6\r\n")
+            .write(b":0\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        for cmd in [
+            BitCountCmd::new("bar"),
+            BitCountCmd::new("bar").range(1, 1),
+            BitCountCmd::new("missing").range(0, -1),
+        ] {
+            cmd.apply(&db, &mut conn).await.unwrap();
+        }
+        assert_eq!(db.bitcount("bar", Some((-3, -2))).unwrap(), 6);
+
+        db.push("list", vec![Bytes::from("a")], ListEnd::Left)
+            .unwrap();
+        assert!(matches!(db.getbit("list", 0), Err(Error::WrongType)));
+        assert!(matches!(db.setbit("list", 0, true), Err(Error::WrongType)));
+        assert!(matches!(db.bitcount("list", None), Err(Error::WrongType)));
+    }
+
+    #[tokio::test]
+    async fn test_bitfield_set_get_and_incrby_wrap() {
+        let db = Db::default();
+        let u8_at = |offset| BitFieldOp::Get {
+            ty: BitFieldType::Unsigned(8),
+            offset,
+        };
+
+        // Reading a missing key does not create it.
+        assert_eq!(db.bitfield("foo", &[u8_at(0)]).unwrap(), vec![0]);
+        assert_eq!(db.get("foo").unwrap(), None);
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*2\r\n:0\r\n:200\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        let set = BitFieldOp::Set {
+            ty: BitFieldType::Unsigned(8),
+            offset: 4,
+            value: 200,
+        };
+        BitFieldCmd::new("foo", vec![set, u8_at(4)])
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get("foo").unwrap(),
+            Some(Bytes::from_static(&[0x0c, 0x80]))
+        );
+
+        // Increments wrap around, for unsigned and signed fields alike.
+        let incr = |ty, increment| BitFieldOp::IncrBy {
+            ty,
+            offset: 4,
+            increment,
+        };
+        let ops = [
+            incr(BitFieldType::Unsigned(8), 100),
+            incr(BitFieldType::Signed(8), 100),
+            incr(BitFieldType::Signed(8), -20),
+        ];
+        assert_eq!(db.bitfield("foo", &ops).unwrap(), vec![44, -112, 124]);
+
+        db.push("list", vec![Bytes::from("a")], ListEnd::Left)
+            .unwrap();
+        assert!(matches!(
+            db.bitfield("list", &[u8_at(0)]),
+            Err(Error::WrongType)
+        ));
+    }
+
+    #[test]
+    fn test_bitfield_parse() {
+        let frame = |args: &[&str]| {
+            let mut frames = vec![Frame::BulkString(Bytes::from("BITFIELD"))];
+            frames.extend(
+                args.iter()
+                    .map(|arg| Frame::BulkString(Bytes::from(arg.to_string()))),
+            );
+            CommandVariant::from_frame(Frame::Array(frames))
+        };
+
+        // `#` offsets are multiplied by the width of the field.
+        let CommandVariant::BitField(cmd) =
+            frame(&["foo", "get", "i16", "#2", "INCRBY", "u4", "3", "-1"]).unwrap()
+        else {
+            panic!("not a BITFIELD command");
+        };
+        assert_eq!(
+            cmd.ops(),
+            [
+                BitFieldOp::Get {
+                    ty: BitFieldType::Signed(16),
+                    offset: 32,
+                },
+                BitFieldOp::IncrBy {
+                    ty: BitFieldType::Unsigned(4),
+                    offset: 3,
+                    increment: -1,
+                },
+            ]
+        );
+
+        for (args, expected) in [
+            (&["foo", "GET", "u64", "0"][..], "Invalid bitfield type"),
+            (&["foo", "GET", "x8", "0"], "Invalid bitfield type"),
+            (&["foo", "GET", "u8", "-1"], "bit offset"),
+            (&["foo", "GET", "u8", "#"], "bit offset"),
+            (&["foo", "FOO", "u8", "0"], "syntax error"),
+        ] {
+            let err = frame(args).unwrap_err();
+            assert!(err.to_string().starts_with(expected), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_setbit_rejects_invalid_arguments() {
+        for (offset, bit, expected) in [
+            ("-1", "1", "bit offset"),
+            ("4294967296", "1", "bit offset"),
+            ("0", "2", "bit is"),
+        ] {
+            let frame = Frame::Array(vec![
+                Frame::BulkString(Bytes::from("SETBIT")),
+                Frame::BulkString(Bytes::from("foo")),
+                Frame::BulkString(Bytes::from(offset)),
+                Frame::BulkString(Bytes::from(bit)),
+            ]);
+            let err = CommandVariant::from_frame(frame).unwrap_err();
+            assert!(err.to_string().starts_with(expected), "{err}");
+        }
+    }
+}
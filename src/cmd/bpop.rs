@@ -0,0 +1,356 @@
+//! Implementation of the `BLPOP` and `BRPOP` commands.
+use crate::{
+    db::ListEnd, parse::Parse, Connection, ConnectionStream, Db, Error, Frame, Result, Shutdown,
+};
+use bytes::Bytes;
+use std::{fmt::Display, future::pending, sync::Arc, time::Duration};
+use tokio::{sync::Notify, time::Instant};
+use tracing::debug;
+
+/// Pop a value from the head of the first non empty list stored at one of
+/// `keys`, blocking until a value is pushed if they are all empty.
+///
+/// Replies with an array of the key and the popped value, or with a null
+/// array once `timeout` is reached. A zero `timeout` blocks forever. Inside a
+/// transaction, the command never blocks.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BLPopCmd {
+    /// The keys of the lists, checked in order.
+    keys: Vec<String>,
+    /// Maximum time to block for. A zero timeout blocks forever.
+    timeout: Duration,
+    /// Whether to block at all.
+    block: bool,
+}
+
+/// Pop a value from the tail of the first non empty list stored at one of
+/// `keys`, blocking until a value is pushed if they are all empty.
+///
+/// Replies like [`BLPopCmd`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct BRPopCmd {
+    /// The keys of the lists, checked in order.
+    keys: Vec<String>,
+    /// Maximum time to block for. A zero timeout blocks forever.
+    timeout: Duration,
+    /// Whether to block at all.
+    block: bool,
+}
+
+/// The outcome of waiting for a value to be pushed.
+enum Wait {
+    /// A value was popped from the list at the key.
+    Popped(String, Bytes),
+    /// A value was pushed to one of the lists, which may be popped now.
+    Pushed,
+    /// The timeout was reached.
+    TimedOut,
+    /// The server is shutting down.
+    Shutdown,
+}
+
+impl BLPopCmd {
+    /// Creates a new [`BLPopCmd`] command.
+    pub fn new(keys: Vec<String>, timeout: Duration) -> Self {
+        Self {
+            keys,
+            timeout,
+            block: true,
+        }
+    }
+
+    /// Get the keys.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Get the timeout.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Make the command reply right away if the lists are empty.
+    pub(crate) fn without_blocking(mut self) -> Self {
+        self.block = false;
+        self
+    }
+
+    /// Parse a [`BLPopCmd`] instance from a received frame.
+    ///
+    /// The `BLPOP` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// BLPOP key [key ...] timeout
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Self> {
+        let (keys, timeout) = parse_bpop(parse)?;
+        Ok(Self::new(keys, timeout))
+    }
+
+    /// Apply the `BLPOP` command to the specified [`Db`] instance.
+    ///
+    /// Nothing is written if the server shuts down while blocked.
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn apply<S: ConnectionStream>(
+        self,
+        db: &Db,
+        dst: &mut Connection<S>,
+        shutdown: &mut Shutdown,
+    ) -> Result<()> {
+        let timeout = self.block.then_some(self.timeout);
+        blocking_pop(db, dst, &self.keys, ListEnd::Left, timeout, shutdown).await
+    }
+
+    /// Creates the request frame of the command.
+    pub(crate) fn into_frame(self) -> Result<Frame> {
+        bpop_frame("blpop", self.keys, self.timeout)
+    }
+}
+
+impl BRPopCmd {
+    /// Creates a new [`BRPopCmd`] command.
+    pub fn new(keys: Vec<String>, timeout: Duration) -> Self {
+        Self {
+            keys,
+            timeout,
+            block: true,
+        }
+    }
+
+    /// Get the keys.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Get the timeout.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Make the command reply right away if the lists are empty.
+    pub(crate) fn without_blocking(mut self) -> Self {
+        self.block = false;
+        self
+    }
+
+    /// Parse a [`BRPopCmd`] instance from a received frame.
+    ///
+    /// The `BRPOP` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// BRPOP key [key ...] timeout
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Self> {
+        let (keys, timeout) = parse_bpop(parse)?;
+        Ok(Self::new(keys, timeout))
+    }
+
+    /// Apply the `BRPOP` command to the specified [`Db`] instance.
+    ///
+    /// Nothing is written if the server shuts down while blocked.
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn apply<S: ConnectionStream>(
+        self,
+        db: &Db,
+        dst: &mut Connection<S>,
+        shutdown: &mut Shutdown,
+    ) -> Result<()> {
+        let timeout = self.block.then_some(self.timeout);
+        blocking_pop(db, dst, &self.keys, ListEnd::Right, timeout, shutdown).await
+    }
+
+    /// Creates the request frame of the command.
+    pub(crate) fn into_frame(self) -> Result<Frame> {
+        bpop_frame("brpop", self.keys, self.timeout)
+    }
+}
+
+impl Display for BLPopCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let timeout = self.timeout.as_secs_f64();
+        write!(f, "BLPOP {} {timeout}", self.keys.join(" "))
+    }
+}
+
+impl Display for BRPopCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let timeout = self.timeout.as_secs_f64();
+        write!(f, "BRPOP {} {timeout}", self.keys.join(" "))
+    }
+}
+
+/// Parse the one or more keys, and the timeout in seconds, of a blocking pop
+/// command.
+fn parse_bpop(parse: &mut Parse) -> Result<(Vec<String>, Duration)> {
+    let mut args = vec![parse.next_string()?];
+    loop {
+        match parse.next_string() {
+            Ok(arg) => args.push(arg),
+            Err(Error::EndOfStream) => break,
+            Err(err) => return Err(err),
+        }
+    }
+    // The timeout comes last, after at least one key.
+    let timeout = args.pop().expect("at least one argument was parsed");
+    if args.is_empty() {
+        return Err(Error::Syntax);
+    }
+
+    let timeout: f64 = timeout
+        .parse()
+        .map_err(|_| Error::Protocol("timeout is not a float or out of range".to_string()))?;
+    if timeout < 0.0 {
+        return Err(Error::Protocol("timeout is negative".to_string()));
+    }
+    let timeout = Duration::try_from_secs_f64(timeout)
+        .map_err(|_| Error::Protocol("timeout is out of range".to_string()))?;
+
+    Ok((args, timeout))
+}
+
+/// Creates the request frame of the blocking pop command `name`.
+fn bpop_frame(name: &'static str, keys: Vec<String>, timeout: Duration) -> Result<Frame> {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from(name))?;
+    for key in keys {
+        frame.push_bulk(Bytes::from(key))?;
+    }
+    frame.push_bulk(Bytes::from(timeout.as_secs_f64().to_string()))?;
+    Ok(frame)
+}
+
+/// Pop a value from the `end` of the first non empty list stored at one of
+/// `keys`, waiting for values to be pushed for up to `timeout`, and write the
+/// reply to `dst`.
+///
+/// A zero `timeout` waits forever, while `None` does not wait at all.
+async fn blocking_pop<S: ConnectionStream>(
+    db: &Db,
+    dst: &mut Connection<S>,
+    keys: &[String],
+    end: ListEnd,
+    timeout: Option<Duration>,
+    shutdown: &mut Shutdown,
+) -> Result<()> {
+    let deadline = timeout
+        .filter(|timeout| !timeout.is_zero())
+        .map(|timeout| Instant::now() + timeout);
+
+    let response = loop {
+        let waiters = db.list_waiters(keys);
+        let wait =
+            wait_for_push(db, keys, end, &waiters, timeout.map(|_| deadline), shutdown).await;
+        db.release_list_waiters(keys, waiters);
+
+        match wait? {
+            Wait::Popped(key, value) => {
+                let mut response = Frame::array();
+                response.push_bulk(Bytes::from(key))?;
+                response.push_bulk(value)?;
+                break response;
+            }
+            Wait::Pushed => continue,
+            Wait::TimedOut => break Frame::NullArray,
+            Wait::Shutdown => return Ok(()),
+        }
+    };
+
+    debug!(?response);
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+/// Try to pop a value, waiting on `waiters` for a push otherwise.
+///
+/// `deadline` is `None` to not wait at all, and `Some(None)` to wait forever.
+async fn wait_for_push(
+    db: &Db,
+    keys: &[String],
+    end: ListEnd,
+    waiters: &[Arc<Notify>],
+    deadline: Option<Option<Instant>>,
+    shutdown: &mut Shutdown,
+) -> Result<Wait> {
+    // Register for the pushes before checking the lists, so a value pushed in
+    // between is not missed.
+    let mut notified: Vec<_> = waiters
+        .iter()
+        .map(|notify| Box::pin(notify.notified()))
+        .collect();
+    for notified in &mut notified {
+        notified.as_mut().enable();
+    }
+
+    if let Some((key, mut values)) = db.pop_first(keys, end, 1)? {
+        return Ok(Wait::Popped(key, values.remove(0)));
+    }
+    let Some(deadline) = deadline else {
+        return Ok(Wait::TimedOut);
+    };
+
+    let timeout = async {
+        match deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline).await,
+            None => pending().await,
+        }
+    };
+    tokio::select! {
+        _ = futures::future::select_all(notified) => Ok(Wait::Pushed),
+        _ = timeout => Ok(Wait::TimedOut),
+        _ = shutdown.recv() => Ok(Wait::Shutdown),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::broadcast;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bpop_blocks_until_push_or_timeout() {
+        let (client, server) = tokio::io::duplex(1024);
+        let (_notify_shutdown, rx) = broadcast::channel(1);
+        let db = Db::default();
+
+        let blocked = tokio::spawn({
+            let db = db.clone();
+            async move {
+                let mut conn = Connection::new(server);
+                let mut shutdown = Shutdown::new(rx);
+                let keys = vec!["foo".to_string(), "bar".to_string()];
+                BLPopCmd::new(keys.clone(), Duration::ZERO)
+                    .apply(&db, &mut conn, &mut shutdown)
+                    .await?;
+                BRPopCmd::new(keys, Duration::from_secs(1))
+                    .apply(&db, &mut conn, &mut shutdown)
+                    .await
+            }
+        });
+
+        // Without a timeout, the pop waits for as long as it takes.
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert!(!blocked.is_finished());
+        db.push("bar", vec![Bytes::from("a")], ListEnd::Left)
+            .unwrap();
+
+        let mut client = Connection::new(client);
+        let expected = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("bar")),
+            Frame::BulkString(Bytes::from("a")),
+        ]);
+        assert_eq!(client.read_frame().await.unwrap(), Some(expected));
+        // The emptied list was removed.
+        assert!(!db.exists("bar"));
+
+        let start = Instant::now();
+        assert_eq!(client.read_frame().await.unwrap(), Some(Frame::NullArray));
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        blocked.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,218 @@
+//! Implementation of the `CLIENT` command.
+use super::help::{help_frame, unknown_subcommand};
+use crate::{
+    parse::Parse, server::ServerState, Connection, ConnectionStream, Error, Frame, Result,
+};
+use bytes::Bytes;
+use std::{fmt::Display, time::Duration};
+use tracing::debug;
+
+/// Controls the client connections.
+///
+/// # Subcommands
+///
+/// * PAUSE `timeout` `[WRITE | ALL]` -- Suspends the processing of commands
+///   from every connection for `timeout` milliseconds. With `WRITE`, only the
+///   commands which may modify the keyspace are suspended. `ALL` is the
+///   default. `CLIENT` commands are never suspended.
+/// * UNPAUSE -- Resumes the processing of the suspended commands.
+/// * HELP -- Returns the documentation of the subcommands.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ClientCmd {
+    subcommand: Subcommand,
+}
+
+/// The `CLIENT` subcommands.
+#[derive(Debug, PartialEq, Eq)]
+enum Subcommand {
+    /// `CLIENT PAUSE timeout [WRITE | ALL]`
+    Pause(Duration, PauseMode),
+    /// `CLIENT UNPAUSE`
+    Unpause,
+    /// `CLIENT HELP`
+    Help,
+}
+
+/// The documentation of the subcommands, returned by `CLIENT HELP`.
+const HELP: &[&str] = &[
+    "PAUSE <timeout> [WRITE|ALL]",
+    "    Suspend the processing of commands for <timeout> milliseconds. With WRITE,",
+    "    only the commands which may modify the keyspace are suspended.",
+    "UNPAUSE",
+    "    Resume the processing of the suspended commands.",
+];
+
+/// Which commands are suspended by `CLIENT PAUSE`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PauseMode {
+    /// Only the commands which may modify the keyspace.
+    Write,
+    /// Every command.
+    #[default]
+    All,
+}
+
+impl ClientCmd {
+    /// Creates a new `CLIENT PAUSE` command, suspending the commands affected
+    /// by `mode` for `timeout`.
+    pub fn pause(timeout: Duration, mode: PauseMode) -> Self {
+        Self {
+            subcommand: Subcommand::Pause(timeout, mode),
+        }
+    }
+
+    /// Creates a new `CLIENT UNPAUSE` command.
+    pub fn unpause() -> Self {
+        Self {
+            subcommand: Subcommand::Unpause,
+        }
+    }
+
+    /// Creates a new `CLIENT HELP` command.
+    pub fn help() -> Self {
+        Self {
+            subcommand: Subcommand::Help,
+        }
+    }
+
+    /// Parse a [`ClientCmd`] instance from a received frame.
+    ///
+    /// The `CLIENT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// CLIENT PAUSE timeout [WRITE | ALL]
+    /// CLIENT UNPAUSE
+    /// CLIENT HELP
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Self> {
+        let subcommand = parse.next_string()?;
+        let subcommand = match subcommand.to_uppercase().as_str() {
+            "PAUSE" => {
+                let timeout = u64::try_from(parse.next_int_signed()?)
+                    .map_err(|_| Error::Protocol("timeout is out of range".to_string()))?;
+                let mode = match parse.next_string() {
+                    Ok(s) if s.to_uppercase() == "WRITE" => PauseMode::Write,
+                    Ok(s) if s.to_uppercase() == "ALL" => PauseMode::All,
+                    Ok(_) => return Err(Error::Syntax),
+                    Err(Error::EndOfStream) => PauseMode::All,
+                    Err(err) => return Err(err),
+                };
+                Subcommand::Pause(Duration::from_millis(timeout), mode)
+            }
+            "UNPAUSE" => Subcommand::Unpause,
+            "HELP" => Subcommand::Help,
+            _ => return Err(unknown_subcommand("CLIENT", subcommand)),
+        };
+
+        Ok(Self { subcommand })
+    }
+
+    /// Apply the `CLIENT` command to the connections of `server`.
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn apply<S: ConnectionStream>(
+        self,
+        server: &ServerState,
+        dst: &mut Connection<S>,
+    ) -> Result<()> {
+        let response = match self.subcommand {
+            Subcommand::Pause(timeout, mode) => {
+                server.client_pause().pause(timeout, mode);
+                Frame::SimpleString("OK".to_string())
+            }
+            Subcommand::Unpause => {
+                server.client_pause().unpause();
+                Frame::SimpleString("OK".to_string())
+            }
+            Subcommand::Help => help_frame("CLIENT", HELP)?,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Creates the request frame of the command.
+    // No client sends `CLIENT` yet.
+    #[allow(dead_code)]
+    pub(crate) fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("client"))?;
+        match self.subcommand {
+            Subcommand::Pause(timeout, mode) => {
+                frame.push_bulk(Bytes::from("pause"))?;
+                frame.push_int(timeout.as_millis() as i64)?;
+                frame.push_bulk(Bytes::from(mode.to_string()))?;
+            }
+            Subcommand::Unpause => frame.push_bulk(Bytes::from("unpause"))?,
+            Subcommand::Help => frame.push_bulk(Bytes::from("help"))?,
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for PauseMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PauseMode::Write => write!(f, "WRITE"),
+            PauseMode::All => write!(f, "ALL"),
+        }
+    }
+}
+
+impl Display for ClientCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.subcommand {
+            Subcommand::Pause(timeout, mode) => {
+                write!(f, "CLIENT PAUSE {} {mode}", timeout.as_millis())
+            }
+            Subcommand::Unpause => write!(f, "CLIENT UNPAUSE"),
+            Subcommand::Help => write!(f, "CLIENT HELP"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommandVariant, Connection};
+
+    #[tokio::test]
+    async fn test_client_help_and_unknown_subcommand() {
+        let (client, server) = tokio::io::duplex(4096);
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("CLIENT")),
+            Frame::BulkString(Bytes::from("help")),
+        ]);
+        let CommandVariant::Client(cmd) = CommandVariant::from_frame(frame).unwrap() else {
+            panic!("unexpected command");
+        };
+        assert_eq!(cmd, ClientCmd::help());
+        cmd.apply(&ServerState::default(), &mut Connection::new(server))
+            .await
+            .unwrap();
+
+        let Some(Frame::Array(lines)) = Connection::new(client).read_frame().await.unwrap() else {
+            panic!("expected an array");
+        };
+        assert!(!lines.is_empty());
+        assert!(lines
+            .iter()
+            .all(|line| matches!(line, Frame::BulkString(_))));
+
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("CLIENT")),
+            Frame::BulkString(Bytes::from("BOGUS")),
+        ]);
+        let err = CommandVariant::from_frame(frame).unwrap_err();
+        assert_eq!(
+            err.to_frame(),
+            Some(Frame::SimpleError(
+                "ERR unknown subcommand 'BOGUS'. Try CLIENT HELP.".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,422 @@
+//! Implementation of the `COMMAND` command.
+use super::{
+    help::{help_frame, unknown_subcommand},
+    Command, CommandFactory, CommandSpec, KeySpec, COMMAND_TABLE,
+};
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Introspects the commands supported by the server.
+///
+/// # Subcommands
+///
+/// * COUNT -- Returns the number of supported commands. This is also the
+///   behavior of the bare `COMMAND` form.
+/// * DOCS `[command ...]` -- Returns the documentation of the given commands,
+///   or of every command if none is given.
+/// * GETKEYS `command [arg ...]` -- Returns the arguments of the given
+///   command line which are keys.
+/// * HELP -- Returns the documentation of the subcommands.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CommandCmd {
+    subcommand: Subcommand,
+}
+
+/// The `COMMAND` subcommands.
+#[derive(Debug, PartialEq, Eq)]
+enum Subcommand {
+    /// `COMMAND COUNT`
+    Count,
+    /// `COMMAND DOCS [command ...]`
+    Docs(Vec<String>),
+    /// `COMMAND GETKEYS command [arg ...]`
+    GetKeys(String, Vec<Bytes>),
+    /// `COMMAND HELP`
+    Help,
+}
+
+/// The documentation of the subcommands, returned by `COMMAND HELP`.
+const HELP: &[&str] = &[
+    "(no subcommand)",
+    "    Return the number of supported commands.",
+    "COUNT",
+    "    Return the number of supported commands.",
+    "DOCS [<command-name> ...]",
+    "    Return the documentation of the given commands, or of every command.",
+    "GETKEYS <full-command>",
+    "    Return the keys from a full command.",
+];
+
+/// Positions of the key arguments of the commands operating on keys, keyed
+/// by command name.
+///
+/// Commands missing from this table have no key arguments, as far as
+/// `COMMAND GETKEYS` is concerned. This includes the commands whose keys
+/// follow a subcommand, such as `OBJECT` and `MEMORY`.
+const KEY_SPECS: &[(&str, KeySpec)] = &[
+    ("GET", KeySpec::SINGLE),
+    ("SET", KeySpec::SINGLE),
+    ("GETEX", KeySpec::SINGLE),
+    ("EXPIRE", KeySpec::SINGLE),
+    ("GETSET", KeySpec::SINGLE),
+    ("INCR", KeySpec::SINGLE),
+    ("DECR", KeySpec::SINGLE),
+    ("INCRBY", KeySpec::SINGLE),
+    ("DECRBY", KeySpec::SINGLE),
+    ("INCRBYFLOAT", KeySpec::SINGLE),
+    ("SETBIT", KeySpec::SINGLE),
+    ("GETBIT", KeySpec::SINGLE),
+    ("BITCOUNT", KeySpec::SINGLE),
+    ("BITFIELD", KeySpec::SINGLE),
+    ("PFADD", KeySpec::SINGLE),
+    ("PFCOUNT", KeySpec::ALL),
+    ("DUMP", KeySpec::SINGLE),
+    ("RESTORE", KeySpec::SINGLE),
+    ("COPY", KeySpec::new(1, 2, 1)),
+    ("LPUSH", KeySpec::SINGLE),
+    ("RPUSH", KeySpec::SINGLE),
+    ("LLEN", KeySpec::SINGLE),
+    ("LINDEX", KeySpec::SINGLE),
+    ("LSET", KeySpec::SINGLE),
+    ("LREM", KeySpec::SINGLE),
+    ("LTRIM", KeySpec::SINGLE),
+    ("LPOS", KeySpec::SINGLE),
+    ("RPOPLPUSH", KeySpec::new(1, 2, 1)),
+    // The last argument is the timeout.
+    ("BLPOP", KeySpec::new(1, -2, 1)),
+    ("BRPOP", KeySpec::new(1, -2, 1)),
+    ("HSET", KeySpec::SINGLE),
+    ("HGET", KeySpec::SINGLE),
+    ("HINCRBY", KeySpec::SINGLE),
+    ("HMGET", KeySpec::SINGLE),
+    ("HKEYS", KeySpec::SINGLE),
+    ("HRANDFIELD", KeySpec::SINGLE),
+    ("HVALS", KeySpec::SINGLE),
+    ("HSCAN", KeySpec::SINGLE),
+    ("HEXPIRE", KeySpec::SINGLE),
+    ("HTTL", KeySpec::SINGLE),
+    ("HPERSIST", KeySpec::SINGLE),
+    ("SADD", KeySpec::SINGLE),
+    ("SSCAN", KeySpec::SINGLE),
+    ("SMOVE", KeySpec::new(1, 2, 1)),
+    ("SINTER", KeySpec::ALL),
+    ("SUNION", KeySpec::ALL),
+    ("SDIFF", KeySpec::ALL),
+    ("SINTERSTORE", KeySpec::ALL),
+    ("SUNIONSTORE", KeySpec::ALL),
+    ("SDIFFSTORE", KeySpec::ALL),
+    ("ZADD", KeySpec::SINGLE),
+    ("ZSCORE", KeySpec::SINGLE),
+    ("ZRANDMEMBER", KeySpec::SINGLE),
+    ("ZRANGE", KeySpec::SINGLE),
+    ("ZRANGEBYSCORE", KeySpec::SINGLE),
+    ("ZRANGEBYLEX", KeySpec::SINGLE),
+    ("ZRANK", KeySpec::SINGLE),
+    ("ZREVRANK", KeySpec::SINGLE),
+    ("ZCARD", KeySpec::SINGLE),
+    ("ZCOUNT", KeySpec::SINGLE),
+    ("ZREM", KeySpec::SINGLE),
+    ("ZSCAN", KeySpec::SINGLE),
+];
+
+/// Whether the command `name`, as registered in the command table, takes key
+/// arguments.
+pub(crate) fn has_key_arguments(name: &str) -> bool {
+    KEY_SPECS.iter().any(|(command, _)| *command == name)
+}
+
+/// The keys of the full command line `args`, whose first argument is the
+/// command name.
+fn command_keys(name: &str, args: &[Bytes]) -> Result<Vec<Bytes>> {
+    let spec = CommandSpec::lookup(name).ok_or(Error::InvalidCommand)?;
+    if !spec.accepts(args.len()) {
+        return Err(Error::InvalidCommandArguments);
+    }
+    let (_, keys) = KEY_SPECS
+        .iter()
+        .find(|(command, _)| *command == spec.name)
+        .ok_or(Error::NoKeyArguments)?;
+    let keys = keys.keys(args).ok_or(Error::InvalidCommandArguments)?;
+    Ok(keys.into_iter().cloned().collect())
+}
+
+impl CommandCmd {
+    /// Creates a new `COMMAND COUNT` command.
+    pub fn count() -> Self {
+        Self {
+            subcommand: Subcommand::Count,
+        }
+    }
+
+    /// Creates a new `COMMAND DOCS` command for the given command `names`.
+    ///
+    /// If `names` is empty, every command is documented.
+    pub fn docs(names: Vec<String>) -> Self {
+        Self {
+            subcommand: Subcommand::Docs(names),
+        }
+    }
+
+    /// Creates a new `COMMAND GETKEYS` command, extracting the keys of the
+    /// `command` line with the given `args`.
+    pub fn getkeys(command: impl ToString, args: Vec<Bytes>) -> Self {
+        Self {
+            subcommand: Subcommand::GetKeys(command.to_string(), args),
+        }
+    }
+
+    /// Creates a new `COMMAND HELP` command.
+    pub fn help() -> Self {
+        Self {
+            subcommand: Subcommand::Help,
+        }
+    }
+}
+
+impl Command for CommandCmd {
+    /// Parse a [`CommandCmd`] instance from a received frame.
+    ///
+    /// The `COMMAND` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// COMMAND [COUNT | DOCS [command ...] | GETKEYS command [arg ...] | HELP]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let subcommand = match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "COUNT" => Subcommand::Count,
+            Ok(s) if s.to_uppercase() == "DOCS" => {
+                let mut names = vec![];
+                loop {
+                    match parse.next_string() {
+                        Ok(name) => names.push(name),
+                        Err(Error::EndOfStream) => break,
+                        Err(err) => return Err(err),
+                    }
+                }
+                Subcommand::Docs(names)
+            }
+            Ok(s) if s.to_uppercase() == "GETKEYS" => {
+                let command = parse.next_string()?;
+                let mut args = vec![];
+                loop {
+                    match parse.next_bytes() {
+                        Ok(arg) => args.push(arg),
+                        Err(Error::EndOfStream) => break,
+                        Err(err) => return Err(err),
+                    }
+                }
+                Subcommand::GetKeys(command, args)
+            }
+            Ok(s) if s.to_uppercase() == "HELP" => Subcommand::Help,
+            Ok(s) => return Err(unknown_subcommand("COMMAND", s)),
+            // The bare `COMMAND` form behaves like `COMMAND COUNT`.
+            Err(Error::EndOfStream) => Subcommand::Count,
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self { subcommand })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        _db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = match self.subcommand {
+            Subcommand::Count => Frame::Integer(COMMAND_TABLE.len() as i64),
+            Subcommand::Docs(names) => {
+                let specs: Vec<&CommandSpec> = if names.is_empty() {
+                    COMMAND_TABLE.iter().collect()
+                } else {
+                    // Unknown commands are silently skipped, like Redis does.
+                    names
+                        .iter()
+                        .filter_map(|name| CommandSpec::lookup(name))
+                        .collect()
+                };
+
+                let mut response = Frame::array();
+                for spec in specs {
+                    response.push_bulk(Bytes::from(spec.name.to_lowercase()))?;
+                    let mut doc = Frame::array();
+                    doc.push_bulk(Bytes::from_static(b"arity"))?;
+                    doc.push_int(spec.arity)?;
+                    response.push_frame(doc)?;
+                }
+                response
+            }
+            Subcommand::GetKeys(command, args) => {
+                let mut line = vec![Bytes::from(command.clone())];
+                line.extend(args);
+                let mut response = Frame::array();
+                for key in command_keys(&command, &line)? {
+                    response.push_bulk(key)?;
+                }
+                response
+            }
+            Subcommand::Help => help_frame("COMMAND", HELP)?,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("command"))?;
+        match self.subcommand {
+            Subcommand::Count => frame.push_bulk(Bytes::from("count"))?,
+            Subcommand::Docs(names) => {
+                frame.push_bulk(Bytes::from("docs"))?;
+                for name in names {
+                    frame.push_bulk(Bytes::from(name))?;
+                }
+            }
+            Subcommand::GetKeys(command, args) => {
+                frame.push_bulk(Bytes::from("getkeys"))?;
+                frame.push_bulk(Bytes::from(command))?;
+                for arg in args {
+                    frame.push_bulk(arg)?;
+                }
+            }
+            Subcommand::Help => frame.push_bulk(Bytes::from("help"))?,
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for CommandCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.subcommand {
+            Subcommand::Count => write!(f, "COMMAND COUNT"),
+            Subcommand::Docs(names) if names.is_empty() => write!(f, "COMMAND DOCS"),
+            Subcommand::Docs(names) => write!(f, "COMMAND DOCS {}", names.join(" ")),
+            Subcommand::GetKeys(command, args) => {
+                write!(f, "COMMAND GETKEYS {command}")?;
+                for arg in args {
+                    write!(f, " {arg:?}")?;
+                }
+                Ok(())
+            }
+            Subcommand::Help => write!(f, "COMMAND HELP"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommandVariant, Connection, Db};
+
+    #[test]
+    fn test_registered_commands_are_dispatched() {
+        for spec in COMMAND_TABLE {
+            let frame = Frame::Array(vec![Frame::BulkString(Bytes::from(spec.name))]);
+            let res = CommandVariant::from_frame(frame);
+            assert!(
+                !matches!(res, Err(Error::UnknownCommand { .. })),
+                "`{}` is registered but not dispatched",
+                spec.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_count() {
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("COMMAND")),
+            Frame::BulkString(Bytes::from("COUNT")),
+        ]);
+        let CommandVariant::Command(cmd) = CommandVariant::from_frame(frame).unwrap() else {
+            panic!("unexpected command");
+        };
+        assert_eq!(cmd, CommandCmd::count());
+
+        let expected = format!(":{}\r\n", COMMAND_TABLE.len());
+        let stream = tokio_test::io::Builder::new()
+            .write(expected.as_bytes())
+            .build();
+        let mut conn = Connection::new(stream);
+        cmd.apply(&Db::default(), &mut conn).await.unwrap();
+    }
+
+    /// The keys `COMMAND GETKEYS` extracts from the `line` command.
+    async fn getkeys(line: &[&str]) -> Result<Frame> {
+        let (command, args) = line.split_first().unwrap();
+        let args = args
+            .iter()
+            .map(|arg| Bytes::from(arg.to_string()))
+            .collect();
+        let (client, server) = tokio::io::duplex(1024);
+        let mut conn = Connection::new(server);
+        CommandCmd::getkeys(command, args)
+            .apply(&Db::default(), &mut conn)
+            .await?;
+        drop(conn);
+        Ok(Connection::new(client).read_frame().await?.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_command_getkeys() {
+        let bulks = |keys: &[&str]| {
+            let keys = keys
+                .iter()
+                .map(|key| Frame::BulkString(Bytes::from(key.to_string())));
+            Frame::Array(keys.collect())
+        };
+        assert_eq!(getkeys(&["get", "foo"]).await.unwrap(), bulks(&["foo"]));
+        assert_eq!(
+            getkeys(&["SET", "foo", "bar", "EX", "10"]).await.unwrap(),
+            bulks(&["foo"])
+        );
+        assert_eq!(
+            getkeys(&["SINTERSTORE", "dst", "a", "b"]).await.unwrap(),
+            bulks(&["dst", "a", "b"])
+        );
+        // The timeout of blocking pops is not a key.
+        assert_eq!(
+            getkeys(&["BLPOP", "a", "b", "0"]).await.unwrap(),
+            bulks(&["a", "b"])
+        );
+
+        let err = getkeys(&["PING"]).await.unwrap_err();
+        assert!(matches!(err, Error::NoKeyArguments));
+        let err = getkeys(&["FOO", "bar"]).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidCommand));
+        let err = getkeys(&["GET"]).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidCommandArguments));
+    }
+
+    #[test]
+    fn test_key_specs_name_registered_commands() {
+        for (name, _) in KEY_SPECS {
+            assert!(
+                CommandSpec::lookup(name).is_some(),
+                "`{name}` has keys but is not registered"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_docs() {
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*2\r\n$3\r\nget\r\n*2\r\n$5\r\narity\r\n:2\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        CommandCmd::docs(vec!["GET".to_string(), "FOO".to_string()])
+            .apply(&Db::default(), &mut conn)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,215 @@
+//! Implementation of the `COPY` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Copy the value stored at `source` to `destination`.
+///
+/// The remaining time to live of `source`, if any, is carried over to
+/// `destination`. Replies with `1` if the value was copied, and `0` if
+/// `source` does not exist or `destination` already exists.
+///
+/// # Options
+///
+/// * DB `destination-db` -- The database to copy to. As the server holds a
+///   single database, only `0` is accepted.
+/// * REPLACE -- Overwrite `destination` if it already exists.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CopyCmd {
+    /// The key to copy from.
+    source: String,
+    /// The key to copy to.
+    destination: String,
+    /// The database to copy to.
+    db: Option<u64>,
+    /// Whether to overwrite an existing destination.
+    replace: bool,
+}
+
+impl CopyCmd {
+    /// Creates a new [`CopyCmd`] command.
+    pub fn new(
+        source: impl ToString,
+        destination: impl ToString,
+        db: Option<u64>,
+        replace: bool,
+    ) -> Self {
+        Self {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            db,
+            replace,
+        }
+    }
+
+    /// Get the source key.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Get the destination key.
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Get the destination database.
+    pub fn db(&self) -> Option<u64> {
+        self.db
+    }
+
+    /// Whether an existing destination is overwritten.
+    pub fn replace(&self) -> bool {
+        self.replace
+    }
+}
+
+impl Command for CopyCmd {
+    /// Parse a [`CopyCmd`] instance from a received frame.
+    ///
+    /// The `COPY` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// COPY source destination [DB destination-db] [REPLACE]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let source = parse.next_string()?;
+        let destination = parse.next_string()?;
+        let mut db = None;
+        let mut replace = false;
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "DB" => db = Some(parse.next_int_unsigned()?),
+                Ok(s) if s.to_uppercase() == "REPLACE" => replace = true,
+                Ok(_) => return Err(Error::Syntax),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Self {
+            source,
+            destination,
+            db,
+            replace,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        // There is a single database, so it is the only valid target.
+        if self.db.is_some_and(|index| index != 0) {
+            return Err(Error::DbIndexOutOfRange);
+        }
+
+        let copied = db.copy(&self.source, &self.destination, self.replace)?;
+        let response = Frame::Integer(copied as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("copy"))?;
+        frame.push_bulk(Bytes::from(self.source))?;
+        frame.push_bulk(Bytes::from(self.destination))?;
+        if let Some(db) = self.db {
+            frame.push_bulk(Bytes::from("db"))?;
+            frame.push_int(db as i64)?;
+        }
+        if self.replace {
+            frame.push_bulk(Bytes::from("replace"))?;
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for CopyCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "COPY {} {}", self.source, self.destination)?;
+        if let Some(db) = self.db {
+            write!(f, " DB {db}")?;
+        }
+        if self.replace {
+            write!(f, " REPLACE")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_copy_replace() {
+        let db = Db::default();
+        db.set("foo".to_string(), Bytes::from("a"), None).unwrap();
+        db.set("bar".to_string(), Bytes::from("b"), None).unwrap();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b":0\r\n")
+            .write(b":1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        CopyCmd::new("foo", "bar", None, false)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        assert_eq!(db.get("bar").unwrap(), Some(Bytes::from("b")));
+
+        CopyCmd::new("foo", "bar", Some(0), true)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        assert_eq!(db.get("bar").unwrap(), Some(Bytes::from("a")));
+        assert_eq!(db.get("foo").unwrap(), Some(Bytes::from("a")));
+
+        assert!(!db.copy("missing", "baz", true).unwrap());
+        assert!(matches!(
+            db.copy("foo", "foo", true),
+            Err(Error::SameObject)
+        ));
+        let err = CopyCmd::new("foo", "baz", Some(1), false)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::DbIndexOutOfRange));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_copy_preserves_ttl() {
+        let db = Db::default();
+        let ttl = Duration::from_secs(10);
+        db.set("foo".to_string(), Bytes::from("a"), Some(ttl))
+            .unwrap();
+
+        tokio::time::advance(Duration::from_secs(4)).await;
+        assert!(db.copy("foo", "bar", false).unwrap());
+
+        // The copy expires along with the source, not 10 seconds after the
+        // copy was made.
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(db.get("bar").unwrap(), Some(Bytes::from("a")));
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert_eq!(db.get("bar").unwrap(), None);
+        assert_eq!(db.get("foo").unwrap(), None);
+    }
+}
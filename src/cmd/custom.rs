@@ -0,0 +1,135 @@
+//! Commands registered at runtime, outside of the builtin command table.
+use super::{CommandFactory, CommandVariant, KeySpec};
+use crate::{parse::Parse, Connection, ConnectionStream, Db, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// A command parsed by a [`CustomSpec`], applied through a trait object.
+///
+/// Unlike [`Command`](super::Command), whose `apply` is generic over the
+/// connection stream, applying it returns the reply rather than writing it,
+/// so that it may be boxed.
+pub(crate) trait DynCommand: std::fmt::Debug + Display + Send {
+    /// Apply the command to `db`, returning the reply to the client.
+    fn apply(self: Box<Self>, db: &Db) -> Result<Frame>;
+}
+
+/// Static information about a command registered at runtime in a
+/// [`CommandRegistry`](super::CommandRegistry).
+// The server does not register any custom command yet.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CustomSpec {
+    /// Name of the command, in uppercase.
+    pub(crate) name: &'static str,
+    /// Number of arguments, following the convention of
+    /// [`CommandSpec::arity`](super::CommandSpec::arity).
+    pub(crate) arity: i64,
+    /// Whether the command may modify the keyspace.
+    pub(crate) write: bool,
+    /// Positions of the key arguments, if the command takes any.
+    pub(crate) keys: Option<KeySpec>,
+    /// Parser of the arguments following the command name.
+    pub(crate) parse: fn(&mut Parse) -> Result<Box<dyn DynCommand>>,
+}
+
+impl CommandFactory for CustomSpec {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn arity(&self) -> i64 {
+        self.arity
+    }
+
+    fn parse(&self, parse: &mut Parse) -> Result<CommandVariant> {
+        // The keys are selected on a copy of the arguments, before the parser
+        // consumes them.
+        let keys = match self.keys {
+            Some(spec) => {
+                let mut line = vec![Bytes::from_static(self.name.as_bytes())];
+                let mut args = parse.clone();
+                while let Ok(arg) = args.next_bytes() {
+                    line.push(arg);
+                }
+                spec.keys(&line)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|key| String::from_utf8_lossy(key).into_owned())
+                    .collect()
+            }
+            None => vec![],
+        };
+
+        Ok(CommandVariant::Custom(CustomCmd {
+            name: self.name,
+            write: self.write,
+            keys,
+            command: (self.parse)(parse)?,
+        }))
+    }
+}
+
+/// A command registered at runtime, see [`CustomSpec`].
+///
+/// Its name, whether it writes and its keys are those of the spec it was
+/// parsed by, as the boxed command itself is opaque.
+#[derive(Debug)]
+pub struct CustomCmd {
+    name: &'static str,
+    write: bool,
+    keys: Vec<String>,
+    command: Box<dyn DynCommand>,
+}
+
+impl CustomCmd {
+    /// Get the name of the command, as registered.
+    pub(crate) fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Whether the command may modify the keyspace.
+    pub(crate) fn is_write(&self) -> bool {
+        self.write
+    }
+
+    /// Get the keys of the command, as selected by its spec.
+    pub(crate) fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Apply the command, writing its reply to `dst`.
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn apply<S: ConnectionStream>(
+        self,
+        db: &Db,
+        dst: &mut Connection<S>,
+    ) -> Result<()> {
+        let response = self.command.apply(db)?;
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+/// Boxed commands cannot be compared, two custom commands are equal if they
+/// render the same.
+impl PartialEq for CustomCmd {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.keys == other.keys
+            && self.command.to_string() == other.command.to_string()
+    }
+}
+
+impl Eq for CustomCmd {}
+
+impl Display for CustomCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.command)
+    }
+}
@@ -0,0 +1,352 @@
+//! Implementation of the `DEBUG` command.
+use super::help::{help_frame, unknown_subcommand};
+use crate::{
+    parse::Parse, server::ServerState, Connection, ConnectionStream, Db, Error, Frame, Result,
+};
+use bytes::Bytes;
+use std::{fmt::Display, time::Duration};
+use tracing::debug;
+
+/// Helpers to test and troubleshoot the server.
+///
+/// # Subcommands
+///
+/// * SLEEP `seconds` -- Block the connection for the given, possibly
+///   fractional, number of seconds, then reply with `OK`.
+/// * OBJECT `key` -- Returns a line describing the internals of the value
+///   stored at `key`, as `field:value` pairs separated by spaces. The
+///   `encoding` field names the representation Redis would pick for the
+///   value, and lists also report `ql_nodes`, the number of quicklist nodes.
+/// * RELOAD -- Snapshot the keyspace, then replace it with the keys loaded
+///   back from the snapshot, replying with `OK`. Values and expirations are
+///   expected to be unchanged.
+/// * EXPIRE-CYCLE -- Remove every expired key right away, rather than
+///   waiting for the background task, replying with the number of keys
+///   removed.
+/// * SET-RUN-ID `id` -- Replace the `run_id` reported by `INFO` with `id`,
+///   which must be 40 hexadecimal characters, replying with `OK`.
+/// * HELP -- Returns the documentation of the subcommands.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DebugCmd {
+    subcommand: Subcommand,
+}
+
+/// The `DEBUG` subcommands.
+#[derive(Debug, PartialEq, Eq)]
+enum Subcommand {
+    /// `DEBUG SLEEP seconds`
+    Sleep(Duration),
+    /// `DEBUG OBJECT key`
+    Object(String),
+    /// `DEBUG RELOAD`
+    Reload,
+    /// `DEBUG EXPIRE-CYCLE`
+    ExpireCycle,
+    /// `DEBUG SET-RUN-ID id`
+    SetRunId(String),
+    /// `DEBUG HELP`
+    Help,
+}
+
+/// The documentation of the subcommands, returned by `DEBUG HELP`.
+const HELP: &[&str] = &[
+    "SLEEP <seconds>",
+    "    Block the connection for <seconds>, which may be fractional.",
+    "OBJECT <key>",
+    "    Show low level info about <key> and its value.",
+    "RELOAD",
+    "    Save the keyspace to a snapshot, then load it back.",
+    "EXPIRE-CYCLE",
+    "    Remove the expired keys now, returning how many were removed.",
+    "SET-RUN-ID <id>",
+    "    Replace the run id reported by INFO with <id>, 40 hex characters.",
+];
+
+impl DebugCmd {
+    /// Creates a new `DEBUG SLEEP` command, sleeping for `duration`.
+    pub fn sleep(duration: Duration) -> Self {
+        Self {
+            subcommand: Subcommand::Sleep(duration),
+        }
+    }
+
+    /// Creates a new `DEBUG OBJECT` command for `key`.
+    pub fn object(key: impl ToString) -> Self {
+        Self {
+            subcommand: Subcommand::Object(key.to_string()),
+        }
+    }
+
+    /// Creates a new `DEBUG RELOAD` command.
+    pub fn reload() -> Self {
+        Self {
+            subcommand: Subcommand::Reload,
+        }
+    }
+
+    /// Creates a new `DEBUG EXPIRE-CYCLE` command.
+    pub fn expire_cycle() -> Self {
+        Self {
+            subcommand: Subcommand::ExpireCycle,
+        }
+    }
+
+    /// Creates a new `DEBUG SET-RUN-ID` command, setting the run id to `id`.
+    pub fn set_run_id(id: impl ToString) -> Self {
+        Self {
+            subcommand: Subcommand::SetRunId(id.to_string()),
+        }
+    }
+
+    /// Creates a new `DEBUG HELP` command.
+    pub fn help() -> Self {
+        Self {
+            subcommand: Subcommand::Help,
+        }
+    }
+
+    /// Parse a [`DebugCmd`] instance from a received frame.
+    ///
+    /// The `DEBUG` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// DEBUG SLEEP seconds
+    /// DEBUG OBJECT key
+    /// DEBUG RELOAD
+    /// DEBUG EXPIRE-CYCLE
+    /// DEBUG SET-RUN-ID id
+    /// DEBUG HELP
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Self> {
+        let subcommand = parse.next_string()?;
+        let subcommand = match subcommand.to_uppercase().as_str() {
+            "SLEEP" => {
+                let duration = Duration::try_from_secs_f64(parse.next_float()?)
+                    .map_err(|_| Error::Protocol("invalid sleep duration".to_string()))?;
+                Subcommand::Sleep(duration)
+            }
+            "OBJECT" => Subcommand::Object(parse.next_string()?),
+            "RELOAD" => Subcommand::Reload,
+            "EXPIRE-CYCLE" => Subcommand::ExpireCycle,
+            "SET-RUN-ID" => {
+                let id = parse.next_string()?;
+                if id.len() != 40 || !id.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return Err(Error::Protocol("invalid run id".to_string()));
+                }
+                Subcommand::SetRunId(id.to_lowercase())
+            }
+            "HELP" => Subcommand::Help,
+            _ => return Err(unknown_subcommand("DEBUG", subcommand)),
+        };
+
+        Ok(Self { subcommand })
+    }
+
+    /// Apply the `DEBUG` command to `db`, or to `server` for the subcommands
+    /// which concern the whole server.
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn apply<S: ConnectionStream>(
+        self,
+        db: &Db,
+        server: &ServerState,
+        dst: &mut Connection<S>,
+    ) -> Result<()> {
+        let response = match self.subcommand {
+            Subcommand::Sleep(duration) => {
+                tokio::time::sleep(duration).await;
+                Frame::SimpleString("OK".to_string())
+            }
+            Subcommand::Object(key) => match db.debug_object(&key) {
+                Some(object) => {
+                    let mut line = format!(
+                        "refcount:1 encoding:{} serializedlength:{} lru_seconds_idle:{}",
+                        object.encoding,
+                        object.serialized_length,
+                        object.idle.as_secs()
+                    );
+                    if let Some(nodes) = object.list_nodes {
+                        line.push_str(&format!(" ql_nodes:{nodes}"));
+                    }
+                    Frame::SimpleString(line)
+                }
+                None => return Err(Error::NoSuchKey),
+            },
+            Subcommand::Reload => {
+                let snapshot = db.snapshot();
+                db.load_snapshot(&snapshot)?;
+                Frame::SimpleString("OK".to_string())
+            }
+            Subcommand::ExpireCycle => Frame::Integer(db.sweep_expired() as i64),
+            Subcommand::SetRunId(id) => {
+                server.set_run_id(id);
+                Frame::SimpleString("OK".to_string())
+            }
+            Subcommand::Help => help_frame("DEBUG", HELP)?,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Creates the request frame of the command.
+    // No client sends `DEBUG` yet.
+    #[allow(dead_code)]
+    pub(crate) fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("debug"))?;
+        match self.subcommand {
+            Subcommand::Sleep(duration) => {
+                frame.push_bulk(Bytes::from("sleep"))?;
+                frame.push_bulk(Bytes::from(duration.as_secs_f64().to_string()))?;
+            }
+            Subcommand::Object(key) => {
+                frame.push_bulk(Bytes::from("object"))?;
+                frame.push_bulk(Bytes::from(key))?;
+            }
+            Subcommand::Reload => frame.push_bulk(Bytes::from("reload"))?,
+            Subcommand::ExpireCycle => frame.push_bulk(Bytes::from("expire-cycle"))?,
+            Subcommand::SetRunId(id) => {
+                frame.push_bulk(Bytes::from("set-run-id"))?;
+                frame.push_bulk(Bytes::from(id))?;
+            }
+            Subcommand::Help => frame.push_bulk(Bytes::from("help"))?,
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for DebugCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.subcommand {
+            Subcommand::Sleep(duration) => write!(f, "DEBUG SLEEP {}", duration.as_secs_f64()),
+            Subcommand::Object(key) => write!(f, "DEBUG OBJECT {key}"),
+            Subcommand::Reload => write!(f, "DEBUG RELOAD"),
+            Subcommand::ExpireCycle => write!(f, "DEBUG EXPIRE-CYCLE"),
+            Subcommand::SetRunId(id) => write!(f, "DEBUG SET-RUN-ID {id}"),
+            Subcommand::Help => write!(f, "DEBUG HELP"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::ListEnd;
+
+    /// Apply `DEBUG OBJECT key`, returning the reply line.
+    async fn debug_object(db: &Db, key: &str) -> String {
+        let (client, server) = tokio::io::duplex(1024);
+        DebugCmd::object(key)
+            .apply(db, &ServerState::default(), &mut Connection::new(server))
+            .await
+            .unwrap();
+        match Connection::new(client).read_frame().await.unwrap() {
+            Some(Frame::SimpleString(line)) => line,
+            frame => panic!("unexpected reply {frame:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_debug_object_reports_encoding() {
+        let db = Db::default();
+        db.set("int".to_string(), Bytes::from("12345"), None)
+            .unwrap();
+        db.set("str".to_string(), Bytes::from("012345"), None)
+            .unwrap();
+        db.push("list", vec![Bytes::from("a")], ListEnd::Left)
+            .unwrap();
+
+        let line = debug_object(&db, "int").await;
+        assert!(line.contains("encoding:int"), "{line}");
+        assert!(line.contains("serializedlength:"), "{line}");
+        assert!(!line.contains("ql_nodes:"), "{line}");
+        // Leading zeros would be lost as an integer.
+        let line = debug_object(&db, "str").await;
+        assert!(line.contains("encoding:embstr"), "{line}");
+        let line = debug_object(&db, "list").await;
+        assert!(line.contains("encoding:quicklist"), "{line}");
+        assert!(line.contains("ql_nodes:1"), "{line}");
+
+        let mut conn = Connection::new(tokio_test::io::Builder::new().build());
+        let err = DebugCmd::object("missing")
+            .apply(&db, &ServerState::default(), &mut conn)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NoSuchKey));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_debug_reload_keeps_values_and_ttls() {
+        let db = Db::default();
+        let short = Some(Duration::from_secs(10));
+        db.set("short".to_string(), Bytes::from("a"), short)
+            .unwrap();
+        let long = Some(Duration::from_secs(100));
+        db.set("long".to_string(), Bytes::from("b"), long).unwrap();
+        db.set("persistent".to_string(), Bytes::from("c"), None)
+            .unwrap();
+        db.push(
+            "list",
+            vec![Bytes::from("x"), Bytes::from("y")],
+            ListEnd::Right,
+        )
+        .unwrap();
+        let list_dump = db.dump("list").unwrap();
+        tokio::time::advance(Duration::from_secs(5)).await;
+
+        let stream = tokio_test::io::Builder::new().write(b"+OK\r\n").build();
+        let mut conn = Connection::new(stream);
+        DebugCmd::reload()
+            .apply(&db, &ServerState::default(), &mut conn)
+            .await
+            .unwrap();
+
+        assert_eq!(db.len(), 4);
+        assert_eq!(db.get("short").unwrap(), Some(Bytes::from("a")));
+        assert_eq!(db.get("long").unwrap(), Some(Bytes::from("b")));
+        assert_eq!(db.get("persistent").unwrap(), Some(Bytes::from("c")));
+        assert_eq!(db.dump("list").unwrap(), list_dump);
+
+        // The remaining time to live is kept, give or take the time taken to
+        // reload.
+        tokio::time::advance(Duration::from_millis(4900)).await;
+        assert_eq!(db.get("short").unwrap(), Some(Bytes::from("a")));
+        tokio::time::advance(Duration::from_millis(200)).await;
+        assert_eq!(db.get("short").unwrap(), None);
+        assert_eq!(db.get("long").unwrap(), Some(Bytes::from("b")));
+        tokio::time::advance(Duration::from_secs(100)).await;
+        assert_eq!(db.get("long").unwrap(), None);
+        assert_eq!(db.get("persistent").unwrap(), Some(Bytes::from("c")));
+    }
+
+    #[tokio::test]
+    async fn test_debug_expire_cycle_removes_expired_keys() {
+        let db = Db::default();
+        let ttl = Some(Duration::from_millis(10));
+        db.set("a".to_string(), Bytes::from("1"), ttl).unwrap();
+        db.set("b".to_string(), Bytes::from("2"), ttl).unwrap();
+        db.set("c".to_string(), Bytes::from("3"), None).unwrap();
+        // Sleeping without yielding keeps the background task from purging
+        // the keys first.
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(db.len(), 3);
+
+        let stream = tokio_test::io::Builder::new().write(b":2\r\n").build();
+        let mut conn = Connection::new(stream);
+        DebugCmd::expire_cycle()
+            .apply(&db, &ServerState::default(), &mut conn)
+            .await
+            .unwrap();
+
+        assert_eq!(db.len(), 1);
+        assert_eq!(db.get("a").unwrap(), None);
+        assert_eq!(db.get("b").unwrap(), None);
+        assert_eq!(db.get("c").unwrap(), Some(Bytes::from("3")));
+        assert_eq!(db.sweep_expired(), 0);
+    }
+}
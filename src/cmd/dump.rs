@@ -0,0 +1,256 @@
+//! Implementation of the `DUMP` and `RESTORE` commands.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::{fmt::Display, time::Duration};
+use tracing::debug;
+
+/// Serialize the value stored at `key`.
+///
+/// Replies with an opaque payload, which [`RestoreCmd`] recreates the value
+/// from, or with a null bulk string if the key does not exist. The time to
+/// live of the key is not part of the payload.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DumpCmd {
+    key: String,
+}
+
+impl DumpCmd {
+    /// Creates a new [`DumpCmd`] command.
+    pub fn new(key: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+        }
+    }
+
+    /// Returns a reference to the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Command for DumpCmd {
+    /// Parse a [`DumpCmd`] instance from a received frame.
+    ///
+    /// The `DUMP` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// DUMP key
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        Ok(Self { key })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = match db.dump(&self.key) {
+            Some(payload) => Frame::BulkString(payload),
+            None => Frame::NullBulkString,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("dump"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        Ok(frame)
+    }
+}
+
+/// Recreate `key` from a payload produced by [`DumpCmd`].
+///
+/// A zero `ttl`, in milliseconds, restores the key without an expiration.
+/// Replies with an error if the key already exists, unless `REPLACE` is given.
+///
+/// # Options
+///
+/// * REPLACE -- Overwrite `key` if it already exists.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RestoreCmd {
+    /// The key to restore.
+    key: String,
+    /// Time to live of the restored key.
+    ttl: Option<Duration>,
+    /// The payload produced by `DUMP`.
+    payload: Bytes,
+    /// Whether to overwrite an existing key.
+    replace: bool,
+}
+
+impl RestoreCmd {
+    /// Creates a new [`RestoreCmd`] command, which does not overwrite an
+    /// existing key.
+    pub fn new(key: impl ToString, ttl: Option<Duration>, payload: Bytes) -> Self {
+        Self {
+            key: key.to_string(),
+            ttl,
+            payload,
+            replace: false,
+        }
+    }
+
+    /// Set whether an existing key is overwritten.
+    pub fn replace(mut self, replace: bool) -> Self {
+        self.replace = replace;
+        self
+    }
+
+    /// Returns a reference to the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the time to live of the restored key.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Get the payload.
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+}
+
+impl Command for RestoreCmd {
+    /// Parse a [`RestoreCmd`] instance from a received frame.
+    ///
+    /// The `RESTORE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// RESTORE key ttl serialized-value [REPLACE]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        // Parse the TTL as signed, in order to report negative TTLs
+        // accurately.
+        let ttl = u64::try_from(parse.next_int_signed()?)
+            .map_err(|_| Error::Protocol("Invalid TTL value, must be >= 0".to_string()))?;
+        let ttl = (ttl > 0).then(|| Duration::from_millis(ttl));
+        let payload = parse.next_bytes()?;
+        let mut replace = false;
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "REPLACE" => replace = true,
+                Ok(_) => return Err(Error::Syntax),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Self {
+            key,
+            ttl,
+            payload,
+            replace,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        db.restore(self.key, &self.payload, self.ttl, self.replace)?;
+        let response = Frame::SimpleString("OK".to_string());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("restore"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_int(self.ttl.map_or(0, |ttl| ttl.as_millis() as i64))?;
+        frame.push_bulk(self.payload)?;
+        if self.replace {
+            frame.push_bulk(Bytes::from("replace"))?;
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for RestoreCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ttl = self.ttl.map_or(0, |ttl| ttl.as_millis());
+        write!(f, "RESTORE {} {ttl} {:?}", self.key, self.payload)?;
+        if self.replace {
+            write!(f, " REPLACE")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    /// Apply `cmd`, expecting `reply` to be written.
+    async fn restore(db: &Db, cmd: RestoreCmd, reply: &[u8]) {
+        let stream = tokio_test::io::Builder::new().write(reply).build();
+        let mut conn = Connection::new(stream);
+        cmd.apply(db, &mut conn).await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dump_restore_round_trip() {
+        let db = Db::default();
+        db.set("foo".to_string(), Bytes::from("bar"), None).unwrap();
+        let payload = db.dump("foo").unwrap();
+        assert!(db.dump("missing").is_none());
+
+        // Without a TTL, the key is kept.
+        restore(
+            &db,
+            RestoreCmd::new("copy", None, payload.clone()),
+            b"+OK\r\n",
+        )
+        .await;
+        assert_eq!(db.get("copy").unwrap(), Some(Bytes::from("bar")));
+
+        // The key exists, so it is only overwritten with `REPLACE`.
+        let stream = tokio_test::io::Builder::new().build();
+        let mut conn = Connection::new(stream);
+        let err = RestoreCmd::new("copy", None, payload.clone())
+            .apply(&db, &mut conn)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::BusyKey));
+
+        // With a TTL, the key expires.
+        let ttl = Some(Duration::from_secs(10));
+        let cmd = RestoreCmd::new("copy", ttl, payload).replace(true);
+        restore(&db, cmd, b"+OK\r\n").await;
+        assert_eq!(db.get("copy").unwrap(), Some(Bytes::from("bar")));
+        tokio::time::advance(Duration::from_secs(11)).await;
+        assert!(db.dump("copy").is_none());
+        assert_eq!(db.get("copy").unwrap(), None);
+    }
+}
@@ -0,0 +1,246 @@
+//! Implementation of the `EXPIRE` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::{fmt::Display, time::Duration};
+use tracing::debug;
+
+/// Set a time to live on `key`, in seconds.
+///
+/// Replies with `1` if the time to live was set, or `0` if the key does not
+/// exist or the condition is not met. A time to live which is not positive
+/// expires the key right away.
+///
+/// # Options
+///
+/// * NX -- Only set the time to live if the key has none.
+/// * XX -- Only set the time to live if the key has one.
+/// * GT -- Only set the time to live if it is greater than the current one.
+/// * LT -- Only set the time to live if it is less than the current one.
+///
+/// A key without a time to live counts as having an infinite one, for `GT`
+/// and `LT`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExpireCmd {
+    /// The key to set the time to live on.
+    key: String,
+    /// The time to live, in seconds.
+    seconds: i64,
+    /// The condition for setting the time to live, if any.
+    condition: Option<ExpireCondition>,
+}
+
+/// When `EXPIRE` sets the time to live of a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpireCondition {
+    /// Only if the key has no time to live.
+    Nx,
+    /// Only if the key has a time to live.
+    Xx,
+    /// Only if the new time to live is greater than the current one.
+    Gt,
+    /// Only if the new time to live is less than the current one.
+    Lt,
+}
+
+impl Display for ExpireCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpireCondition::Nx => write!(f, "NX"),
+            ExpireCondition::Xx => write!(f, "XX"),
+            ExpireCondition::Gt => write!(f, "GT"),
+            ExpireCondition::Lt => write!(f, "LT"),
+        }
+    }
+}
+
+impl ExpireCmd {
+    /// Creates a new [`ExpireCmd`] command.
+    pub fn new(key: impl ToString, seconds: i64, condition: Option<ExpireCondition>) -> Self {
+        Self {
+            key: key.to_string(),
+            seconds,
+            condition,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the time to live, in seconds.
+    pub fn seconds(&self) -> i64 {
+        self.seconds
+    }
+
+    /// Get the condition.
+    pub fn condition(&self) -> Option<ExpireCondition> {
+        self.condition
+    }
+}
+
+impl Command for ExpireCmd {
+    /// Parse a [`ExpireCmd`] instance from a received frame.
+    ///
+    /// The `EXPIRE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// EXPIRE key seconds [NX | XX | GT | LT]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+
+        // The expire time must fit in milliseconds, like Redis requires.
+        let seconds = parse.next_int_signed()?;
+        if seconds.checked_mul(1000).is_none() {
+            return Err(Error::Protocol(
+                "invalid expire time in 'expire' command".to_string(),
+            ));
+        }
+
+        let condition = match parse.next_string() {
+            Ok(s) => Some(match s.to_uppercase().as_str() {
+                "NX" => ExpireCondition::Nx,
+                "XX" => ExpireCondition::Xx,
+                "GT" => ExpireCondition::Gt,
+                "LT" => ExpireCondition::Lt,
+                _ => return Err(Error::Syntax),
+            }),
+            Err(Error::EndOfStream) => None,
+            Err(err) => return Err(err),
+        };
+        if parse.remaining() > 0 {
+            return Err(Error::Syntax);
+        }
+
+        Ok(Self {
+            key,
+            seconds,
+            condition,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let ttl = Duration::from_secs(self.seconds.try_into().unwrap_or_default());
+        let updated = db.expire(&self.key, ttl, self.condition)?;
+        let response = Frame::Integer(updated as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("expire"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_int(self.seconds)?;
+        if let Some(condition) = self.condition {
+            frame.push_bulk(Bytes::from(condition.to_string()))?;
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for ExpireCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EXPIRE {} {}", self.key, self.seconds)?;
+        if let Some(condition) = self.condition {
+            write!(f, " {condition}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    /// Applies `EXPIRE key seconds condition` to `db`, returning whether the
+    /// time to live was set.
+    async fn expire(db: &Db, key: &str, seconds: i64, condition: Option<ExpireCondition>) -> bool {
+        let (client, server) = tokio::io::duplex(64);
+        let mut conn = Connection::new(server);
+        ExpireCmd::new(key, seconds, condition)
+            .apply(db, &mut conn)
+            .await
+            .unwrap();
+        drop(conn);
+        match Connection::new(client).read_frame().await.unwrap() {
+            Some(Frame::Integer(updated)) => updated == 1,
+            frame => panic!("unexpected frame: {frame:?}"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_expire_conditions() {
+        let db = Db::default();
+        for key in ["nx", "xx", "gt", "lt"] {
+            db.set(key.to_string(), Bytes::from("a"), None).unwrap();
+        }
+
+        assert!(!expire(&db, "missing", 10, None).await);
+
+        // Without a time to live, only NX and LT apply. A missing time to
+        // live is infinite, so GT never applies.
+        assert!(expire(&db, "nx", 10, Some(ExpireCondition::Nx)).await);
+        assert!(!expire(&db, "xx", 10, Some(ExpireCondition::Xx)).await);
+        assert!(!expire(&db, "gt", 10, Some(ExpireCondition::Gt)).await);
+        assert!(expire(&db, "lt", 10, Some(ExpireCondition::Lt)).await);
+
+        // With a time to live, NX no longer applies while XX does.
+        assert!(!expire(&db, "nx", 20, Some(ExpireCondition::Nx)).await);
+        assert!(expire(&db, "xx", 20, None).await);
+        assert!(expire(&db, "xx", 5, Some(ExpireCondition::Xx)).await);
+
+        // GT and LT compare with the current time to live.
+        assert!(expire(&db, "gt", 10, None).await);
+        assert!(!expire(&db, "gt", 5, Some(ExpireCondition::Gt)).await);
+        assert!(expire(&db, "gt", 20, Some(ExpireCondition::Gt)).await);
+        assert!(!expire(&db, "lt", 20, Some(ExpireCondition::Lt)).await);
+        assert!(expire(&db, "lt", 5, Some(ExpireCondition::Lt)).await);
+
+        tokio::time::advance(Duration::from_secs(6)).await;
+        for (key, live) in [("nx", true), ("xx", false), ("gt", true), ("lt", false)] {
+            assert_eq!(db.get(key).unwrap().is_some(), live, "{key}");
+        }
+
+        // A time to live which is not positive expires the key right away.
+        assert!(expire(&db, "nx", -1, None).await);
+        assert_eq!(db.get("nx").unwrap(), None);
+    }
+
+    #[test]
+    fn test_expire_parse() {
+        for (args, expected) in [
+            (&["EXPIRE", "foo", "10", "SOON"][..], "syntax error"),
+            (&["EXPIRE", "foo", "10", "NX", "XX"], "syntax error"),
+            (
+                &["EXPIRE", "foo", "9223372036854775807"],
+                "protocol error: invalid expire time",
+            ),
+        ] {
+            let frame = Frame::Array(
+                args.iter()
+                    .map(|arg| Frame::BulkString(Bytes::from(arg.to_string())))
+                    .collect(),
+            );
+            let err = crate::CommandVariant::from_frame(frame).unwrap_err();
+            assert!(err.to_string().starts_with(expected), "{err}");
+        }
+    }
+}
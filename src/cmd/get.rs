@@ -41,8 +41,8 @@ impl Command for GetCmd {
         db: &crate::Db,
         dst: &mut crate::Connection<S>,
     ) -> Result<()> {
-        let response = if let Some(value) = db.get(&self.key) {
-            Frame::BulkString(value.clone())
+        let response = if let Some(value) = db.get(&self.key)? {
+            Frame::BulkString(value)
         } else {
             Frame::NullBulkString
         };
@@ -61,3 +61,27 @@ impl Command for GetCmd {
         Ok(frame)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::ListEnd, Connection, Db, Error};
+
+    #[tokio::test]
+    async fn test_get_wrong_type() {
+        let db = Db::default();
+        db.push("foo", vec![Bytes::from("a")], ListEnd::Left)
+            .unwrap();
+
+        let stream = tokio_test::io::Builder::new().build();
+        let mut conn = Connection::new(stream);
+        let err = GetCmd::new("foo").apply(&db, &mut conn).await.unwrap_err();
+        assert!(matches!(err, Error::WrongType));
+        assert_eq!(
+            err.to_frame(),
+            Some(Frame::SimpleError(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Implementation of the `GETEX` command.
+use super::Command;
+use crate::{db::Expiry, parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::{
+    fmt::Display,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::debug;
+
+/// Get the `value` for a given `key`, and optionally update its time to live.
+///
+/// If the key does not exist, a `Null` RESP type is returned. Without an
+/// option, this behaves like `GET`.
+///
+/// # Options
+///
+/// * EX `seconds` -- Set the specified expire time, in seconds.
+/// * PX `milliseconds` -- Set the specified expire time, in milliseconds.
+/// * EXAT `timestamp` -- Set the specified unix time at which the key
+///   expires, in seconds.
+/// * PXAT `timestamp` -- Set the specified unix time at which the key
+///   expires, in milliseconds.
+/// * PERSIST -- Remove the time to live of the key.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetExCmd {
+    /// The lookup key.
+    key: String,
+    /// How to update the time to live of the key.
+    option: Option<GetExOption>,
+}
+
+/// How `GETEX` updates the time to live of a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GetExOption {
+    /// Expire after the given number of seconds.
+    Ex(u64),
+    /// Expire after the given number of milliseconds.
+    Px(u64),
+    /// Expire at the given unix time, in seconds.
+    ExAt(u64),
+    /// Expire at the given unix time, in milliseconds.
+    PxAt(u64),
+    /// Remove the time to live.
+    Persist,
+}
+
+impl GetExOption {
+    /// Converts the option into a TTL change, relative to the current time.
+    ///
+    /// Unix times in the past expire the key right away.
+    fn expiry(self) -> Expiry {
+        let at = |timestamp: Duration| {
+            (UNIX_EPOCH + timestamp)
+                .duration_since(SystemTime::now())
+                .unwrap_or_default()
+        };
+        match self {
+            GetExOption::Ex(secs) => Expiry::After(Duration::from_secs(secs)),
+            GetExOption::Px(ms) => Expiry::After(Duration::from_millis(ms)),
+            GetExOption::ExAt(secs) => Expiry::After(at(Duration::from_secs(secs))),
+            GetExOption::PxAt(ms) => Expiry::After(at(Duration::from_millis(ms))),
+            GetExOption::Persist => Expiry::Persist,
+        }
+    }
+}
+
+impl Display for GetExOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GetExOption::Ex(secs) => write!(f, "EX {secs}"),
+            GetExOption::Px(ms) => write!(f, "PX {ms}"),
+            GetExOption::ExAt(secs) => write!(f, "EXAT {secs}"),
+            GetExOption::PxAt(ms) => write!(f, "PXAT {ms}"),
+            GetExOption::Persist => write!(f, "PERSIST"),
+        }
+    }
+}
+
+impl GetExCmd {
+    /// Creates a new [`GetExCmd`] command.
+    pub fn new(key: impl ToString, option: Option<GetExOption>) -> Self {
+        Self {
+            key: key.to_string(),
+            option,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the option.
+    pub fn option(&self) -> Option<GetExOption> {
+        self.option
+    }
+}
+
+impl Command for GetExCmd {
+    /// Parse a [`GetExCmd`] instance from a received frame.
+    ///
+    /// The `GETEX` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// GETEX key [EX seconds | PX milliseconds | EXAT timestamp | PXAT timestamp | PERSIST]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+
+        // Expire times must be positive, like Redis requires.
+        let expire_time = |parse: &mut Parse| match parse.next_int_signed()? {
+            time if time > 0 => Ok(time as u64),
+            _ => Err(Error::Protocol(
+                "invalid expire time in 'getex' command".to_string(),
+            )),
+        };
+        let option = match parse.next_string() {
+            Ok(s) => Some(match s.to_uppercase().as_str() {
+                "EX" => GetExOption::Ex(expire_time(parse)?),
+                "PX" => GetExOption::Px(expire_time(parse)?),
+                "EXAT" => GetExOption::ExAt(expire_time(parse)?),
+                "PXAT" => GetExOption::PxAt(expire_time(parse)?),
+                "PERSIST" => GetExOption::Persist,
+                _ => return Err(Error::Protocol("syntax error".to_string())),
+            }),
+            Err(Error::EndOfStream) => None,
+            Err(err) => return Err(err),
+        };
+        parse
+            .finish()
+            .map_err(|_| Error::Protocol("syntax error".to_string()))?;
+
+        Ok(Self { key, option })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let expiry = self.option.map(GetExOption::expiry);
+        let response = match db.get_ex(&self.key, expiry)? {
+            Some(value) => Frame::BulkString(value),
+            None => Frame::NullBulkString,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("getex"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        match self.option {
+            Some(GetExOption::Ex(secs)) => {
+                frame.push_bulk(Bytes::from("ex"))?;
+                frame.push_int(secs as i64)?;
+            }
+            Some(GetExOption::Px(ms)) => {
+                frame.push_bulk(Bytes::from("px"))?;
+                frame.push_int(ms as i64)?;
+            }
+            Some(GetExOption::ExAt(secs)) => {
+                frame.push_bulk(Bytes::from("exat"))?;
+                frame.push_int(secs as i64)?;
+            }
+            Some(GetExOption::PxAt(ms)) => {
+                frame.push_bulk(Bytes::from("pxat"))?;
+                frame.push_int(ms as i64)?;
+            }
+            Some(GetExOption::Persist) => frame.push_bulk(Bytes::from("persist"))?,
+            None => {}
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for GetExCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.option {
+            Some(option) => write!(f, "GETEX {} {option}", self.key),
+            None => write!(f, "GETEX {}", self.key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    /// Applies `GETEX key option` to `db`, expecting the value `a` back.
+    async fn getex(db: &Db, key: &str, option: Option<GetExOption>) {
+        let stream = tokio_test::io::Builder::new().write(b"$1\r\na\r\n").build();
+        let mut conn = Connection::new(stream);
+        GetExCmd::new(key, option)
+            .apply(db, &mut conn)
+            .await
+            .unwrap();
+    }
+
+    fn unix_time() -> Duration {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_getex_options() {
+        let db = Db::default();
+        let a = Some(Bytes::from("a"));
+        for key in ["none", "ex", "px", "exat", "pxat", "persist"] {
+            db.set(
+                key.to_string(),
+                Bytes::from("a"),
+                Some(Duration::from_secs(5)),
+            )
+            .unwrap();
+        }
+        db.set("past".to_string(), Bytes::from("a"), None).unwrap();
+
+        getex(&db, "none", None).await;
+        getex(&db, "ex", Some(GetExOption::Ex(20))).await;
+        getex(&db, "px", Some(GetExOption::Px(20_000))).await;
+        let exat = unix_time().as_secs() + 20;
+        getex(&db, "exat", Some(GetExOption::ExAt(exat))).await;
+        let pxat = unix_time().as_millis() as u64 + 20_000;
+        getex(&db, "pxat", Some(GetExOption::PxAt(pxat))).await;
+        getex(&db, "persist", Some(GetExOption::Persist)).await;
+        // A unix time in the past still replies with the value, but expires
+        // the key right away.
+        getex(&db, "past", Some(GetExOption::PxAt(1))).await;
+        assert_eq!(db.get("past").unwrap(), None);
+
+        // Without an option, the TTL is left untouched.
+        tokio::time::advance(Duration::from_secs(6)).await;
+        assert_eq!(db.get("none").unwrap(), None);
+        for key in ["ex", "px", "exat", "pxat", "persist"] {
+            assert_eq!(db.get(key).unwrap(), a, "{key}");
+        }
+
+        tokio::time::advance(Duration::from_secs(15)).await;
+        for key in ["ex", "px", "exat", "pxat"] {
+            assert_eq!(db.get(key).unwrap(), None, "{key}");
+        }
+        assert_eq!(db.get("persist").unwrap(), a);
+    }
+
+    #[tokio::test]
+    async fn test_getex_wrong_type() {
+        let db = Db::default();
+        db.sadd("foo", vec![Bytes::from("a")]).unwrap();
+        assert!(matches!(
+            db.get_ex("foo", Some(Expiry::Persist)),
+            Err(Error::WrongType)
+        ));
+        assert_eq!(db.get_ex("bar", Some(Expiry::Persist)).unwrap(), None);
+    }
+}
@@ -0,0 +1,106 @@
+//! Implementation of the `GETSET` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Set `key` to hold the string `value`, replying with the value previously
+/// stored at `key`.
+///
+/// If the key did not exist, a `Null` RESP type is returned. Any previous time
+/// to live associated with the key is discarded. This is equivalent to
+/// `SET key value GET`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetSetCmd {
+    key: String,
+    value: Bytes,
+}
+
+impl GetSetCmd {
+    /// Creates a new [`GetSetCmd`] command.
+    pub fn new(key: impl ToString, value: Bytes) -> Self {
+        Self {
+            key: key.to_string(),
+            value,
+        }
+    }
+
+    /// Returns a reference to the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the value.
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+}
+
+impl Command for GetSetCmd {
+    /// Parse a [`GetSetCmd`] instance from a received frame.
+    ///
+    /// The `GETSET` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// GETSET key value
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let value = parse.next_bytes()?;
+        Ok(Self { key, value })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = match db.set_returning_previous(self.key, self.value, None)? {
+            Some(previous) => Frame::BulkString(previous),
+            None => Frame::NullBulkString,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("getset"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_bulk(self.value)?;
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    #[tokio::test]
+    async fn test_getset_replies_with_previous_value() {
+        let db = Db::default();
+        let stream = tokio_test::io::Builder::new()
+            .write(b"$-1\r\n")
+            .write(b"$1\r\na\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        for value in ["a", "b"] {
+            GetSetCmd::new("foo", Bytes::from(value))
+                .apply(&db, &mut conn)
+                .await
+                .unwrap();
+        }
+        assert_eq!(db.get("foo").unwrap(), Some(Bytes::from("b")));
+    }
+}
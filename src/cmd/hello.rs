@@ -0,0 +1,160 @@
+//! Implementation of the `HELLO` command.
+use super::Command;
+use crate::{frame::Protocol, parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Switch the protocol of the connection, and describe the server.
+///
+/// Without a protocol version, the connection keeps its current protocol.
+/// Replies with a map of properties of the server, which is a flat array of
+/// alternating keys and values under RESP2.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct HelloCmd {
+    /// Protocol to switch the connection to.
+    protocol: Option<Protocol>,
+}
+
+impl HelloCmd {
+    /// Creates a new [`HelloCmd`] command.
+    pub fn new(protocol: Option<Protocol>) -> Self {
+        Self { protocol }
+    }
+
+    /// Get the protocol to switch to.
+    pub fn protocol(&self) -> Option<Protocol> {
+        self.protocol
+    }
+}
+
+impl Command for HelloCmd {
+    /// Parse a [`HelloCmd`] instance from a received frame.
+    ///
+    /// The `HELLO` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HELLO [protover]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let protocol = match parse.next_int_signed() {
+            Ok(2) => Some(Protocol::Resp2),
+            Ok(3) => Some(Protocol::Resp3),
+            Ok(_) => return Err(Error::NoProto),
+            Err(Error::EndOfStream) => None,
+            Err(Error::Protocol(_)) => {
+                return Err(Error::Protocol(
+                    "Protocol version is not an integer or out of range".to_string(),
+                ))
+            }
+            Err(err) => return Err(err),
+        };
+        // Authenticating and naming the connection are not supported.
+        if parse.remaining() > 0 {
+            return Err(Error::Syntax);
+        }
+        Ok(Self { protocol })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        _db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        if let Some(protocol) = self.protocol {
+            dst.set_protocol(protocol);
+        }
+        let property = |name: &'static str, value| (Frame::BulkString(Bytes::from(name)), value);
+        let response = Frame::Map(vec![
+            property("server", Frame::BulkString(Bytes::from("loja"))),
+            property(
+                "version",
+                Frame::BulkString(Bytes::from(env!("CARGO_PKG_VERSION"))),
+            ),
+            property("proto", Frame::Integer(dst.protocol().version())),
+            property("mode", Frame::BulkString(Bytes::from("standalone"))),
+            property("role", Frame::BulkString(Bytes::from("master"))),
+            property("modules", Frame::array()),
+        ]);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hello"))?;
+        if let Some(protocol) = self.protocol {
+            frame.push_int(protocol.version())?;
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for HelloCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HELLO")?;
+        if let Some(protocol) = self.protocol {
+            write!(f, " {}", protocol.version())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    #[tokio::test]
+    async fn test_hello_reply_depends_on_protocol() {
+        let db = Db::default();
+        let (client, server) = tokio::io::duplex(1024);
+        let mut conn = Connection::new(server);
+        let mut client = Connection::new(client);
+
+        // The connection starts with RESP2, where the reply is an array.
+        HelloCmd::new(None).apply(&db, &mut conn).await.unwrap();
+        let Some(Frame::Array(reply)) = client.read_frame().await.unwrap() else {
+            panic!("unexpected reply");
+        };
+        assert_eq!(reply.len(), 12);
+        assert_eq!(reply[4], Frame::BulkString(Bytes::from("proto")));
+        assert_eq!(reply[5], Frame::Integer(2));
+
+        // Once RESP3 is negotiated, the same command replies with a map.
+        HelloCmd::new(Some(Protocol::Resp3))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        assert_eq!(conn.protocol(), Protocol::Resp3);
+        let Some(Frame::Map(reply)) = client.read_frame().await.unwrap() else {
+            panic!("unexpected reply");
+        };
+        assert_eq!(reply.len(), 6);
+        assert_eq!(
+            reply[2],
+            (Frame::BulkString(Bytes::from("proto")), Frame::Integer(3))
+        );
+
+        // Unsupported versions are rejected.
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("HELLO")),
+            Frame::BulkString(Bytes::from("4")),
+        ]);
+        let err = crate::CommandVariant::from_frame(frame).unwrap_err();
+        assert_eq!(
+            err.to_frame().unwrap(),
+            Frame::SimpleError("NOPROTO unsupported protocol version".to_string())
+        );
+    }
+}
@@ -0,0 +1,29 @@
+//! The `HELP` subcommand shared by the commands with subcommands.
+use crate::{Error, Frame, Result};
+use bytes::Bytes;
+
+/// Creates the reply to `<command> HELP`.
+///
+/// `lines` documents every other subcommand, usually its syntax followed by
+/// an indented description, like Redis does. The reply opens with a summary
+/// line and closes with the documentation of `HELP` itself.
+pub(crate) fn help_frame(command: &str, lines: &[&str]) -> Result<Frame> {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from(format!(
+        "{command} <subcommand> [<arg> [value] [opt] ...]. Subcommands are:"
+    )))?;
+    for line in lines {
+        frame.push_bulk(Bytes::copy_from_slice(line.as_bytes()))?;
+    }
+    frame.push_bulk(Bytes::from_static(b"HELP"))?;
+    frame.push_bulk(Bytes::from_static(b"    Print this help."))?;
+    Ok(frame)
+}
+
+/// The error reported for a `subcommand` which `command` does not support.
+pub(crate) fn unknown_subcommand(command: &str, subcommand: impl ToString) -> Error {
+    Error::UnknownSubcommand {
+        command: command.to_string(),
+        subcommand: subcommand.to_string(),
+    }
+}
@@ -0,0 +1,486 @@
+//! Implementation of the `HEXPIRE`, `HTTL` and `HPERSIST` commands.
+use super::{Command, ExpireCondition};
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::{fmt::Display, time::Duration};
+use tracing::debug;
+
+/// Set a time to live on `fields` of the hash stored at `key`, in seconds.
+///
+/// Replies with an array holding, for each field, `-2` if the field or the
+/// key do not exist, `0` if the condition is not met, `1` if the time to live
+/// was set, or `2` if the field was deleted because the time to live is not
+/// positive. Setting a field with `HSET` clears its time to live.
+///
+/// # Options
+///
+/// * NX -- Only set the time to live if the field has none.
+/// * XX -- Only set the time to live if the field has one.
+/// * GT -- Only set the time to live if it is greater than the current one.
+/// * LT -- Only set the time to live if it is less than the current one.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HExpireCmd {
+    /// The key of the hash.
+    key: String,
+    /// The time to live, in seconds.
+    seconds: i64,
+    /// The condition for setting the time to live, if any.
+    condition: Option<ExpireCondition>,
+    /// The fields to set the time to live on.
+    fields: Vec<Bytes>,
+}
+
+impl HExpireCmd {
+    /// Creates a new [`HExpireCmd`] command.
+    pub fn new(
+        key: impl ToString,
+        seconds: i64,
+        condition: Option<ExpireCondition>,
+        fields: Vec<Bytes>,
+    ) -> Self {
+        Self {
+            key: key.to_string(),
+            seconds,
+            condition,
+            fields,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the time to live, in seconds.
+    pub fn seconds(&self) -> i64 {
+        self.seconds
+    }
+
+    /// Get the condition.
+    pub fn condition(&self) -> Option<ExpireCondition> {
+        self.condition
+    }
+
+    /// Get the fields.
+    pub fn fields(&self) -> &[Bytes] {
+        &self.fields
+    }
+}
+
+impl Command for HExpireCmd {
+    /// Parse a [`HExpireCmd`] instance from a received frame.
+    ///
+    /// The `HEXPIRE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HEXPIRE key seconds [NX | XX | GT | LT] FIELDS numfields field [field ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+
+        // The expire time must fit in milliseconds, like Redis requires.
+        let seconds = parse.next_int_signed()?;
+        if seconds.checked_mul(1000).is_none() {
+            return Err(Error::Protocol(
+                "invalid expire time in 'hexpire' command".to_string(),
+            ));
+        }
+
+        let mut keyword = parse.next_string()?;
+        let condition = match keyword.to_uppercase().as_str() {
+            "NX" => Some(ExpireCondition::Nx),
+            "XX" => Some(ExpireCondition::Xx),
+            "GT" => Some(ExpireCondition::Gt),
+            "LT" => Some(ExpireCondition::Lt),
+            _ => None,
+        };
+        if condition.is_some() {
+            keyword = parse.next_string()?;
+        }
+        let fields = parse_fields(parse, &keyword)?;
+
+        Ok(Self {
+            key,
+            seconds,
+            condition,
+            fields,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let ttl = Duration::from_secs(self.seconds.try_into().unwrap_or_default());
+        let results = db.hexpire(&self.key, ttl, self.condition, &self.fields)?;
+        let response = Frame::Array(results.into_iter().map(Frame::Integer).collect());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hexpire"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_int(self.seconds)?;
+        if let Some(condition) = self.condition {
+            frame.push_bulk(Bytes::from(condition.to_string()))?;
+        }
+        push_fields(&mut frame, self.fields)?;
+        Ok(frame)
+    }
+}
+
+impl Display for HExpireCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HEXPIRE {} {}", self.key, self.seconds)?;
+        if let Some(condition) = self.condition {
+            write!(f, " {condition}")?;
+        }
+        write_fields(f, &self.fields)
+    }
+}
+
+/// Get the remaining time to live of `fields` of the hash stored at `key`, in
+/// seconds.
+///
+/// Replies with an array holding, for each field, `-2` if the field or the
+/// key do not exist, `-1` if the field has no time to live, or the time to
+/// live otherwise.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HTtlCmd {
+    /// The key of the hash.
+    key: String,
+    /// The fields to get the time to live of.
+    fields: Vec<Bytes>,
+}
+
+impl HTtlCmd {
+    /// Creates a new [`HTtlCmd`] command.
+    pub fn new(key: impl ToString, fields: Vec<Bytes>) -> Self {
+        Self {
+            key: key.to_string(),
+            fields,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the fields.
+    pub fn fields(&self) -> &[Bytes] {
+        &self.fields
+    }
+}
+
+impl Command for HTtlCmd {
+    /// Parse a [`HTtlCmd`] instance from a received frame.
+    ///
+    /// The `HTTL` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HTTL key FIELDS numfields field [field ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let keyword = parse.next_string()?;
+        let fields = parse_fields(parse, &keyword)?;
+        Ok(Self { key, fields })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let ttls = db.httl(&self.key, &self.fields)?;
+        let response = Frame::Array(ttls.into_iter().map(Frame::Integer).collect());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("httl"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        push_fields(&mut frame, self.fields)?;
+        Ok(frame)
+    }
+}
+
+impl Display for HTtlCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HTTL {}", self.key)?;
+        write_fields(f, &self.fields)
+    }
+}
+
+/// Remove the time to live of `fields` of the hash stored at `key`.
+///
+/// Replies with an array holding, for each field, `-2` if the field or the
+/// key do not exist, `-1` if the field has no time to live, or `1` if its
+/// time to live was removed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HPersistCmd {
+    /// The key of the hash.
+    key: String,
+    /// The fields to remove the time to live of.
+    fields: Vec<Bytes>,
+}
+
+impl HPersistCmd {
+    /// Creates a new [`HPersistCmd`] command.
+    pub fn new(key: impl ToString, fields: Vec<Bytes>) -> Self {
+        Self {
+            key: key.to_string(),
+            fields,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the fields.
+    pub fn fields(&self) -> &[Bytes] {
+        &self.fields
+    }
+}
+
+impl Command for HPersistCmd {
+    /// Parse a [`HPersistCmd`] instance from a received frame.
+    ///
+    /// The `HPERSIST` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HPERSIST key FIELDS numfields field [field ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let keyword = parse.next_string()?;
+        let fields = parse_fields(parse, &keyword)?;
+        Ok(Self { key, fields })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let results = db.hpersist(&self.key, &self.fields)?;
+        let response = Frame::Array(results.into_iter().map(Frame::Integer).collect());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hpersist"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        push_fields(&mut frame, self.fields)?;
+        Ok(frame)
+    }
+}
+
+impl Display for HPersistCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HPERSIST {}", self.key)?;
+        write_fields(f, &self.fields)
+    }
+}
+
+/// Parse the `FIELDS numfields field [field ...]` block closing the command,
+/// whose `FIELDS` keyword was already consumed as `keyword`.
+fn parse_fields(parse: &mut Parse, keyword: &str) -> Result<Vec<Bytes>> {
+    if !keyword.eq_ignore_ascii_case("FIELDS") {
+        return Err(Error::Protocol(
+            "Mandatory argument FIELDS is missing or not at the right position".to_string(),
+        ));
+    }
+
+    let numfields = parse.next_int_unsigned()?;
+    if numfields == 0 {
+        return Err(Error::Protocol(
+            "Parameter `numFields` should be greater than 0".to_string(),
+        ));
+    }
+    if parse.remaining() as u64 != numfields {
+        return Err(Error::Protocol(
+            "The `numfields` parameter must match the number of arguments".to_string(),
+        ));
+    }
+
+    (0..numfields).map(|_| parse.next_bytes()).collect()
+}
+
+/// Push the `FIELDS numfields field [field ...]` block closing the command.
+fn push_fields(frame: &mut Frame, fields: Vec<Bytes>) -> Result<()> {
+    frame.push_bulk(Bytes::from("FIELDS"))?;
+    frame.push_int(fields.len() as i64)?;
+    for field in fields {
+        frame.push_bulk(field)?;
+    }
+    Ok(())
+}
+
+/// Write the `FIELDS numfields field [field ...]` block closing the command.
+fn write_fields(f: &mut std::fmt::Formatter<'_>, fields: &[Bytes]) -> std::fmt::Result {
+    write!(f, " FIELDS {}", fields.len())?;
+    for field in fields {
+        write!(f, " {field:?}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    /// Applies `cmd` to `db`, returning the integers of the reply.
+    async fn apply(db: &Db, cmd: impl Command) -> Vec<i64> {
+        let (client, server) = tokio::io::duplex(256);
+        let mut conn = Connection::new(server);
+        cmd.apply(db, &mut conn).await.unwrap();
+        drop(conn);
+        match Connection::new(client).read_frame().await.unwrap() {
+            Some(Frame::Array(items)) => items
+                .into_iter()
+                .map(|item| match item {
+                    Frame::Integer(n) => n,
+                    frame => panic!("unexpected frame: {frame:?}"),
+                })
+                .collect(),
+            frame => panic!("unexpected frame: {frame:?}"),
+        }
+    }
+
+    fn fields(fields: &[&'static str]) -> Vec<Bytes> {
+        fields.iter().map(|field| Bytes::from(*field)).collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_expired_field_is_removed_while_others_remain() {
+        let db = Db::default();
+        let values = vec![
+            (Bytes::from("a"), Bytes::from("1")),
+            (Bytes::from("b"), Bytes::from("2")),
+        ];
+        db.hset("foo", values).unwrap();
+
+        let cmd = HExpireCmd::new("foo", 10, None, fields(&["a", "missing"]));
+        assert_eq!(apply(&db, cmd).await, vec![1, -2]);
+        let cmd = HTtlCmd::new("foo", fields(&["a", "b", "missing"]));
+        assert_eq!(apply(&db, cmd).await, vec![10, -1, -2]);
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(db.hget("foo", b"a").unwrap(), None);
+        assert_eq!(db.hget("foo", b"b").unwrap(), Some(Bytes::from("2")));
+        assert_eq!(db.hkeys("foo").unwrap(), fields(&["b"]));
+
+        // The hash goes away along with its last field.
+        let cmd = HExpireCmd::new("foo", 5, None, fields(&["b"]));
+        assert_eq!(apply(&db, cmd).await, vec![1]);
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert!(!db.exists("foo"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hexpire_conditions_and_hpersist() {
+        let db = Db::default();
+        let values = vec![
+            (Bytes::from("a"), Bytes::from("1")),
+            (Bytes::from("b"), Bytes::from("2")),
+        ];
+        db.hset("foo", values).unwrap();
+
+        let cmd = HExpireCmd::new("foo", 10, Some(ExpireCondition::Xx), fields(&["a"]));
+        assert_eq!(apply(&db, cmd).await, vec![0]);
+        let cmd = HExpireCmd::new("foo", 10, Some(ExpireCondition::Nx), fields(&["a"]));
+        assert_eq!(apply(&db, cmd).await, vec![1]);
+        let cmd = HExpireCmd::new("foo", 5, Some(ExpireCondition::Gt), fields(&["a"]));
+        assert_eq!(apply(&db, cmd).await, vec![0]);
+        let cmd = HExpireCmd::new("foo", 5, Some(ExpireCondition::Lt), fields(&["a"]));
+        assert_eq!(apply(&db, cmd).await, vec![1]);
+
+        let cmd = HPersistCmd::new("foo", fields(&["a", "b", "missing"]));
+        assert_eq!(apply(&db, cmd).await, vec![1, -1, -2]);
+        let cmd = HTtlCmd::new("foo", fields(&["a"]));
+        assert_eq!(apply(&db, cmd).await, vec![-1]);
+
+        // A time to live which is not positive deletes the field right away.
+        let cmd = HExpireCmd::new("foo", 0, None, fields(&["a"]));
+        assert_eq!(apply(&db, cmd).await, vec![2]);
+        assert_eq!(db.hkeys("foo").unwrap(), fields(&["b"]));
+
+        let cmd = HTtlCmd::new("missing", fields(&["a", "b"]));
+        assert_eq!(apply(&db, cmd).await, vec![-2, -2]);
+    }
+
+    #[test]
+    fn test_hexpire_parse() {
+        let parse = |args: &[&str]| {
+            let frame = Frame::Array(
+                args.iter()
+                    .map(|arg| Frame::BulkString(Bytes::from(arg.to_string())))
+                    .collect(),
+            );
+            crate::CommandVariant::from_frame(frame)
+        };
+
+        let cmd = parse(&["HEXPIRE", "foo", "10", "nx", "fields", "2", "a", "b"]).unwrap();
+        assert_eq!(cmd.to_string(), "HEXPIRE foo 10 NX FIELDS 2 b\"a\" b\"b\"");
+
+        for (args, expected) in [
+            (
+                &["HEXPIRE", "foo", "10", "NX", "a", "b"][..],
+                "protocol error: Mandatory argument FIELDS is missing",
+            ),
+            (
+                &["HTTL", "foo", "FIELDS", "2", "a"],
+                "protocol error: The `numfields` parameter must match",
+            ),
+            (
+                &["HPERSIST", "foo", "FIELDS", "0", "a"],
+                "protocol error: Parameter `numFields` should be greater than 0",
+            ),
+        ] {
+            let err = parse(args).unwrap_err();
+            assert!(err.to_string().starts_with(expected), "{err}");
+        }
+    }
+}
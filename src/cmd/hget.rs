@@ -0,0 +1,82 @@
+//! Implementation of the `HGET` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Get the value of `field` in the hash stored at `key`.
+///
+/// If the key or the field do not exist, a `Null` RESP type is returned.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HGetCmd {
+    /// The key of the hash.
+    key: String,
+    /// The field to get the value of.
+    field: Bytes,
+}
+
+impl HGetCmd {
+    /// Creates a new [`HGetCmd`] command.
+    pub fn new(key: impl ToString, field: Bytes) -> Self {
+        Self {
+            key: key.to_string(),
+            field,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the field.
+    pub fn field(&self) -> &Bytes {
+        &self.field
+    }
+}
+
+impl Command for HGetCmd {
+    /// Parse a [`HGetCmd`] instance from a received frame.
+    ///
+    /// The `HGET` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HGET key field
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let field = parse.next_bytes()?;
+        Ok(Self { key, field })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = match db.hget(&self.key, &self.field)? {
+            Some(value) => Frame::BulkString(value),
+            None => Frame::NullBulkString,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hget"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_bulk(self.field)?;
+        Ok(frame)
+    }
+}
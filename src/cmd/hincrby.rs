@@ -0,0 +1,141 @@
+//! Implementation of the `HINCRBY` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Increment the integer stored at `field` in the hash stored at `key` by
+/// `increment`.
+///
+/// If the key or the field do not exist, the field is set to `0` before
+/// performing the operation. An error is returned if the field holds a value
+/// that cannot be interpreted as an integer.
+///
+/// Replies with the value of the field after the increment.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HIncrByCmd {
+    /// The key of the hash.
+    key: String,
+    /// The field to increment.
+    field: Bytes,
+    /// The amount to increment by.
+    increment: i64,
+}
+
+impl HIncrByCmd {
+    /// Creates a new [`HIncrByCmd`] command.
+    pub fn new(key: impl ToString, field: Bytes, increment: i64) -> Self {
+        Self {
+            key: key.to_string(),
+            field,
+            increment,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the field.
+    pub fn field(&self) -> &Bytes {
+        &self.field
+    }
+
+    /// Get the increment.
+    pub fn increment(&self) -> i64 {
+        self.increment
+    }
+}
+
+impl Command for HIncrByCmd {
+    /// Parse a [`HIncrByCmd`] instance from a received frame.
+    ///
+    /// The `HINCRBY` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HINCRBY key field increment
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let field = parse.next_bytes()?;
+        let increment = parse.next_int_signed()?;
+        Ok(Self {
+            key,
+            field,
+            increment,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let value = db.hincr_by(&self.key, self.field, self.increment)?;
+        let response = Frame::Integer(value);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hincrby"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_bulk(self.field)?;
+        frame.push_int(self.increment)?;
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db, Error};
+
+    #[tokio::test]
+    async fn test_hincrby_absent_field() {
+        let db = Db::default();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b":5\r\n")
+            .write(b":2\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        for increment in [5, -3] {
+            HIncrByCmd::new("foo", Bytes::from("a"), increment)
+                .apply(&db, &mut conn)
+                .await
+                .unwrap();
+        }
+        assert_eq!(db.hget("foo", b"a").unwrap(), Some(Bytes::from("2")));
+
+        db.hset("foo", vec![(Bytes::from("b"), Bytes::from("x"))])
+            .unwrap();
+        let err = db.hincr_by("foo", Bytes::from("b"), 1).unwrap_err();
+        assert_eq!(
+            err.to_frame(),
+            Some(Frame::SimpleError(
+                "ERR hash value is not an integer".to_string()
+            ))
+        );
+        let err = db.hincr_by("foo", Bytes::from("a"), i64::MAX).unwrap_err();
+        assert!(matches!(err, Error::Overflow));
+
+        db.set("bar".to_string(), Bytes::from("baz"), None).unwrap();
+        let err = db.hincr_by("bar", Bytes::from("a"), 1).unwrap_err();
+        assert!(err.to_frame().is_some_and(
+            |frame| matches!(frame, Frame::SimpleError(msg) if msg.starts_with("WRONGTYPE"))
+        ));
+    }
+}
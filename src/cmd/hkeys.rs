@@ -0,0 +1,72 @@
+//! Implementation of the `HKEYS` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Get the fields of the hash stored at `key`.
+///
+/// Replies with the field names, in order. A missing key is an empty hash.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HKeysCmd {
+    /// The key of the hash.
+    key: String,
+}
+
+impl HKeysCmd {
+    /// Creates a new [`HKeysCmd`] command.
+    pub fn new(key: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Command for HKeysCmd {
+    /// Parse a [`HKeysCmd`] instance from a received frame.
+    ///
+    /// The `HKEYS` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HKEYS key
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        Ok(Self { key })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let mut response = Frame::array();
+        for field in db.hkeys(&self.key)? {
+            response.push_bulk(field)?;
+        }
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hkeys"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        Ok(frame)
+    }
+}
@@ -0,0 +1,132 @@
+//! Implementation of the `HMGET` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Get the values of `fields` in the hash stored at `key`.
+///
+/// Replies with an array holding the value of every requested field, in
+/// order, with a `Null` RESP type for each field that does not exist.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HMGetCmd {
+    /// The key of the hash.
+    key: String,
+    /// The fields to get the values of.
+    fields: Vec<Bytes>,
+}
+
+impl HMGetCmd {
+    /// Creates a new [`HMGetCmd`] command.
+    pub fn new(key: impl ToString, fields: Vec<Bytes>) -> Self {
+        Self {
+            key: key.to_string(),
+            fields,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the fields.
+    pub fn fields(&self) -> &[Bytes] {
+        &self.fields
+    }
+}
+
+impl Command for HMGetCmd {
+    /// Parse a [`HMGetCmd`] instance from a received frame.
+    ///
+    /// The `HMGET` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HMGET key field [field ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+
+        // At least one field must be provided.
+        let mut fields = vec![parse.next_bytes()?];
+        loop {
+            match parse.next_bytes() {
+                Ok(field) => fields.push(field),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Self { key, fields })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let mut response = Frame::array();
+        for value in db.hmget(&self.key, &self.fields)? {
+            match value {
+                Some(value) => response.push_bulk(value)?,
+                None => response.push_frame(Frame::NullBulkString)?,
+            }
+        }
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hmget"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        for field in self.fields {
+            frame.push_bulk(field)?;
+        }
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    #[tokio::test]
+    async fn test_hmget_present_and_absent_fields() {
+        let db = Db::default();
+        let fields = vec![
+            (Bytes::from("a"), Bytes::from("1")),
+            (Bytes::from("c"), Bytes::from("3")),
+        ];
+        db.hset("foo", fields).unwrap();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*3\r\n$1\r\n1\r\n$-1\r\n$1\r\n3\r\n")
+            .write(b"*2\r\n$-1\r\n$-1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        let fields = vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("c")];
+        HMGetCmd::new("foo", fields)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+
+        let fields = vec![Bytes::from("a"), Bytes::from("b")];
+        HMGetCmd::new("missing", fields)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+    }
+}
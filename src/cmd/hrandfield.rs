@@ -0,0 +1,224 @@
+//! Implementation of the `HRANDFIELD` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Get random fields of the hash stored at `key`.
+///
+/// Without a count, replies with a single field, or null if the key does not
+/// exist. With a positive count, replies with up to `count` distinct fields.
+/// With a negative count, replies with exactly `-count` fields, which may
+/// repeat. A missing key is an empty hash.
+///
+/// # Options
+///
+/// * WITHVALUES -- Reply with the value of each field after the field.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HRandFieldCmd {
+    /// The key of the hash.
+    key: String,
+    /// Number of fields to pick, if any was given.
+    count: Option<i64>,
+    /// Whether to reply with the values.
+    with_values: bool,
+}
+
+impl HRandFieldCmd {
+    /// Creates a new [`HRandFieldCmd`] command, picking a single field.
+    pub fn new(key: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+            count: None,
+            with_values: false,
+        }
+    }
+
+    /// Pick `count` fields, replying with an array.
+    pub fn count(mut self, count: i64) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Reply with the value of each field.
+    ///
+    /// The values are only part of the reply when a count is given too.
+    pub fn with_values(mut self) -> Self {
+        self.with_values = true;
+        self
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Command for HRandFieldCmd {
+    /// Parse a [`HRandFieldCmd`] instance from a received frame.
+    ///
+    /// The `HRANDFIELD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HRANDFIELD key [count [WITHVALUES]]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let (count, with_values) = parse_count(parse, "WITHVALUES")?;
+        Ok(Self {
+            key,
+            count,
+            with_values,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = match self.count {
+            Some(count) => {
+                let mut response = Frame::array();
+                for (field, value) in db.hrandfield(&self.key, count)? {
+                    response.push_bulk(field)?;
+                    if self.with_values {
+                        response.push_bulk(value)?;
+                    }
+                }
+                response
+            }
+            None => match db.hrandfield(&self.key, 1)?.pop() {
+                Some((field, _)) => Frame::BulkString(field),
+                None => Frame::NullBulkString,
+            },
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hrandfield"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        if let Some(count) = self.count {
+            frame.push_int(count)?;
+            if self.with_values {
+                frame.push_bulk(Bytes::from("withvalues"))?;
+            }
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for HRandFieldCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HRANDFIELD {}", self.key)?;
+        if let Some(count) = self.count {
+            write!(f, " {count}")?;
+            if self.with_values {
+                write!(f, " WITHVALUES")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse the optional `count` of `HRANDFIELD` and `ZRANDMEMBER`, followed by
+/// the optional `flag` asking for the values or scores.
+///
+/// The flag is only accepted after a count. Counts so negative that the reply
+/// could not be represented are out of range, like Redis does.
+pub(super) fn parse_count(parse: &mut Parse, flag: &str) -> Result<(Option<i64>, bool)> {
+    let count = match parse.next_int_signed() {
+        Ok(count) => count,
+        Err(Error::EndOfStream) => return Ok((None, false)),
+        Err(err) => return Err(err),
+    };
+    let with_flag = match parse.next_string() {
+        Ok(s) if s.to_uppercase() == flag => true,
+        Ok(_) => return Err(Error::Syntax),
+        Err(Error::EndOfStream) => false,
+        Err(err) => return Err(err),
+    };
+    if with_flag && count < -(i64::MAX / 2) {
+        return Err(Error::NotAnInteger);
+    }
+    Ok((Some(count), with_flag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn test_hrandfield_negative_count_repeats() {
+        let db = Db::default();
+        let fields = ["a", "b", "c"].map(|field| (Bytes::from(field), Bytes::from("v")));
+        db.hset("foo", fields.to_vec()).unwrap();
+
+        // A positive count picks distinct fields, at most all of them.
+        let picked = db.hrandfield("foo", 2).unwrap();
+        assert_eq!(picked.len(), 2);
+        assert_ne!(picked[0], picked[1]);
+        let picked: HashSet<_> = db.hrandfield("foo", 10).unwrap().into_iter().collect();
+        assert_eq!(picked, fields.iter().cloned().collect());
+
+        // A negative count picks exactly that many, which must repeat here.
+        let picked = db.hrandfield("foo", -10).unwrap();
+        assert_eq!(picked.len(), 10);
+        assert!(picked.iter().all(|pair| fields.contains(pair)));
+
+        assert!(db.hrandfield("missing", -10).unwrap().is_empty());
+        db.set("str".to_string(), Bytes::from("bar"), None).unwrap();
+        assert!(matches!(db.hrandfield("str", 1), Err(Error::WrongType)));
+    }
+
+    #[tokio::test]
+    async fn test_hrandfield_reply_shape() {
+        let db = Db::default();
+        db.hset("foo", vec![(Bytes::from("f"), Bytes::from("v"))])
+            .unwrap();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b"$1\r\nf\r\n")
+            .write(b"*6\r\n$1\r\nf\r\n$1\r\nv\r\n$1\r\nf\r\n$1\r\nv\r\n$1\r\nf\r\n$1\r\nv\r\n")
+            .write(b"$-1\r\n")
+            .write(b"*0\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        HRandFieldCmd::new("foo")
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        HRandFieldCmd::new("foo")
+            .count(-3)
+            .with_values()
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        HRandFieldCmd::new("missing")
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        HRandFieldCmd::new("missing")
+            .count(2)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+    }
+}
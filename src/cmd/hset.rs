@@ -0,0 +1,98 @@
+//! Implementation of the `HSET` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Set the given `fields` to their values in the hash stored at `key`.
+///
+/// If a field already exists in the hash, its value is overwritten. If `key`
+/// does not exist, a new hash is created before setting the fields.
+///
+/// Replies with the number of fields that were added.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HSetCmd {
+    /// The key of the hash.
+    key: String,
+    /// The `(field, value)` pairs to set.
+    fields: Vec<(Bytes, Bytes)>,
+}
+
+impl HSetCmd {
+    /// Creates a new [`HSetCmd`] command.
+    pub fn new(key: impl ToString, fields: Vec<(Bytes, Bytes)>) -> Self {
+        Self {
+            key: key.to_string(),
+            fields,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the `(field, value)` pairs.
+    pub fn fields(&self) -> &[(Bytes, Bytes)] {
+        &self.fields
+    }
+}
+
+impl Command for HSetCmd {
+    /// Parse a [`HSetCmd`] instance from a received frame.
+    ///
+    /// The `HSET` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HSET key field value [field value ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+
+        // Fields and values come in pairs.
+        if !parse.remaining().is_multiple_of(2) {
+            return Err(Error::WrongNumberOfArguments("hset".to_string()));
+        }
+
+        let mut fields = vec![];
+        while parse.remaining() > 0 {
+            let field = parse.next_bytes()?;
+            let value = parse.next_bytes()?;
+            fields.push((field, value));
+        }
+
+        Ok(Self { key, fields })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let added = db.hset(&self.key, self.fields)?;
+        let response = Frame::Integer(added as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hset"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        for (field, value) in self.fields {
+            frame.push_bulk(field)?;
+            frame.push_bulk(value)?;
+        }
+        Ok(frame)
+    }
+}
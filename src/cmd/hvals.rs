@@ -0,0 +1,73 @@
+//! Implementation of the `HVALS` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Get the values of the hash stored at `key`.
+///
+/// Replies with the values, ordered by their field. A missing key is an empty
+/// hash.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HValsCmd {
+    /// The key of the hash.
+    key: String,
+}
+
+impl HValsCmd {
+    /// Creates a new [`HValsCmd`] command.
+    pub fn new(key: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Command for HValsCmd {
+    /// Parse a [`HValsCmd`] instance from a received frame.
+    ///
+    /// The `HVALS` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HVALS key
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        Ok(Self { key })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let mut response = Frame::array();
+        for value in db.hvals(&self.key)? {
+            response.push_bulk(value)?;
+        }
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hvals"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        Ok(frame)
+    }
+}
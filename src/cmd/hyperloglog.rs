@@ -0,0 +1,216 @@
+//! Implementation of the `PFADD` and `PFCOUNT` commands.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Add `elements` to the HyperLogLog stored at `key`.
+///
+/// The HyperLogLog is created if the key does not exist, and is stored as a
+/// plain string. Replies with `1` if the HyperLogLog was created or its
+/// estimate may have changed, `0` otherwise.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PfAddCmd {
+    /// The key of the HyperLogLog.
+    key: String,
+    /// The elements to add.
+    elements: Vec<Bytes>,
+}
+
+impl PfAddCmd {
+    /// Creates a new [`PfAddCmd`] command.
+    pub fn new(key: impl ToString, elements: Vec<Bytes>) -> Self {
+        Self {
+            key: key.to_string(),
+            elements,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the elements.
+    pub fn elements(&self) -> &[Bytes] {
+        &self.elements
+    }
+}
+
+impl Command for PfAddCmd {
+    /// Parse a [`PfAddCmd`] instance from a received frame.
+    ///
+    /// The `PFADD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// PFADD key [element [element ...]]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let mut elements = vec![];
+        loop {
+            match parse.next_bytes() {
+                Ok(element) => elements.push(element),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(Self { key, elements })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let updated = db.pfadd(&self.key, &self.elements)?;
+        let response = Frame::Integer(updated as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("pfadd"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        for element in self.elements {
+            frame.push_bulk(element)?;
+        }
+        Ok(frame)
+    }
+}
+
+/// Estimate the number of distinct elements added to the HyperLogLogs stored
+/// at `keys`.
+///
+/// With several keys, the estimate is for the union of the HyperLogLogs.
+/// Missing keys count as empty HyperLogLogs.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PfCountCmd {
+    /// The keys of the HyperLogLogs.
+    keys: Vec<String>,
+}
+
+impl PfCountCmd {
+    /// Creates a new [`PfCountCmd`] command.
+    pub fn new(keys: Vec<String>) -> Self {
+        Self { keys }
+    }
+
+    /// Get the keys.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+}
+
+impl Command for PfCountCmd {
+    /// Parse a [`PfCountCmd`] instance from a received frame.
+    ///
+    /// The `PFCOUNT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// PFCOUNT key [key ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        // At least one key must be provided.
+        let mut keys = vec![parse.next_string()?];
+        loop {
+            match parse.next_string() {
+                Ok(key) => keys.push(key),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(Self { keys })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let count = db.pfcount(&self.keys)?;
+        let response = Frame::Integer(count as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("pfcount"))?;
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key))?;
+        }
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    #[tokio::test]
+    async fn test_pfcount_estimates_distinct_adds() {
+        let db = Db::default();
+        let stream = tokio_test::io::Builder::new()
+            .write(b":1\r\n")
+            .write(b":0\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        let elements: Vec<Bytes> = (0..1000)
+            .map(|i| Bytes::from(format!("element-{i}")))
+            .collect();
+        PfAddCmd::new("hll", elements.clone())
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        // Adding the same elements again leaves the estimate unchanged.
+        PfAddCmd::new("hll", elements)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+
+        let count = db.pfcount(&["hll".to_string()]).unwrap();
+        assert!(count.abs_diff(1000) <= 20, "count is {count}");
+        let count = db
+            .pfcount(&["hll".to_string(), "missing".to_string()])
+            .unwrap();
+        assert!(count.abs_diff(1000) <= 20, "count is {count}");
+
+        // The HyperLogLog is a plain string, which can be copied around.
+        let data = db.get("hll").unwrap().unwrap();
+        db.set("copy".to_string(), data, None).unwrap();
+        assert_eq!(db.pfcount(&["copy".to_string()]).unwrap(), count);
+
+        db.set("str".to_string(), Bytes::from("foo"), None).unwrap();
+        assert!(matches!(
+            db.pfadd("str", &[]),
+            Err(Error::InvalidHyperLogLog)
+        ));
+        assert!(matches!(
+            db.pfcount(&["str".to_string()]),
+            Err(Error::InvalidHyperLogLog)
+        ));
+    }
+}
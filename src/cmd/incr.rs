@@ -0,0 +1,377 @@
+//! Implementation of the `INCR`, `DECR`, `INCRBY` and `DECRBY` commands.
+use super::Command;
+use crate::{db::IncrError, parse::Parse, Connection, ConnectionStream, Db, Error, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Parse an increment argument, which must fit in an `i64`.
+fn parse_delta(parse: &mut Parse) -> Result<i64> {
+    parse.next_int_signed().map_err(|_| Error::NotAnInteger)
+}
+
+/// Increment the integer stored at `key` by `delta`, replying with the new
+/// value.
+///
+/// This is the logic shared by the whole increment family, so they behave
+/// the same at the boundaries of `i64`.
+async fn apply_incr<S: ConnectionStream>(
+    key: &str,
+    delta: i64,
+    db: &Db,
+    dst: &mut Connection<S>,
+) -> Result<()> {
+    let value = db.incr_by(key, delta).map_err(|err| {
+        match err {
+            IncrError::NotAnInteger => debug!(key, "value is not an integer"),
+            IncrError::Overflow => debug!(key, delta, "increment would overflow"),
+            IncrError::WrongType => return Error::WrongType,
+            IncrError::OutOfMemory => return Error::OutOfMemory,
+        }
+        // Redis replies with the same error whether the value is not an
+        // integer or the increment overflows.
+        Error::NotAnInteger
+    })?;
+    let response = Frame::Integer(value);
+
+    debug!(?response);
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+/// Increment the integer stored at `key` by one.
+///
+/// If the key does not exist, it is set to `0` before performing the
+/// operation. An error is returned if the value stored at `key` is not the
+/// string of an integer, or if the result does not fit in 64 bits.
+#[derive(Debug, PartialEq, Eq)]
+pub struct IncrCmd {
+    key: String,
+}
+
+impl IncrCmd {
+    /// Creates a new [`IncrCmd`] command.
+    pub fn new(key: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Command for IncrCmd {
+    /// Parse an [`IncrCmd`] instance from a received frame.
+    ///
+    /// The `INCR` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// INCR key
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        Ok(Self { key })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(self, db: &Db, dst: &mut Connection<S>) -> Result<()> {
+        apply_incr(&self.key, 1, db, dst).await
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("incr"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        Ok(frame)
+    }
+}
+
+/// Decrement the integer stored at `key` by one.
+///
+/// Behaves like [`IncrCmd`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct DecrCmd {
+    key: String,
+}
+
+impl DecrCmd {
+    /// Creates a new [`DecrCmd`] command.
+    pub fn new(key: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Command for DecrCmd {
+    /// Parse a [`DecrCmd`] instance from a received frame.
+    ///
+    /// The `DECR` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// DECR key
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        Ok(Self { key })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(self, db: &Db, dst: &mut Connection<S>) -> Result<()> {
+        apply_incr(&self.key, -1, db, dst).await
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("decr"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        Ok(frame)
+    }
+}
+
+/// Increment the integer stored at `key` by `increment`.
+///
+/// Behaves like [`IncrCmd`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct IncrByCmd {
+    key: String,
+    increment: i64,
+}
+
+impl IncrByCmd {
+    /// Creates a new [`IncrByCmd`] command.
+    pub fn new(key: impl ToString, increment: i64) -> Self {
+        Self {
+            key: key.to_string(),
+            increment,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the increment.
+    pub fn increment(&self) -> i64 {
+        self.increment
+    }
+}
+
+impl Command for IncrByCmd {
+    /// Parse an [`IncrByCmd`] instance from a received frame.
+    ///
+    /// The `INCRBY` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// INCRBY key increment
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let increment = parse_delta(parse)?;
+        Ok(Self { key, increment })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(self, db: &Db, dst: &mut Connection<S>) -> Result<()> {
+        apply_incr(&self.key, self.increment, db, dst).await
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("incrby"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_int(self.increment)?;
+        Ok(frame)
+    }
+}
+
+/// Decrement the integer stored at `key` by `decrement`.
+///
+/// Behaves like [`IncrCmd`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct DecrByCmd {
+    key: String,
+    decrement: i64,
+}
+
+impl DecrByCmd {
+    /// Creates a new [`DecrByCmd`] command.
+    pub fn new(key: impl ToString, decrement: i64) -> Self {
+        Self {
+            key: key.to_string(),
+            decrement,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the decrement.
+    pub fn decrement(&self) -> i64 {
+        self.decrement
+    }
+}
+
+impl Command for DecrByCmd {
+    /// Parse a [`DecrByCmd`] instance from a received frame.
+    ///
+    /// The `DECRBY` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// DECRBY key decrement
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let decrement = parse_delta(parse)?;
+        Ok(Self { key, decrement })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(self, db: &Db, dst: &mut Connection<S>) -> Result<()> {
+        // `i64::MIN` cannot be negated, which is an overflow as well.
+        let Some(delta) = self.decrement.checked_neg() else {
+            debug!(
+                key = self.key,
+                decrement = self.decrement,
+                "decrement would overflow"
+            );
+            return Err(Error::NotAnInteger);
+        };
+        apply_incr(&self.key, delta, db, dst).await
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("decrby"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_int(self.decrement)?;
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The reply to increments failing at the boundaries of `i64`.
+    const NOT_AN_INTEGER: &str = "ERR value is not an integer or out of range";
+
+    /// Apply `cmd`, returning the error frame it failed with.
+    async fn error_frame(db: &Db, cmd: impl Command) -> Frame {
+        let mut conn = Connection::new(tokio_test::io::Builder::new().build());
+        cmd.apply(db, &mut conn)
+            .await
+            .unwrap_err()
+            .to_frame()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_increments_at_the_boundaries() {
+        let db = Db::default();
+        let max = Bytes::from(i64::MAX.to_string());
+        let min = Bytes::from(i64::MIN.to_string());
+        let not_an_integer = Frame::SimpleError(NOT_AN_INTEGER.to_string());
+
+        // Every command going past `i64::MAX` fails, leaving the value as is.
+        db.set("max".to_string(), max.clone(), None).unwrap();
+        assert_eq!(error_frame(&db, IncrCmd::new("max")).await, not_an_integer);
+        assert_eq!(
+            error_frame(&db, IncrByCmd::new("max", 1)).await,
+            not_an_integer
+        );
+        assert_eq!(
+            error_frame(&db, DecrByCmd::new("max", -1)).await,
+            not_an_integer
+        );
+        assert_eq!(db.get("max").unwrap(), Some(max));
+
+        // Likewise past `i64::MIN`.
+        db.set("min".to_string(), min.clone(), None).unwrap();
+        assert_eq!(error_frame(&db, DecrCmd::new("min")).await, not_an_integer);
+        assert_eq!(
+            error_frame(&db, DecrByCmd::new("min", 1)).await,
+            not_an_integer
+        );
+        assert_eq!(
+            error_frame(&db, IncrByCmd::new("min", -1)).await,
+            not_an_integer
+        );
+        assert_eq!(db.get("min").unwrap(), Some(min));
+
+        // Reaching the boundaries is fine.
+        let stream = tokio_test::io::Builder::new()
+            .write(format!(":{}\r\n", i64::MAX).as_bytes())
+            .write(format!(":{}\r\n", -i64::MAX).as_bytes())
+            .write(format!(":{}\r\n", i64::MIN).as_bytes())
+            .write(b":-1\r\n")
+            .write(b":0\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        db.set("a".to_string(), Bytes::from("0"), None).unwrap();
+        IncrByCmd::new("a", i64::MAX)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        DecrByCmd::new("b", i64::MAX)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        DecrCmd::new("b").apply(&db, &mut conn).await.unwrap();
+        DecrCmd::new("c").apply(&db, &mut conn).await.unwrap();
+        IncrCmd::new("c").apply(&db, &mut conn).await.unwrap();
+
+        // `i64::MIN` cannot be negated into an increment.
+        assert_eq!(
+            error_frame(&db, DecrByCmd::new("c", i64::MIN)).await,
+            not_an_integer
+        );
+    }
+
+    #[tokio::test]
+    async fn test_increments_of_non_integers() {
+        let db = Db::default();
+        let not_an_integer = Frame::SimpleError(NOT_AN_INTEGER.to_string());
+        for value in ["foo", "1.5", " 1", "+1", "9223372036854775808"] {
+            db.set("foo".to_string(), Bytes::from(value), None).unwrap();
+            assert_eq!(error_frame(&db, IncrCmd::new("foo")).await, not_an_integer);
+        }
+
+        db.sadd("set", vec![Bytes::from("a")]).unwrap();
+        let mut conn = Connection::new(tokio_test::io::Builder::new().build());
+        let err = IncrCmd::new("set").apply(&db, &mut conn).await.unwrap_err();
+        assert!(matches!(err, Error::WrongType));
+    }
+}
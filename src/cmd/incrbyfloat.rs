@@ -0,0 +1,149 @@
+//! Implementation of the `INCRBYFLOAT` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Increment the string representing a floating point number stored at `key`
+/// by the specified `increment`.
+///
+/// If the key does not exist, it is set to `0` before performing the
+/// operation. An error is returned if the value stored at `key` cannot be
+/// parsed as a floating point number, or if the result is not finite.
+///
+/// The resulting value is returned as a bulk string, without trailing zeros.
+#[derive(Debug, PartialEq)]
+pub struct IncrByFloatCmd {
+    /// The lookup key.
+    key: String,
+    /// The amount to increment by.
+    increment: f64,
+}
+
+// `increment` is never `NaN`, as it is rejected while parsing.
+impl Eq for IncrByFloatCmd {}
+
+impl IncrByFloatCmd {
+    /// Creates a new [`IncrByFloatCmd`] command.
+    pub fn new(key: impl ToString, increment: f64) -> Self {
+        Self {
+            key: key.to_string(),
+            increment,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the increment.
+    pub fn increment(&self) -> f64 {
+        self.increment
+    }
+}
+
+impl Command for IncrByFloatCmd {
+    /// Parse an [`IncrByFloatCmd`] instance from a received frame.
+    ///
+    /// The `INCRBYFLOAT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// INCRBYFLOAT key increment
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let increment = parse.next_float()?;
+        Ok(Self { key, increment })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let value = db.incr_by_float(&self.key, self.increment)?;
+        let response = Frame::BulkString(Bytes::from(value.to_string()));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("incrbyfloat"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_bulk(Bytes::from(self.increment.to_string()))?;
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommandVariant, Connection, Db, Error};
+
+    #[tokio::test]
+    async fn test_incr_by_float_formatting() {
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("INCRBYFLOAT")),
+            Frame::BulkString(Bytes::from("foo")),
+            Frame::BulkString(Bytes::from("3.0e3")),
+        ]);
+        let CommandVariant::IncrByFloat(cmd) = CommandVariant::from_frame(frame).unwrap() else {
+            panic!("unexpected command");
+        };
+        assert_eq!(cmd, IncrByFloatCmd::new("foo", 3000.0));
+
+        let db = Db::default();
+        let stream = tokio_test::io::Builder::new()
+            .write(b"$4\r\n3000\r\n")
+            .write(b"$6\r\n3000.5\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        cmd.apply(&db, &mut conn).await.unwrap();
+        IncrByFloatCmd::new("foo", 0.5)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        assert_eq!(db.get("foo").unwrap(), Some(Bytes::from("3000.5")));
+    }
+
+    #[tokio::test]
+    async fn test_incr_by_float_errors() {
+        let db = Db::default();
+        db.set("foo".to_string(), Bytes::from("bar"), None).unwrap();
+        db.set("big".to_string(), Bytes::from(f64::MAX.to_string()), None)
+            .unwrap();
+
+        let stream = tokio_test::io::Builder::new().build();
+        let mut conn = Connection::new(stream);
+        let err = IncrByFloatCmd::new("foo", 1.0)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_frame().unwrap(),
+            Frame::SimpleError("ERR value is not a valid float".to_string())
+        );
+        let err = IncrByFloatCmd::new("big", f64::MAX)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NanOrInfinity));
+
+        // Failed increments leave the values untouched.
+        assert_eq!(db.get("foo").unwrap(), Some(Bytes::from("bar")));
+    }
+}
@@ -0,0 +1,192 @@
+//! Implementation of the `INFO` command.
+use crate::{
+    parse::Parse, server::ServerState, Connection, ConnectionStream, Error, Frame, Result,
+};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Reports information and statistics about the server.
+///
+/// The reply is a bulk string made of sections, each starting with a
+/// `# Section` header line followed by `field:value` lines.
+///
+/// # Sections
+///
+/// * server -- The `run_id`, a random identifier of 40 hexadecimal
+///   characters, generated once per server instance.
+/// * stats -- The total number of commands processed, and of those reading
+///   and writing keys, as `total_commands_processed`,
+///   `total_reads_processed` and `total_writes_processed`.
+/// * commandstats -- The number of calls and the cumulative time spent in each
+///   command, e.g. `cmdstat_get:calls=2,usec=12,usec_per_call=6.00`.
+///
+/// Every section is reported when no section, `default`, `all` or
+/// `everything` is requested. Unknown sections are ignored.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InfoCmd {
+    /// The requested sections, in lowercase.
+    sections: Vec<String>,
+}
+
+impl InfoCmd {
+    /// Creates a new [`InfoCmd`] command for the given `sections`.
+    ///
+    /// If `sections` is empty, the default sections are reported.
+    pub fn new(sections: Vec<String>) -> Self {
+        let sections = sections
+            .into_iter()
+            .map(|section| section.to_lowercase())
+            .collect();
+        Self { sections }
+    }
+
+    /// Get the requested sections.
+    pub fn sections(&self) -> &[String] {
+        &self.sections
+    }
+
+    /// Returns `true` if the section `name` was requested.
+    fn includes(&self, name: &str) -> bool {
+        self.sections.is_empty()
+            || self.sections.iter().any(|section| {
+                matches!(section.as_str(), "default" | "all" | "everything") || section == name
+            })
+    }
+
+    /// Parse an [`InfoCmd`] instance from a received frame.
+    ///
+    /// The `INFO` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// INFO [section [section ...]]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Self> {
+        let mut sections = vec![];
+        loop {
+            match parse.next_string() {
+                Ok(section) => sections.push(section),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Self::new(sections))
+    }
+
+    /// Apply the `INFO` command, reporting on `server`.
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn apply<S: ConnectionStream>(
+        self,
+        server: &ServerState,
+        dst: &mut Connection<S>,
+    ) -> Result<()> {
+        let mut info = String::new();
+        if self.includes("server") {
+            info.push_str(&format!("# Server\r\nrun_id:{}\r\n", server.run_id()));
+        }
+        if self.includes("stats") {
+            info.push_str(&server.stats().stats_info());
+        }
+        if self.includes("commandstats") {
+            info.push_str(&server.stats().info());
+        }
+        let response = Frame::BulkString(Bytes::from(info));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Creates the request frame of the command.
+    pub(crate) fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("info"))?;
+        for section in self.sections {
+            frame.push_bulk(Bytes::from(section))?;
+        }
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommandVariant, Db, Shutdown};
+    use tokio::sync::broadcast;
+
+    #[tokio::test]
+    async fn test_commandstats_counts_calls() {
+        let db = Db::default();
+        let server = ServerState::default();
+        let stream = tokio_test::io::Builder::new()
+            .write(b"$-1\r\n")
+            .write(b"$-1\r\n")
+            .write(b"$-1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        let (_notify_shutdown, rx) = broadcast::channel(1);
+        let mut shutdown = Shutdown::new(rx);
+
+        for _ in 0..3 {
+            let frame = Frame::Array(vec![
+                Frame::BulkString(Bytes::from("GET")),
+                Frame::BulkString(Bytes::from("foo")),
+            ]);
+            CommandVariant::from_frame(frame)
+                .unwrap()
+                .apply(&db, &server, &mut conn, &mut shutdown)
+                .await
+                .unwrap();
+        }
+
+        let info = server.stats().info();
+        assert!(info.starts_with("# Commandstats\r\n"));
+        assert!(info.contains("cmdstat_get:calls=3,"), "{info}");
+        assert!(!info.contains("cmdstat_set"));
+        assert!(!InfoCmd::new(vec!["server".to_string()]).includes("commandstats"));
+    }
+
+    #[tokio::test]
+    async fn test_stats_count_reads_and_writes() {
+        let db = Db::default();
+        let server = ServerState::default();
+        let stream = tokio_test::io::Builder::new()
+            .write(b"+OK\r\n")
+            .write(b"$3\r\nbar\r\n")
+            .write(b"$3\r\nbar\r\n")
+            .write(b"+PONG\r\n")
+            .write(b"+OK\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        let (_notify_shutdown, rx) = broadcast::channel(1);
+        let mut shutdown = Shutdown::new(rx);
+
+        let mut apply = async |args: &[&str]| {
+            let args = args
+                .iter()
+                .map(|arg| Frame::BulkString(Bytes::from(arg.to_string())));
+            CommandVariant::from_frame(Frame::Array(args.collect()))
+                .unwrap()
+                .apply(&db, &server, &mut conn, &mut shutdown)
+                .await
+                .unwrap();
+            (server.stats().total_reads(), server.stats().total_writes())
+        };
+        assert_eq!(apply(&["SET", "foo", "bar"]).await, (0, 1));
+        assert_eq!(apply(&["GET", "foo"]).await, (1, 1));
+        assert_eq!(apply(&["GET", "foo"]).await, (2, 1));
+        // Commands without keys are neither.
+        assert_eq!(apply(&["PING"]).await, (2, 1));
+        assert_eq!(apply(&["SET", "foo", "baz"]).await, (2, 2));
+
+        let info = server.stats().stats_info();
+        assert!(info.starts_with("# Stats\r\n"));
+        assert!(info.contains("total_commands_processed:5\r\n"), "{info}");
+        assert!(info.contains("total_reads_processed:2\r\n"), "{info}");
+        assert!(info.contains("total_writes_processed:2\r\n"), "{info}");
+    }
+}
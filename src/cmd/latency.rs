@@ -0,0 +1,248 @@
+//! Implementation of the `LATENCY` command.
+use super::help::{help_frame, unknown_subcommand};
+use crate::{
+    parse::Parse, server::ServerState, Connection, ConnectionStream, Error, Frame, Result,
+};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Inspects the latency spikes recorded by the latency monitor.
+///
+/// Latencies are reported in milliseconds. Events are `command`, the time
+/// taken to apply a command, and `expire-cycle`, the time taken to purge the
+/// expired keys.
+///
+/// # Subcommands
+///
+/// * LATEST -- Returns the latest sample of every event, as an array of the
+///   event name, unix timestamp, latency, and highest latency ever recorded.
+/// * HISTORY `event` -- Returns the samples of `event`, oldest first, each an
+///   array of its unix timestamp and latency.
+/// * RESET `[event ...]` -- Removes the samples of the given events, or of
+///   every event. Returns the number of events removed.
+/// * HELP -- Returns the documentation of the subcommands.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LatencyCmd {
+    subcommand: Subcommand,
+}
+
+/// The `LATENCY` subcommands.
+#[derive(Debug, PartialEq, Eq)]
+enum Subcommand {
+    /// `LATENCY LATEST`
+    Latest,
+    /// `LATENCY HISTORY event`
+    History(String),
+    /// `LATENCY RESET [event ...]`
+    Reset(Vec<String>),
+    /// `LATENCY HELP`
+    Help,
+}
+
+/// The documentation of the subcommands, returned by `LATENCY HELP`.
+const HELP: &[&str] = &[
+    "LATEST",
+    "    Return the latest latency sample of every event.",
+    "HISTORY <event>",
+    "    Return the latency samples of <event>, oldest first.",
+    "RESET [<event> ...]",
+    "    Remove the samples of the given events, or of every event.",
+];
+
+impl LatencyCmd {
+    /// Creates a new `LATENCY LATEST` command.
+    pub fn latest() -> Self {
+        Self {
+            subcommand: Subcommand::Latest,
+        }
+    }
+
+    /// Creates a new `LATENCY HISTORY` command, for the samples of `event`.
+    pub fn history(event: impl ToString) -> Self {
+        Self {
+            subcommand: Subcommand::History(event.to_string()),
+        }
+    }
+
+    /// Creates a new `LATENCY RESET` command, removing the samples of
+    /// `events`, or of every event if it is empty.
+    pub fn reset(events: Vec<String>) -> Self {
+        Self {
+            subcommand: Subcommand::Reset(events),
+        }
+    }
+
+    /// Creates a new `LATENCY HELP` command.
+    pub fn help() -> Self {
+        Self {
+            subcommand: Subcommand::Help,
+        }
+    }
+
+    /// Parse a [`LatencyCmd`] instance from a received frame.
+    ///
+    /// The `LATENCY` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LATENCY LATEST | HISTORY event | RESET [event ...] | HELP
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Self> {
+        let subcommand = parse.next_string()?;
+        let subcommand = match subcommand.to_uppercase().as_str() {
+            "LATEST" => Subcommand::Latest,
+            "HISTORY" => Subcommand::History(parse.next_string()?),
+            "RESET" => {
+                let mut events = vec![];
+                loop {
+                    match parse.next_string() {
+                        Ok(event) => events.push(event),
+                        Err(Error::EndOfStream) => break,
+                        Err(err) => return Err(err),
+                    }
+                }
+                Subcommand::Reset(events)
+            }
+            "HELP" => Subcommand::Help,
+            _ => return Err(unknown_subcommand("LATENCY", subcommand)),
+        };
+
+        Ok(Self { subcommand })
+    }
+
+    /// Apply the `LATENCY` command to the monitor of `server`.
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn apply<S: ConnectionStream>(
+        self,
+        server: &ServerState,
+        dst: &mut Connection<S>,
+    ) -> Result<()> {
+        let response = match self.subcommand {
+            Subcommand::Latest => {
+                let mut response = Frame::array();
+                for latest in server.latency().latest() {
+                    let mut frame = Frame::array();
+                    frame.push_bulk(Bytes::from(latest.event))?;
+                    frame.push_int(latest.sample.timestamp as i64)?;
+                    frame.push_int(latest.sample.latency.as_millis() as i64)?;
+                    frame.push_int(latest.max.as_millis() as i64)?;
+                    response.push_frame(frame)?;
+                }
+                response
+            }
+            Subcommand::History(event) => {
+                let mut response = Frame::array();
+                for sample in server.latency().history(&event) {
+                    let mut frame = Frame::array();
+                    frame.push_int(sample.timestamp as i64)?;
+                    frame.push_int(sample.latency.as_millis() as i64)?;
+                    response.push_frame(frame)?;
+                }
+                response
+            }
+            Subcommand::Reset(events) => Frame::Integer(server.latency().reset(&events) as i64),
+            Subcommand::Help => help_frame("LATENCY", HELP)?,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Creates the request frame of the command.
+    pub(crate) fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("latency"))?;
+        match self.subcommand {
+            Subcommand::Latest => frame.push_bulk(Bytes::from("latest"))?,
+            Subcommand::History(event) => {
+                frame.push_bulk(Bytes::from("history"))?;
+                frame.push_bulk(Bytes::from(event))?;
+            }
+            Subcommand::Reset(events) => {
+                frame.push_bulk(Bytes::from("reset"))?;
+                for event in events {
+                    frame.push_bulk(Bytes::from(event))?;
+                }
+            }
+            Subcommand::Help => frame.push_bulk(Bytes::from("help"))?,
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for LatencyCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.subcommand {
+            Subcommand::Latest => write!(f, "LATENCY LATEST"),
+            Subcommand::History(event) => write!(f, "LATENCY HISTORY {event}"),
+            Subcommand::Reset(events) => {
+                write!(f, "LATENCY RESET")?;
+                for event in events {
+                    write!(f, " {event}")?;
+                }
+                Ok(())
+            }
+            Subcommand::Help => write!(f, "LATENCY HELP"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cmd::DebugCmd, server::ServerConfig, CommandVariant, Connection, Db, Shutdown};
+    use std::time::Duration;
+    use tokio::sync::broadcast;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_command_shows_up_in_latest() {
+        let db = Db::default();
+        let state = ServerState::new(&ServerConfig {
+            latency_monitor_threshold: Some(Duration::from_millis(100)),
+            ..Default::default()
+        });
+        let (client, server) = tokio::io::duplex(1024);
+        let mut conn = Connection::new(server);
+        let (_notify_shutdown, rx) = broadcast::channel(1);
+        let mut shutdown = Shutdown::new(rx);
+
+        for cmd in [
+            CommandVariant::Debug(DebugCmd::sleep(Duration::from_millis(50))),
+            CommandVariant::Latency(LatencyCmd::latest()),
+            CommandVariant::Debug(DebugCmd::sleep(Duration::from_millis(250))),
+            CommandVariant::Latency(LatencyCmd::latest()),
+        ] {
+            cmd.apply(&db, &state, &mut conn, &mut shutdown)
+                .await
+                .unwrap();
+        }
+        drop(conn);
+
+        let mut client = Connection::new(client);
+        let mut replies = vec![];
+        while let Some(frame) = client.read_frame().await.unwrap() {
+            replies.push(frame);
+        }
+        let ok = Frame::SimpleString("OK".to_string());
+        // Nothing reached the threshold before the second sleep.
+        assert_eq!(replies[..3], [ok.clone(), Frame::array(), ok]);
+        let Frame::Array(latest) = &replies[3] else {
+            panic!("unexpected reply: {:?}", replies[3]);
+        };
+        let [Frame::Array(event)] = &latest[..] else {
+            panic!("unexpected reply: {latest:?}");
+        };
+        assert_eq!(event[0], Frame::BulkString(Bytes::from("command")));
+        assert_eq!(event[2], Frame::Integer(250));
+        assert_eq!(event[3], Frame::Integer(250));
+        assert_eq!(state.latency().history("command").len(), 1);
+
+        assert_eq!(state.latency().reset(&[]), 1);
+        assert!(state.latency().latest().is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+//! Implementation of the `LINDEX` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Get the element at `index` in the list stored at `key`.
+///
+/// Negative indices count from the tail of the list, `-1` being the last
+/// element. If the key does not exist or the index is out of range, a `Null`
+/// RESP type is returned.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LIndexCmd {
+    /// The key of the list.
+    key: String,
+    /// The index of the element.
+    index: i64,
+}
+
+impl LIndexCmd {
+    /// Creates a new [`LIndexCmd`] command.
+    pub fn new(key: impl ToString, index: i64) -> Self {
+        Self {
+            key: key.to_string(),
+            index,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the index.
+    pub fn index(&self) -> i64 {
+        self.index
+    }
+}
+
+impl Command for LIndexCmd {
+    /// Parse a [`LIndexCmd`] instance from a received frame.
+    ///
+    /// The `LINDEX` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LINDEX key index
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let index = parse.next_int_signed()?;
+        Ok(Self { key, index })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = match db.lindex(&self.key, self.index)? {
+            Some(value) => Frame::BulkString(value),
+            None => Frame::NullBulkString,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lindex"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_int(self.index)?;
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::ListEnd, Connection, Db};
+
+    #[tokio::test]
+    async fn test_lindex_negative_indices() {
+        let db = Db::default();
+        let values = vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("c")];
+        db.push("foo", values, ListEnd::Right).unwrap();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b"$1\r\na\r\n")
+            .write(b"$1\r\nc\r\n")
+            .write(b"$1\r\na\r\n")
+            .write(b"$-1\r\n")
+            .write(b"$-1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        for index in [0, -1, -3, -4, 3] {
+            LIndexCmd::new("foo", index)
+                .apply(&db, &mut conn)
+                .await
+                .unwrap();
+        }
+    }
+}
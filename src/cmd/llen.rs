@@ -0,0 +1,70 @@
+//! Implementation of the `LLEN` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Get the length of the list stored at `key`.
+///
+/// If `key` does not exist, it is interpreted as an empty list and `0` is
+/// returned.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LLenCmd {
+    /// The key of the list.
+    key: String,
+}
+
+impl LLenCmd {
+    /// Creates a new [`LLenCmd`] command.
+    pub fn new(key: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Command for LLenCmd {
+    /// Parse a [`LLenCmd`] instance from a received frame.
+    ///
+    /// The `LLEN` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LLEN key
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        Ok(Self { key })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = Frame::Integer(db.llen(&self.key)? as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("llen"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        Ok(frame)
+    }
+}
@@ -0,0 +1,134 @@
+//! Implementation of the `LOLWUT` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// The banner leading the `LOLWUT` reply.
+const BANNER: &str = r"
+ _        _
+| |  ___ (_) __ _
+| | / _ \| |/ _` |
+| || (_) | | (_| |
+|_| \___// |\__,_|
+       |__/
+";
+
+/// Replies with a banner, along with the server name and version.
+///
+/// The banner is the same for every `VERSION`, the argument is only accepted
+/// for compatibility.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LolwutCmd {
+    version: Option<u64>,
+}
+
+impl LolwutCmd {
+    /// Creates a new [`LolwutCmd`] command.
+    pub fn new(version: Option<u64>) -> Self {
+        Self { version }
+    }
+
+    /// Get the requested version.
+    pub fn version(&self) -> Option<u64> {
+        self.version
+    }
+
+    /// Renders the reply of the command.
+    fn response() -> String {
+        format!(
+            "{BANNER}\n{} ver. {}\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        )
+    }
+}
+
+impl Command for LolwutCmd {
+    /// Parse a [`LolwutCmd`] instance from a received frame.
+    ///
+    /// The `LOLWUT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LOLWUT [VERSION version]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let version = match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "VERSION" => Some(parse.next_int_unsigned()?),
+            Ok(_) => return Err(Error::Protocol("syntax error".to_string())),
+            Err(Error::EndOfStream) => None,
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self { version })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        _db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = Frame::BulkString(Bytes::from(Self::response()));
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lolwut"))?;
+        if let Some(version) = self.version {
+            frame.push_bulk(Bytes::from("version"))?;
+            frame.push_int(version as i64)?;
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for LolwutCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.version {
+            Some(version) => write!(f, "LOLWUT VERSION {version}"),
+            None => write!(f, "LOLWUT"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommandVariant, Connection, Db};
+
+    #[tokio::test]
+    async fn test_lolwut_reports_version() {
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("LOLWUT")),
+            Frame::BulkString(Bytes::from("VERSION")),
+            Frame::BulkString(Bytes::from("5")),
+        ]);
+        let CommandVariant::Lolwut(cmd) = CommandVariant::from_frame(frame).unwrap() else {
+            panic!("unexpected command");
+        };
+        assert_eq!(cmd, LolwutCmd::new(Some(5)));
+
+        let response = LolwutCmd::response();
+        assert!(response.contains(env!("CARGO_PKG_VERSION")));
+
+        let expected = format!("${}\r\n{response}\r\n", response.len());
+        let stream = tokio_test::io::Builder::new()
+            .write(expected.as_bytes())
+            .build();
+        let mut conn = Connection::new(stream);
+        cmd.apply(&Db::default(), &mut conn).await.unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+//! Implementation of the `LPOS` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Get the index of matching elements in the list stored at `key`.
+///
+/// The `RANK` option selects the first match to report, a negative rank
+/// searching from tail to head. Without `COUNT`, replies with the index of the
+/// match, or null if there is none. With `COUNT`, replies with an array of up
+/// to `count` indices, zero meaning every match.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LPosCmd {
+    /// The key of the list.
+    key: String,
+    /// The element to look for.
+    element: Bytes,
+    /// The first match to report, never zero.
+    rank: i64,
+    /// The maximum number of matches to report.
+    count: Option<u64>,
+}
+
+impl LPosCmd {
+    /// Creates a new [`LPosCmd`] command, reporting the first match.
+    pub fn new(key: impl ToString, element: Bytes) -> Self {
+        Self {
+            key: key.to_string(),
+            element,
+            rank: 1,
+            count: None,
+        }
+    }
+
+    /// Set the first match to report.
+    ///
+    /// Negative ranks search from tail to head. A zero `rank` is rejected by
+    /// the server.
+    pub fn rank(mut self, rank: i64) -> Self {
+        self.rank = rank;
+        self
+    }
+
+    /// Report up to `count` matches, zero meaning every match.
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the element.
+    pub fn element(&self) -> &Bytes {
+        &self.element
+    }
+}
+
+impl Command for LPosCmd {
+    /// Parse a [`LPosCmd`] instance from a received frame.
+    ///
+    /// The `LPOS` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LPOS key element [RANK rank] [COUNT count]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let element = parse.next_bytes()?;
+        let mut cmd = Self::new(key, element);
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "RANK" => {
+                    let rank = parse.next_int_signed()?;
+                    if rank == 0 {
+                        return Err(Error::Protocol(
+                            "RANK can't be zero: use 1 to start from the first match, 2 from the second ... or use negative to start from the end of the list".to_string(),
+                        ));
+                    }
+                    cmd.rank = rank;
+                }
+                Ok(s) if s.to_uppercase() == "COUNT" => {
+                    let count = u64::try_from(parse.next_int_signed()?)
+                        .map_err(|_| Error::Protocol("COUNT can't be negative".to_string()))?;
+                    cmd.count = Some(count);
+                }
+                Ok(_) => return Err(Error::Protocol("syntax error".to_string())),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(cmd)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let count = self.count.unwrap_or(1);
+        let count = usize::try_from(count).unwrap_or(usize::MAX);
+        let indices = db.lpos(&self.key, &self.element, self.rank, count)?;
+        let response = match self.count {
+            Some(_) => {
+                let mut response = Frame::array();
+                for index in indices {
+                    response.push_int(index as i64)?;
+                }
+                response
+            }
+            None => match indices.first() {
+                Some(&index) => Frame::Integer(index as i64),
+                None => Frame::NullBulkString,
+            },
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lpos"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_bulk(self.element)?;
+        if self.rank != 1 {
+            frame.push_bulk(Bytes::from("rank"))?;
+            frame.push_int(self.rank)?;
+        }
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count"))?;
+            frame.push_int(count as i64)?;
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for LPosCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "LPOS {} {}",
+            self.key,
+            String::from_utf8_lossy(&self.element)
+        )?;
+        if self.rank != 1 {
+            write!(f, " RANK {}", self.rank)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, " COUNT {count}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::ListEnd, CommandVariant, Connection, Db};
+
+    #[tokio::test]
+    async fn test_lpos_rank_and_count() {
+        let db = Db::default();
+        let values = ["a", "x", "b", "x", "c", "x"].map(Bytes::from).to_vec();
+        db.push("foo", values, ListEnd::Right).unwrap();
+
+        assert_eq!(db.lpos("foo", b"x", 1, 1).unwrap(), [1]);
+        assert_eq!(db.lpos("foo", b"x", 2, 1).unwrap(), [3]);
+        // A negative rank searches from the tail.
+        assert_eq!(db.lpos("foo", b"x", -1, 1).unwrap(), [5]);
+        assert_eq!(db.lpos("foo", b"x", -2, 0).unwrap(), [3, 1]);
+        // A zero count reports every match.
+        assert_eq!(db.lpos("foo", b"x", 1, 0).unwrap(), [1, 3, 5]);
+        assert!(db.lpos("foo", b"y", 1, 0).unwrap().is_empty());
+        assert!(db.lpos("bar", b"x", 1, 0).unwrap().is_empty());
+
+        db.set("bar".to_string(), Bytes::from("x"), None).unwrap();
+        assert!(matches!(db.lpos("bar", b"x", 1, 1), Err(Error::WrongType)));
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b":5\r\n")
+            .write(b"*3\r\n:1\r\n:3\r\n:5\r\n")
+            .write(b"$-1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        for cmd in [
+            LPosCmd::new("foo", Bytes::from("x")).rank(-1),
+            LPosCmd::new("foo", Bytes::from("x")).count(0),
+            LPosCmd::new("foo", Bytes::from("y")),
+        ] {
+            cmd.apply(&db, &mut conn).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_lpos_rank_zero() {
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("LPOS")),
+            Frame::BulkString(Bytes::from("foo")),
+            Frame::BulkString(Bytes::from("x")),
+            Frame::BulkString(Bytes::from("RANK")),
+            Frame::BulkString(Bytes::from("0")),
+        ]);
+        let err = CommandVariant::from_frame(frame).unwrap_err();
+        assert!(matches!(err, Error::Protocol(msg) if msg.starts_with("RANK can't be zero")));
+    }
+}
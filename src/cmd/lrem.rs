@@ -0,0 +1,132 @@
+//! Implementation of the `LREM` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Remove up to `count` occurrences of `value` from the list stored at `key`.
+///
+/// A positive `count` removes occurrences moving from head to tail, a negative
+/// one moving from tail to head, and zero removes every occurrence. Replies
+/// with the number of removed elements.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LRemCmd {
+    /// The key of the list.
+    key: String,
+    /// The number of occurrences to remove, and the direction.
+    count: i64,
+    /// The value to remove.
+    value: Bytes,
+}
+
+impl LRemCmd {
+    /// Creates a new [`LRemCmd`] command.
+    pub fn new(key: impl ToString, count: i64, value: Bytes) -> Self {
+        Self {
+            key: key.to_string(),
+            count,
+            value,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the count.
+    pub fn count(&self) -> i64 {
+        self.count
+    }
+
+    /// Get the value.
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+}
+
+impl Command for LRemCmd {
+    /// Parse a [`LRemCmd`] instance from a received frame.
+    ///
+    /// The `LREM` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LREM key count value
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let count = parse.next_int_signed()?;
+        let value = parse.next_bytes()?;
+        Ok(Self { key, count, value })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let removed = db.lrem(&self.key, self.count, &self.value)?;
+        let response = Frame::Integer(removed as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lrem"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_int(self.count)?;
+        frame.push_bulk(self.value)?;
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{db::ListEnd, Db};
+    use bytes::Bytes;
+
+    fn list(db: &Db) -> Vec<Bytes> {
+        (0..db.llen("foo").unwrap() as i64)
+            .map(|index| db.lindex("foo", index).unwrap().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_lrem_count_sign_direction() {
+        let db = Db::default();
+        let values = ["a", "x", "b", "x", "c", "x"].map(Bytes::from).to_vec();
+        db.push("foo", values, ListEnd::Right).unwrap();
+
+        // Positive counts remove from the head.
+        assert_eq!(db.lrem("foo", 1, &Bytes::from("x")).unwrap(), 1);
+        assert_eq!(list(&db), ["a", "b", "x", "c", "x"]);
+
+        // Negative counts remove from the tail.
+        assert_eq!(db.lrem("foo", -1, &Bytes::from("x")).unwrap(), 1);
+        assert_eq!(list(&db), ["a", "b", "x", "c"]);
+
+        // Zero removes every occurrence.
+        db.push("foo", vec![Bytes::from("x")], ListEnd::Left)
+            .unwrap();
+        assert_eq!(db.lrem("foo", 0, &Bytes::from("x")).unwrap(), 2);
+        assert_eq!(list(&db), ["a", "b", "c"]);
+
+        // The key is deleted once the list is empty.
+        for value in ["a", "b", "c"] {
+            assert_eq!(db.lrem("foo", -5, &Bytes::from(value)).unwrap(), 1);
+        }
+        assert!(!db.exists("foo"));
+        assert_eq!(db.lrem("foo", 0, &Bytes::from("a")).unwrap(), 0);
+    }
+}
@@ -0,0 +1,130 @@
+//! Implementation of the `LSET` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Set the element at `index` in the list stored at `key` to `value`.
+///
+/// Negative indices count from the tail of the list. An error is returned if
+/// the key does not exist or the index is out of range.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LSetCmd {
+    /// The key of the list.
+    key: String,
+    /// The index of the element.
+    index: i64,
+    /// The value to store.
+    value: Bytes,
+}
+
+impl LSetCmd {
+    /// Creates a new [`LSetCmd`] command.
+    pub fn new(key: impl ToString, index: i64, value: Bytes) -> Self {
+        Self {
+            key: key.to_string(),
+            index,
+            value,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the index.
+    pub fn index(&self) -> i64 {
+        self.index
+    }
+
+    /// Get the value.
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+}
+
+impl Command for LSetCmd {
+    /// Parse a [`LSetCmd`] instance from a received frame.
+    ///
+    /// The `LSET` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LSET key index value
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let index = parse.next_int_signed()?;
+        let value = parse.next_bytes()?;
+        Ok(Self { key, index, value })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        db.lset(&self.key, self.index, self.value)?;
+        let response = Frame::SimpleString("OK".to_string());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lset"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_int(self.index)?;
+        frame.push_bulk(self.value)?;
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::ListEnd, Db, Error};
+
+    #[tokio::test]
+    async fn test_lset_errors() {
+        let db = Db::default();
+        let values = vec![Bytes::from("a"), Bytes::from("b")];
+        db.push("foo", values, ListEnd::Left).unwrap();
+
+        db.lset("foo", -1, Bytes::from("z")).unwrap();
+        assert_eq!(db.lindex("foo", 1).unwrap(), Some(Bytes::from("z")));
+        assert_eq!(db.lindex("foo", 0).unwrap(), Some(Bytes::from("b")));
+        assert_eq!(db.llen("foo").unwrap(), 2);
+
+        let err = db.lset("foo", 2, Bytes::from("z")).unwrap_err();
+        assert_eq!(
+            err.to_frame(),
+            Some(Frame::SimpleError("ERR index out of range".to_string()))
+        );
+        let err = db.lset("foo", -3, Bytes::from("z")).unwrap_err();
+        assert!(matches!(err, Error::IndexOutOfRange));
+        let err = db.lset("missing", 0, Bytes::from("z")).unwrap_err();
+        assert_eq!(
+            err.to_frame(),
+            Some(Frame::SimpleError("ERR no such key".to_string()))
+        );
+
+        db.set("bar".to_string(), Bytes::from("baz"), None).unwrap();
+        assert!(matches!(db.llen("bar"), Err(Error::WrongType)));
+        assert!(matches!(db.lindex("bar", 0), Err(Error::WrongType)));
+        assert!(matches!(
+            db.lset("bar", 0, Bytes::from("z")),
+            Err(Error::WrongType)
+        ));
+    }
+}
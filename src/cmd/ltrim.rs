@@ -0,0 +1,92 @@
+//! Implementation of the `LTRIM` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Trim the list stored at `key` to the inclusive range from `start` to
+/// `stop`.
+///
+/// Negative indices count from the tail of the list. The key is deleted if the
+/// range is empty.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LTrimCmd {
+    /// The key of the list.
+    key: String,
+    /// The index of the first element to keep.
+    start: i64,
+    /// The index of the last element to keep.
+    stop: i64,
+}
+
+impl LTrimCmd {
+    /// Creates a new [`LTrimCmd`] command.
+    pub fn new(key: impl ToString, start: i64, stop: i64) -> Self {
+        Self {
+            key: key.to_string(),
+            start,
+            stop,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the start index.
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    /// Get the stop index.
+    pub fn stop(&self) -> i64 {
+        self.stop
+    }
+}
+
+impl Command for LTrimCmd {
+    /// Parse a [`LTrimCmd`] instance from a received frame.
+    ///
+    /// The `LTRIM` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LTRIM key start stop
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let start = parse.next_int_signed()?;
+        let stop = parse.next_int_signed()?;
+        Ok(Self { key, start, stop })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        db.ltrim(&self.key, self.start, self.stop)?;
+        let response = Frame::SimpleString("OK".to_string());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("ltrim"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_int(self.start)?;
+        frame.push_int(self.stop)?;
+        Ok(frame)
+    }
+}
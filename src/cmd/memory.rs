@@ -0,0 +1,172 @@
+//! Implementation of the `MEMORY USAGE` command.
+use super::{
+    help::{help_frame, unknown_subcommand},
+    Command,
+};
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Reports the approximate number of bytes used to store `key` and its value.
+///
+/// The size is the length of the key and value, as counted against the
+/// `maxmemory` budget, plus a fixed per-entry overhead. If the key does not
+/// exist, a `Null` RESP type is returned.
+///
+/// `MEMORY HELP` is also supported, returning the documentation of the
+/// subcommands.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MemoryUsageCmd {
+    subcommand: Subcommand,
+}
+
+/// The `MEMORY` subcommands.
+#[derive(Debug, PartialEq, Eq)]
+enum Subcommand {
+    /// `MEMORY USAGE key`, with the key to report the size of.
+    Usage(String),
+    /// `MEMORY HELP`
+    Help,
+}
+
+/// The documentation of the subcommands, returned by `MEMORY HELP`.
+const HELP: &[&str] = &[
+    "USAGE <key> [SAMPLES <count>]",
+    "    Return the number of bytes used to store <key> and its value.",
+];
+
+impl MemoryUsageCmd {
+    /// Creates a new [`MemoryUsageCmd`] command.
+    pub fn new(key: impl ToString) -> Self {
+        Self {
+            subcommand: Subcommand::Usage(key.to_string()),
+        }
+    }
+
+    /// Creates a new `MEMORY HELP` command.
+    pub fn help() -> Self {
+        Self {
+            subcommand: Subcommand::Help,
+        }
+    }
+
+    /// Get the key, unless this is `MEMORY HELP`.
+    pub fn key(&self) -> Option<&str> {
+        match &self.subcommand {
+            Subcommand::Usage(key) => Some(key),
+            Subcommand::Help => None,
+        }
+    }
+}
+
+impl Command for MemoryUsageCmd {
+    /// Parse a [`MemoryUsageCmd`] instance from a received frame.
+    ///
+    /// The `MEMORY` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// MEMORY USAGE key [SAMPLES count]
+    /// MEMORY HELP
+    /// ```
+    ///
+    /// `SAMPLES` is accepted for compatibility, but ignored, as the size of
+    /// every value is known exactly.
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let subcommand = parse.next_string()?;
+        match subcommand.to_uppercase().as_str() {
+            "USAGE" => {}
+            "HELP" => {
+                return Ok(Self {
+                    subcommand: Subcommand::Help,
+                })
+            }
+            _ => return Err(unknown_subcommand("MEMORY", subcommand)),
+        }
+
+        let key = parse.next_string()?;
+        match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "SAMPLES" => {
+                parse.next_int_unsigned()?;
+            }
+            Ok(_) => return Err(Error::Syntax),
+            Err(Error::EndOfStream) => {}
+            Err(err) => return Err(err),
+        }
+
+        Ok(Self {
+            subcommand: Subcommand::Usage(key),
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = match self.subcommand {
+            Subcommand::Usage(key) => match db.memory_usage(&key) {
+                Some(size) => Frame::Integer(size as i64),
+                None => Frame::NullBulkString,
+            },
+            Subcommand::Help => help_frame("MEMORY", HELP)?,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("memory"))?;
+        match self.subcommand {
+            Subcommand::Usage(key) => {
+                frame.push_bulk(Bytes::from("usage"))?;
+                frame.push_bulk(Bytes::from(key))?;
+            }
+            Subcommand::Help => frame.push_bulk(Bytes::from("help"))?,
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for MemoryUsageCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.subcommand {
+            Subcommand::Usage(key) => write!(f, "MEMORY USAGE {key}"),
+            Subcommand::Help => write!(f, "MEMORY HELP"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommandVariant, Db};
+
+    #[tokio::test]
+    async fn test_memory_usage() {
+        let db = Db::default();
+        db.set("foo".to_string(), Bytes::from(vec![0; 1000]), None)
+            .unwrap();
+
+        let frame = MemoryUsageCmd::new("foo").into_frame().unwrap();
+        let CommandVariant::MemoryUsage(cmd) = CommandVariant::from_frame(frame).unwrap() else {
+            panic!("unexpected command");
+        };
+        let size = db.memory_usage(cmd.key().unwrap()).unwrap();
+        // The key and value lengths, plus a bounded overhead.
+        assert!((1003..1003 + 256).contains(&size), "size is {size}");
+
+        assert_eq!(db.memory_usage("missing"), None);
+    }
+}
@@ -1,21 +1,206 @@
 //! Commands module.
-use crate::{parse::Parse, Connection, ConnectionStream, Db, Error, Frame, Result, Shutdown};
-use std::fmt::Display;
+use crate::{
+    latency::EVENT_COMMAND, parse::Parse, server::ServerState, Connection, ConnectionStream, Db,
+    Error, Frame, Result, Shutdown,
+};
+use bytes::Bytes;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, LazyLock},
+};
+use tokio::time::Instant;
+use tracing::info;
+
+pub mod auth;
+pub use auth::AuthCmd;
+
+pub mod bit;
+pub use bit::{BitCountCmd, BitFieldCmd, BitFieldOp, BitFieldType, GetBitCmd, SetBitCmd};
+
+pub mod bpop;
+pub use bpop::{BLPopCmd, BRPopCmd};
+
+pub mod client;
+pub use client::{ClientCmd, PauseMode};
+
+pub mod command;
+pub use command::CommandCmd;
+
+pub mod copy;
+pub use copy::CopyCmd;
+
+pub mod custom;
+pub use custom::CustomCmd;
+
+pub mod debug;
+pub use debug::DebugCmd;
+
+pub mod dump;
+pub use dump::{DumpCmd, RestoreCmd};
+
+pub mod expire;
+pub use expire::{ExpireCmd, ExpireCondition};
 
 pub mod get;
 pub use get::GetCmd;
 
+mod help;
+
+pub mod getex;
+pub use getex::{GetExCmd, GetExOption};
+
+pub mod getset;
+pub use getset::GetSetCmd;
+
+pub mod hello;
+pub use hello::HelloCmd;
+
+pub mod hget;
+pub use hget::HGetCmd;
+
+pub mod hexpire;
+pub use hexpire::{HExpireCmd, HPersistCmd, HTtlCmd};
+
+pub mod hincrby;
+pub use hincrby::HIncrByCmd;
+
+pub mod hkeys;
+pub use hkeys::HKeysCmd;
+
+pub mod hmget;
+pub use hmget::HMGetCmd;
+
+pub mod hrandfield;
+pub use hrandfield::HRandFieldCmd;
+
+pub mod hset;
+pub use hset::HSetCmd;
+
+pub mod hvals;
+pub use hvals::HValsCmd;
+
+pub mod hyperloglog;
+pub use hyperloglog::{PfAddCmd, PfCountCmd};
+
+pub mod incr;
+pub use incr::{DecrByCmd, DecrCmd, IncrByCmd, IncrCmd};
+
+pub mod incrbyfloat;
+pub use incrbyfloat::IncrByFloatCmd;
+
+pub mod info;
+pub use info::InfoCmd;
+
+pub mod latency;
+pub use latency::LatencyCmd;
+
+pub mod lindex;
+pub use lindex::LIndexCmd;
+
+pub mod llen;
+pub use llen::LLenCmd;
+
+pub mod lolwut;
+pub use lolwut::LolwutCmd;
+
+pub mod lpos;
+pub use lpos::LPosCmd;
+
+pub mod lrem;
+pub use lrem::LRemCmd;
+
+pub mod lset;
+pub use lset::LSetCmd;
+
+pub mod ltrim;
+pub use ltrim::LTrimCmd;
+
+pub mod memory;
+pub use memory::MemoryUsageCmd;
+
+pub mod mpop;
+pub use mpop::{LMPopCmd, ListDirection, ScoreDirection, ScoredMember, ZMPopCmd};
+
+pub mod monitor;
+pub use monitor::MonitorCmd;
+
+pub mod object;
+pub use object::ObjectCmd;
+
 pub mod ping;
 pub use ping::PingCmd;
 
+pub mod push;
+pub use push::{LPushCmd, RPushCmd};
+
+pub mod quit;
+pub use quit::QuitCmd;
+
+pub mod replication;
+pub use replication::{PSyncCmd, ReplConfCmd};
+
+pub mod reset;
+pub use reset::ResetCmd;
+
+pub mod rpoplpush;
+pub use rpoplpush::RPopLPushCmd;
+
+pub mod sadd;
+pub use sadd::SAddCmd;
+
+pub mod scan;
+pub use scan::{HScanCmd, SScanCmd, ZScanCmd};
+
 pub mod set;
-pub use set::SetCmd;
+pub use set::{SetCmd, SetCondition};
+
+pub mod setops;
+pub use setops::{
+    SDiffCmd, SDiffStoreCmd, SInterCardCmd, SInterCmd, SInterStoreCmd, SUnionCmd, SUnionStoreCmd,
+};
 
 pub mod publish;
 pub use publish::PublishCmd;
 
+pub mod slowlog;
+pub use slowlog::SlowLogCmd;
+
+pub mod smove;
+pub use smove::SMoveCmd;
+
 pub mod subscribe;
-pub use subscribe::SubscribeCmd;
+pub use subscribe::{SubscribeCmd, UnsubscribeCmd};
+
+pub mod time;
+pub use time::TimeCmd;
+
+pub mod transaction;
+pub use transaction::{DiscardCmd, ExecCmd, MultiCmd};
+
+pub mod wait;
+pub use wait::WaitCmd;
+
+pub mod zadd;
+pub use zadd::ZAddCmd;
+
+pub mod zcard;
+pub use zcard::{ZCardCmd, ZCountCmd};
+
+pub mod zrandmember;
+pub use zrandmember::ZRandMemberCmd;
+
+pub mod zrange;
+pub use zrange::{LexBound, ZRangeByLexCmd, ZRangeByScoreCmd, ZRangeCmd};
+
+pub mod zrank;
+pub use zrank::{ZRankCmd, ZRevRankCmd};
+
+pub mod zrem;
+pub use zrem::ZRemCmd;
+
+pub mod zscore;
+pub use zscore::ZScoreCmd;
 
 /// `Command` trait that has methods to create a `Command` from received frames,
 /// creating frames from a `Command`, and applying a `Command` to
@@ -34,56 +219,1283 @@ pub(crate) trait Command {
     fn into_frame(self) -> Result<Frame>;
 }
 
+/// Static information about a command supported by the server.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CommandSpec {
+    /// Name of the command, in uppercase.
+    pub(crate) name: &'static str,
+    /// Number of arguments, including the command name itself.
+    ///
+    /// Follows the Redis convention, a positive arity is an exact number of
+    /// arguments, while a negative arity is a minimum of `-arity` arguments.
+    pub(crate) arity: i64,
+    /// Parser of the arguments following the command name.
+    pub(crate) parse: fn(&mut Parse) -> Result<CommandVariant>,
+}
+
+impl CommandSpec {
+    /// Look up the spec of a builtin command by name, ignoring case.
+    pub(crate) fn lookup(name: &str) -> Option<&'static CommandSpec> {
+        COMMAND_TABLE
+            .iter()
+            .find(|spec| spec.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Parses the commands of a given name, once registered in a
+/// [`CommandRegistry`].
+///
+/// Builtin commands are described by a [`CommandSpec`], commands registered
+/// at runtime by a [`CustomSpec`](custom::CustomSpec).
+pub(crate) trait CommandFactory: std::fmt::Debug + Send + Sync {
+    /// Name of the command, in uppercase.
+    fn name(&self) -> &'static str;
+
+    /// Number of arguments, following the convention of
+    /// [`CommandSpec::arity`].
+    fn arity(&self) -> i64;
+
+    /// Parse the arguments following the command name.
+    fn parse(&self, parse: &mut Parse) -> Result<CommandVariant>;
+
+    /// Returns `true` if `argc` arguments, including the command name itself,
+    /// satisfy the arity of the command.
+    fn accepts(&self, argc: usize) -> bool {
+        let (argc, arity) = (argc as i64, self.arity());
+        if arity >= 0 {
+            argc == arity
+        } else {
+            argc >= -arity
+        }
+    }
+}
+
+impl CommandFactory for CommandSpec {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn arity(&self) -> i64 {
+        self.arity
+    }
+
+    fn parse(&self, parse: &mut Parse) -> Result<CommandVariant> {
+        (self.parse)(parse)
+    }
+}
+
+/// Positions of the key arguments of a command, like the `first_key`,
+/// `last_key` and `step` of the Redis command table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct KeySpec {
+    /// Index of the first key, the command name being at index `0`.
+    first: usize,
+    /// Index of the last key. Negative indexes count from the end of the
+    /// arguments, `-1` being the last one.
+    last: isize,
+    /// Distance between two keys.
+    step: usize,
+}
+
+impl KeySpec {
+    /// A single key, right after the command name.
+    pub(crate) const SINGLE: KeySpec = KeySpec::new(1, 1, 1);
+    /// Every argument is a key.
+    pub(crate) const ALL: KeySpec = KeySpec::new(1, -1, 1);
+
+    pub(crate) const fn new(first: usize, last: isize, step: usize) -> Self {
+        Self { first, last, step }
+    }
+
+    /// Select the keys of `args`, a full command line including the command
+    /// name.
+    ///
+    /// Returns `None` if `args` is too short for the key positions.
+    pub(crate) fn keys<'a>(&self, args: &'a [Bytes]) -> Option<Vec<&'a Bytes>> {
+        let last = if self.last < 0 {
+            args.len().checked_add_signed(self.last)?
+        } else {
+            self.last as usize
+        };
+        if last >= args.len() || last < self.first {
+            return None;
+        }
+        Some(args[self.first..=last].iter().step_by(self.step).collect())
+    }
+}
+
+/// Maps command names to their [`CommandFactory`], dispatching received frames
+/// to the parser of the command they name.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct CommandRegistry {
+    /// Factories keyed by the uppercase command name.
+    commands: HashMap<&'static str, Arc<dyn CommandFactory>>,
+}
+
+/// Registry of every command in [`COMMAND_TABLE`].
+static BUILTIN_REGISTRY: LazyLock<CommandRegistry> = LazyLock::new(|| {
+    let mut registry = CommandRegistry::default();
+    for spec in COMMAND_TABLE {
+        registry.register(*spec);
+    }
+    registry
+});
+
+impl CommandRegistry {
+    /// The registry of the commands supported by the server.
+    pub(crate) fn builtin() -> &'static CommandRegistry {
+        &BUILTIN_REGISTRY
+    }
+
+    /// Register a command, replacing any command with the same name.
+    pub(crate) fn register(&mut self, factory: impl CommandFactory + 'static) {
+        debug_assert_eq!(factory.name(), factory.name().to_uppercase());
+        self.commands.insert(factory.name(), Arc::new(factory));
+    }
+
+    /// Remove a command by name, ignoring case, so it is parsed as an unknown
+    /// command.
+    pub(crate) fn unregister(&mut self, name: &str) {
+        self.commands.remove(name.to_ascii_uppercase().as_str());
+    }
+
+    /// Look up the factory of a command by name, ignoring case.
+    pub(crate) fn lookup(&self, name: &str) -> Option<&dyn CommandFactory> {
+        self.commands
+            .get(name.to_ascii_uppercase().as_str())
+            .map(|factory| &**factory)
+    }
+
+    /// Parse a command from a received frame, with the factory registered for
+    /// its name.
+    ///
+    /// The number of arguments is checked against the arity of the command
+    /// before its parser runs, and again after it, as optional arguments may
+    /// be left over.
+    pub(crate) fn parse(&self, frame: Frame) -> Result<CommandVariant> {
+        let mut parse = Parse::new(frame)?;
+
+        let command_name = parse.next_string()?;
+
+        let Some(factory) = self.lookup(&command_name) else {
+            return Err(Error::UnknownCommand {
+                name: command_name,
+                args: parse.remaining_lossy(),
+            });
+        };
+        let name = factory.name().to_lowercase();
+
+        // Validate the number of arguments before extracting any field, the
+        // command name itself counts as an argument.
+        if !factory.accepts(parse.remaining() + 1) {
+            return Err(Error::WrongNumberOfArguments(name));
+        }
+
+        // A parser running out of arguments means one was missing.
+        let command = factory.parse(&mut parse).map_err(|err| match err {
+            Error::EndOfStream => Error::WrongNumberOfArguments(name.clone()),
+            err => err,
+        })?;
+
+        // Optional arguments may still be left over after parsing, which means
+        // the command was given too many of them.
+        parse
+            .finish()
+            .map_err(|_| Error::WrongNumberOfArguments(name))?;
+
+        Ok(command)
+    }
+}
+
+/// Every command supported by the server.
+///
+/// [`CommandVariant::from_frame`] rejects any command missing from this table,
+/// making it the single place a command is registered in, for both parsing and
+/// `COMMAND` introspection.
+pub(crate) const COMMAND_TABLE: &[CommandSpec] = &[
+    CommandSpec {
+        name: "COMMAND",
+        arity: -1,
+        parse: |parse| Ok(CommandVariant::Command(CommandCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "GET",
+        arity: 2,
+        parse: |parse| Ok(CommandVariant::Get(GetCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "RESET",
+        arity: 1,
+        parse: |parse| Ok(CommandVariant::Reset(ResetCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "SET",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::Set(SetCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "INCRBYFLOAT",
+        arity: 3,
+        parse: |parse| {
+            Ok(CommandVariant::IncrByFloat(IncrByFloatCmd::parse_frames(
+                parse,
+            )?))
+        },
+    },
+    CommandSpec {
+        name: "PING",
+        arity: -1,
+        parse: |parse| Ok(CommandVariant::Ping(PingCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "PUBLISH",
+        arity: 3,
+        parse: |parse| Ok(CommandVariant::Publish(PublishCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "SUBSCRIBE",
+        arity: -2,
+        parse: |parse| {
+            Ok(CommandVariant::Subscribe(SubscribeCmd::parse_frames(
+                parse,
+            )?))
+        },
+    },
+    CommandSpec {
+        name: "UNSUBSCRIBE",
+        arity: -1,
+        parse: |parse| {
+            Ok(CommandVariant::Unsubscribe(UnsubscribeCmd::parse_frames(
+                parse,
+            )?))
+        },
+    },
+    CommandSpec {
+        name: "WAIT",
+        arity: 3,
+        parse: |parse| Ok(CommandVariant::Wait(WaitCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "SADD",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::SAdd(SAddCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "SINTER",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::SInter(SInterCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "SUNION",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::SUnion(SUnionCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "SDIFF",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::SDiff(SDiffCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "ZADD",
+        arity: -4,
+        parse: |parse| Ok(CommandVariant::ZAdd(ZAddCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "ZSCORE",
+        arity: 3,
+        parse: |parse| Ok(CommandVariant::ZScore(ZScoreCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "ZRANGE",
+        arity: -4,
+        parse: |parse| Ok(CommandVariant::ZRange(ZRangeCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "LPUSH",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::LPush(LPushCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "RPUSH",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::RPush(RPushCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "LLEN",
+        arity: 2,
+        parse: |parse| Ok(CommandVariant::LLen(LLenCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "LINDEX",
+        arity: 3,
+        parse: |parse| Ok(CommandVariant::LIndex(LIndexCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "LSET",
+        arity: 4,
+        parse: |parse| Ok(CommandVariant::LSet(LSetCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "LREM",
+        arity: 4,
+        parse: |parse| Ok(CommandVariant::LRem(LRemCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "LTRIM",
+        arity: 4,
+        parse: |parse| Ok(CommandVariant::LTrim(LTrimCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "RPOPLPUSH",
+        arity: 3,
+        parse: |parse| {
+            Ok(CommandVariant::RPopLPush(RPopLPushCmd::parse_frames(
+                parse,
+            )?))
+        },
+    },
+    CommandSpec {
+        name: "HSET",
+        arity: -4,
+        parse: |parse| Ok(CommandVariant::HSet(HSetCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "HGET",
+        arity: 3,
+        parse: |parse| Ok(CommandVariant::HGet(HGetCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "HINCRBY",
+        arity: 4,
+        parse: |parse| Ok(CommandVariant::HIncrBy(HIncrByCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "HMGET",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::HMGet(HMGetCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "HKEYS",
+        arity: 2,
+        parse: |parse| Ok(CommandVariant::HKeys(HKeysCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "HVALS",
+        arity: 2,
+        parse: |parse| Ok(CommandVariant::HVals(HValsCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "HSCAN",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::HScan(HScanCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "SSCAN",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::SScan(SScanCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "ZSCAN",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::ZScan(ZScanCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "COPY",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::Copy(CopyCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "AUTH",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::Auth(AuthCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "QUIT",
+        arity: -1,
+        parse: |parse| Ok(CommandVariant::Quit(QuitCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "MEMORY",
+        arity: -2,
+        parse: |parse| {
+            Ok(CommandVariant::MemoryUsage(MemoryUsageCmd::parse_frames(
+                parse,
+            )?))
+        },
+    },
+    CommandSpec {
+        name: "INFO",
+        arity: -1,
+        parse: |parse| Ok(CommandVariant::Info(InfoCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "DEBUG",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::Debug(DebugCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "SLOWLOG",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::SlowLog(SlowLogCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "LOLWUT",
+        arity: -1,
+        parse: |parse| Ok(CommandVariant::Lolwut(LolwutCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "LPOS",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::LPos(LPosCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "GETEX",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::GetEx(GetExCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "OBJECT",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::Object(ObjectCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "MULTI",
+        arity: 1,
+        parse: |parse| Ok(CommandVariant::Multi(MultiCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "EXEC",
+        arity: 1,
+        parse: |parse| Ok(CommandVariant::Exec(ExecCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "DISCARD",
+        arity: 1,
+        parse: |parse| Ok(CommandVariant::Discard(DiscardCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "CLIENT",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::Client(ClientCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "DUMP",
+        arity: 2,
+        parse: |parse| Ok(CommandVariant::Dump(DumpCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "RESTORE",
+        arity: -4,
+        parse: |parse| Ok(CommandVariant::Restore(RestoreCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "TIME",
+        arity: 1,
+        parse: |parse| Ok(CommandVariant::Time(TimeCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "SETBIT",
+        arity: 4,
+        parse: |parse| Ok(CommandVariant::SetBit(SetBitCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "GETBIT",
+        arity: 3,
+        parse: |parse| Ok(CommandVariant::GetBit(GetBitCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "BITCOUNT",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::BitCount(BitCountCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "GETSET",
+        arity: 3,
+        parse: |parse| Ok(CommandVariant::GetSet(GetSetCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "HELLO",
+        arity: -1,
+        parse: |parse| Ok(CommandVariant::Hello(HelloCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "LATENCY",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::Latency(LatencyCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "BLPOP",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::BLPop(BLPopCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "BRPOP",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::BRPop(BRPopCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "SINTERSTORE",
+        arity: -3,
+        parse: |parse| {
+            Ok(CommandVariant::SInterStore(SInterStoreCmd::parse_frames(
+                parse,
+            )?))
+        },
+    },
+    CommandSpec {
+        name: "SUNIONSTORE",
+        arity: -3,
+        parse: |parse| {
+            Ok(CommandVariant::SUnionStore(SUnionStoreCmd::parse_frames(
+                parse,
+            )?))
+        },
+    },
+    CommandSpec {
+        name: "SDIFFSTORE",
+        arity: -3,
+        parse: |parse| {
+            Ok(CommandVariant::SDiffStore(SDiffStoreCmd::parse_frames(
+                parse,
+            )?))
+        },
+    },
+    CommandSpec {
+        name: "SMOVE",
+        arity: 4,
+        parse: |parse| Ok(CommandVariant::SMove(SMoveCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "ZRANGEBYSCORE",
+        arity: -4,
+        parse: |parse| {
+            Ok(CommandVariant::ZRangeByScore(
+                ZRangeByScoreCmd::parse_frames(parse)?,
+            ))
+        },
+    },
+    CommandSpec {
+        name: "ZRANGEBYLEX",
+        arity: -4,
+        parse: |parse| {
+            Ok(CommandVariant::ZRangeByLex(ZRangeByLexCmd::parse_frames(
+                parse,
+            )?))
+        },
+    },
+    CommandSpec {
+        name: "ZRANK",
+        arity: 3,
+        parse: |parse| Ok(CommandVariant::ZRank(ZRankCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "ZREVRANK",
+        arity: 3,
+        parse: |parse| Ok(CommandVariant::ZRevRank(ZRevRankCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "ZCARD",
+        arity: 2,
+        parse: |parse| Ok(CommandVariant::ZCard(ZCardCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "ZCOUNT",
+        arity: 4,
+        parse: |parse| Ok(CommandVariant::ZCount(ZCountCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "ZREM",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::ZRem(ZRemCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "PFADD",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::PfAdd(PfAddCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "PFCOUNT",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::PfCount(PfCountCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "INCR",
+        arity: 2,
+        parse: |parse| Ok(CommandVariant::Incr(IncrCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "DECR",
+        arity: 2,
+        parse: |parse| Ok(CommandVariant::Decr(DecrCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "INCRBY",
+        arity: 3,
+        parse: |parse| Ok(CommandVariant::IncrBy(IncrByCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "DECRBY",
+        arity: 3,
+        parse: |parse| Ok(CommandVariant::DecrBy(DecrByCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "BITFIELD",
+        arity: -2,
+        parse: |parse| Ok(CommandVariant::BitField(BitFieldCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "LMPOP",
+        arity: -4,
+        parse: |parse| Ok(CommandVariant::LMPop(LMPopCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "ZMPOP",
+        arity: -4,
+        parse: |parse| Ok(CommandVariant::ZMPop(ZMPopCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "HRANDFIELD",
+        arity: -2,
+        parse: |parse| {
+            Ok(CommandVariant::HRandField(HRandFieldCmd::parse_frames(
+                parse,
+            )?))
+        },
+    },
+    CommandSpec {
+        name: "ZRANDMEMBER",
+        arity: -2,
+        parse: |parse| {
+            Ok(CommandVariant::ZRandMember(ZRandMemberCmd::parse_frames(
+                parse,
+            )?))
+        },
+    },
+    CommandSpec {
+        name: "SINTERCARD",
+        arity: -3,
+        parse: |parse| {
+            Ok(CommandVariant::SInterCard(SInterCardCmd::parse_frames(
+                parse,
+            )?))
+        },
+    },
+    CommandSpec {
+        name: "EXPIRE",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::Expire(ExpireCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "HEXPIRE",
+        arity: -6,
+        parse: |parse| Ok(CommandVariant::HExpire(HExpireCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "HTTL",
+        arity: -5,
+        parse: |parse| Ok(CommandVariant::HTtl(HTtlCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "HPERSIST",
+        arity: -5,
+        parse: |parse| Ok(CommandVariant::HPersist(HPersistCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "MONITOR",
+        arity: 1,
+        parse: |parse| Ok(CommandVariant::Monitor(MonitorCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "REPLCONF",
+        arity: -3,
+        parse: |parse| Ok(CommandVariant::ReplConf(ReplConfCmd::parse_frames(parse)?)),
+    },
+    CommandSpec {
+        name: "PSYNC",
+        arity: 3,
+        parse: |parse| Ok(CommandVariant::PSync(PSyncCmd::parse_frames(parse)?)),
+    },
+];
+
 /// All possible command variants.
 #[derive(Debug, PartialEq, Eq)]
 pub enum CommandVariant {
+    /// A command registered at runtime, see [`CustomSpec`](custom::CustomSpec).
+    Custom(CustomCmd),
+    /// `COMMAND` command.
+    Command(CommandCmd),
     /// `GET` command.
     Get(GetCmd),
+    /// `RESET` command.
+    Reset(ResetCmd),
     /// `SET` command.
     Set(SetCmd),
+    /// `INCRBYFLOAT` command.
+    IncrByFloat(IncrByFloatCmd),
     /// `PING` command.
     Ping(PingCmd),
     /// `PUBLISH` command.
     Publish(PublishCmd),
     /// `SUBSCRIBE` command.
     Subscribe(SubscribeCmd),
+    /// `UNSUBSCRIBE` command.
+    Unsubscribe(UnsubscribeCmd),
+    /// `WAIT` command.
+    Wait(WaitCmd),
+    /// `SADD` command.
+    SAdd(SAddCmd),
+    /// `SINTER` command.
+    SInter(SInterCmd),
+    /// `SUNION` command.
+    SUnion(SUnionCmd),
+    /// `SDIFF` command.
+    SDiff(SDiffCmd),
+    /// `ZADD` command.
+    ZAdd(ZAddCmd),
+    /// `ZSCORE` command.
+    ZScore(ZScoreCmd),
+    /// `ZRANGE` command.
+    ZRange(ZRangeCmd),
+    /// `LPUSH` command.
+    LPush(LPushCmd),
+    /// `RPUSH` command.
+    RPush(RPushCmd),
+    /// `LLEN` command.
+    LLen(LLenCmd),
+    /// `LINDEX` command.
+    LIndex(LIndexCmd),
+    /// `LSET` command.
+    LSet(LSetCmd),
+    /// `LREM` command.
+    LRem(LRemCmd),
+    /// `LTRIM` command.
+    LTrim(LTrimCmd),
+    /// `RPOPLPUSH` command.
+    RPopLPush(RPopLPushCmd),
+    /// `HSET` command.
+    HSet(HSetCmd),
+    /// `HGET` command.
+    HGet(HGetCmd),
+    /// `HINCRBY` command.
+    HIncrBy(HIncrByCmd),
+    /// `HMGET` command.
+    HMGet(HMGetCmd),
+    /// `HKEYS` command.
+    HKeys(HKeysCmd),
+    /// `HVALS` command.
+    HVals(HValsCmd),
+    /// `HSCAN` command.
+    HScan(HScanCmd),
+    /// `SSCAN` command.
+    SScan(SScanCmd),
+    /// `ZSCAN` command.
+    ZScan(ZScanCmd),
+    /// `COPY` command.
+    Copy(CopyCmd),
+    /// `AUTH` command.
+    Auth(AuthCmd),
+    /// `QUIT` command.
+    Quit(QuitCmd),
+    /// `MEMORY USAGE` command.
+    MemoryUsage(MemoryUsageCmd),
+    /// `INFO` command.
+    Info(InfoCmd),
+    /// `DEBUG` command.
+    Debug(DebugCmd),
+    /// `SLOWLOG` command.
+    SlowLog(SlowLogCmd),
+    /// `LOLWUT` command.
+    Lolwut(LolwutCmd),
+    /// `LPOS` command.
+    LPos(LPosCmd),
+    /// `GETEX` command.
+    GetEx(GetExCmd),
+    /// `OBJECT` command.
+    Object(ObjectCmd),
+    /// `MULTI` command.
+    Multi(MultiCmd),
+    /// `EXEC` command.
+    Exec(ExecCmd),
+    /// `DISCARD` command.
+    Discard(DiscardCmd),
+    /// `CLIENT` command.
+    Client(ClientCmd),
+    /// `DUMP` command.
+    Dump(DumpCmd),
+    /// `RESTORE` command.
+    Restore(RestoreCmd),
+    /// `TIME` command.
+    Time(TimeCmd),
+    /// `SETBIT` command.
+    SetBit(SetBitCmd),
+    /// `GETBIT` command.
+    GetBit(GetBitCmd),
+    /// `BITCOUNT` command.
+    BitCount(BitCountCmd),
+    /// `GETSET` command.
+    GetSet(GetSetCmd),
+    /// `HELLO` command.
+    Hello(HelloCmd),
+    /// `LATENCY` command.
+    Latency(LatencyCmd),
+    /// `BLPOP` command.
+    BLPop(BLPopCmd),
+    /// `BRPOP` command.
+    BRPop(BRPopCmd),
+    /// `SINTERSTORE` command.
+    SInterStore(SInterStoreCmd),
+    /// `SUNIONSTORE` command.
+    SUnionStore(SUnionStoreCmd),
+    /// `SDIFFSTORE` command.
+    SDiffStore(SDiffStoreCmd),
+    /// `SMOVE` command.
+    SMove(SMoveCmd),
+    /// `ZRANGEBYSCORE` command.
+    ZRangeByScore(ZRangeByScoreCmd),
+    /// `ZRANGEBYLEX` command.
+    ZRangeByLex(ZRangeByLexCmd),
+    /// `ZRANK` command.
+    ZRank(ZRankCmd),
+    /// `ZREVRANK` command.
+    ZRevRank(ZRevRankCmd),
+    /// `ZCARD` command.
+    ZCard(ZCardCmd),
+    /// `ZCOUNT` command.
+    ZCount(ZCountCmd),
+    /// `ZREM` command.
+    ZRem(ZRemCmd),
+    /// `PFADD` command.
+    PfAdd(PfAddCmd),
+    /// `PFCOUNT` command.
+    PfCount(PfCountCmd),
+    /// `INCR` command.
+    Incr(IncrCmd),
+    /// `DECR` command.
+    Decr(DecrCmd),
+    /// `INCRBY` command.
+    IncrBy(IncrByCmd),
+    /// `DECRBY` command.
+    DecrBy(DecrByCmd),
+    /// `BITFIELD` command.
+    BitField(BitFieldCmd),
+    /// `LMPOP` command.
+    LMPop(LMPopCmd),
+    /// `ZMPOP` command.
+    ZMPop(ZMPopCmd),
+    /// `HRANDFIELD` command.
+    HRandField(HRandFieldCmd),
+    /// `ZRANDMEMBER` command.
+    ZRandMember(ZRandMemberCmd),
+    /// `SINTERCARD` command.
+    SInterCard(SInterCardCmd),
+    /// `EXPIRE` command.
+    Expire(ExpireCmd),
+    /// `REPLCONF` command.
+    ReplConf(ReplConfCmd),
+    /// `PSYNC` command.
+    PSync(PSyncCmd),
+    /// `MONITOR` command.
+    Monitor(MonitorCmd),
+    /// `HEXPIRE` command.
+    HExpire(HExpireCmd),
+    /// `HTTL` command.
+    HTtl(HTtlCmd),
+    /// `HPERSIST` command.
+    HPersist(HPersistCmd),
 }
 
 impl CommandVariant {
     /// Parse a frame into a command variant.
     #[tracing::instrument(ret, skip_all, level = "debug")]
     pub fn from_frame(frame: Frame) -> Result<Self> {
-        let mut parse = Parse::new(frame)?;
+        CommandRegistry::builtin().parse(frame)
+    }
+
+    /// Whether the command reads keys without modifying the keyspace.
+    ///
+    /// Commands neither reading nor writing keys, such as `PING`, are
+    /// neither reads nor writes.
+    pub(crate) fn is_read(&self) -> bool {
+        if let CommandVariant::Custom(cmd) = self {
+            return !cmd.is_write() && !cmd.keys().is_empty();
+        }
+        !self.is_write() && command::has_key_arguments(self.name())
+    }
 
-        let command_name = parse.next_string()?.to_uppercase();
+    /// Whether the command may modify the keyspace.
+    pub(crate) fn is_write(&self) -> bool {
+        use CommandVariant as C;
 
-        let command = match &command_name[..] {
-            "GET" => CommandVariant::Get(GetCmd::parse_frames(&mut parse)?),
-            "SET" => CommandVariant::Set(SetCmd::parse_frames(&mut parse)?),
-            "PING" => CommandVariant::Ping(PingCmd::parse_frames(&mut parse)?),
-            "PUB" => CommandVariant::Publish(PublishCmd::parse_frames(&mut parse)?),
-            _ => return Err(Error::UnknownCommand(command_name)),
-        };
+        if let C::Custom(cmd) = self {
+            return cmd.is_write();
+        }
+        matches!(
+            self,
+            C::Set(_)
+                | C::IncrByFloat(_)
+                | C::Publish(_)
+                | C::SAdd(_)
+                | C::ZAdd(_)
+                | C::LPush(_)
+                | C::RPush(_)
+                | C::LSet(_)
+                | C::LRem(_)
+                | C::LTrim(_)
+                | C::RPopLPush(_)
+                | C::HSet(_)
+                | C::HIncrBy(_)
+                | C::Copy(_)
+                | C::GetEx(_)
+                | C::Restore(_)
+                | C::SetBit(_)
+                | C::GetSet(_)
+                | C::BLPop(_)
+                | C::BRPop(_)
+                | C::SInterStore(_)
+                | C::SUnionStore(_)
+                | C::SDiffStore(_)
+                | C::SMove(_)
+                | C::ZRem(_)
+                | C::PfAdd(_)
+                | C::Incr(_)
+                | C::Decr(_)
+                | C::IncrBy(_)
+                | C::DecrBy(_)
+                | C::BitField(_)
+                | C::LMPop(_)
+                | C::ZMPop(_)
+                | C::Expire(_)
+                | C::HExpire(_)
+                | C::HPersist(_)
+        )
+    }
 
-        parse.finish()?;
+    /// The name of the command, as registered in [`COMMAND_TABLE`].
+    pub(crate) fn name(&self) -> &'static str {
+        use CommandVariant as C;
 
-        Ok(command)
+        match self {
+            C::Custom(cmd) => cmd.name(),
+            C::Command(_) => "COMMAND",
+            C::Get(_) => "GET",
+            C::Reset(_) => "RESET",
+            C::Set(_) => "SET",
+            C::IncrByFloat(_) => "INCRBYFLOAT",
+            C::Ping(_) => "PING",
+            C::Publish(_) => "PUBLISH",
+            C::Subscribe(_) => "SUBSCRIBE",
+            C::Unsubscribe(_) => "UNSUBSCRIBE",
+            C::Wait(_) => "WAIT",
+            C::SAdd(_) => "SADD",
+            C::SInter(_) => "SINTER",
+            C::SUnion(_) => "SUNION",
+            C::SDiff(_) => "SDIFF",
+            C::ZAdd(_) => "ZADD",
+            C::ZScore(_) => "ZSCORE",
+            C::ZRange(_) => "ZRANGE",
+            C::LPush(_) => "LPUSH",
+            C::RPush(_) => "RPUSH",
+            C::LLen(_) => "LLEN",
+            C::LIndex(_) => "LINDEX",
+            C::LSet(_) => "LSET",
+            C::LRem(_) => "LREM",
+            C::LTrim(_) => "LTRIM",
+            C::RPopLPush(_) => "RPOPLPUSH",
+            C::HSet(_) => "HSET",
+            C::HGet(_) => "HGET",
+            C::HIncrBy(_) => "HINCRBY",
+            C::HMGet(_) => "HMGET",
+            C::HKeys(_) => "HKEYS",
+            C::HVals(_) => "HVALS",
+            C::HScan(_) => "HSCAN",
+            C::SScan(_) => "SSCAN",
+            C::ZScan(_) => "ZSCAN",
+            C::Copy(_) => "COPY",
+            C::Auth(_) => "AUTH",
+            C::Quit(_) => "QUIT",
+            C::MemoryUsage(_) => "MEMORY",
+            C::Info(_) => "INFO",
+            C::Debug(_) => "DEBUG",
+            C::SlowLog(_) => "SLOWLOG",
+            C::Lolwut(_) => "LOLWUT",
+            C::LPos(_) => "LPOS",
+            C::GetEx(_) => "GETEX",
+            C::Object(_) => "OBJECT",
+            C::Multi(_) => "MULTI",
+            C::Exec(_) => "EXEC",
+            C::Discard(_) => "DISCARD",
+            C::Client(_) => "CLIENT",
+            C::Dump(_) => "DUMP",
+            C::Restore(_) => "RESTORE",
+            C::Time(_) => "TIME",
+            C::SetBit(_) => "SETBIT",
+            C::GetBit(_) => "GETBIT",
+            C::BitCount(_) => "BITCOUNT",
+            C::GetSet(_) => "GETSET",
+            C::Hello(_) => "HELLO",
+            C::Latency(_) => "LATENCY",
+            C::BLPop(_) => "BLPOP",
+            C::BRPop(_) => "BRPOP",
+            C::SInterStore(_) => "SINTERSTORE",
+            C::SUnionStore(_) => "SUNIONSTORE",
+            C::SDiffStore(_) => "SDIFFSTORE",
+            C::SMove(_) => "SMOVE",
+            C::ZRangeByScore(_) => "ZRANGEBYSCORE",
+            C::ZRangeByLex(_) => "ZRANGEBYLEX",
+            C::ZRank(_) => "ZRANK",
+            C::ZRevRank(_) => "ZREVRANK",
+            C::ZCard(_) => "ZCARD",
+            C::ZCount(_) => "ZCOUNT",
+            C::ZRem(_) => "ZREM",
+            C::PfAdd(_) => "PFADD",
+            C::PfCount(_) => "PFCOUNT",
+            C::Incr(_) => "INCR",
+            C::Decr(_) => "DECR",
+            C::IncrBy(_) => "INCRBY",
+            C::DecrBy(_) => "DECRBY",
+            C::BitField(_) => "BITFIELD",
+            C::LMPop(_) => "LMPOP",
+            C::ZMPop(_) => "ZMPOP",
+            C::HRandField(_) => "HRANDFIELD",
+            C::ZRandMember(_) => "ZRANDMEMBER",
+            C::SInterCard(_) => "SINTERCARD",
+            C::Expire(_) => "EXPIRE",
+            C::ReplConf(_) => "REPLCONF",
+            C::PSync(_) => "PSYNC",
+            C::Monitor(_) => "MONITOR",
+            C::HExpire(_) => "HEXPIRE",
+            C::HTtl(_) => "HTTL",
+            C::HPersist(_) => "HPERSIST",
+        }
+    }
+
+    /// Make blocking commands reply right away instead of blocking, as done
+    /// inside a transaction.
+    pub(crate) fn without_blocking(self) -> Self {
+        use CommandVariant as C;
+
+        match self {
+            C::BLPop(cmd) => C::BLPop(cmd.without_blocking()),
+            C::BRPop(cmd) => C::BRPop(cmd.without_blocking()),
+            cmd => cmd,
+        }
+    }
+
+    /// The key the command operates on, if it operates on a single one, or
+    /// the source key for commands moving values across keys.
+    pub(crate) fn key(&self) -> Option<&str> {
+        use CommandVariant as C;
+
+        match self {
+            C::Custom(cmd) => cmd.keys().first().map(String::as_str),
+            C::Get(cmd) => Some(cmd.key()),
+            C::Set(cmd) => Some(cmd.key()),
+            C::IncrByFloat(cmd) => Some(cmd.key()),
+            C::Incr(cmd) => Some(cmd.key()),
+            C::Decr(cmd) => Some(cmd.key()),
+            C::IncrBy(cmd) => Some(cmd.key()),
+            C::DecrBy(cmd) => Some(cmd.key()),
+            C::SAdd(cmd) => Some(cmd.key()),
+            C::ZAdd(cmd) => Some(cmd.key()),
+            C::ZScore(cmd) => Some(cmd.key()),
+            C::ZRange(cmd) => Some(cmd.key()),
+            C::ZRangeByScore(cmd) => Some(cmd.key()),
+            C::ZRangeByLex(cmd) => Some(cmd.key()),
+            C::ZRank(cmd) => Some(cmd.key()),
+            C::ZRevRank(cmd) => Some(cmd.key()),
+            C::ZCard(cmd) => Some(cmd.key()),
+            C::ZCount(cmd) => Some(cmd.key()),
+            C::ZRem(cmd) => Some(cmd.key()),
+            C::LPush(cmd) => Some(cmd.key()),
+            C::RPush(cmd) => Some(cmd.key()),
+            C::LLen(cmd) => Some(cmd.key()),
+            C::LIndex(cmd) => Some(cmd.key()),
+            C::LSet(cmd) => Some(cmd.key()),
+            C::LRem(cmd) => Some(cmd.key()),
+            C::LTrim(cmd) => Some(cmd.key()),
+            C::RPopLPush(cmd) => Some(cmd.source()),
+            C::SMove(cmd) => Some(cmd.source()),
+            C::HSet(cmd) => Some(cmd.key()),
+            C::HGet(cmd) => Some(cmd.key()),
+            C::HIncrBy(cmd) => Some(cmd.key()),
+            C::HMGet(cmd) => Some(cmd.key()),
+            C::HKeys(cmd) => Some(cmd.key()),
+            C::HRandField(cmd) => Some(cmd.key()),
+            C::ZRandMember(cmd) => Some(cmd.key()),
+            C::HVals(cmd) => Some(cmd.key()),
+            C::HScan(cmd) => Some(cmd.key()),
+            C::SScan(cmd) => Some(cmd.key()),
+            C::ZScan(cmd) => Some(cmd.key()),
+            C::Copy(cmd) => Some(cmd.source()),
+            C::MemoryUsage(cmd) => cmd.key(),
+            C::LPos(cmd) => Some(cmd.key()),
+            C::GetEx(cmd) => Some(cmd.key()),
+            C::Expire(cmd) => Some(cmd.key()),
+            C::HExpire(cmd) => Some(cmd.key()),
+            C::HTtl(cmd) => Some(cmd.key()),
+            C::HPersist(cmd) => Some(cmd.key()),
+            C::Dump(cmd) => Some(cmd.key()),
+            C::Restore(cmd) => Some(cmd.key()),
+            C::SetBit(cmd) => Some(cmd.key()),
+            C::GetBit(cmd) => Some(cmd.key()),
+            C::BitCount(cmd) => Some(cmd.key()),
+            C::BitField(cmd) => Some(cmd.key()),
+            C::LMPop(cmd) => cmd.keys().first().map(String::as_str),
+            C::ZMPop(cmd) => cmd.keys().first().map(String::as_str),
+            C::PfAdd(cmd) => Some(cmd.key()),
+            C::GetSet(cmd) => Some(cmd.key()),
+            C::BLPop(cmd) => cmd.keys().first().map(String::as_str),
+            C::BRPop(cmd) => cmd.keys().first().map(String::as_str),
+            _ => None,
+        }
     }
 
     pub(crate) async fn apply<S: ConnectionStream>(
         self,
         db: &Db,
+        server: &ServerState,
         dst: &mut Connection<S>,
-        _shutdown: &mut Shutdown,
+        shutdown: &mut Shutdown,
     ) -> Result<()> {
         use CommandVariant as C;
 
-        match self {
+        let name = self.name();
+        let key = server
+            .access_log()
+            .then(|| self.key().map(str::to_string))
+            .flatten();
+        // Rendering the command has a cost, only pay it if it may be logged.
+        // Subscriptions and blocking pops last as long as the client wants,
+        // they are not slow.
+        let is_blocking = matches!(
+            self,
+            C::Subscribe(_) | C::Monitor(_) | C::BLPop(_) | C::BRPop(_)
+        );
+        let (is_read, is_write) = (self.is_read(), self.is_write());
+        let preview = (server.slowlog().is_enabled() && !is_blocking).then(|| self.to_string());
+        let start = Instant::now();
+
+        let res = match self {
+            C::Custom(cmd) => cmd.apply(db, dst).await,
+            C::Command(cmd) => cmd.apply(db, dst).await,
             C::Get(cmd) => cmd.apply(db, dst).await,
+            C::Reset(cmd) => cmd.apply(db, dst).await,
             C::Set(cmd) => cmd.apply(db, dst).await,
+            C::IncrByFloat(cmd) => cmd.apply(db, dst).await,
             C::Ping(cmd) => cmd.apply(db, dst).await,
             C::Publish(cmd) => cmd.apply(db, dst).await,
+            C::Subscribe(cmd) => cmd.apply(db, dst, shutdown).await,
+            // `Unsubscribe` cannot be applied. It may only be received from the
+            // context of a `Subscribe` command.
+            C::Unsubscribe(_) => Err(Error::Protocol(
+                "`UNSUBSCRIBE` is unsupported in this context".to_string(),
+            )),
+            C::Wait(cmd) => cmd.apply(db, dst).await,
+            C::SAdd(cmd) => cmd.apply(db, dst).await,
+            C::SInter(cmd) => cmd.apply(db, dst).await,
+            C::SUnion(cmd) => cmd.apply(db, dst).await,
+            C::SDiff(cmd) => cmd.apply(db, dst).await,
+            C::ZAdd(cmd) => cmd.apply(db, dst).await,
+            C::ZScore(cmd) => cmd.apply(db, dst).await,
+            C::ZRange(cmd) => cmd.apply(db, dst).await,
+            C::LPush(cmd) => cmd.apply(db, dst).await,
+            C::RPush(cmd) => cmd.apply(db, dst).await,
+            C::LLen(cmd) => cmd.apply(db, dst).await,
+            C::LIndex(cmd) => cmd.apply(db, dst).await,
+            C::LSet(cmd) => cmd.apply(db, dst).await,
+            C::LRem(cmd) => cmd.apply(db, dst).await,
+            C::LTrim(cmd) => cmd.apply(db, dst).await,
+            C::RPopLPush(cmd) => cmd.apply(db, dst).await,
+            C::HSet(cmd) => cmd.apply(db, dst).await,
+            C::HGet(cmd) => cmd.apply(db, dst).await,
+            C::HIncrBy(cmd) => cmd.apply(db, dst).await,
+            C::HMGet(cmd) => cmd.apply(db, dst).await,
+            C::HKeys(cmd) => cmd.apply(db, dst).await,
+            C::HVals(cmd) => cmd.apply(db, dst).await,
+            C::HScan(cmd) => cmd.apply(db, dst).await,
+            C::SScan(cmd) => cmd.apply(db, dst).await,
+            C::ZScan(cmd) => cmd.apply(db, dst).await,
+            C::Copy(cmd) => cmd.apply(db, dst).await,
+            C::Auth(cmd) => cmd.apply(server, dst).await,
+            C::Quit(cmd) => cmd.apply(db, dst).await,
+            C::MemoryUsage(cmd) => cmd.apply(db, dst).await,
+            C::Info(cmd) => cmd.apply(server, dst).await,
+            C::Debug(cmd) => cmd.apply(db, server, dst).await,
+            C::SlowLog(cmd) => cmd.apply(server, dst).await,
+            C::Lolwut(cmd) => cmd.apply(db, dst).await,
+            C::LPos(cmd) => cmd.apply(db, dst).await,
+            C::GetEx(cmd) => cmd.apply(db, dst).await,
+            C::Object(cmd) => cmd.apply(db, dst).await,
+            C::Multi(cmd) => cmd.apply(db, dst).await,
+            C::Exec(cmd) => cmd.apply(db, dst).await,
+            C::Discard(cmd) => cmd.apply(db, dst).await,
+            C::Client(cmd) => cmd.apply(server, dst).await,
+            C::Dump(cmd) => cmd.apply(db, dst).await,
+            C::Restore(cmd) => cmd.apply(db, dst).await,
+            C::Time(cmd) => cmd.apply(db, dst).await,
+            C::SetBit(cmd) => cmd.apply(db, dst).await,
+            C::GetBit(cmd) => cmd.apply(db, dst).await,
+            C::BitCount(cmd) => cmd.apply(db, dst).await,
+            C::GetSet(cmd) => cmd.apply(db, dst).await,
+            C::Hello(cmd) => cmd.apply(db, dst).await,
+            C::Latency(cmd) => cmd.apply(server, dst).await,
+            C::BLPop(cmd) => cmd.apply(db, dst, shutdown).await,
+            C::BRPop(cmd) => cmd.apply(db, dst, shutdown).await,
+            C::SInterStore(cmd) => cmd.apply(db, dst).await,
+            C::SUnionStore(cmd) => cmd.apply(db, dst).await,
+            C::SDiffStore(cmd) => cmd.apply(db, dst).await,
+            C::SMove(cmd) => cmd.apply(db, dst).await,
+            C::ZRangeByScore(cmd) => cmd.apply(db, dst).await,
+            C::ZRangeByLex(cmd) => cmd.apply(db, dst).await,
+            C::ZRank(cmd) => cmd.apply(db, dst).await,
+            C::ZRevRank(cmd) => cmd.apply(db, dst).await,
+            C::ZCard(cmd) => cmd.apply(db, dst).await,
+            C::ZCount(cmd) => cmd.apply(db, dst).await,
+            C::ZRem(cmd) => cmd.apply(db, dst).await,
+            C::PfAdd(cmd) => cmd.apply(db, dst).await,
+            C::PfCount(cmd) => cmd.apply(db, dst).await,
+            C::Incr(cmd) => cmd.apply(db, dst).await,
+            C::Decr(cmd) => cmd.apply(db, dst).await,
+            C::IncrBy(cmd) => cmd.apply(db, dst).await,
+            C::DecrBy(cmd) => cmd.apply(db, dst).await,
+            C::BitField(cmd) => cmd.apply(db, dst).await,
+            C::LMPop(cmd) => cmd.apply(db, dst).await,
+            C::ZMPop(cmd) => cmd.apply(db, dst).await,
+            C::HRandField(cmd) => cmd.apply(db, dst).await,
+            C::ZRandMember(cmd) => cmd.apply(db, dst).await,
+            C::SInterCard(cmd) => cmd.apply(db, dst).await,
+            C::Expire(cmd) => cmd.apply(db, dst).await,
+            C::ReplConf(cmd) => cmd.apply(db, dst).await,
+            C::PSync(cmd) => cmd.apply(db, server, dst).await,
+            C::Monitor(cmd) => cmd.apply(server, dst, shutdown).await,
+            C::HExpire(cmd) => cmd.apply(db, dst).await,
+            C::HTtl(cmd) => cmd.apply(db, dst).await,
+            C::HPersist(cmd) => cmd.apply(db, dst).await,
+        };
+
+        let elapsed = start.elapsed();
+        server.stats().record(name, elapsed);
+        if is_read {
+            server.stats().record_read();
+        } else if is_write {
+            server.stats().record_write();
         }
+        if server.access_log() {
+            info!(
+                target: "loja::access",
+                command = name,
+                key,
+                latency_us = elapsed.as_micros() as u64,
+                ok = res.is_ok(),
+            );
+        }
+        if let Some(preview) = preview {
+            server.slowlog().record(elapsed, &preview);
+        }
+        if !is_blocking {
+            server.latency().record(EVENT_COMMAND, elapsed);
+        }
+        res
     }
 }
 
@@ -92,20 +1504,26 @@ impl Display for CommandVariant {
         use CommandVariant as C;
 
         match self {
+            C::Custom(cmd) => write!(f, "{cmd}"),
+            C::Command(cmd) => write!(f, "{cmd}"),
             C::Get(cmd) => write!(f, "GET {}", cmd.key()),
+            C::Reset(_) => write!(f, "RESET"),
             C::Set(cmd) => {
+                write!(f, "SET {} {:?}", cmd.key(), cmd.value())?;
                 if let Some(exp) = cmd.expire() {
-                    write!(
-                        f,
-                        "SET {} {:?} EX {}",
-                        cmd.key(),
-                        cmd.value(),
-                        exp.as_millis()
-                    )
-                } else {
-                    write!(f, "SET {} {:?}", cmd.key(), cmd.value())
+                    write!(f, " EX {}", exp.as_millis())?;
+                }
+                match cmd.set_condition() {
+                    Some(SetCondition::Nx) => write!(f, " NX")?,
+                    Some(SetCondition::Xx) => write!(f, " XX")?,
+                    None => {}
                 }
+                if cmd.returns_previous() {
+                    write!(f, " GET")?;
+                }
+                Ok(())
             }
+            C::IncrByFloat(cmd) => write!(f, "INCRBYFLOAT {} {}", cmd.key(), cmd.increment()),
             C::Ping(cmd) => {
                 if let Some(msg) = cmd.msg() {
                     write!(f, "PING {:?}", msg)
@@ -113,23 +1531,235 @@ impl Display for CommandVariant {
                     write!(f, "PING")
                 }
             }
-            C::Publish(cmd) => write!(f, "PUB {} {:?}", cmd.channel(), cmd.message()),
+            C::Publish(cmd) => write!(f, "PUBLISH {} {:?}", cmd.channel(), cmd.message()),
+            C::Subscribe(cmd) => write!(f, "SUBSCRIBE {}", cmd.channels().join(" ")),
+            C::Unsubscribe(cmd) => {
+                if cmd.channels().is_empty() {
+                    write!(f, "UNSUBSCRIBE")
+                } else {
+                    write!(f, "UNSUBSCRIBE {}", cmd.channels().join(" "))
+                }
+            }
+            C::Wait(cmd) => write!(
+                f,
+                "WAIT {} {}",
+                cmd.numreplicas(),
+                cmd.timeout().as_millis()
+            ),
+            C::SAdd(cmd) => {
+                write!(f, "SADD {}", cmd.key())?;
+                for member in cmd.members() {
+                    write!(f, " {member:?}")?;
+                }
+                Ok(())
+            }
+            C::SInter(cmd) => write!(f, "SINTER {}", cmd.keys().join(" ")),
+            C::SUnion(cmd) => write!(f, "SUNION {}", cmd.keys().join(" ")),
+            C::SDiff(cmd) => write!(f, "SDIFF {}", cmd.keys().join(" ")),
+            C::ZAdd(cmd) => {
+                write!(f, "ZADD {}", cmd.key())?;
+                for (score, member) in cmd.members() {
+                    write!(f, " {score} {member:?}")?;
+                }
+                Ok(())
+            }
+            C::ZScore(cmd) => write!(f, "ZSCORE {} {:?}", cmd.key(), cmd.member()),
+            C::ZRange(cmd) => {
+                write!(f, "ZRANGE {} {} {}", cmd.key(), cmd.start(), cmd.stop())?;
+                if cmd.with_scores() {
+                    write!(f, " WITHSCORES")?;
+                }
+                Ok(())
+            }
+            C::LPush(cmd) => {
+                write!(f, "LPUSH {}", cmd.key())?;
+                for value in cmd.values() {
+                    write!(f, " {value:?}")?;
+                }
+                Ok(())
+            }
+            C::RPush(cmd) => {
+                write!(f, "RPUSH {}", cmd.key())?;
+                for value in cmd.values() {
+                    write!(f, " {value:?}")?;
+                }
+                Ok(())
+            }
+            C::LLen(cmd) => write!(f, "LLEN {}", cmd.key()),
+            C::LIndex(cmd) => write!(f, "LINDEX {} {}", cmd.key(), cmd.index()),
+            C::LSet(cmd) => write!(f, "LSET {} {} {:?}", cmd.key(), cmd.index(), cmd.value()),
+            C::LRem(cmd) => write!(f, "LREM {} {} {:?}", cmd.key(), cmd.count(), cmd.value()),
+            C::LTrim(cmd) => write!(f, "LTRIM {} {} {}", cmd.key(), cmd.start(), cmd.stop()),
+            C::RPopLPush(cmd) => write!(f, "RPOPLPUSH {} {}", cmd.source(), cmd.destination()),
+            C::HSet(cmd) => {
+                write!(f, "HSET {}", cmd.key())?;
+                for (field, value) in cmd.fields() {
+                    write!(f, " {field:?} {value:?}")?;
+                }
+                Ok(())
+            }
+            C::HGet(cmd) => write!(f, "HGET {} {:?}", cmd.key(), cmd.field()),
+            C::HIncrBy(cmd) => write!(
+                f,
+                "HINCRBY {} {:?} {}",
+                cmd.key(),
+                cmd.field(),
+                cmd.increment()
+            ),
+            C::HMGet(cmd) => {
+                write!(f, "HMGET {}", cmd.key())?;
+                for field in cmd.fields() {
+                    write!(f, " {field:?}")?;
+                }
+                Ok(())
+            }
+            C::HKeys(cmd) => write!(f, "HKEYS {}", cmd.key()),
+            C::HVals(cmd) => write!(f, "HVALS {}", cmd.key()),
+            C::HScan(cmd) => write!(f, "{cmd}"),
+            C::SScan(cmd) => write!(f, "{cmd}"),
+            C::ZScan(cmd) => write!(f, "{cmd}"),
+            C::Copy(cmd) => write!(f, "{cmd}"),
+            C::Auth(cmd) => write!(f, "{cmd}"),
+            C::Quit(_) => write!(f, "QUIT"),
+            C::MemoryUsage(cmd) => write!(f, "{cmd}"),
+            C::Info(cmd) if cmd.sections().is_empty() => write!(f, "INFO"),
+            C::Info(cmd) => write!(f, "INFO {}", cmd.sections().join(" ")),
+            C::Debug(cmd) => write!(f, "{cmd}"),
+            C::SlowLog(cmd) => write!(f, "{cmd}"),
+            C::Lolwut(cmd) => write!(f, "{cmd}"),
+            C::LPos(cmd) => write!(f, "{cmd}"),
+            C::GetEx(cmd) => write!(f, "{cmd}"),
+            C::Object(cmd) => write!(f, "{cmd}"),
+            C::Multi(_) => write!(f, "MULTI"),
+            C::Exec(_) => write!(f, "EXEC"),
+            C::Discard(_) => write!(f, "DISCARD"),
+            C::Client(cmd) => write!(f, "{cmd}"),
+            C::Dump(cmd) => write!(f, "DUMP {}", cmd.key()),
+            C::Restore(cmd) => write!(f, "{cmd}"),
+            C::Time(_) => write!(f, "TIME"),
+            C::SetBit(cmd) => write!(
+                f,
+                "SETBIT {} {} {}",
+                cmd.key(),
+                cmd.offset(),
+                cmd.bit() as u8
+            ),
+            C::GetBit(cmd) => write!(f, "GETBIT {} {}", cmd.key(), cmd.offset()),
+            C::BitCount(cmd) => write!(f, "{cmd}"),
+            C::GetSet(cmd) => write!(f, "GETSET {} {:?}", cmd.key(), cmd.value()),
+            C::Hello(cmd) => write!(f, "{cmd}"),
+            C::Latency(cmd) => write!(f, "{cmd}"),
+            C::BLPop(cmd) => write!(f, "{cmd}"),
+            C::BRPop(cmd) => write!(f, "{cmd}"),
+            C::SInterStore(cmd) => write!(
+                f,
+                "SINTERSTORE {} {}",
+                cmd.destination(),
+                cmd.keys().join(" ")
+            ),
+            C::SUnionStore(cmd) => write!(
+                f,
+                "SUNIONSTORE {} {}",
+                cmd.destination(),
+                cmd.keys().join(" ")
+            ),
+            C::SDiffStore(cmd) => write!(
+                f,
+                "SDIFFSTORE {} {}",
+                cmd.destination(),
+                cmd.keys().join(" ")
+            ),
+            C::SMove(cmd) => write!(f, "{cmd}"),
+            C::ZRangeByScore(cmd) => write!(f, "{cmd}"),
+            C::ZRangeByLex(cmd) => write!(f, "{cmd}"),
+            C::ZRank(cmd) => write!(f, "ZRANK {} {:?}", cmd.key(), cmd.member()),
+            C::ZRevRank(cmd) => write!(f, "ZREVRANK {} {:?}", cmd.key(), cmd.member()),
+            C::ZCard(cmd) => write!(f, "ZCARD {}", cmd.key()),
+            C::ZCount(cmd) => write!(f, "{cmd}"),
+            C::ZRem(cmd) => {
+                write!(f, "ZREM {}", cmd.key())?;
+                for member in cmd.members() {
+                    write!(f, " {member:?}")?;
+                }
+                Ok(())
+            }
+            C::PfAdd(cmd) => {
+                write!(f, "PFADD {}", cmd.key())?;
+                for element in cmd.elements() {
+                    write!(f, " {element:?}")?;
+                }
+                Ok(())
+            }
+            C::PfCount(cmd) => write!(f, "PFCOUNT {}", cmd.keys().join(" ")),
+            C::Incr(cmd) => write!(f, "INCR {}", cmd.key()),
+            C::Decr(cmd) => write!(f, "DECR {}", cmd.key()),
+            C::IncrBy(cmd) => write!(f, "INCRBY {} {}", cmd.key(), cmd.increment()),
+            C::DecrBy(cmd) => write!(f, "DECRBY {} {}", cmd.key(), cmd.decrement()),
+            C::BitField(cmd) => write!(f, "{cmd}"),
+            C::LMPop(cmd) => write!(f, "{cmd}"),
+            C::ZMPop(cmd) => write!(f, "{cmd}"),
+            C::HRandField(cmd) => write!(f, "{cmd}"),
+            C::ZRandMember(cmd) => write!(f, "{cmd}"),
+            C::SInterCard(cmd) => write!(f, "{cmd}"),
+            C::Expire(cmd) => write!(f, "{cmd}"),
+            C::ReplConf(cmd) => write!(f, "{cmd}"),
+            C::PSync(cmd) => write!(f, "{cmd}"),
+            C::Monitor(_) => write!(f, "MONITOR"),
+            C::HExpire(cmd) => write!(f, "{cmd}"),
+            C::HTtl(cmd) => write!(f, "{cmd}"),
+            C::HPersist(cmd) => write!(f, "{cmd}"),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::time::Duration;
+    use std::{ops::Bound, time::Duration};
 
     use super::*;
+    use crate::{
+        cmd::custom::{CustomSpec, DynCommand},
+        frame::Protocol,
+        server::ServerConfig,
+    };
     use bytes::Bytes;
 
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_access_log() {
+        let db = Db::default();
+        let server = ServerState::new(&ServerConfig {
+            access_log: true,
+            ..Default::default()
+        });
+        let (_tx, rx) = tokio::sync::broadcast::channel(1);
+        let mut shutdown = Shutdown::new(rx);
+        let stream = tokio_test::io::Builder::new().write(b"$-1\r\n").build();
+        let mut conn = Connection::new(stream);
+        CommandVariant::Get(GetCmd::new("foo"))
+            .apply(&db, &server, &mut conn, &mut shutdown)
+            .await
+            .unwrap();
+
+        assert!(logs_contain("loja::access"));
+        assert!(logs_contain("command=\"GET\""));
+        assert!(logs_contain("key=\"foo\""));
+    }
+
     #[test]
     fn test_cmd_variant_display() {
+        let cmd = CommandVariant::Command(CommandCmd::count());
+        assert_eq!(cmd.to_string(), "COMMAND COUNT");
+
+        let cmd = CommandVariant::Command(CommandCmd::docs(vec!["get".to_string()]));
+        assert_eq!(cmd.to_string(), "COMMAND DOCS get");
+
         let cmd = CommandVariant::Get(GetCmd::new("foo"));
         assert_eq!(cmd.to_string(), "GET foo");
 
+        let cmd = CommandVariant::Reset(ResetCmd::new());
+        assert_eq!(cmd.to_string(), "RESET");
+
         let cmd = CommandVariant::Set(SetCmd::new("foo", Bytes::from("bar"), None));
         assert_eq!(cmd.to_string(), "SET foo b\"bar\"");
 
@@ -140,6 +1770,9 @@ mod tests {
         ));
         assert_eq!(cmd.to_string(), "SET foo b\"bar\" EX 10000");
 
+        let cmd = CommandVariant::IncrByFloat(IncrByFloatCmd::new("foo", 1.5));
+        assert_eq!(cmd.to_string(), "INCRBYFLOAT foo 1.5");
+
         let cmd = CommandVariant::Ping(PingCmd::new(None));
         assert_eq!(cmd.to_string(), "PING");
 
@@ -147,7 +1780,242 @@ mod tests {
         assert_eq!(cmd.to_string(), "PING b\"hello\"");
 
         let cmd = CommandVariant::Publish(PublishCmd::new("foo".to_string(), Bytes::from("bar")));
-        assert_eq!(cmd.to_string(), "PUB foo b\"bar\"");
+        assert_eq!(cmd.to_string(), "PUBLISH foo b\"bar\"");
+
+        let cmd = CommandVariant::Subscribe(SubscribeCmd::new(vec![
+            "foo".to_string(),
+            "bar".to_string(),
+        ]));
+        assert_eq!(cmd.to_string(), "SUBSCRIBE foo bar");
+
+        let cmd = CommandVariant::Unsubscribe(UnsubscribeCmd::new(vec![]));
+        assert_eq!(cmd.to_string(), "UNSUBSCRIBE");
+
+        let cmd = CommandVariant::Wait(WaitCmd::new(1, Duration::from_millis(100)));
+        assert_eq!(cmd.to_string(), "WAIT 1 100");
+
+        let cmd = CommandVariant::SAdd(SAddCmd::new(
+            "foo",
+            vec![Bytes::from("a"), Bytes::from("b")],
+        ));
+        assert_eq!(cmd.to_string(), "SADD foo b\"a\" b\"b\"");
+
+        let keys = vec!["foo".to_string(), "bar".to_string()];
+        let cmd = CommandVariant::SInter(SInterCmd::new(keys.clone()));
+        assert_eq!(cmd.to_string(), "SINTER foo bar");
+
+        let cmd = CommandVariant::SUnion(SUnionCmd::new(keys.clone()));
+        assert_eq!(cmd.to_string(), "SUNION foo bar");
+
+        let cmd = CommandVariant::SDiff(SDiffCmd::new(keys.clone()));
+        assert_eq!(cmd.to_string(), "SDIFF foo bar");
+
+        let cmd = CommandVariant::SDiffStore(SDiffStoreCmd::new("dest", keys));
+        assert_eq!(cmd.to_string(), "SDIFFSTORE dest foo bar");
+
+        let cmd = CommandVariant::ZAdd(ZAddCmd::new("foo", vec![(1.5, Bytes::from("a"))]));
+        assert_eq!(cmd.to_string(), "ZADD foo 1.5 b\"a\"");
+
+        let cmd = CommandVariant::ZScore(ZScoreCmd::new("foo", Bytes::from("a")));
+        assert_eq!(cmd.to_string(), "ZSCORE foo b\"a\"");
+
+        let cmd = CommandVariant::ZRange(ZRangeCmd::new("foo", 0, -1, true));
+        assert_eq!(cmd.to_string(), "ZRANGE foo 0 -1 WITHSCORES");
+
+        let cmd = CommandVariant::LPush(LPushCmd::new("foo", vec![Bytes::from("a")]));
+        assert_eq!(cmd.to_string(), "LPUSH foo b\"a\"");
+
+        let cmd = CommandVariant::RPush(RPushCmd::new("foo", vec![Bytes::from("a")]));
+        assert_eq!(cmd.to_string(), "RPUSH foo b\"a\"");
+
+        let cmd = CommandVariant::LLen(LLenCmd::new("foo"));
+        assert_eq!(cmd.to_string(), "LLEN foo");
+
+        let cmd = CommandVariant::LIndex(LIndexCmd::new("foo", -1));
+        assert_eq!(cmd.to_string(), "LINDEX foo -1");
+
+        let cmd = CommandVariant::LSet(LSetCmd::new("foo", 0, Bytes::from("a")));
+        assert_eq!(cmd.to_string(), "LSET foo 0 b\"a\"");
+
+        let cmd = CommandVariant::LRem(LRemCmd::new("foo", -2, Bytes::from("a")));
+        assert_eq!(cmd.to_string(), "LREM foo -2 b\"a\"");
+
+        let cmd = CommandVariant::LTrim(LTrimCmd::new("foo", 1, -1));
+        assert_eq!(cmd.to_string(), "LTRIM foo 1 -1");
+
+        let cmd = CommandVariant::RPopLPush(RPopLPushCmd::new("foo", "bar"));
+        assert_eq!(cmd.to_string(), "RPOPLPUSH foo bar");
+
+        let cmd = CommandVariant::HSet(HSetCmd::new(
+            "foo",
+            vec![(Bytes::from("a"), Bytes::from("1"))],
+        ));
+        assert_eq!(cmd.to_string(), "HSET foo b\"a\" b\"1\"");
+
+        let cmd = CommandVariant::HGet(HGetCmd::new("foo", Bytes::from("a")));
+        assert_eq!(cmd.to_string(), "HGET foo b\"a\"");
+
+        let cmd = CommandVariant::HIncrBy(HIncrByCmd::new("foo", Bytes::from("a"), -2));
+        assert_eq!(cmd.to_string(), "HINCRBY foo b\"a\" -2");
+
+        let cmd = CommandVariant::HMGet(HMGetCmd::new(
+            "foo",
+            vec![Bytes::from("a"), Bytes::from("b")],
+        ));
+        assert_eq!(cmd.to_string(), "HMGET foo b\"a\" b\"b\"");
+
+        let cmd = CommandVariant::HKeys(HKeysCmd::new("foo"));
+        assert_eq!(cmd.to_string(), "HKEYS foo");
+
+        let cmd = CommandVariant::HVals(HValsCmd::new("foo"));
+        assert_eq!(cmd.to_string(), "HVALS foo");
+
+        let cmd = CommandVariant::HScan(HScanCmd::new("foo", 0, None, None));
+        assert_eq!(cmd.to_string(), "HSCAN foo 0");
+
+        let cmd = CommandVariant::SScan(SScanCmd::new("foo", 3, Some("a*".to_string()), None));
+        assert_eq!(cmd.to_string(), "SSCAN foo 3 MATCH a*");
+
+        let cmd = CommandVariant::ZScan(ZScanCmd::new("foo", 0, Some("a*".to_string()), Some(5)));
+        assert_eq!(cmd.to_string(), "ZSCAN foo 0 MATCH a* COUNT 5");
+
+        let cmd = CommandVariant::Copy(CopyCmd::new("foo", "bar", Some(0), true));
+        assert_eq!(cmd.to_string(), "COPY foo bar DB 0 REPLACE");
+
+        let cmd = CommandVariant::Auth(AuthCmd::new(Some("default".to_string()), "secret"));
+        assert_eq!(cmd.to_string(), "AUTH default <redacted>");
+
+        let cmd = CommandVariant::Quit(QuitCmd::new());
+        assert_eq!(cmd.to_string(), "QUIT");
+
+        let cmd = CommandVariant::MemoryUsage(MemoryUsageCmd::new("foo"));
+        assert_eq!(cmd.to_string(), "MEMORY USAGE foo");
+
+        let cmd = CommandVariant::Info(InfoCmd::new(vec!["CommandStats".to_string()]));
+        assert_eq!(cmd.to_string(), "INFO commandstats");
+
+        let cmd = CommandVariant::Debug(DebugCmd::sleep(Duration::from_millis(1500)));
+        assert_eq!(cmd.to_string(), "DEBUG SLEEP 1.5");
+
+        let cmd = CommandVariant::SlowLog(SlowLogCmd::get(Some(5)));
+        assert_eq!(cmd.to_string(), "SLOWLOG GET 5");
+
+        let cmd = CommandVariant::SlowLog(SlowLogCmd::reset());
+        assert_eq!(cmd.to_string(), "SLOWLOG RESET");
+
+        let cmd = CommandVariant::Lolwut(LolwutCmd::new(Some(5)));
+        assert_eq!(cmd.to_string(), "LOLWUT VERSION 5");
+
+        let cmd = CommandVariant::LPos(LPosCmd::new("foo", Bytes::from("x")).rank(-1).count(2));
+        assert_eq!(cmd.to_string(), "LPOS foo x RANK -1 COUNT 2");
+
+        let cmd = CommandVariant::GetEx(GetExCmd::new("foo", Some(GetExOption::Px(100))));
+        assert_eq!(cmd.to_string(), "GETEX foo PX 100");
+
+        let cmd = CommandVariant::Object(ObjectCmd::idle_time("foo"));
+        assert_eq!(cmd.to_string(), "OBJECT IDLETIME foo");
+
+        let cmd = CommandVariant::Exec(ExecCmd::new());
+        assert_eq!(cmd.to_string(), "EXEC");
+
+        let cmd = CommandVariant::Client(ClientCmd::pause(
+            Duration::from_millis(100),
+            PauseMode::Write,
+        ));
+        assert_eq!(cmd.to_string(), "CLIENT PAUSE 100 WRITE");
+
+        let cmd =
+            CommandVariant::Restore(RestoreCmd::new("foo", None, Bytes::from("x")).replace(true));
+        assert_eq!(cmd.to_string(), "RESTORE foo 0 b\"x\" REPLACE");
+
+        let cmd = CommandVariant::Time(TimeCmd::new());
+        assert_eq!(cmd.to_string(), "TIME");
+
+        let cmd = CommandVariant::SetBit(SetBitCmd::new("foo", 7, true));
+        assert_eq!(cmd.to_string(), "SETBIT foo 7 1");
+        let cmd = CommandVariant::BitCount(BitCountCmd::new("foo").range(0, -1));
+        assert_eq!(cmd.to_string(), "BITCOUNT foo 0 -1");
+
+        let cmd = CommandVariant::Set(SetCmd::new("foo", Bytes::from("bar"), None).get(true));
+        assert_eq!(cmd.to_string(), "SET foo b\"bar\" GET");
+        let cmd = CommandVariant::GetSet(GetSetCmd::new("foo", Bytes::from("bar")));
+        assert_eq!(cmd.to_string(), "GETSET foo b\"bar\"");
+
+        let cmd = CommandVariant::Hello(HelloCmd::new(Some(Protocol::Resp3)));
+        assert_eq!(cmd.to_string(), "HELLO 3");
+
+        let cmd = CommandVariant::Latency(LatencyCmd::history("command"));
+        assert_eq!(cmd.to_string(), "LATENCY HISTORY command");
+
+        let cmd = CommandVariant::BLPop(BLPopCmd::new(
+            vec!["foo".to_string(), "bar".to_string()],
+            Duration::from_millis(1500),
+        ));
+        assert_eq!(cmd.to_string(), "BLPOP foo bar 1.5");
+
+        let cmd = CommandVariant::SMove(SMoveCmd::new("src", "dst", Bytes::from("a")));
+        assert_eq!(cmd.to_string(), "SMOVE src dst b\"a\"");
+
+        let cmd = CommandVariant::ZRangeByLex(
+            ZRangeByLexCmd::new("foo", LexBound::Min, LexBound::Exclusive(Bytes::from("b")))
+                .limit(0, 10),
+        );
+        assert_eq!(cmd.to_string(), "ZRANGEBYLEX foo b\"-\" b\"(b\" LIMIT 0 10");
+
+        let cmd = CommandVariant::ZCount(ZCountCmd::new(
+            "foo",
+            Bound::Excluded(1.5),
+            Bound::Unbounded,
+        ));
+        assert_eq!(cmd.to_string(), "ZCOUNT foo (1.5 +inf");
+
+        let cmd = CommandVariant::ZRem(ZRemCmd::new("foo", vec![Bytes::from("a")]));
+        assert_eq!(cmd.to_string(), "ZREM foo b\"a\"");
+
+        let cmd = CommandVariant::PfAdd(PfAddCmd::new("hll", vec![Bytes::from("a")]));
+        assert_eq!(cmd.to_string(), "PFADD hll b\"a\"");
+
+        let cmd = CommandVariant::DecrBy(DecrByCmd::new("foo", -3));
+        assert_eq!(cmd.to_string(), "DECRBY foo -3");
+
+        let ops = vec![
+            BitFieldOp::Set {
+                ty: BitFieldType::Unsigned(8),
+                offset: 8,
+                value: 255,
+            },
+            BitFieldOp::Get {
+                ty: BitFieldType::Signed(4),
+                offset: 0,
+            },
+        ];
+        let cmd = CommandVariant::BitField(BitFieldCmd::new("foo", ops));
+        assert_eq!(cmd.to_string(), "BITFIELD foo SET u8 8 255 GET i4 0");
+
+        let keys = vec!["foo".to_string(), "bar".to_string()];
+        let cmd = CommandVariant::LMPop(LMPopCmd::new(keys.clone(), ListDirection::Left).count(2));
+        assert_eq!(cmd.to_string(), "LMPOP 2 foo bar LEFT COUNT 2");
+        let cmd = CommandVariant::ZMPop(ZMPopCmd::new(keys, ScoreDirection::Max));
+        assert_eq!(cmd.to_string(), "ZMPOP 2 foo bar MAX COUNT 1");
+
+        let cmd = CommandVariant::HRandField(HRandFieldCmd::new("foo").count(-3).with_values());
+        assert_eq!(cmd.to_string(), "HRANDFIELD foo -3 WITHVALUES");
+        let cmd = CommandVariant::ZRandMember(ZRandMemberCmd::new("foo"));
+        assert_eq!(cmd.to_string(), "ZRANDMEMBER foo");
+
+        let cmd = CommandVariant::SInterCard(
+            SInterCardCmd::new(vec!["a".to_string(), "b".to_string()]).limit(3),
+        );
+        assert_eq!(cmd.to_string(), "SINTERCARD 2 a b LIMIT 3");
+
+        let cmd = CommandVariant::Expire(ExpireCmd::new("foo", 10, Some(ExpireCondition::Gt)));
+        assert_eq!(cmd.to_string(), "EXPIRE foo 10 GT");
+
+        let cmd = CommandVariant::PSync(PSyncCmd::full());
+        assert_eq!(cmd.to_string(), "PSYNC ? -1");
+
+        let cmd = CommandVariant::HTtl(HTtlCmd::new("foo", vec![Bytes::from("a")]));
+        assert_eq!(cmd.to_string(), "HTTL foo FIELDS 1 b\"a\"");
     }
 
     #[test]
@@ -202,14 +2070,153 @@ mod tests {
         );
 
         let frame = Frame::Array(vec![
-            Frame::SimpleString("PUB".to_string()),
+            Frame::SimpleString("PUBLISH".to_string()),
             Frame::SimpleString("foo".to_string()),
             Frame::BulkString(Bytes::from("bar")),
         ]);
         let cmd = CommandVariant::from_frame(frame).unwrap();
+        let publish = PublishCmd::new("foo".to_string(), Bytes::from("bar"));
+        assert_eq!(cmd, CommandVariant::Publish(publish));
+
+        let frame = PublishCmd::new("foo".to_string(), Bytes::from("bar"))
+            .into_frame()
+            .unwrap();
         assert_eq!(
-            cmd,
+            CommandVariant::from_frame(frame).unwrap(),
             CommandVariant::Publish(PublishCmd::new("foo".to_string(), Bytes::from("bar")))
         );
     }
+
+    /// Build a command frame out of its arguments.
+    fn command_frame(args: &[&'static str]) -> Frame {
+        Frame::Array(
+            args.iter()
+                .map(|arg| Frame::BulkString(Bytes::from(*arg)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_cmd_variant_arity() {
+        let cases: &[(&[&'static str], &str)] = &[
+            // Too few arguments.
+            (&["GET"], "get"),
+            (&["SET"], "set"),
+            (&["SET", "foo"], "set"),
+            // Too many arguments.
+            (&["GET", "foo", "bar"], "get"),
+            (&["PING", "foo", "bar"], "ping"),
+        ];
+        for (args, name) in cases {
+            let err = CommandVariant::from_frame(command_frame(args)).unwrap_err();
+            assert_eq!(
+                err.to_frame().unwrap(),
+                Frame::SimpleError(format!(
+                    "ERR wrong number of arguments for '{name}' command"
+                ))
+            );
+        }
+
+        // Too many arguments left over after the `SET` options.
+        let mut frame = command_frame(&["SET", "foo", "bar", "EX", "10"]);
+        frame.push_int(5).unwrap();
+        let err = CommandVariant::from_frame(frame).unwrap_err();
+        assert!(matches!(err, Error::WrongNumberOfArguments(name) if name == "set"));
+
+        // The right number of arguments is accepted.
+        assert!(CommandVariant::from_frame(command_frame(&["GET", "foo"])).is_ok());
+        assert!(CommandVariant::from_frame(command_frame(&["SET", "foo", "bar"])).is_ok());
+        assert!(CommandVariant::from_frame(command_frame(&["PING"])).is_ok());
+        assert!(CommandVariant::from_frame(command_frame(&["PING", "foo"])).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_command_error() {
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("FOO")),
+            Frame::BulkString(Bytes::from("bar")),
+            Frame::BulkString(Bytes::from("baz")),
+        ]);
+        let err = CommandVariant::from_frame(frame).unwrap_err();
+        assert_eq!(
+            err.to_frame().unwrap(),
+            Frame::SimpleError(
+                "ERR unknown command 'FOO', with args beginning with: 'bar', 'baz'".to_string()
+            )
+        );
+
+        let frame = Frame::Array(vec![Frame::BulkString(Bytes::from("foo"))]);
+        let err = CommandVariant::from_frame(frame).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown command 'foo', with args beginning with: "
+        );
+    }
+
+    /// `GREET key`, a custom command storing a greeting at `key`.
+    #[derive(Debug)]
+    struct GreetCmd {
+        key: String,
+    }
+
+    impl DynCommand for GreetCmd {
+        fn apply(self: Box<Self>, db: &Db) -> Result<Frame> {
+            db.set(self.key, Bytes::from("hello"), None)?;
+            Ok(Frame::SimpleString("greeted".to_string()))
+        }
+    }
+
+    impl Display for GreetCmd {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "GREET {}", self.key)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_custom_command() {
+        let mut registry = CommandRegistry::default();
+        registry.register(CustomSpec {
+            name: "GREET",
+            arity: 2,
+            write: true,
+            keys: Some(KeySpec::SINGLE),
+            parse: |parse| {
+                let key = parse.next_string()?;
+                Ok(Box::new(GreetCmd { key }))
+            },
+        });
+
+        let command = registry.parse(command_frame(&["greet", "loja"])).unwrap();
+        assert_eq!(command.name(), "GREET");
+        assert_eq!(command.key(), Some("loja"));
+        assert!(command.is_write());
+        assert!(!command.is_read());
+        assert_eq!(command.to_string(), "GREET loja");
+
+        // The reply comes from the custom command, not from any builtin one.
+        let db = Db::default();
+        let server = ServerState::default();
+        let (_tx, rx) = tokio::sync::broadcast::channel(1);
+        let mut shutdown = Shutdown::new(rx);
+        let stream = tokio_test::io::Builder::new()
+            .write(b"+greeted\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        command
+            .apply(&db, &server, &mut conn, &mut shutdown)
+            .await
+            .unwrap();
+        assert_eq!(db.get("loja").unwrap(), Some(Bytes::from("hello")));
+
+        // The arity is still enforced around the custom parser.
+        let err = registry
+            .parse(command_frame(&["GREET", "loja", "again"]))
+            .unwrap_err();
+        assert!(matches!(err, Error::WrongNumberOfArguments(name) if name == "greet"));
+
+        // Only registered commands are known to the registry.
+        let err = registry.parse(command_frame(&["GET", "foo"])).unwrap_err();
+        assert!(matches!(err, Error::UnknownCommand { .. }));
+        assert!(CommandRegistry::builtin().lookup("greet").is_none());
+    }
 }
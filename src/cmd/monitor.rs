@@ -0,0 +1,133 @@
+//! Implement the `MONITOR` command.
+use crate::{
+    parse::Parse, server::ServerState, Connection, ConnectionStream, Frame, Result, Shutdown,
+};
+use std::{fmt::Write, net::SocketAddr, time::SystemTime};
+use tokio::{select, sync::broadcast::error::RecvError};
+use tokio_stream::StreamExt;
+use tracing::debug;
+
+/// Streams every command processed by the server.
+///
+/// Once `OK` is replied, the connection receives a simple string for each
+/// command any connection sends, formatted as
+/// `<unix time> [<db> <client address>] "COMMAND" "arg" ...`, until it is
+/// closed. Commands sent by the monitoring connection itself are ignored.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MonitorCmd;
+
+impl MonitorCmd {
+    /// Creates a new [`MonitorCmd`] command.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Parse a [`MonitorCmd`] instance from a received frame.
+    ///
+    /// The `MONITOR` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// MONITOR
+    /// ```
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> Result<Self> {
+        Ok(Self)
+    }
+
+    /// Apply the `MONITOR` command, streaming the commands fed to `server` to
+    /// `dst` until the peer disconnects or the server shuts down.
+    pub(crate) async fn apply<S: ConnectionStream>(
+        self,
+        server: &ServerState,
+        dst: &mut Connection<S>,
+        shutdown: &mut Shutdown,
+    ) -> Result<()> {
+        // Subscribe before replying, so no command sent after the reply is
+        // missed.
+        let mut monitor = server.monitor();
+        dst.write_frame(&Frame::SimpleString("OK".to_string()))
+            .await?;
+
+        loop {
+            select! {
+                res = monitor.recv() => match res {
+                    Ok(line) => dst.write_frame(&Frame::SimpleString(line)).await?,
+                    // A slow monitor misses commands rather than slowing down
+                    // the server.
+                    Err(RecvError::Lagged(skipped)) => debug!(skipped, "monitor lagged"),
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                res = dst.next() => match res {
+                    Some(frame) => {
+                        debug!(?frame, "ignoring command sent while monitoring");
+                    }
+                    // This happens if the remote client has disconnected.
+                    None => return Ok(()),
+                },
+                _ = shutdown.recv() => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Render the command received as `frame`, from the client at `addr`, as
+/// reported to the monitors.
+///
+/// Arguments are quoted, with the bytes which are not printable escaped.
+pub(crate) fn monitor_line(addr: Option<SocketAddr>, frame: &Frame) -> String {
+    // A clock set before the Unix epoch is reported as the epoch.
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let mut line = format!("{}.{:06} [0 ", now.as_secs(), now.subsec_micros());
+    match addr {
+        Some(addr) => write!(line, "{addr}]").unwrap(),
+        None => line.push_str("unknown]"),
+    }
+
+    let Frame::Array(args) = frame else {
+        return line;
+    };
+    for arg in args {
+        let arg = match arg {
+            Frame::BulkString(arg) => &arg[..],
+            Frame::SimpleString(arg) => arg.as_bytes(),
+            _ => continue,
+        };
+        line.push_str(" \"");
+        for &byte in arg {
+            match byte {
+                b'\\' => line.push_str("\\\\"),
+                b'"' => line.push_str("\\\""),
+                b'\n' => line.push_str("\\n"),
+                b'\r' => line.push_str("\\r"),
+                b'\t' => line.push_str("\\t"),
+                byte if byte.is_ascii_graphic() || byte == b' ' => line.push(byte as char),
+                byte => write!(line, "\\x{byte:02x}").unwrap(),
+            }
+        }
+        line.push('"');
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    #[test]
+    fn test_monitor_line_quotes_arguments() {
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("SET")),
+            Frame::BulkString(Bytes::from("foo")),
+            Frame::BulkString(Bytes::from_static(b"a \"b\"\n\x01")),
+        ]);
+        let addr = "127.0.0.1:4242".parse().ok();
+        let line = monitor_line(addr, &frame);
+        let (timestamp, rest) = line.split_once(' ').unwrap();
+        assert!(timestamp.parse::<f64>().is_ok(), "{timestamp}");
+        assert_eq!(rest, r#"[0 127.0.0.1:4242] "SET" "foo" "a \"b\"\n\x01""#);
+    }
+}
@@ -0,0 +1,434 @@
+//! Implementation of the `LMPOP` and `ZMPOP` commands.
+use super::Command;
+use crate::{db::ListEnd, parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// The end of the lists [`LMPopCmd`] pops from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListDirection {
+    /// Pop from the head of the list.
+    Left,
+    /// Pop from the tail of the list.
+    Right,
+}
+
+impl From<ListDirection> for ListEnd {
+    fn from(direction: ListDirection) -> Self {
+        match direction {
+            ListDirection::Left => ListEnd::Left,
+            ListDirection::Right => ListEnd::Right,
+        }
+    }
+}
+
+impl Display for ListDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListDirection::Left => write!(f, "LEFT"),
+            ListDirection::Right => write!(f, "RIGHT"),
+        }
+    }
+}
+
+/// The members [`ZMPopCmd`] pops from the sorted sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreDirection {
+    /// Pop the members with the lowest scores.
+    Min,
+    /// Pop the members with the highest scores.
+    Max,
+}
+
+impl Display for ScoreDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScoreDirection::Min => write!(f, "MIN"),
+            ScoreDirection::Max => write!(f, "MAX"),
+        }
+    }
+}
+
+/// A member of a sorted set, along with its score.
+pub type ScoredMember = (Bytes, f64);
+
+/// Pop up to `count` elements from the first non empty list stored at one of
+/// `keys`.
+///
+/// Replies with an array of the key and the popped elements, or with a null
+/// array if every list is empty or missing.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LMPopCmd {
+    /// The keys of the lists, checked in order.
+    keys: Vec<String>,
+    /// The end of the list to pop from.
+    direction: ListDirection,
+    /// Maximum number of elements to pop.
+    count: u64,
+}
+
+impl LMPopCmd {
+    /// Creates a new [`LMPopCmd`] command, popping a single element.
+    pub fn new(keys: Vec<String>, direction: ListDirection) -> Self {
+        Self {
+            keys,
+            direction,
+            count: 1,
+        }
+    }
+
+    /// Pop up to `count` elements.
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Get the keys.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Get the end of the list to pop from.
+    pub fn direction(&self) -> ListDirection {
+        self.direction
+    }
+}
+
+impl Command for LMPopCmd {
+    /// Parse a [`LMPopCmd`] instance from a received frame.
+    ///
+    /// The `LMPOP` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LMPOP numkeys key [key ...] LEFT | RIGHT [COUNT count]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let keys = parse_keys(parse)?;
+        let direction = match parse_direction(parse)?.as_str() {
+            "LEFT" => ListDirection::Left,
+            "RIGHT" => ListDirection::Right,
+            _ => return Err(Error::Syntax),
+        };
+        let count = parse_count(parse)?;
+        Ok(Self {
+            keys,
+            direction,
+            count,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let count = usize::try_from(self.count).unwrap_or(usize::MAX);
+        let response = match db.pop_first(&self.keys, self.direction.into(), count)? {
+            Some((key, values)) => {
+                let mut response = Frame::array();
+                response.push_bulk(Bytes::from(key))?;
+                response.push_frame(Frame::Array(
+                    values.into_iter().map(Frame::BulkString).collect(),
+                ))?;
+                response
+            }
+            None => Frame::NullArray,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = mpop_frame("lmpop", self.keys)?;
+        frame.push_bulk(Bytes::from(self.direction.to_string()))?;
+        frame.push_bulk(Bytes::from("count"))?;
+        frame.push_int(self.count as i64)?;
+        Ok(frame)
+    }
+}
+
+impl Display for LMPopCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "LMPOP {} {} {} COUNT {}",
+            self.keys.len(),
+            self.keys.join(" "),
+            self.direction,
+            self.count
+        )
+    }
+}
+
+/// Pop up to `count` members from the first non empty sorted set stored at
+/// one of `keys`.
+///
+/// Replies with an array of the key and the popped members, each paired with
+/// its score in an array, or with a null array if every sorted set is empty
+/// or missing.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ZMPopCmd {
+    /// The keys of the sorted sets, checked in order.
+    keys: Vec<String>,
+    /// Whether to pop the members with the lowest or the highest scores.
+    direction: ScoreDirection,
+    /// Maximum number of members to pop.
+    count: u64,
+}
+
+impl ZMPopCmd {
+    /// Creates a new [`ZMPopCmd`] command, popping a single member.
+    pub fn new(keys: Vec<String>, direction: ScoreDirection) -> Self {
+        Self {
+            keys,
+            direction,
+            count: 1,
+        }
+    }
+
+    /// Pop up to `count` members.
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Get the keys.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Get whether the members with the lowest or the highest scores are
+    /// popped.
+    pub fn direction(&self) -> ScoreDirection {
+        self.direction
+    }
+}
+
+impl Command for ZMPopCmd {
+    /// Parse a [`ZMPopCmd`] instance from a received frame.
+    ///
+    /// The `ZMPOP` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZMPOP numkeys key [key ...] MIN | MAX [COUNT count]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let keys = parse_keys(parse)?;
+        let direction = match parse_direction(parse)?.as_str() {
+            "MIN" => ScoreDirection::Min,
+            "MAX" => ScoreDirection::Max,
+            _ => return Err(Error::Syntax),
+        };
+        let count = parse_count(parse)?;
+        Ok(Self {
+            keys,
+            direction,
+            count,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let count = usize::try_from(self.count).unwrap_or(usize::MAX);
+        let response = match db.zpop_first(&self.keys, self.direction, count)? {
+            Some((key, members)) => {
+                let mut popped = Frame::array();
+                for (member, score) in members {
+                    let mut pair = Frame::array();
+                    pair.push_bulk(member)?;
+                    pair.push_bulk(Bytes::from(score.to_string()))?;
+                    popped.push_frame(pair)?;
+                }
+                let mut response = Frame::array();
+                response.push_bulk(Bytes::from(key))?;
+                response.push_frame(popped)?;
+                response
+            }
+            None => Frame::NullArray,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = mpop_frame("zmpop", self.keys)?;
+        frame.push_bulk(Bytes::from(self.direction.to_string()))?;
+        frame.push_bulk(Bytes::from("count"))?;
+        frame.push_int(self.count as i64)?;
+        Ok(frame)
+    }
+}
+
+impl Display for ZMPopCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ZMPOP {} {} {} COUNT {}",
+            self.keys.len(),
+            self.keys.join(" "),
+            self.direction,
+            self.count
+        )
+    }
+}
+
+/// Parse the number of keys, followed by the keys themselves.
+pub(super) fn parse_keys(parse: &mut Parse) -> Result<Vec<String>> {
+    let numkeys = parse.next_int_signed()?;
+    if numkeys <= 0 {
+        return Err(Error::NumKeysNotPositive);
+    }
+    (0..numkeys).map(|_| parse.next_string()).collect()
+}
+
+/// Parse the direction following the keys, in uppercase.
+///
+/// A missing direction means fewer keys were given than announced.
+fn parse_direction(parse: &mut Parse) -> Result<String> {
+    match parse.next_string() {
+        Ok(direction) => Ok(direction.to_uppercase()),
+        Err(Error::EndOfStream) => Err(Error::Syntax),
+        Err(err) => Err(err),
+    }
+}
+
+/// Parse the optional `COUNT` option, ending the arguments.
+fn parse_count(parse: &mut Parse) -> Result<u64> {
+    let count = match parse.next_string() {
+        Ok(s) if s.to_uppercase() == "COUNT" => u64::try_from(parse.next_int_signed()?)
+            .ok()
+            .filter(|count| *count > 0)
+            .ok_or(Error::CountNotPositive)?,
+        Ok(_) => return Err(Error::Syntax),
+        Err(Error::EndOfStream) => return Ok(1),
+        Err(err) => return Err(err),
+    };
+    if parse.remaining() > 0 {
+        return Err(Error::Syntax);
+    }
+    Ok(count)
+}
+
+/// Creates the start of the request frame of the command `name`, up to its
+/// keys.
+fn mpop_frame(name: &'static str, keys: Vec<String>) -> Result<Frame> {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from(name))?;
+    frame.push_int(keys.len() as i64)?;
+    for key in keys {
+        frame.push_bulk(Bytes::from(key))?;
+    }
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    #[tokio::test]
+    async fn test_lmpop_skips_empty_lists() {
+        let db = Db::default();
+        db.push(
+            "second",
+            ["a", "b", "c"].map(Bytes::from).to_vec(),
+            ListEnd::Right,
+        )
+        .unwrap();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*2\r\n$6\r\nsecond\r\n*2\r\n$1\r\nc\r\n$1\r\nb\r\n")
+            .write(b"*2\r\n$6\r\nsecond\r\n*1\r\n$1\r\na\r\n")
+            .write(b"*-1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        let keys = vec!["first".to_string(), "second".to_string()];
+        LMPopCmd::new(keys.clone(), ListDirection::Right)
+            .count(2)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        // Popping the last element deletes the list.
+        LMPopCmd::new(keys.clone(), ListDirection::Left)
+            .count(10)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        assert_eq!(db.llen("second").unwrap(), 0);
+        LMPopCmd::new(keys, ListDirection::Left)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_zmpop_skips_empty_sorted_sets() {
+        let db = Db::default();
+        let members = [(1.0, "one"), (2.0, "two"), (3.0, "three")]
+            .map(|(score, member)| (score, Bytes::from(member)))
+            .to_vec();
+        db.zadd("second", members).unwrap();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*2\r\n$6\r\nsecond\r\n*2\r\n*2\r\n$5\r\nthree\r\n$1\r\n3\r\n*2\r\n$3\r\ntwo\r\n$1\r\n2\r\n")
+            .write(b"*2\r\n$6\r\nsecond\r\n*1\r\n*2\r\n$3\r\none\r\n$1\r\n1\r\n")
+            .write(b"*-1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        let keys = vec!["first".to_string(), "second".to_string()];
+        for cmd in [
+            ZMPopCmd::new(keys.clone(), ScoreDirection::Max).count(2),
+            ZMPopCmd::new(keys.clone(), ScoreDirection::Min),
+            ZMPopCmd::new(keys, ScoreDirection::Min),
+        ] {
+            cmd.apply(&db, &mut conn).await.unwrap();
+        }
+        assert_eq!(db.zcard("second").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_mpop_rejects_invalid_arguments() {
+        for (args, expected) in [
+            (&["LMPOP", "0", "foo", "LEFT"][..], "numkeys"),
+            (&["LMPOP", "2", "foo", "LEFT"], "syntax error"),
+            (&["LMPOP", "1", "foo", "UP"], "syntax error"),
+            (&["LMPOP", "1", "foo", "LEFT", "COUNT", "0"], "count"),
+            (
+                &["ZMPOP", "1", "foo", "MIN", "COUNT", "1", "x"],
+                "syntax error",
+            ),
+        ] {
+            let frame = Frame::Array(
+                args.iter()
+                    .map(|arg| Frame::BulkString(Bytes::from(arg.to_string())))
+                    .collect(),
+            );
+            let err = crate::CommandVariant::from_frame(frame).unwrap_err();
+            assert!(err.to_string().starts_with(expected), "{err}");
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Implementation of the `OBJECT` command.
+use super::{
+    help::{help_frame, unknown_subcommand},
+    Command,
+};
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Inspects the internals of the value stored at a key.
+///
+/// # Subcommands
+///
+/// * FREQ `key` -- Returns the logarithmic access frequency counter of the
+///   key, or null if the key does not exist.
+/// * IDLETIME `key` -- Returns the number of seconds since the key was last
+///   accessed, or null if the key does not exist.
+/// * HELP -- Returns the documentation of the subcommands.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ObjectCmd {
+    subcommand: Subcommand,
+}
+
+/// The `OBJECT` subcommands.
+#[derive(Debug, PartialEq, Eq)]
+enum Subcommand {
+    /// `OBJECT FREQ key`
+    Freq(String),
+    /// `OBJECT IDLETIME key`
+    IdleTime(String),
+    /// `OBJECT HELP`
+    Help,
+}
+
+/// The documentation of the subcommands, returned by `OBJECT HELP`.
+const HELP: &[&str] = &[
+    "FREQ <key>",
+    "    Return the logarithmic access frequency counter of <key>.",
+    "IDLETIME <key>",
+    "    Return the number of seconds since <key> was last accessed.",
+];
+
+impl ObjectCmd {
+    /// Creates a new `OBJECT FREQ` command for `key`.
+    pub fn freq(key: impl ToString) -> Self {
+        Self {
+            subcommand: Subcommand::Freq(key.to_string()),
+        }
+    }
+
+    /// Creates a new `OBJECT IDLETIME` command for `key`.
+    pub fn idle_time(key: impl ToString) -> Self {
+        Self {
+            subcommand: Subcommand::IdleTime(key.to_string()),
+        }
+    }
+
+    /// Creates a new `OBJECT HELP` command.
+    pub fn help() -> Self {
+        Self {
+            subcommand: Subcommand::Help,
+        }
+    }
+}
+
+impl Command for ObjectCmd {
+    /// Parse a [`ObjectCmd`] instance from a received frame.
+    ///
+    /// The `OBJECT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// OBJECT FREQ | IDLETIME key
+    /// OBJECT HELP
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let subcommand = parse.next_string()?;
+        let subcommand = match subcommand.to_uppercase().as_str() {
+            "FREQ" => Subcommand::Freq(parse.next_string()?),
+            "IDLETIME" => Subcommand::IdleTime(parse.next_string()?),
+            "HELP" => Subcommand::Help,
+            _ => return Err(unknown_subcommand("OBJECT", subcommand)),
+        };
+
+        Ok(Self { subcommand })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = match self.subcommand {
+            Subcommand::Freq(key) => match db.frequency(&key) {
+                Some(freq) => Frame::Integer(i64::from(freq)),
+                None => Frame::NullBulkString,
+            },
+            Subcommand::IdleTime(key) => match db.idle_time(&key) {
+                Some(idle) => Frame::Integer(idle.as_secs() as i64),
+                None => Frame::NullBulkString,
+            },
+            Subcommand::Help => help_frame("OBJECT", HELP)?,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("object"))?;
+        match self.subcommand {
+            Subcommand::Freq(key) => {
+                frame.push_bulk(Bytes::from("freq"))?;
+                frame.push_bulk(Bytes::from(key))?;
+            }
+            Subcommand::IdleTime(key) => {
+                frame.push_bulk(Bytes::from("idletime"))?;
+                frame.push_bulk(Bytes::from(key))?;
+            }
+            Subcommand::Help => frame.push_bulk(Bytes::from("help"))?,
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for ObjectCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.subcommand {
+            Subcommand::Freq(key) => write!(f, "OBJECT FREQ {key}"),
+            Subcommand::IdleTime(key) => write!(f, "OBJECT IDLETIME {key}"),
+            Subcommand::Help => write!(f, "OBJECT HELP"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+    use std::time::Duration;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_object_idletime() {
+        let db = Db::default();
+        db.set("foo".to_string(), Bytes::from("a"), None).unwrap();
+
+        tokio::time::advance(Duration::from_millis(3500)).await;
+        assert_eq!(db.idle_time("foo").unwrap().as_secs(), 3);
+        // Inspecting the key does not count as an access.
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert_eq!(db.idle_time("foo").unwrap().as_secs(), 5);
+
+        db.get("foo").unwrap();
+        assert_eq!(db.idle_time("foo").unwrap().as_secs(), 0);
+
+        tokio::time::advance(Duration::from_millis(7500)).await;
+        let stream = tokio_test::io::Builder::new()
+            .write(b":7\r\n")
+            .write(b"$-1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        for key in ["foo", "bar"] {
+            ObjectCmd::idle_time(key)
+                .apply(&db, &mut conn)
+                .await
+                .unwrap();
+        }
+    }
+}
@@ -1,6 +1,6 @@
 //! Implementation of the `PING` command.
 use super::Command;
-use crate::{ConnectionStream, Frame};
+use crate::{frame::Protocol, ConnectionStream, Frame};
 use bytes::Bytes;
 use tracing::debug;
 
@@ -21,6 +21,30 @@ impl PingCmd {
     }
 }
 
+/// Creates the reply to a ping carrying `msg`, if any.
+///
+/// Outside of the subscribed state, the reply is `PONG`, or `msg` itself.
+/// Subscribed clients expect pub/sub replies instead, so the reply is
+/// `["pong", msg]`, with an empty message by default. It is a push under
+/// RESP3, and a plain array under RESP2.
+pub(crate) fn pong_frame(msg: Option<Bytes>, subscribed: bool, protocol: Protocol) -> Frame {
+    if !subscribed {
+        return match msg {
+            None => Frame::SimpleString("PONG".to_string()),
+            Some(msg) => Frame::BulkString(msg),
+        };
+    }
+
+    let reply = vec![
+        Frame::BulkString(Bytes::from_static(b"pong")),
+        Frame::BulkString(msg.unwrap_or_default()),
+    ];
+    match protocol {
+        Protocol::Resp3 => Frame::Push(reply),
+        Protocol::Resp2 => Frame::Array(reply),
+    }
+}
+
 impl Command for PingCmd {
     fn parse_frames(parse: &mut super::Parse) -> crate::Result<Self>
     where
@@ -39,10 +63,7 @@ impl Command for PingCmd {
         _db: &crate::Db,
         dst: &mut crate::Connection<S>,
     ) -> crate::Result<()> {
-        let response = match self.msg {
-            None => Frame::SimpleString("PONG".to_string()),
-            Some(msg) => Frame::BulkString(msg),
-        };
+        let response = pong_frame(self.msg, false, dst.protocol());
 
         debug!(?response);
 
@@ -92,4 +113,43 @@ mod tests {
             expected_frame
         );
     }
+
+    #[test]
+    fn test_pong_frame() {
+        let msg = || Some(Bytes::from("hi"));
+        let pong = || {
+            vec![
+                Frame::BulkString(Bytes::from("pong")),
+                Frame::BulkString(Bytes::from("hi")),
+            ]
+        };
+
+        // Outside of the subscribed state, the protocol makes no difference.
+        for protocol in [Protocol::Resp2, Protocol::Resp3] {
+            assert_eq!(
+                pong_frame(None, false, protocol),
+                Frame::SimpleString("PONG".to_string())
+            );
+            assert_eq!(
+                pong_frame(msg(), false, protocol),
+                Frame::BulkString(Bytes::from("hi"))
+            );
+        }
+
+        assert_eq!(
+            pong_frame(msg(), true, Protocol::Resp2),
+            Frame::Array(pong())
+        );
+        assert_eq!(
+            pong_frame(msg(), true, Protocol::Resp3),
+            Frame::Push(pong())
+        );
+        assert_eq!(
+            pong_frame(None, true, Protocol::Resp2),
+            Frame::Array(vec![
+                Frame::BulkString(Bytes::from("pong")),
+                Frame::BulkString(Bytes::new()),
+            ])
+        );
+    }
 }
@@ -14,7 +14,7 @@ pub struct PublishCmd {
 
 impl PublishCmd {
     /// Create a new [`PublishCmd`].
-    pub(crate) fn new(channel: String, message: Bytes) -> Self {
+    pub fn new(channel: String, message: Bytes) -> Self {
         Self { channel, message }
     }
 
@@ -79,6 +79,10 @@ impl Command for PublishCmd {
     }
 
     fn into_frame(self) -> crate::Result<crate::Frame> {
-        todo!()
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("publish"))?;
+        frame.push_bulk(Bytes::from(self.channel))?;
+        frame.push_bulk(self.message)?;
+        Ok(frame)
     }
 }
@@ -0,0 +1,172 @@
+//! Implementation of the `LPUSH` and `RPUSH` commands.
+use super::Command;
+use crate::{db::ListEnd, parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Insert all the given `values` at the head of the list stored at `key`.
+///
+/// Values are inserted one after the other, so the last value ends up at the
+/// head of the list. If `key` does not exist, a new list is created.
+///
+/// Replies with the length of the list after the push.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LPushCmd {
+    /// The key of the list.
+    key: String,
+    /// The values to push.
+    values: Vec<Bytes>,
+}
+
+/// Insert all the given `values` at the tail of the list stored at `key`.
+///
+/// If `key` does not exist, a new list is created.
+///
+/// Replies with the length of the list after the push.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RPushCmd {
+    /// The key of the list.
+    key: String,
+    /// The values to push.
+    values: Vec<Bytes>,
+}
+
+impl LPushCmd {
+    /// Creates a new [`LPushCmd`] command.
+    pub fn new(key: impl ToString, values: Vec<Bytes>) -> Self {
+        Self {
+            key: key.to_string(),
+            values,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the values.
+    pub fn values(&self) -> &[Bytes] {
+        &self.values
+    }
+}
+
+impl RPushCmd {
+    /// Creates a new [`RPushCmd`] command.
+    pub fn new(key: impl ToString, values: Vec<Bytes>) -> Self {
+        Self {
+            key: key.to_string(),
+            values,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the values.
+    pub fn values(&self) -> &[Bytes] {
+        &self.values
+    }
+}
+
+impl Command for LPushCmd {
+    /// Parse a [`LPushCmd`] instance from a received frame.
+    ///
+    /// The `LPUSH` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LPUSH key value [value ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let (key, values) = parse_push(parse)?;
+        Ok(Self { key, values })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let len = db.push(&self.key, self.values, ListEnd::Left)?;
+        let response = Frame::Integer(len as i64);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        push_frame("lpush", self.key, self.values)
+    }
+}
+
+impl Command for RPushCmd {
+    /// Parse a [`RPushCmd`] instance from a received frame.
+    ///
+    /// The `RPUSH` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// RPUSH key value [value ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let (key, values) = parse_push(parse)?;
+        Ok(Self { key, values })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let len = db.push(&self.key, self.values, ListEnd::Right)?;
+        let response = Frame::Integer(len as i64);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        push_frame("rpush", self.key, self.values)
+    }
+}
+
+/// Parse the key and the one or more values of a push command.
+fn parse_push(parse: &mut Parse) -> Result<(String, Vec<Bytes>)> {
+    let key = parse.next_string()?;
+
+    // At least one value must be provided.
+    let mut values = vec![parse.next_bytes()?];
+    loop {
+        match parse.next_bytes() {
+            Ok(value) => values.push(value),
+            Err(Error::EndOfStream) => break,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok((key, values))
+}
+
+/// Creates the request frame of the push command `name`.
+fn push_frame(name: &'static str, key: String, values: Vec<Bytes>) -> Result<Frame> {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from(name))?;
+    frame.push_bulk(Bytes::from(key))?;
+    for value in values {
+        frame.push_bulk(value)?;
+    }
+    Ok(frame)
+}
@@ -0,0 +1,59 @@
+//! Implementation of the `QUIT` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Asks the server to close the connection.
+///
+/// The server replies with `OK`, and closes the connection once the reply was
+/// written.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct QuitCmd;
+
+impl QuitCmd {
+    /// Creates a new [`QuitCmd`] command.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Command for QuitCmd {
+    /// Parse a [`QuitCmd`] instance from a received frame.
+    ///
+    /// The `QUIT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// QUIT
+    /// ```
+    fn parse_frames(_parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        _db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        // Closing the connection is up to the caller, once the reply is out.
+        let response = Frame::SimpleString("OK".to_string());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("quit"))?;
+        Ok(frame)
+    }
+}
@@ -0,0 +1,287 @@
+//! Implementation of the `REPLCONF` and `PSYNC` commands.
+use super::Command;
+use crate::{parse::Parse, server::ServerState, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Configure the replication stream, sent by a replica during the handshake.
+///
+/// Replies with `OK`. The options are only validated, there is no replica
+/// state to update yet.
+///
+/// # Options
+///
+/// * listening-port `port` -- The port the replica listens on.
+/// * capa `capability` -- A capability the replica supports, e.g. `psync2`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ReplConfCmd {
+    /// The options, as lowercase name and value pairs.
+    options: Vec<(String, String)>,
+}
+
+impl ReplConfCmd {
+    /// Creates a new [`ReplConfCmd`] command, with no option.
+    pub fn new() -> Self {
+        Self { options: vec![] }
+    }
+
+    /// Advertise the port the replica listens on.
+    pub fn listening_port(mut self, port: u16) -> Self {
+        self.options
+            .push(("listening-port".to_string(), port.to_string()));
+        self
+    }
+
+    /// Advertise a capability of the replica.
+    pub fn capa(mut self, capability: impl ToString) -> Self {
+        self.options
+            .push(("capa".to_string(), capability.to_string()));
+        self
+    }
+
+    /// Get the options, as name and value pairs.
+    pub fn options(&self) -> &[(String, String)] {
+        &self.options
+    }
+}
+
+impl Default for ReplConfCmd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for ReplConfCmd {
+    /// Parse a [`ReplConfCmd`] instance from a received frame.
+    ///
+    /// The `REPLCONF` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// REPLCONF option value [option value ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut options = vec![];
+        loop {
+            let option = match parse.next_string() {
+                Ok(option) => option.to_lowercase(),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            };
+            let value = match parse.next_string() {
+                Ok(value) => value,
+                Err(Error::EndOfStream) => return Err(Error::Syntax),
+                Err(err) => return Err(err),
+            };
+            match option.as_str() {
+                "listening-port" => {
+                    value.parse::<u16>().map_err(|_| Error::NotAnInteger)?;
+                }
+                "capa" => {}
+                _ => {
+                    return Err(Error::Protocol(format!(
+                        "Unrecognized REPLCONF option: {option}"
+                    )))
+                }
+            }
+            options.push((option, value));
+        }
+
+        Ok(Self { options })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        _db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = Frame::SimpleString("OK".to_string());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("replconf"))?;
+        for (option, value) in self.options {
+            frame.push_bulk(Bytes::from(option))?;
+            frame.push_bulk(Bytes::from(value))?;
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for ReplConfCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "REPLCONF")?;
+        for (option, value) in &self.options {
+            write!(f, " {option} {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Request the replication stream, sent by a replica once configured.
+///
+/// Only full synchronizations are supported: whatever the requested
+/// replication id and offset, the reply is a `FULLRESYNC replid 0` simple
+/// string, followed by a bulk string holding a snapshot of the keyspace.
+/// The replication id is the `run_id` of the server, and the snapshot is in
+/// the format used by `DEBUG RELOAD`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PSyncCmd {
+    /// The replication id the replica last synchronized with, or `?`.
+    replid: String,
+    /// The replication offset the replica reached, or `-1`.
+    offset: i64,
+}
+
+impl PSyncCmd {
+    /// Creates a new [`PSyncCmd`] command, resuming from `offset` of the
+    /// `replid` replication stream.
+    pub fn new(replid: impl ToString, offset: i64) -> Self {
+        Self {
+            replid: replid.to_string(),
+            offset,
+        }
+    }
+
+    /// Creates a new [`PSyncCmd`] command, requesting a full
+    /// synchronization.
+    pub fn full() -> Self {
+        Self::new("?", -1)
+    }
+
+    /// Get the replication id.
+    pub fn replid(&self) -> &str {
+        &self.replid
+    }
+
+    /// Get the replication offset.
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    /// Parse a [`PSyncCmd`] instance from a received frame.
+    ///
+    /// The `PSYNC` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// PSYNC replid offset
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Self> {
+        let replid = parse.next_string()?;
+        let offset = parse.next_int_signed()?;
+        Ok(Self { replid, offset })
+    }
+
+    /// Apply the `PSYNC` command, replying with the run id of `server` and a
+    /// snapshot of `db`.
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        server: &ServerState,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let resync = Frame::SimpleString(format!("FULLRESYNC {} 0", server.run_id()));
+        let snapshot = Frame::BulkString(db.snapshot());
+
+        debug!(response = ?resync);
+
+        dst.write_frames(&[resync, snapshot]).await?;
+
+        Ok(())
+    }
+
+    /// Creates the request frame of the command.
+    // No client sends `PSYNC` yet.
+    #[allow(dead_code)]
+    pub(crate) fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("psync"))?;
+        frame.push_bulk(Bytes::from(self.replid))?;
+        frame.push_int(self.offset)?;
+        Ok(frame)
+    }
+}
+
+impl Display for PSyncCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PSYNC {} {}", self.replid, self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dump, Connection, Db};
+
+    #[tokio::test]
+    async fn test_psync_replies_with_a_full_resync() {
+        let db = Db::default();
+        db.set("foo".to_string(), Bytes::from("bar"), None).unwrap();
+        let state = ServerState::default();
+
+        let (client, server) = tokio::io::duplex(1024);
+        PSyncCmd::full()
+            .apply(&db, &state, &mut Connection::new(server))
+            .await
+            .unwrap();
+
+        let mut client = Connection::new(client);
+        let line = format!("FULLRESYNC {} 0", state.run_id());
+        assert_eq!(
+            client.read_frame().await.unwrap(),
+            Some(Frame::SimpleString(line))
+        );
+        let Some(Frame::BulkString(snapshot)) = client.read_frame().await.unwrap() else {
+            panic!("expected a bulk string snapshot");
+        };
+        let entries = dump::deserialize_snapshot(&snapshot).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "foo");
+    }
+
+    #[test]
+    fn test_replconf_parse() {
+        let parse = |args: &[&str]| {
+            let frame = Frame::Array(
+                args.iter()
+                    .map(|arg| Frame::BulkString(Bytes::from(arg.to_string())))
+                    .collect(),
+            );
+            crate::CommandVariant::from_frame(frame)
+        };
+
+        let cmd = parse(&["REPLCONF", "listening-port", "6380", "CAPA", "psync2"]).unwrap();
+        assert_eq!(cmd.to_string(), "REPLCONF listening-port 6380 capa psync2");
+
+        for (args, expected) in [
+            (
+                &["REPLCONF", "listening-port", "port"][..],
+                "value is not an integer",
+            ),
+            (&["REPLCONF", "capa", "eof", "capa"], "syntax error"),
+            (
+                &["REPLCONF", "ip-address", "127.0.0.1"],
+                "protocol error: Unrecognized REPLCONF option: ip-address",
+            ),
+        ] {
+            let err = parse(args).unwrap_err();
+            assert!(err.to_string().starts_with(expected), "{err}");
+        }
+    }
+}
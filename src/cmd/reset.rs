@@ -0,0 +1,70 @@
+//! Implementation of the `RESET` command.
+use super::Command;
+use crate::{frame::Protocol, parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Resets the connection to its default state.
+///
+/// The protocol is set back to RESP2. When issued while subscribed, every
+/// channel subscription is dropped and the connection leaves the subscribed
+/// state. The server replies with `RESET`.
+///
+/// Unlike Redis, there is no selected database, watched key or client name to
+/// reset, as the server has no `SELECT`, `WATCH` or `CLIENT SETNAME`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ResetCmd;
+
+impl ResetCmd {
+    /// Creates a new [`ResetCmd`] command.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// The reply sent once the connection has been reset.
+    pub(crate) fn response() -> Frame {
+        Frame::SimpleString("RESET".to_string())
+    }
+}
+
+impl Command for ResetCmd {
+    /// Parse a [`ResetCmd`] instance from a received frame.
+    ///
+    /// The `RESET` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// RESET
+    /// ```
+    fn parse_frames(_parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        _db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        // Outside of the subscribed state, the per-connection state, such as a
+        // pending transaction, is reset by the connection handler.
+        dst.set_protocol(Protocol::default());
+        let response = Self::response();
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("reset"))?;
+        Ok(frame)
+    }
+}
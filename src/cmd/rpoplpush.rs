@@ -0,0 +1,133 @@
+//! Implementation of the `RPOPLPUSH` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Atomically pop the last element of the list stored at `source` and push it
+/// at the head of the list stored at `destination`.
+///
+/// Replies with the moved element, or a null bulk string if `source` does not
+/// exist. When `source` and `destination` are the same key, the list is
+/// rotated.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RPopLPushCmd {
+    /// The key of the list to pop from.
+    source: String,
+    /// The key of the list to push to.
+    destination: String,
+}
+
+impl RPopLPushCmd {
+    /// Creates a new [`RPopLPushCmd`] command.
+    pub fn new(source: impl ToString, destination: impl ToString) -> Self {
+        Self {
+            source: source.to_string(),
+            destination: destination.to_string(),
+        }
+    }
+
+    /// Get the source key.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Get the destination key.
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+}
+
+impl Command for RPopLPushCmd {
+    /// Parse a [`RPopLPushCmd`] instance from a received frame.
+    ///
+    /// The `RPOPLPUSH` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// RPOPLPUSH source destination
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let source = parse.next_string()?;
+        let destination = parse.next_string()?;
+        Ok(Self {
+            source,
+            destination,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = match db.rpoplpush(&self.source, &self.destination)? {
+            Some(value) => Frame::BulkString(value),
+            None => Frame::NullBulkString,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("rpoplpush"))?;
+        frame.push_bulk(Bytes::from(self.source))?;
+        frame.push_bulk(Bytes::from(self.destination))?;
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::ListEnd, Connection, Db, Error};
+
+    #[tokio::test]
+    async fn test_rpoplpush_moves_atomically() {
+        let db = Db::default();
+        let values = vec![Bytes::from("a"), Bytes::from("b")];
+        db.push("src", values, ListEnd::Right).unwrap();
+        db.push("dst", vec![Bytes::from("c")], ListEnd::Right)
+            .unwrap();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b"$1\r\nb\r\n")
+            .write(b"$1\r\na\r\n")
+            .write(b"$-1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        for _ in 0..3 {
+            RPopLPushCmd::new("src", "dst")
+                .apply(&db, &mut conn)
+                .await
+                .unwrap();
+        }
+
+        // The emptied source is deleted, and every element was moved.
+        assert!(!db.exists("src"));
+        assert_eq!(db.llen("dst").unwrap(), 3);
+        assert_eq!(db.lindex("dst", 0).unwrap(), Some(Bytes::from("a")));
+        assert_eq!(db.lindex("dst", -1).unwrap(), Some(Bytes::from("c")));
+
+        // Nothing is popped if the destination holds the wrong type.
+        db.set("str".to_string(), Bytes::from("x"), None).unwrap();
+        let err = db.rpoplpush("dst", "str").unwrap_err();
+        assert!(matches!(err, Error::WrongType));
+        assert_eq!(db.llen("dst").unwrap(), 3);
+
+        // Using the same key for both rotates the list.
+        assert_eq!(db.rpoplpush("dst", "dst").unwrap(), Some(Bytes::from("c")));
+        assert_eq!(db.lindex("dst", 0).unwrap(), Some(Bytes::from("c")));
+        assert_eq!(db.llen("dst").unwrap(), 3);
+    }
+}
@@ -0,0 +1,128 @@
+//! Implementation of the `SADD` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Add the given `members` to the set stored at `key`.
+///
+/// Members already in the set are ignored. If `key` does not exist, a new set
+/// is created before adding the members.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SAddCmd {
+    /// The key of the set.
+    key: String,
+    /// The members to add.
+    members: Vec<Bytes>,
+}
+
+impl SAddCmd {
+    /// Creates a new [`SAddCmd`] command.
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> Self {
+        Self {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the members.
+    pub fn members(&self) -> &[Bytes] {
+        &self.members
+    }
+}
+
+impl Command for SAddCmd {
+    /// Parse a [`SAddCmd`] instance from a received frame.
+    ///
+    /// The `SADD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SADD key member [member ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+
+        // At least one member must be provided.
+        let mut members = vec![parse.next_bytes()?];
+        loop {
+            match parse.next_bytes() {
+                Ok(member) => members.push(member),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Self { key, members })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let added = db.sadd(&self.key, self.members)?;
+        let response = Frame::Integer(added as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("sadd"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        for member in self.members {
+            frame.push_bulk(member)?;
+        }
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    #[tokio::test]
+    async fn test_sadd_counts_new_members() {
+        let db = Db::default();
+        let stream = tokio_test::io::Builder::new()
+            .write(b":2\r\n")
+            .write(b":1\r\n")
+            .write(b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        let members = vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("a")];
+        SAddCmd::new("foo", members)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        let members = vec![Bytes::from("b"), Bytes::from("c")];
+        SAddCmd::new("foo", members)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+
+        db.set("bar".to_string(), Bytes::from("baz"), None).unwrap();
+        let err = SAddCmd::new("bar", vec![Bytes::from("a")])
+            .apply(&db, &mut conn)
+            .await
+            .unwrap_err();
+        conn.write_frame(&err.to_frame().unwrap()).await.unwrap();
+    }
+}
@@ -0,0 +1,456 @@
+//! Implementation of the `HSCAN`, `SSCAN` and `ZSCAN` cursor iterations.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// The number of elements visited by a scan call, unless `COUNT` is given.
+const DEFAULT_COUNT: u64 = 10;
+
+/// Iterate the fields of the hash stored at `key`, along with their values.
+///
+/// Replies with the cursor to continue from, and the visited fields and
+/// values. The iteration is complete once the returned cursor is `0`.
+///
+/// # Options
+///
+/// * MATCH `pattern` -- Only return the fields matching the glob `pattern`.
+/// * COUNT `count` -- The number of fields to visit, `10` by default.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HScanCmd {
+    /// The key of the hash.
+    key: String,
+    /// The cursor and options of the iteration.
+    args: ScanArgs,
+}
+
+/// Iterate the members of the set stored at `key`.
+///
+/// Replies with the cursor to continue from, and the visited members. The
+/// iteration is complete once the returned cursor is `0`.
+///
+/// # Options
+///
+/// * MATCH `pattern` -- Only return the members matching the glob `pattern`.
+/// * COUNT `count` -- The number of members to visit, `10` by default.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SScanCmd {
+    /// The key of the set.
+    key: String,
+    /// The cursor and options of the iteration.
+    args: ScanArgs,
+}
+
+/// Iterate the members of the sorted set stored at `key`, along with their
+/// scores.
+///
+/// Replies with the cursor to continue from, and the visited members and
+/// scores. The iteration is complete once the returned cursor is `0`.
+///
+/// # Options
+///
+/// * MATCH `pattern` -- Only return the members matching the glob `pattern`.
+/// * COUNT `count` -- The number of members to visit, `10` by default.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ZScanCmd {
+    /// The key of the sorted set.
+    key: String,
+    /// The cursor and options of the iteration.
+    args: ScanArgs,
+}
+
+/// The cursor and options shared by the scan commands.
+#[derive(Debug, PartialEq, Eq)]
+struct ScanArgs {
+    /// The cursor to continue the iteration from.
+    cursor: u64,
+    /// The glob pattern the returned elements must match.
+    pattern: Option<String>,
+    /// The number of elements to visit.
+    count: Option<u64>,
+}
+
+impl HScanCmd {
+    /// Creates a new [`HScanCmd`] command.
+    pub fn new(
+        key: impl ToString,
+        cursor: u64,
+        pattern: Option<String>,
+        count: Option<u64>,
+    ) -> Self {
+        Self {
+            key: key.to_string(),
+            args: ScanArgs {
+                cursor,
+                pattern,
+                count,
+            },
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl SScanCmd {
+    /// Creates a new [`SScanCmd`] command.
+    pub fn new(
+        key: impl ToString,
+        cursor: u64,
+        pattern: Option<String>,
+        count: Option<u64>,
+    ) -> Self {
+        Self {
+            key: key.to_string(),
+            args: ScanArgs {
+                cursor,
+                pattern,
+                count,
+            },
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl ZScanCmd {
+    /// Creates a new [`ZScanCmd`] command.
+    pub fn new(
+        key: impl ToString,
+        cursor: u64,
+        pattern: Option<String>,
+        count: Option<u64>,
+    ) -> Self {
+        Self {
+            key: key.to_string(),
+            args: ScanArgs {
+                cursor,
+                pattern,
+                count,
+            },
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Command for HScanCmd {
+    /// Parse a [`HScanCmd`] instance from a received frame.
+    ///
+    /// The `HSCAN` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HSCAN key cursor [MATCH pattern] [COUNT count]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let args = ScanArgs::parse_frames(parse)?;
+        Ok(Self { key, args })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let (cursor, fields) = db.hscan(
+            &self.key,
+            self.args.cursor,
+            self.args.count(),
+            self.args.pattern(),
+        )?;
+        let mut page = Frame::array();
+        for (field, value) in fields {
+            page.push_bulk(field)?;
+            page.push_bulk(value)?;
+        }
+        let response = scan_frame(cursor, page)?;
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        self.args.into_frame("hscan", self.key)
+    }
+}
+
+impl Command for SScanCmd {
+    /// Parse a [`SScanCmd`] instance from a received frame.
+    ///
+    /// The `SSCAN` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SSCAN key cursor [MATCH pattern] [COUNT count]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let args = ScanArgs::parse_frames(parse)?;
+        Ok(Self { key, args })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let (cursor, members) = db.sscan(
+            &self.key,
+            self.args.cursor,
+            self.args.count(),
+            self.args.pattern(),
+        )?;
+        let mut page = Frame::array();
+        for member in members {
+            page.push_bulk(member)?;
+        }
+        let response = scan_frame(cursor, page)?;
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        self.args.into_frame("sscan", self.key)
+    }
+}
+
+impl Command for ZScanCmd {
+    /// Parse a [`ZScanCmd`] instance from a received frame.
+    ///
+    /// The `ZSCAN` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZSCAN key cursor [MATCH pattern] [COUNT count]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let args = ScanArgs::parse_frames(parse)?;
+        Ok(Self { key, args })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let (cursor, members) = db.zscan(
+            &self.key,
+            self.args.cursor,
+            self.args.count(),
+            self.args.pattern(),
+        )?;
+        let mut page = Frame::array();
+        for (member, score) in members {
+            page.push_bulk(member)?;
+            page.push_bulk(Bytes::from(score.to_string()))?;
+        }
+        let response = scan_frame(cursor, page)?;
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        self.args.into_frame("zscan", self.key)
+    }
+}
+
+impl ScanArgs {
+    /// Parse the cursor and options, after the key was consumed.
+    fn parse_frames(parse: &mut Parse) -> Result<Self> {
+        let cursor = parse.next_int_unsigned()?;
+        let mut pattern = None;
+        let mut count = None;
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "MATCH" => pattern = Some(parse.next_string()?),
+                Ok(s) if s.to_uppercase() == "COUNT" => {
+                    // Visiting no element at all would never make progress.
+                    let n = parse.next_int_unsigned()?;
+                    if n == 0 {
+                        return Err(Error::Syntax);
+                    }
+                    count = Some(n);
+                }
+                Ok(_) => return Err(Error::Syntax),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Self {
+            cursor,
+            pattern,
+            count,
+        })
+    }
+
+    /// The number of elements to visit.
+    fn count(&self) -> usize {
+        let count = self.count.unwrap_or(DEFAULT_COUNT);
+        usize::try_from(count).unwrap_or(usize::MAX)
+    }
+
+    /// The glob pattern, as bytes.
+    fn pattern(&self) -> Option<&[u8]> {
+        self.pattern.as_deref().map(str::as_bytes)
+    }
+
+    /// Creates the frame of the `name` command iterating `key`.
+    fn into_frame(self, name: &'static str, key: String) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(name))?;
+        frame.push_bulk(Bytes::from(key))?;
+        frame.push_bulk(Bytes::from(self.cursor.to_string()))?;
+        if let Some(pattern) = self.pattern {
+            frame.push_bulk(Bytes::from("match"))?;
+            frame.push_bulk(Bytes::from(pattern))?;
+        }
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count"))?;
+            frame.push_int(count as i64)?;
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for ScanArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cursor)?;
+        if let Some(pattern) = &self.pattern {
+            write!(f, " MATCH {pattern}")?;
+        }
+        if let Some(count) = self.count {
+            write!(f, " COUNT {count}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for HScanCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HSCAN {} {}", self.key, self.args)
+    }
+}
+
+impl Display for SScanCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SSCAN {} {}", self.key, self.args)
+    }
+}
+
+impl Display for ZScanCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ZSCAN {} {}", self.key, self.args)
+    }
+}
+
+/// Creates the reply of a scan command, made of the next `cursor` and the
+/// `page` of visited elements.
+fn scan_frame(cursor: u64, page: Frame) -> Result<Frame> {
+    let mut response = Frame::array();
+    response.push_bulk(Bytes::from(cursor.to_string()))?;
+    response.push_frame(page)?;
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommandVariant, Connection, Db};
+    use std::collections::BTreeSet;
+
+    #[tokio::test]
+    async fn test_hscan_visits_every_field_once() {
+        let db = Db::default();
+        let fields = (0..5)
+            .map(|i| (Bytes::from(format!("f{i}")), Bytes::from(i.to_string())))
+            .collect();
+        db.hset("foo", fields).unwrap();
+
+        let mut visited = BTreeSet::new();
+        let mut cursor = 0;
+        let mut calls = 0;
+        loop {
+            let (next, page) = db.hscan("foo", cursor, 1, None).unwrap();
+            calls += 1;
+            for (field, _) in page {
+                assert!(visited.insert(field), "field visited twice");
+            }
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        assert_eq!(calls, 5);
+        assert_eq!(visited.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_hscan_reply_with_match() {
+        let db = Db::default();
+        let fields = vec![
+            (Bytes::from("a1"), Bytes::from("1")),
+            (Bytes::from("b1"), Bytes::from("2")),
+            (Bytes::from("a2"), Bytes::from("3")),
+        ];
+        db.hset("foo", fields).unwrap();
+
+        let frame = HScanCmd::new("foo", 0, Some("a*".to_string()), Some(2))
+            .into_frame()
+            .unwrap();
+        let CommandVariant::HScan(cmd) = CommandVariant::from_frame(frame).unwrap() else {
+            panic!("unexpected command");
+        };
+
+        // The first page visits `a1` and `a2`, the second one `b1`.
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*2\r\n$1\r\n2\r\n*4\r\n$2\r\na1\r\n$1\r\n1\r\n$2\r\na2\r\n$1\r\n3\r\n")
+            .write(b"*2\r\n$1\r\n0\r\n*0\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        cmd.apply(&db, &mut conn).await.unwrap();
+        HScanCmd::new("foo", 2, Some("a*".to_string()), Some(2))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+    }
+}
@@ -17,6 +17,14 @@ use tracing::debug;
 ///
 /// * EX `seconds` -- Set the specified expire time, in seconds.
 /// * PX `milliseconds` -- Set the specified expire time, in milliseconds.
+/// * NX -- Only set the key if it does not already exist.
+/// * XX -- Only set the key if it already exists.
+/// * GET -- Reply with the value previously stored at `key`, or null if the
+///   key did not exist, instead of `OK`. The previous value is replied with
+///   even if `NX` or `XX` prevented the set. Replies with an error if the key
+///   holds a value other than a string.
+///
+/// Without `GET`, a null reply means `NX` or `XX` prevented the set.
 #[derive(Debug, PartialEq, Eq)]
 pub struct SetCmd {
     /// The lookup key.
@@ -25,6 +33,19 @@ pub struct SetCmd {
     value: Bytes,
     /// When to expire the key.
     expire: Option<Duration>,
+    /// Only set the key depending on whether it exists.
+    condition: Option<SetCondition>,
+    /// Whether to reply with the previous value.
+    get: bool,
+}
+
+/// Condition on the existence of the key for `SET` to store the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetCondition {
+    /// Only set the key if it does not already exist.
+    Nx,
+    /// Only set the key if it already exists.
+    Xx,
 }
 
 impl SetCmd {
@@ -37,9 +58,24 @@ impl SetCmd {
             key: key.to_string(),
             value,
             expire,
+            condition: None,
+            get: false,
         }
     }
 
+    /// Only set the key if its existence satisfies `condition`.
+    pub fn condition(mut self, condition: SetCondition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// Set whether the command replies with the previous value, like the
+    /// `GET` option does.
+    pub fn get(mut self, get: bool) -> Self {
+        self.get = get;
+        self
+    }
+
     /// Get the key.
     pub fn key(&self) -> &str {
         &self.key
@@ -54,6 +90,16 @@ impl SetCmd {
     pub fn expire(&self) -> Option<Duration> {
         self.expire
     }
+
+    /// Get the condition on the existence of the key, if any.
+    pub fn set_condition(&self) -> Option<SetCondition> {
+        self.condition
+    }
+
+    /// Whether the command replies with the previous value.
+    pub fn returns_previous(&self) -> bool {
+        self.get
+    }
 }
 
 impl Command for SetCmd {
@@ -75,7 +121,7 @@ impl Command for SetCmd {
     /// Expects an array frame containing at least 3 entries.
     ///
     /// ```text
-    /// SET key value [EX seconds|PX milliseconds]
+    /// SET key value [NX|XX] [GET] [EX seconds|PX milliseconds]
     /// ```
     fn parse_frames(parse: &mut crate::parse::Parse) -> Result<Self>
     where
@@ -86,39 +132,61 @@ impl Command for SetCmd {
         // The expiration is optional. If nothing else follows,
         // then it is `None`.
         let mut expire = None;
+        let mut condition = None;
+        let mut get = false;
 
-        // Attempt to parse another string.
-        match parse.next_string() {
-            Ok(s) if s.to_uppercase() == "EX" => {
-                // The expiration is specified in seconds.
-                // The next value must be an integer.
-                let secs = parse.next_int_unsigned()?;
-                expire = Some(Duration::from_secs(secs));
-            }
-            Ok(s) if s.to_uppercase() == "PX" => {
-                // The expiration is specified in milliseconds.
-                // The next value must be an integer.
-                let ms = parse.next_int_unsigned()?;
-                expire = Some(Duration::from_millis(ms));
-            }
-            // Currently, we don't support any of the other SET
-            // options. An error here results in the connection being
-            // terminated. Other connections will continue to operate normally.
-            Ok(_) => {
-                return Err(Error::Protocol(
-                    "currently, `SET` only supports the expiration option".into(),
-                ))
+        // Inspect the options without consuming them, so an unsupported one is
+        // reported before anything else is parsed.
+        while let Some(option) = parse.peek_string().map(str::to_uppercase) {
+            match &option[..] {
+                "EX" => {
+                    // The expiration is specified in seconds.
+                    // The next value must be an integer.
+                    parse.next_string()?;
+                    let secs = parse.next_int_unsigned()?;
+                    expire = Some(Duration::from_secs(secs));
+                }
+                "PX" => {
+                    // The expiration is specified in milliseconds.
+                    // The next value must be an integer.
+                    parse.next_string()?;
+                    let ms = parse.next_int_unsigned()?;
+                    expire = Some(Duration::from_millis(ms));
+                }
+                "NX" | "XX" => {
+                    parse.next_string()?;
+                    if condition.is_some() {
+                        return Err(Error::Syntax);
+                    }
+                    condition = Some(if option == "NX" {
+                        SetCondition::Nx
+                    } else {
+                        SetCondition::Xx
+                    });
+                }
+                "GET" => {
+                    parse.next_string()?;
+                    get = true;
+                }
+                // Currently, we don't support any of the other SET
+                // options. An error here results in the connection being
+                // terminated. Other connections will continue to operate normally.
+                _ => {
+                    return Err(Error::Protocol(
+                        "currently, `SET` only supports the expiration, NX, XX and GET options"
+                            .into(),
+                    ))
+                }
             }
-            // The `Error::EndOfStream` error indicates there is no further data to
-            // parse. In this case, it is a normal run time situation and
-            // indicates there are no specified `SET` options.
-            Err(Error::EndOfStream) => {}
-            // All other errors are bubbled up, resulting in the connection
-            // being terminated.
-            Err(err) => return Err(err),
         }
 
-        Ok(Self { key, value, expire })
+        Ok(Self {
+            key,
+            value,
+            expire,
+            condition,
+            get,
+        })
     }
 
     /// Apply the `SetCmd` command to the specified `Db` instance.
@@ -131,8 +199,13 @@ impl Command for SetCmd {
         db: &crate::Db,
         dst: &mut crate::Connection<S>,
     ) -> Result<()> {
-        db.set(self.key, self.value, self.expire);
-        let response = Frame::SimpleString("OK".to_string());
+        let (stored, previous) =
+            db.set_with_options(self.key, self.value, self.expire, self.condition, self.get)?;
+        let response = match previous {
+            Some(previous) => Frame::BulkString(previous),
+            None if self.get || !stored => Frame::NullBulkString,
+            None => Frame::SimpleString("OK".to_string()),
+        };
         debug!(?response);
         dst.write_frame(&response).await?;
         Ok(())
@@ -163,6 +236,53 @@ impl Command for SetCmd {
                 frame.push_int(ms.as_millis() as i64)?;
             }
         }
+        match self.condition {
+            Some(SetCondition::Nx) => frame.push_bulk(Bytes::from("nx"))?,
+            Some(SetCondition::Xx) => frame.push_bulk(Bytes::from("xx"))?,
+            None => {}
+        }
+        if self.get {
+            frame.push_bulk(Bytes::from("get"))?;
+        }
         Ok(frame)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommandVariant, Connection, Db};
+
+    /// Parse and apply `SET foo` with `args`, expecting `reply`.
+    async fn set(db: &Db, args: &[&str], reply: &[u8]) {
+        let frame = ["SET", "foo"]
+            .iter()
+            .chain(args)
+            .map(|arg| Frame::BulkString(Bytes::from(arg.to_string())))
+            .collect();
+        let CommandVariant::Set(cmd) = CommandVariant::from_frame(Frame::Array(frame)).unwrap()
+        else {
+            panic!("unexpected command");
+        };
+        let stream = tokio_test::io::Builder::new().write(reply).build();
+        let mut conn = Connection::new(stream);
+        cmd.apply(db, &mut conn).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_get() {
+        let db = Db::default();
+
+        // The key is missing, so there is no previous value.
+        set(&db, &["a", "GET"], b"$-1\r\n").await;
+        set(&db, &["b", "GET"], b"$1\r\na\r\n").await;
+        assert_eq!(db.get("foo").unwrap(), Some(Bytes::from("b")));
+
+        // The previous value is replied with, even though it is kept.
+        set(&db, &["c", "NX", "GET"], b"$1\r\nb\r\n").await;
+        assert_eq!(db.get("foo").unwrap(), Some(Bytes::from("b")));
+        set(&db, &["c", "NX"], b"$-1\r\n").await;
+        set(&db, &["c", "XX"], b"+OK\r\n").await;
+        assert_eq!(db.get("foo").unwrap(), Some(Bytes::from("c")));
+    }
+}
@@ -0,0 +1,687 @@
+//! Implementation of the `SINTER`, `SUNION` and `SDIFF` set operations, of
+//! their `*STORE` variants, and of `SINTERCARD`.
+use super::{mpop, Command};
+use crate::{db::SetOp, parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::{collections::BTreeSet, fmt::Display};
+use tracing::debug;
+
+/// Returns the members of the intersection of all the given sets.
+///
+/// Keys that do not exist are considered to be empty sets, so the result is
+/// empty if any of the keys is missing.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SInterCmd {
+    /// The keys of the sets.
+    keys: Vec<String>,
+}
+
+/// Returns the number of members of the intersection of all the given sets.
+///
+/// Keys that do not exist are considered to be empty sets.
+///
+/// # Options
+///
+/// * LIMIT limit -- Stop counting once `limit` members are found. A limit of
+///   `0` means no limit.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SInterCardCmd {
+    /// The keys of the sets.
+    keys: Vec<String>,
+    /// Maximum number of members to count, `0` meaning no limit.
+    limit: u64,
+}
+
+/// Returns the members of the union of all the given sets.
+///
+/// Keys that do not exist are considered to be empty sets.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SUnionCmd {
+    /// The keys of the sets.
+    keys: Vec<String>,
+}
+
+/// Returns the members of the first set which are not in any of the
+/// following sets.
+///
+/// Keys that do not exist are considered to be empty sets.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SDiffCmd {
+    /// The keys of the sets.
+    keys: Vec<String>,
+}
+
+/// Stores the intersection of all the given sets at `destination`,
+/// overwriting any value it holds.
+///
+/// Replies with the number of members of the result. An empty result deletes
+/// `destination`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SInterStoreCmd {
+    /// The key to store the result at.
+    destination: String,
+    /// The keys of the sets.
+    keys: Vec<String>,
+}
+
+/// Stores the union of all the given sets at `destination`, overwriting any
+/// value it holds.
+///
+/// Replies like [`SInterStoreCmd`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct SUnionStoreCmd {
+    /// The key to store the result at.
+    destination: String,
+    /// The keys of the sets.
+    keys: Vec<String>,
+}
+
+/// Stores the members of the first set which are not in any of the following
+/// sets at `destination`, overwriting any value it holds.
+///
+/// Replies like [`SInterStoreCmd`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct SDiffStoreCmd {
+    /// The key to store the result at.
+    destination: String,
+    /// The keys of the sets.
+    keys: Vec<String>,
+}
+
+impl SInterCmd {
+    /// Creates a new [`SInterCmd`] command.
+    pub fn new(keys: Vec<String>) -> Self {
+        Self { keys }
+    }
+
+    /// Get the keys.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+}
+
+impl SInterCardCmd {
+    /// Creates a new [`SInterCardCmd`] command, counting every member.
+    pub fn new(keys: Vec<String>) -> Self {
+        Self { keys, limit: 0 }
+    }
+
+    /// Stop counting once `limit` members are found, `0` meaning no limit.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Get the keys.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+}
+
+impl SUnionCmd {
+    /// Creates a new [`SUnionCmd`] command.
+    pub fn new(keys: Vec<String>) -> Self {
+        Self { keys }
+    }
+
+    /// Get the keys.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+}
+
+impl SDiffCmd {
+    /// Creates a new [`SDiffCmd`] command.
+    pub fn new(keys: Vec<String>) -> Self {
+        Self { keys }
+    }
+
+    /// Get the keys.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+}
+
+impl Command for SInterCmd {
+    /// Parse a [`SInterCmd`] instance from a received frame.
+    ///
+    /// The `SINTER` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SINTER key [key ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            keys: parse_keys(parse)?,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = members_frame(db.sinter(&self.keys)?)?;
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        keys_frame("sinter", self.keys)
+    }
+}
+
+impl Command for SInterCardCmd {
+    /// Parse a [`SInterCardCmd`] instance from a received frame.
+    ///
+    /// The `SINTERCARD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SINTERCARD numkeys key [key ...] [LIMIT limit]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let keys = mpop::parse_keys(parse)?;
+        let limit = match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "LIMIT" => {
+                u64::try_from(parse.next_int_signed()?).map_err(|_| Error::LimitNegative)?
+            }
+            Ok(_) => return Err(Error::Syntax),
+            Err(Error::EndOfStream) => 0,
+            Err(err) => return Err(err),
+        };
+        if parse.remaining() > 0 {
+            return Err(Error::Syntax);
+        }
+        Ok(Self { keys, limit })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let limit = match self.limit {
+            0 => usize::MAX,
+            limit => usize::try_from(limit).unwrap_or(usize::MAX),
+        };
+        let response = Frame::Integer(db.sintercard(&self.keys, limit)? as i64);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("sintercard"))?;
+        frame.push_int(self.keys.len() as i64)?;
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key))?;
+        }
+        if self.limit > 0 {
+            frame.push_bulk(Bytes::from("limit"))?;
+            frame.push_int(self.limit as i64)?;
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for SInterCardCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SINTERCARD {} {}", self.keys.len(), self.keys.join(" "))?;
+        if self.limit > 0 {
+            write!(f, " LIMIT {}", self.limit)?;
+        }
+        Ok(())
+    }
+}
+
+impl Command for SUnionCmd {
+    /// Parse a [`SUnionCmd`] instance from a received frame.
+    ///
+    /// The `SUNION` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SUNION key [key ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            keys: parse_keys(parse)?,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = members_frame(db.sunion(&self.keys)?)?;
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        keys_frame("sunion", self.keys)
+    }
+}
+
+impl Command for SDiffCmd {
+    /// Parse a [`SDiffCmd`] instance from a received frame.
+    ///
+    /// The `SDIFF` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SDIFF key [key ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            keys: parse_keys(parse)?,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = members_frame(db.sdiff(&self.keys)?)?;
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        keys_frame("sdiff", self.keys)
+    }
+}
+
+impl SInterStoreCmd {
+    /// Creates a new [`SInterStoreCmd`] command.
+    pub fn new(destination: impl ToString, keys: Vec<String>) -> Self {
+        Self {
+            destination: destination.to_string(),
+            keys,
+        }
+    }
+
+    /// Returns a reference to the destination key.
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Get the keys.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+}
+
+impl Command for SInterStoreCmd {
+    /// Parse a [`SInterStoreCmd`] instance from a received frame.
+    ///
+    /// The `SINTERSTORE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SINTERSTORE destination key [key ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let destination = parse.next_string()?;
+        let keys = parse_keys(parse)?;
+        Ok(Self { destination, keys })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let len = db.set_op_store(SetOp::Inter, &self.destination, &self.keys)?;
+        let response = Frame::Integer(len as i64);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        store_frame("sinterstore", self.destination, self.keys)
+    }
+}
+
+impl SUnionStoreCmd {
+    /// Creates a new [`SUnionStoreCmd`] command.
+    pub fn new(destination: impl ToString, keys: Vec<String>) -> Self {
+        Self {
+            destination: destination.to_string(),
+            keys,
+        }
+    }
+
+    /// Returns a reference to the destination key.
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Get the keys.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+}
+
+impl Command for SUnionStoreCmd {
+    /// Parse a [`SUnionStoreCmd`] instance from a received frame.
+    ///
+    /// The `SUNIONSTORE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SUNIONSTORE destination key [key ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let destination = parse.next_string()?;
+        let keys = parse_keys(parse)?;
+        Ok(Self { destination, keys })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let len = db.set_op_store(SetOp::Union, &self.destination, &self.keys)?;
+        let response = Frame::Integer(len as i64);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        store_frame("sunionstore", self.destination, self.keys)
+    }
+}
+
+impl SDiffStoreCmd {
+    /// Creates a new [`SDiffStoreCmd`] command.
+    pub fn new(destination: impl ToString, keys: Vec<String>) -> Self {
+        Self {
+            destination: destination.to_string(),
+            keys,
+        }
+    }
+
+    /// Returns a reference to the destination key.
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Get the keys.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+}
+
+impl Command for SDiffStoreCmd {
+    /// Parse a [`SDiffStoreCmd`] instance from a received frame.
+    ///
+    /// The `SDIFFSTORE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SDIFFSTORE destination key [key ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let destination = parse.next_string()?;
+        let keys = parse_keys(parse)?;
+        Ok(Self { destination, keys })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let len = db.set_op_store(SetOp::Diff, &self.destination, &self.keys)?;
+        let response = Frame::Integer(len as i64);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        store_frame("sdiffstore", self.destination, self.keys)
+    }
+}
+
+/// Parse one or more keys, until the frame is exhausted.
+fn parse_keys(parse: &mut Parse) -> Result<Vec<String>> {
+    // At least one key must be provided.
+    let mut keys = vec![parse.next_string()?];
+    loop {
+        match parse.next_string() {
+            Ok(key) => keys.push(key),
+            Err(Error::EndOfStream) => break,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(keys)
+}
+
+/// Creates the request frame of the set operation `name` over `keys`.
+fn keys_frame(name: &'static str, keys: Vec<String>) -> Result<Frame> {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from(name))?;
+    for key in keys {
+        frame.push_bulk(Bytes::from(key))?;
+    }
+    Ok(frame)
+}
+
+/// Creates the request frame of the set operation `name` storing its result
+/// at `destination`.
+fn store_frame(name: &'static str, destination: String, keys: Vec<String>) -> Result<Frame> {
+    let mut frame = Frame::array();
+    frame.push_bulk(Bytes::from(name))?;
+    frame.push_bulk(Bytes::from(destination))?;
+    for key in keys {
+        frame.push_bulk(Bytes::from(key))?;
+    }
+    Ok(frame)
+}
+
+/// Creates the response listing the resulting `members`.
+fn members_frame(members: BTreeSet<Bytes>) -> Result<Frame> {
+    let mut response = Frame::array();
+    for member in members {
+        response.push_bulk(member)?;
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    /// Create a database holding the sets `a = {x, y, z}` and `b = {w, y}`.
+    fn db_with_sets() -> Db {
+        let db = Db::default();
+        let members = |members: &[&'static str]| members.iter().map(|m| Bytes::from(*m)).collect();
+        db.sadd("a", members(&["z", "x", "y"])).unwrap();
+        db.sadd("b", members(&["y", "w"])).unwrap();
+        db
+    }
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_sinter_with_absent_key() {
+        let db = db_with_sets();
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*1\r\n$1\r\ny\r\n")
+            .write(b"*0\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        SInterCmd::new(keys(&["a", "b"]))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        SInterCmd::new(keys(&["a", "missing", "b"]))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sunion_is_ordered() {
+        let db = db_with_sets();
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*4\r\n$1\r\nw\r\n$1\r\nx\r\n$1\r\ny\r\n$1\r\nz\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        SUnionCmd::new(keys(&["b", "missing", "a"]))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sdiff() {
+        let db = db_with_sets();
+        assert_eq!(
+            db.sdiff(&keys(&["a", "b"])).unwrap(),
+            BTreeSet::from([Bytes::from("x"), Bytes::from("z")])
+        );
+        assert!(db.sdiff(&keys(&["missing", "a"])).unwrap().is_empty());
+
+        db.set("s".to_string(), Bytes::from("v"), None).unwrap();
+        let err = db.sdiff(&keys(&["a", "s"])).unwrap_err();
+        assert!(matches!(err, Error::WrongType));
+    }
+
+    #[tokio::test]
+    async fn test_empty_store_result_deletes_destination() {
+        let db = db_with_sets();
+        db.sadd("c", vec![Bytes::from("v")]).unwrap();
+        let stream = tokio_test::io::Builder::new()
+            .write(b":4\r\n")
+            .write(b":0\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        // The union overwrites the destination, even a string.
+        db.set("dest".to_string(), Bytes::from("s"), None).unwrap();
+        SUnionStoreCmd::new("dest", keys(&["a", "b"]))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.sinter(&keys(&["dest"])).unwrap(),
+            BTreeSet::from(["w", "x", "y", "z"].map(Bytes::from))
+        );
+
+        // The intersection with `c` is empty, which removes the destination.
+        SInterStoreCmd::new("dest", keys(&["a", "c"]))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        assert!(!db.exists("dest"));
+
+        db.set("s".to_string(), Bytes::from("v"), None).unwrap();
+        let err = db
+            .set_op_store(SetOp::Diff, "a", &keys(&["a", "s"]))
+            .unwrap_err();
+        assert!(matches!(err, Error::WrongType));
+        assert_eq!(db.sdiff(&keys(&["a"])).unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_sintercard_limit_and_absent_key() {
+        let db = db_with_sets();
+        db.sadd("c", ["x", "y", "z"].map(Bytes::from).to_vec())
+            .unwrap();
+        let stream = tokio_test::io::Builder::new()
+            .write(b":3\r\n")
+            .write(b":2\r\n")
+            .write(b":0\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        SInterCardCmd::new(keys(&["a", "c"]))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        // Counting stops at the limit.
+        SInterCardCmd::new(keys(&["a", "c"]))
+            .limit(2)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        // A missing key is an empty set, so the intersection is empty.
+        SInterCardCmd::new(keys(&["a", "missing", "c"]))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+
+        assert_eq!(db.sintercard(&keys(&["a", "b"]), usize::MAX).unwrap(), 1);
+        db.set("s".to_string(), Bytes::from("v"), None).unwrap();
+        let err = db.sintercard(&keys(&["a", "s"]), 1).unwrap_err();
+        assert!(matches!(err, Error::WrongType));
+
+        for (args, expected) in [
+            (&["SINTERCARD", "0", "a"][..], "numkeys"),
+            (&["SINTERCARD", "1", "a", "LIMIT", "-1"], "LIMIT"),
+            (&["SINTERCARD", "1", "a", "b"], "syntax error"),
+        ] {
+            let frame = Frame::Array(
+                args.iter()
+                    .map(|arg| Frame::BulkString(Bytes::from(arg.to_string())))
+                    .collect(),
+            );
+            let err = crate::CommandVariant::from_frame(frame).unwrap_err();
+            assert!(err.to_string().starts_with(expected), "{err}");
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Implementation of the `SLOWLOG` command.
+use super::help::{help_frame, unknown_subcommand};
+use crate::{
+    parse::Parse, server::ServerState, Connection, ConnectionStream, Error, Frame, Result,
+};
+use bytes::Bytes;
+use std::{fmt::Display, time::UNIX_EPOCH};
+use tracing::debug;
+
+/// The number of entries returned by `SLOWLOG GET`, unless a count is given.
+const DEFAULT_COUNT: i64 = 10;
+
+/// Inspects the log of the commands which took longer than the configured
+/// threshold to apply.
+///
+/// # Subcommands
+///
+/// * GET `[count]` -- Returns up to `count` entries, 10 by default, newest
+///   first. A negative `count` returns every entry. Each entry is an array of
+///   its id, unix timestamp, duration in microseconds, and command arguments.
+/// * LEN -- Returns the number of entries.
+/// * RESET -- Removes every entry.
+/// * HELP -- Returns the documentation of the subcommands.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SlowLogCmd {
+    subcommand: Subcommand,
+}
+
+/// The `SLOWLOG` subcommands.
+#[derive(Debug, PartialEq, Eq)]
+enum Subcommand {
+    /// `SLOWLOG GET [count]`
+    Get(Option<i64>),
+    /// `SLOWLOG LEN`
+    Len,
+    /// `SLOWLOG RESET`
+    Reset,
+    /// `SLOWLOG HELP`
+    Help,
+}
+
+/// The documentation of the subcommands, returned by `SLOWLOG HELP`.
+const HELP: &[&str] = &[
+    "GET [<count>]",
+    "    Return up to <count> entries, 10 by default, newest first. A negative",
+    "    <count> returns every entry.",
+    "LEN",
+    "    Return the number of entries.",
+    "RESET",
+    "    Remove every entry.",
+];
+
+impl SlowLogCmd {
+    /// Creates a new `SLOWLOG GET` command, returning up to `count` entries.
+    pub fn get(count: Option<i64>) -> Self {
+        Self {
+            subcommand: Subcommand::Get(count),
+        }
+    }
+
+    /// Creates a new `SLOWLOG LEN` command.
+    pub fn len() -> Self {
+        Self {
+            subcommand: Subcommand::Len,
+        }
+    }
+
+    /// Creates a new `SLOWLOG RESET` command.
+    pub fn reset() -> Self {
+        Self {
+            subcommand: Subcommand::Reset,
+        }
+    }
+
+    /// Creates a new `SLOWLOG HELP` command.
+    pub fn help() -> Self {
+        Self {
+            subcommand: Subcommand::Help,
+        }
+    }
+
+    /// Parse a [`SlowLogCmd`] instance from a received frame.
+    ///
+    /// The `SLOWLOG` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SLOWLOG GET [count] | LEN | RESET | HELP
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Self> {
+        let subcommand = parse.next_string()?;
+        let subcommand = match subcommand.to_uppercase().as_str() {
+            "GET" => match parse.next_int_signed() {
+                Ok(count) => Subcommand::Get(Some(count)),
+                Err(Error::EndOfStream) => Subcommand::Get(None),
+                Err(err) => return Err(err),
+            },
+            "LEN" => Subcommand::Len,
+            "RESET" => Subcommand::Reset,
+            "HELP" => Subcommand::Help,
+            _ => return Err(unknown_subcommand("SLOWLOG", subcommand)),
+        };
+
+        Ok(Self { subcommand })
+    }
+
+    /// Apply the `SLOWLOG` command to the log of `server`.
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn apply<S: ConnectionStream>(
+        self,
+        server: &ServerState,
+        dst: &mut Connection<S>,
+    ) -> Result<()> {
+        let response = match self.subcommand {
+            Subcommand::Get(count) => {
+                let count = count.unwrap_or(DEFAULT_COUNT);
+                let count = usize::try_from(count).ok();
+                let mut response = Frame::array();
+                for entry in server.slowlog().get(count) {
+                    let timestamp = entry
+                        .timestamp
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+                    let mut args = Frame::array();
+                    for arg in entry.args {
+                        args.push_bulk(Bytes::from(arg))?;
+                    }
+
+                    let mut frame = Frame::array();
+                    frame.push_int(entry.id as i64)?;
+                    frame.push_int(timestamp as i64)?;
+                    frame.push_int(entry.duration.as_micros() as i64)?;
+                    frame.push_frame(args)?;
+                    response.push_frame(frame)?;
+                }
+                response
+            }
+            Subcommand::Len => Frame::Integer(server.slowlog().len() as i64),
+            Subcommand::Reset => {
+                server.slowlog().reset();
+                Frame::SimpleString("OK".to_string())
+            }
+            Subcommand::Help => help_frame("SLOWLOG", HELP)?,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    /// Creates the request frame of the command.
+    pub(crate) fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("slowlog"))?;
+        match self.subcommand {
+            Subcommand::Get(count) => {
+                frame.push_bulk(Bytes::from("get"))?;
+                if let Some(count) = count {
+                    frame.push_int(count)?;
+                }
+            }
+            Subcommand::Len => frame.push_bulk(Bytes::from("len"))?,
+            Subcommand::Reset => frame.push_bulk(Bytes::from("reset"))?,
+            Subcommand::Help => frame.push_bulk(Bytes::from("help"))?,
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for SlowLogCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.subcommand {
+            Subcommand::Get(Some(count)) => write!(f, "SLOWLOG GET {count}"),
+            Subcommand::Get(None) => write!(f, "SLOWLOG GET"),
+            Subcommand::Len => write!(f, "SLOWLOG LEN"),
+            Subcommand::Reset => write!(f, "SLOWLOG RESET"),
+            Subcommand::Help => write!(f, "SLOWLOG HELP"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cmd::DebugCmd,
+        server::{ServerConfig, ServerState},
+        CommandVariant, Connection, Db, Shutdown,
+    };
+    use std::time::Duration;
+    use tokio::sync::broadcast;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slowlog_threshold() {
+        let db = Db::default();
+        let server = ServerState::new(&ServerConfig {
+            slowlog_log_slower_than: Some(Duration::from_millis(100)),
+            slowlog_max_len: 8,
+            ..Default::default()
+        });
+        let stream = tokio_test::io::Builder::new()
+            .write(b"+OK\r\n")
+            .write(b"+OK\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        let (_notify_shutdown, rx) = broadcast::channel(1);
+        let mut shutdown = Shutdown::new(rx);
+
+        for duration in [50, 200] {
+            CommandVariant::Debug(DebugCmd::sleep(Duration::from_millis(duration)))
+                .apply(&db, &server, &mut conn, &mut shutdown)
+                .await
+                .unwrap();
+        }
+
+        // Only the sleep above the threshold was logged.
+        let entries = server.slowlog().get(None);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].args, ["DEBUG", "SLEEP", "0.2"]);
+        assert!(entries[0].duration >= Duration::from_millis(200));
+
+        server.slowlog().reset();
+        assert_eq!(server.slowlog().len(), 0);
+    }
+}
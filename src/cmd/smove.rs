@@ -0,0 +1,154 @@
+//! Implementation of the `SMOVE` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Atomically move `member` from the set stored at `source` to the set stored
+/// at `destination`.
+///
+/// Replies with `1` if the member was moved, or `0` if it is not a member of
+/// `source`. Replies with a `WRONGTYPE` error if either key holds a value
+/// other than a set.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SMoveCmd {
+    /// The key of the set to move the member from.
+    source: String,
+    /// The key of the set to move the member to.
+    destination: String,
+    /// The member to move.
+    member: Bytes,
+}
+
+impl SMoveCmd {
+    /// Creates a new [`SMoveCmd`] command.
+    pub fn new(source: impl ToString, destination: impl ToString, member: Bytes) -> Self {
+        Self {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            member,
+        }
+    }
+
+    /// Get the source key.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Get the destination key.
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Get the member.
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+}
+
+impl Command for SMoveCmd {
+    /// Parse a [`SMoveCmd`] instance from a received frame.
+    ///
+    /// The `SMOVE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SMOVE source destination member
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let source = parse.next_string()?;
+        let destination = parse.next_string()?;
+        let member = parse.next_bytes()?;
+        Ok(Self {
+            source,
+            destination,
+            member,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let moved = db.smove(&self.source, &self.destination, self.member)?;
+        let response = Frame::Integer(moved as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("smove"))?;
+        frame.push_bulk(Bytes::from(self.source))?;
+        frame.push_bulk(Bytes::from(self.destination))?;
+        frame.push_bulk(self.member)?;
+        Ok(frame)
+    }
+}
+
+impl Display for SMoveCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "SMOVE {} {} {:?}",
+            self.source, self.destination, self.member
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db, Error};
+    use std::collections::BTreeSet;
+
+    #[tokio::test]
+    async fn test_smove() {
+        let db = Db::default();
+        db.sadd("src", vec![Bytes::from("a"), Bytes::from("b")])
+            .unwrap();
+        db.sadd("dst", vec![Bytes::from("a")]).unwrap();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b":0\r\n")
+            .write(b":1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        // Moving a non member changes nothing.
+        SMoveCmd::new("src", "dst", Bytes::from("c"))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        // A member of both sets leaves the source, and stays in the
+        // destination once.
+        SMoveCmd::new("src", "dst", Bytes::from("a"))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        let members = |key: &str| db.sinter(&[key.to_string()]).unwrap();
+        assert_eq!(members("src"), BTreeSet::from([Bytes::from("b")]));
+        assert_eq!(members("dst"), BTreeSet::from([Bytes::from("a")]));
+
+        // Moving the last member deletes the source.
+        assert!(db.smove("src", "dst", Bytes::from("b")).unwrap());
+        assert!(!db.exists("src"));
+        assert_eq!(members("dst").len(), 2);
+
+        db.set("s".to_string(), Bytes::from("v"), None).unwrap();
+        let err = db.smove("dst", "s", Bytes::from("a")).unwrap_err();
+        assert!(matches!(err, Error::WrongType));
+        assert_eq!(members("dst").len(), 2);
+    }
+}
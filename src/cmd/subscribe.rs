@@ -3,9 +3,17 @@
 use std::pin::Pin;
 
 use bytes::Bytes;
+use futures::FutureExt;
 use tokio::select;
-use tokio::sync::broadcast;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
 use tokio_stream::{Stream, StreamExt, StreamMap};
+use tracing::debug;
+
+use super::{ping::pong_frame, ResetCmd};
+use crate::{
+    frame::Protocol, parse::Parse, CommandVariant, Connection, ConnectionStream, Db, Error, Frame,
+    Result, Shutdown,
+};
 
 /// Subscribes the client to one or more channels.
 ///
@@ -27,14 +35,575 @@ pub struct UnsubscribeCmd {
 }
 
 /// Stream of messages. The stream receives messages from the
-/// `broadcast::Receiver`. We use `stream!` to create a `Stream` that consumes
-/// messages. Because `stream!` values cannot be named, we box the stream using
-/// a trait object.
-type Message = Pin<Box<dyn Stream<Item = Bytes> + Send>>;
+/// `broadcast::Receiver`, which is wrapped in a [`BroadcastStream`]. Because
+/// the adapted stream type cannot be easily named, we box the stream using a
+/// trait object.
+///
+/// If the subscriber lags behind, the number of messages it missed is
+/// yielded as an error, and the stream resumes at the oldest message still
+/// buffered.
+type Message = Pin<Box<dyn Stream<Item = std::result::Result<Bytes, u64>> + Send>>;
+
+/// Maximum number of queued messages written out with a single flush.
+const MAX_COALESCED_MESSAGES: usize = 64;
 
 impl SubscribeCmd {
     /// Creates a new [`SubscribeCmd`] to listen on specified channels.
-    pub(crate) fn new(channels: Vec<String>) -> Self {
+    pub fn new(channels: Vec<String>) -> Self {
         Self { channels }
     }
+
+    /// Get the channel names.
+    pub(crate) fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
+    /// Parse a [`SubscribeCmd`] instance from a received frame.
+    ///
+    /// The `SUBSCRIBE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing two or more entries.
+    ///
+    /// ```text
+    /// SUBSCRIBE channel [channel ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Self> {
+        // At least one channel must be provided.
+        let mut channels = match parse.next_string() {
+            Ok(channel) => vec![channel],
+            Err(Error::EndOfStream) => {
+                return Err(Error::WrongNumberOfArguments("subscribe".to_string()))
+            }
+            Err(err) => return Err(err),
+        };
+
+        // Consume the remaining channels until the frame is exhausted.
+        loop {
+            match parse.next_string() {
+                Ok(channel) => channels.push(channel),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Self { channels })
+    }
+
+    /// Apply the `SUBSCRIBE` command to the specified [`Db`] instance.
+    ///
+    /// This function is the entry point and includes the initial list of
+    /// channels to subscribe to. Additional `SUBSCRIBE` and `UNSUBSCRIBE`
+    /// commands may be received from the client and the list of subscriptions
+    /// is updated accordingly.
+    ///
+    /// The connection stays in the subscribed state until the client
+    /// disconnects or the server shuts down. A client which only shuts down
+    /// its write half keeps receiving messages, until writing to it fails.
+    pub(crate) async fn apply<S: ConnectionStream>(
+        mut self,
+        db: &Db,
+        dst: &mut Connection<S>,
+        shutdown: &mut Shutdown,
+    ) -> Result<()> {
+        // Each individual channel subscription is handled using a
+        // `broadcast::Receiver`. A `StreamMap` is used to track the active
+        // subscriptions and multiplex the messages from all of them.
+        let mut subscriptions = StreamMap::new();
+        // Whether the client shut down its write half. Messages are still
+        // delivered, but no more commands can be received.
+        let mut read_closed = false;
+
+        loop {
+            // `self.channels` is used to track additional channels to subscribe
+            // to. When new `SUBSCRIBE` commands are received during the
+            // execution of `apply`, the new channels are pushed onto this vec.
+            let responses = self
+                .channels
+                .drain(..)
+                .map(|channel_name| subscribe_to_channel(channel_name, &mut subscriptions, db))
+                .collect::<Result<Vec<_>>>()?;
+            if !responses.is_empty() {
+                dst.write_frames(&responses).await?;
+            }
+
+            // Wait for one of the following to happen:
+            //
+            // - Receive a message from one of the subscribed channels.
+            // - Receive a subscribe or unsubscribe command from the client,
+            //   or once it closed its write half, write out buffered output.
+            // - A server shutdown signal.
+            select! {
+                Some((channel_name, msg)) = subscriptions.next() => {
+                    match write_messages(channel_name, msg, &mut subscriptions, dst).await {
+                        // Once the client shut down its write half, failing
+                        // to write means it is gone for good.
+                        Err(Error::Io(err)) if read_closed => {
+                            debug!(cause = %err, "client disconnected");
+                            return Ok(());
+                        }
+                        res => res?,
+                    }
+                }
+                incoming = next_incoming(dst, read_closed) => {
+                    let res = match incoming {
+                        Incoming::Frame(res) => res,
+                        Incoming::Drained(Ok(())) => continue,
+                        Incoming::Drained(Err(Error::Io(err))) => {
+                            debug!(cause = %err, "client disconnected");
+                            return Ok(());
+                        }
+                        Incoming::Drained(Err(err)) => return Err(err),
+                    };
+                    let frame = match res {
+                        Some(frame) => frame?,
+                        // The client may only have shut down its write half,
+                        // while still reading. A client which disconnected
+                        // entirely is noticed once writing to it fails.
+                        None if !subscriptions.is_empty() => {
+                            debug!("client closed its write half, still delivering messages");
+                            read_closed = true;
+                            continue;
+                        }
+                        None => return Ok(()),
+                    };
+
+                    let leave = handle_command(frame, &mut self.channels, &mut subscriptions, dst).await?;
+                    if leave {
+                        // The connection left the subscribed state, return to
+                        // the regular command loop.
+                        return Ok(());
+                    }
+                }
+                _ = shutdown.recv() => {
+                    // Dropping the subscriptions unsubscribes from every
+                    // channel, and returning lets the handler see the signal
+                    // and close the connection.
+                    return Ok(());
+                }
+            };
+        }
+    }
+}
+
+/// What happened on the connection of a subscriber, besides messages to
+/// deliver.
+enum Incoming {
+    /// The client sent a frame, or closed its write half.
+    Frame(Option<Result<Frame>>),
+    /// The write buffer was written out, once the client closed its write
+    /// half.
+    Drained(Result<()>),
+}
+
+/// Wait for the next frame from the client, or once it closed its write half,
+/// for the write buffer to be written out.
+///
+/// Reading frames is what writes out the output left buffered under an
+/// output buffer limit, so it has to be done explicitly once nothing is read
+/// anymore.
+async fn next_incoming<S: ConnectionStream>(
+    dst: &mut Connection<S>,
+    read_closed: bool,
+) -> Incoming {
+    if !read_closed {
+        return Incoming::Frame(dst.next().await);
+    }
+    if dst.buffered() == 0 {
+        return std::future::pending().await;
+    }
+    Incoming::Drained(dst.drain().await)
+}
+
+/// Write the message `msg` received on `channel_name`, along with the ones
+/// already queued on `subscriptions`.
+async fn write_messages<S: ConnectionStream>(
+    channel_name: String,
+    msg: std::result::Result<Bytes, u64>,
+    subscriptions: &mut StreamMap<String, Message>,
+    dst: &mut Connection<S>,
+) -> Result<()> {
+    dst.write_frame_buffered(&make_message_frame(channel_name, msg)?)
+        .await?;
+    // Coalesce the messages which are already queued into a single flush,
+    // instead of flushing every one of them. The batch is bounded, so client
+    // commands are not starved.
+    for _ in 1..MAX_COALESCED_MESSAGES {
+        let Some(Some((channel_name, msg))) = subscriptions.next().now_or_never() else {
+            break;
+        };
+        dst.write_frame_buffered(&make_message_frame(channel_name, msg)?)
+            .await?;
+    }
+    dst.flush().await
+}
+
+/// Subscribe to a single channel, returning the confirmation frame.
+///
+/// If the connection already has as many subscriptions as allowed, an error
+/// frame is returned instead, and the channel is not subscribed to.
+fn subscribe_to_channel(
+    channel_name: String,
+    subscriptions: &mut StreamMap<String, Message>,
+    db: &Db,
+) -> Result<Frame> {
+    // Subscribing again to a channel replaces the subscription, so it does
+    // not count against the limit.
+    let at_limit = db
+        .max_subscriptions()
+        .is_some_and(|max| subscriptions.len() >= max);
+    if at_limit && !subscriptions.contains_key(&channel_name) {
+        let err = Error::TooManySubscriptions(channel_name);
+        return err.to_frame().ok_or(err);
+    }
+
+    let rx = db.subscribe(channel_name.clone());
+
+    // Subscribe to the channel.
+    let rx: Message = Box::pin(
+        BroadcastStream::new(rx)
+            .map(|msg| msg.map_err(|BroadcastStreamRecvError::Lagged(skipped)| skipped)),
+    );
+
+    // Track subscription in this client's subscription set.
+    subscriptions.insert(channel_name.clone(), rx);
+
+    // Respond with the successful subscription.
+    make_subscribe_frame(channel_name, subscriptions.len())
+}
+
+/// Handle a command received while inside [`SubscribeCmd::apply`].
+///
+/// Only subscribe, unsubscribe, ping and reset commands are permitted in this
+/// context. Other commands, and malformed ones, are replied to with an error,
+/// and the connection stays in the subscribed state.
+///
+/// Any new subscriptions are appended to `subscribe_to` instead of modifying
+/// `subscriptions`.
+///
+/// Returns `true` if the connection must leave the subscribed state.
+async fn handle_command<S: ConnectionStream>(
+    frame: Frame,
+    subscribe_to: &mut Vec<String>,
+    subscriptions: &mut StreamMap<String, Message>,
+    dst: &mut Connection<S>,
+) -> Result<bool> {
+    // A command has been received from the client.
+    //
+    // Only `SUBSCRIBE`, `UNSUBSCRIBE`, `PING` and `RESET` commands are
+    // permitted in this context.
+    let cmd = match CommandVariant::from_frame(frame) {
+        Ok(cmd) => cmd,
+        Err(err) => {
+            write_error(err, dst).await?;
+            return Ok(false);
+        }
+    };
+    match cmd {
+        CommandVariant::Subscribe(cmd) => {
+            // The `apply` method will subscribe to the channels we add to this
+            // vector.
+            subscribe_to.extend(cmd.channels);
+        }
+        CommandVariant::Unsubscribe(mut cmd) => {
+            // If no channels are specified, this requests unsubscribing from
+            // **all** channels. To implement this, the `cmd.channels` vec is
+            // populated with the list of channels currently subscribed to.
+            if cmd.channels.is_empty() {
+                cmd.channels = subscriptions
+                    .keys()
+                    .map(|channel_name| channel_name.to_string())
+                    .collect();
+            }
+
+            let mut responses = Vec::with_capacity(cmd.channels.len());
+            for channel_name in cmd.channels {
+                subscriptions.remove(&channel_name);
+                responses.push(make_unsubscribe_frame(channel_name, subscriptions.len())?);
+            }
+            dst.write_frames(&responses).await?;
+        }
+        CommandVariant::Reset(_) => {
+            // Drop every subscription, along with any pending ones, and leave
+            // the subscribed state.
+            subscribe_to.clear();
+            subscriptions.clear();
+            dst.set_protocol(Protocol::default());
+            dst.write_frame(&ResetCmd::response()).await?;
+            return Ok(true);
+        }
+        CommandVariant::Ping(cmd) => {
+            let response = pong_frame(cmd.msg().cloned(), true, dst.protocol());
+            dst.write_frame(&response).await?;
+        }
+        cmd => {
+            let err = Error::NotAllowedInSubscribe(cmd.name().to_lowercase());
+            write_error(err, dst).await?;
+        }
+    }
+
+    Ok(false)
+}
+
+/// Report a command error to the client, keeping the connection subscribed.
+///
+/// Errors which are not recoverable, such as IO errors, are returned instead.
+async fn write_error<S: ConnectionStream>(err: Error, dst: &mut Connection<S>) -> Result<()> {
+    match err.to_frame() {
+        Some(frame) => dst.write_frame(&frame).await,
+        None => Err(err),
+    }
+}
+
+/// Creates the response to a subscribe request.
+///
+/// Pub/sub replies are pushes, sent as arrays unless RESP3 was negotiated.
+fn make_subscribe_frame(channel_name: String, num_subs: usize) -> Result<Frame> {
+    let mut response = Frame::Push(vec![]);
+    response.push_bulk(Bytes::from_static(b"subscribe"))?;
+    response.push_bulk(Bytes::from(channel_name))?;
+    response.push_int(num_subs as i64)?;
+    Ok(response)
+}
+
+/// Creates the response to an unsubscribe request.
+fn make_unsubscribe_frame(channel_name: String, num_subs: usize) -> Result<Frame> {
+    let mut response = Frame::Push(vec![]);
+    response.push_bulk(Bytes::from_static(b"unsubscribe"))?;
+    response.push_bulk(Bytes::from(channel_name))?;
+    response.push_int(num_subs as i64)?;
+    Ok(response)
+}
+
+/// Creates a message informing the client about a new message on a channel that
+/// the client subscribes to.
+///
+/// If the client missed messages, it is sent an error instead, so the loss is
+/// not silent.
+fn make_message_frame(channel_name: String, msg: std::result::Result<Bytes, u64>) -> Result<Frame> {
+    let msg = match msg {
+        Ok(msg) => msg,
+        Err(skipped) => {
+            let err = Error::Lagged {
+                channel: channel_name,
+                skipped,
+            };
+            return Ok(Frame::SimpleError(format!("ERR {err}")));
+        }
+    };
+    let mut response = Frame::Push(vec![]);
+    response.push_bulk(Bytes::from_static(b"message"))?;
+    response.push_bulk(Bytes::from(channel_name))?;
+    response.push_bulk(msg)?;
+    Ok(response)
+}
+
+impl UnsubscribeCmd {
+    /// Create a new [`UnsubscribeCmd`] with the given `channels`.
+    pub fn new(channels: Vec<String>) -> Self {
+        Self { channels }
+    }
+
+    /// Get the channel names.
+    pub(crate) fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
+    /// Parse an [`UnsubscribeCmd`] instance from a received frame.
+    ///
+    /// The `UNSUBSCRIBE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing at least one entry.
+    ///
+    /// ```text
+    /// UNSUBSCRIBE [channel [channel ...]]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Self> {
+        // There may be no channels listed, so start with an empty vec.
+        let mut channels = vec![];
+
+        loop {
+            match parse.next_string() {
+                Ok(channel) => channels.push(channel),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Self { channels })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbConfig;
+    use tokio::sync::broadcast;
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_is_told_about_dropped_messages() {
+        let (client, server) = tokio::io::duplex(1024);
+        let (notify_shutdown, rx) = broadcast::channel(1);
+        let db = Db::new(DbConfig {
+            pubsub_channel_capacity: 2,
+            ..Default::default()
+        });
+
+        let subscriber = tokio::spawn({
+            let db = db.clone();
+            async move {
+                let mut conn = Connection::new(server);
+                // Pub/sub replies are read back as the pushes they were
+                // created as.
+                conn.set_protocol(Protocol::Resp3);
+                let mut shutdown = Shutdown::new(rx);
+                SubscribeCmd::new(vec!["foo".to_string()])
+                    .apply(&db, &mut conn, &mut shutdown)
+                    .await
+            }
+        });
+
+        let mut client = Connection::new(client);
+        let confirmation = make_subscribe_frame("foo".to_string(), 1).unwrap();
+        assert_eq!(client.read_frame().await.unwrap(), Some(confirmation));
+
+        // The subscriber does not get to run in between, so only the last two
+        // messages remain buffered.
+        for msg in ["a", "b", "c", "d", "e"] {
+            assert_eq!(db.publish("foo", Bytes::from(msg)), 1);
+        }
+
+        let lag = Frame::SimpleError(
+            "ERR subscriber lagged behind, 3 messages dropped on channel 'foo'".to_string(),
+        );
+        assert_eq!(client.read_frame().await.unwrap(), Some(lag));
+        for msg in ["d", "e"] {
+            let expected = make_message_frame("foo".to_string(), Ok(Bytes::from(msg))).unwrap();
+            assert_eq!(client.read_frame().await.unwrap(), Some(expected));
+        }
+
+        // The client may only have shut down its write half, so the
+        // subscriber keeps waiting for messages until the server shuts down.
+        drop(client);
+        notify_shutdown.send(()).unwrap();
+        subscriber.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_past_the_limit_are_rejected() {
+        let (client, server) = tokio::io::duplex(1024);
+        let (notify_shutdown, rx) = broadcast::channel(1);
+        let db = Db::new(DbConfig {
+            max_subscriptions: Some(2),
+            ..Default::default()
+        });
+
+        let subscriber = tokio::spawn({
+            let db = db.clone();
+            async move {
+                let mut conn = Connection::new(server);
+                conn.set_protocol(Protocol::Resp3);
+                let mut shutdown = Shutdown::new(rx);
+                let channels = ["a", "b", "c"].map(String::from).to_vec();
+                SubscribeCmd::new(channels)
+                    .apply(&db, &mut conn, &mut shutdown)
+                    .await
+            }
+        });
+
+        let mut client = Connection::new(client);
+        for (channel, count) in [("a", 1), ("b", 2)] {
+            let confirmation = make_subscribe_frame(channel.to_string(), count).unwrap();
+            assert_eq!(client.read_frame().await.unwrap(), Some(confirmation));
+        }
+        let rejection = Frame::SimpleError(
+            "ERR cannot subscribe to 'c', maximum subscriptions exceeded".to_string(),
+        );
+        assert_eq!(client.read_frame().await.unwrap(), Some(rejection));
+
+        // The earlier subscriptions are still active.
+        assert_eq!(db.publish("c", Bytes::from("lost")), 0);
+        assert_eq!(db.publish("a", Bytes::from("hi")), 1);
+        let expected = make_message_frame("a".to_string(), Ok(Bytes::from("hi"))).unwrap();
+        assert_eq!(client.read_frame().await.unwrap(), Some(expected));
+
+        // The client may only have shut down its write half, so the
+        // subscriber keeps waiting for messages until the server shuts down.
+        drop(client);
+        notify_shutdown.send(()).unwrap();
+        subscriber.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reset_leaves_subscribed_state() {
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*3\r\n$9\r\nsubscribe\r\n$3\r\nfoo\r\n:1\r\n")
+            .read(b"*1\r\n$5\r\nRESET\r\n")
+            .write(b"+RESET\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        let (_notify_shutdown, rx) = broadcast::channel(1);
+        let mut shutdown = Shutdown::new(rx);
+        let db = Db::default();
+
+        SubscribeCmd::new(vec!["foo".to_string()])
+            .apply(&db, &mut conn, &mut shutdown)
+            .await
+            .unwrap();
+
+        // No subscriber remains on the channel.
+        assert_eq!(db.publish("foo", Bytes::from("bar")), 0);
+    }
+
+    #[tokio::test]
+    async fn test_ping_while_subscribed() {
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*3\r\n$9\r\nsubscribe\r\n$3\r\nfoo\r\n:1\r\n")
+            .read(b"*1\r\n$4\r\nPING\r\n")
+            .write(b"*2\r\n$4\r\npong\r\n$0\r\n\r\n")
+            .read(b"*2\r\n$3\r\nGET\r\n$3\r\nfoo\r\n")
+            .write(b"-ERR Can't execute 'get': only SUBSCRIBE / UNSUBSCRIBE / PING / RESET are allowed in this context\r\n")
+            .read(b"*1\r\n$9\r\nsubscribe\r\n")
+            .write(b"-ERR wrong number of arguments for 'subscribe' command\r\n")
+            .read(b"*2\r\n$4\r\nPING\r\n$2\r\nhi\r\n")
+            .write(b"*2\r\n$4\r\npong\r\n$2\r\nhi\r\n")
+            .read(b"*1\r\n$5\r\nRESET\r\n")
+            .write(b"+RESET\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        let (_notify_shutdown, rx) = broadcast::channel(1);
+        let mut shutdown = Shutdown::new(rx);
+        let db = Db::default();
+
+        SubscribeCmd::new(vec!["foo".to_string()])
+            .apply(&db, &mut conn, &mut shutdown)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_confirmations_count_subscriptions() {
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n")
+            .write(b"*3\r\n$9\r\nsubscribe\r\n$1\r\nb\r\n:2\r\n")
+            .write(b"*3\r\n$9\r\nsubscribe\r\n$1\r\nc\r\n:3\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+        let (notify_shutdown, rx) = broadcast::channel(1);
+        let mut shutdown = Shutdown::new(rx);
+        let db = Db::default();
+
+        // The mock stream ends after the confirmations, which reads as the
+        // client shutting down its write half, so the subscriber only returns
+        // on shutdown.
+        notify_shutdown.send(()).unwrap();
+        let channels = ["a", "b", "c"].map(String::from).to_vec();
+        SubscribeCmd::new(channels)
+            .apply(&db, &mut conn, &mut shutdown)
+            .await
+            .unwrap();
+    }
 }
@@ -0,0 +1,97 @@
+//! Implementation of the `TIME` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use std::time::SystemTime;
+use tracing::debug;
+
+/// Returns the current server time.
+///
+/// The reply is a two elements array: the Unix time in seconds, and the
+/// microseconds elapsed in the current second.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TimeCmd;
+
+impl TimeCmd {
+    /// Creates a new [`TimeCmd`] command.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Command for TimeCmd {
+    /// Parse a [`TimeCmd`] instance from a received frame.
+    ///
+    /// The `TIME` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// TIME
+    /// ```
+    fn parse_frames(_parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        _db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        // A clock set before the Unix epoch is reported as the epoch.
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut response = Frame::array();
+        response.push_bulk(Bytes::from(now.as_secs().to_string()))?;
+        response.push_bulk(Bytes::from(now.subsec_micros().to_string()))?;
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("time"))?;
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    #[tokio::test]
+    async fn test_time_is_close_to_system_time() {
+        let (client, server) = tokio::io::duplex(64);
+        let mut conn = Connection::new(server);
+        TimeCmd::new()
+            .apply(&Db::default(), &mut conn)
+            .await
+            .unwrap();
+        drop(conn);
+
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let mut client = Connection::new(client);
+        let Some(Frame::Array(parts)) = client.read_frame().await.unwrap() else {
+            panic!("unexpected reply");
+        };
+        let [Frame::BulkString(secs), Frame::BulkString(micros)] = &parts[..] else {
+            panic!("unexpected reply: {parts:?}");
+        };
+        let secs: u64 = std::str::from_utf8(secs).unwrap().parse().unwrap();
+        let micros: u64 = std::str::from_utf8(micros).unwrap().parse().unwrap();
+        assert!(now.as_secs().abs_diff(secs) <= 1);
+        assert!(micros < 1_000_000);
+    }
+}
@@ -0,0 +1,155 @@
+//! Implementation of the `MULTI`, `EXEC` and `DISCARD` commands.
+//!
+//! The queued commands are per-connection state, so the transaction itself is
+//! driven by the connection handler. Applying these commands directly only
+//! covers their behavior outside of a transaction.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Marks the start of a transaction.
+///
+/// The following commands are queued instead of being applied, and replied to
+/// with `QUEUED`, until `EXEC` or `DISCARD` is received.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MultiCmd;
+
+/// Applies every command queued since `MULTI`.
+///
+/// Replies with an array of the replies of the queued commands. If any command
+/// failed to be queued, nothing is applied and the transaction is aborted with
+/// an `EXECABORT` error instead.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ExecCmd;
+
+/// Drops every command queued since `MULTI`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DiscardCmd;
+
+impl MultiCmd {
+    /// Creates a new [`MultiCmd`] command.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ExecCmd {
+    /// Creates a new [`ExecCmd`] command.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl DiscardCmd {
+    /// Creates a new [`DiscardCmd`] command.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Command for MultiCmd {
+    /// Parse a [`MultiCmd`] instance from a received frame.
+    ///
+    /// The `MULTI` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// MULTI
+    /// ```
+    fn parse_frames(_parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        _db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        // Queuing the following commands is up to the caller.
+        let response = Frame::SimpleString("OK".to_string());
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("multi"))?;
+        Ok(frame)
+    }
+}
+
+impl Command for ExecCmd {
+    /// Parse a [`ExecCmd`] instance from a received frame.
+    ///
+    /// The `EXEC` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// EXEC
+    /// ```
+    fn parse_frames(_parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        _db: &crate::Db,
+        _dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        Err(Error::ExecWithoutMulti)
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("exec"))?;
+        Ok(frame)
+    }
+}
+
+impl Command for DiscardCmd {
+    /// Parse a [`DiscardCmd`] instance from a received frame.
+    ///
+    /// The `DISCARD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// DISCARD
+    /// ```
+    fn parse_frames(_parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        _db: &crate::Db,
+        _dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        Err(Error::DiscardWithoutMulti)
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("discard"))?;
+        Ok(frame)
+    }
+}
@@ -0,0 +1,134 @@
+//! Implementation of the `WAIT` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::time::Duration;
+use tracing::debug;
+
+/// Blocks until previous writes are acknowledged by at least `numreplicas`
+/// replicas, or until `timeout` is reached.
+///
+/// As this is a single-node server, there are no replicas to wait for, so the
+/// command immediately replies with `0` acknowledged replicas.
+#[derive(Debug, PartialEq, Eq)]
+pub struct WaitCmd {
+    /// Number of replicas to wait for.
+    numreplicas: u64,
+    /// Maximum time to wait for. A zero timeout blocks forever.
+    timeout: Duration,
+}
+
+impl WaitCmd {
+    /// Creates a new [`WaitCmd`] command.
+    pub fn new(numreplicas: u64, timeout: Duration) -> Self {
+        Self {
+            numreplicas,
+            timeout,
+        }
+    }
+
+    /// Get the number of replicas to wait for.
+    pub fn numreplicas(&self) -> u64 {
+        self.numreplicas
+    }
+
+    /// Get the timeout.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+impl Command for WaitCmd {
+    /// Parse a [`WaitCmd`] instance from a received frame.
+    ///
+    /// The `WAIT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// Expects an array frame containing three entries.
+    ///
+    /// ```text
+    /// WAIT numreplicas timeout
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let numreplicas = parse.next_int_unsigned()?;
+        // Parse the timeout as signed, in order to report negative timeouts
+        // accurately.
+        let timeout = u64::try_from(parse.next_int_signed()?)
+            .map_err(|_| Error::Protocol("timeout is negative".to_string()))?;
+        let timeout = Duration::from_millis(timeout);
+        Ok(Self {
+            numreplicas,
+            timeout,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        _db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        // There are no replicas, so no replica could have acknowledged anything.
+        let response = Frame::Integer(0);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("wait"))?;
+        frame.push_int(self.numreplicas as i64)?;
+        frame.push_int(self.timeout.as_millis() as i64)?;
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommandVariant, Connection, Db};
+
+    #[test]
+    fn test_wait_cmd_malformed() {
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("WAIT")),
+            Frame::BulkString(Bytes::from("one")),
+            Frame::BulkString(Bytes::from("100")),
+        ]);
+        let err = CommandVariant::from_frame(frame).unwrap_err();
+        assert!(matches!(err, Error::Protocol(_)));
+
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("WAIT")),
+            Frame::BulkString(Bytes::from("1")),
+            Frame::BulkString(Bytes::from("-100")),
+        ]);
+        let err = CommandVariant::from_frame(frame).unwrap_err();
+        assert!(matches!(err, Error::Protocol(msg) if msg == "timeout is negative"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_cmd_returns_zero() {
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("WAIT")),
+            Frame::BulkString(Bytes::from("1")),
+            Frame::BulkString(Bytes::from("100")),
+        ]);
+        let CommandVariant::Wait(cmd) = CommandVariant::from_frame(frame).unwrap() else {
+            panic!("unexpected command");
+        };
+        assert_eq!(cmd, WaitCmd::new(1, Duration::from_millis(100)));
+
+        let stream = tokio_test::io::Builder::new().write(b":0\r\n").build();
+        let mut conn = Connection::new(stream);
+        cmd.apply(&Db::default(), &mut conn).await.unwrap();
+    }
+}
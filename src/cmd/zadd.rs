@@ -0,0 +1,132 @@
+//! Implementation of the `ZADD` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Add the given `members` with their scores to the sorted set stored at
+/// `key`.
+///
+/// If a member is already in the sorted set, its score is updated. If `key`
+/// does not exist, a new sorted set is created before adding the members.
+///
+/// Replies with the number of members that were added.
+#[derive(Debug, PartialEq)]
+pub struct ZAddCmd {
+    /// The key of the sorted set.
+    key: String,
+    /// The `(score, member)` pairs to add.
+    members: Vec<(f64, Bytes)>,
+}
+
+// Scores are never `NaN`, as they are rejected while parsing.
+impl Eq for ZAddCmd {}
+
+impl ZAddCmd {
+    /// Creates a new [`ZAddCmd`] command.
+    pub fn new(key: impl ToString, members: Vec<(f64, Bytes)>) -> Self {
+        Self {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the `(score, member)` pairs.
+    pub fn members(&self) -> &[(f64, Bytes)] {
+        &self.members
+    }
+}
+
+impl Command for ZAddCmd {
+    /// Parse a [`ZAddCmd`] instance from a received frame.
+    ///
+    /// The `ZADD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZADD key score member [score member ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+
+        // Scores and members come in pairs.
+        if !parse.remaining().is_multiple_of(2) {
+            return Err(Error::Syntax);
+        }
+
+        let mut members = vec![];
+        while parse.remaining() > 0 {
+            let score = parse.next_float()?;
+            let member = parse.next_bytes()?;
+            members.push((score, member));
+        }
+
+        Ok(Self { key, members })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let added = db.zadd(&self.key, self.members)?;
+        let response = Frame::Integer(added as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zadd"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        for (score, member) in self.members {
+            frame.push_bulk(Bytes::from(score.to_string()))?;
+            frame.push_bulk(member)?;
+        }
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CommandVariant;
+
+    #[test]
+    fn test_zadd_parse() {
+        let frame = ZAddCmd::new(
+            "foo",
+            vec![(1.5, Bytes::from("a")), (-2.0, Bytes::from("b"))],
+        )
+        .into_frame()
+        .unwrap();
+        let CommandVariant::ZAdd(cmd) = CommandVariant::from_frame(frame).unwrap() else {
+            panic!("unexpected command");
+        };
+        assert_eq!(cmd.members()[1], (-2.0, Bytes::from("b")));
+
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("ZADD")),
+            Frame::BulkString(Bytes::from("foo")),
+            Frame::BulkString(Bytes::from("1")),
+            Frame::BulkString(Bytes::from("a")),
+            Frame::BulkString(Bytes::from("2")),
+        ]);
+        let err = CommandVariant::from_frame(frame).unwrap_err();
+        assert!(matches!(err, Error::Syntax));
+    }
+}
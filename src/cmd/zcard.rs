@@ -0,0 +1,213 @@
+//! Implementation of the `ZCARD` and `ZCOUNT` commands.
+use super::{
+    zrange::{parse_score_range, score_bound_arg},
+    Command,
+};
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use std::{fmt::Display, ops::Bound};
+use tracing::debug;
+
+/// Get the number of members of the sorted set stored at `key`.
+///
+/// A missing key is an empty sorted set.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ZCardCmd {
+    /// The key of the sorted set.
+    key: String,
+}
+
+/// Count the members of the sorted set stored at `key` whose score is between
+/// `min` and `max`.
+///
+/// An unbounded `min` or `max` stands for an infinite score. A missing key is
+/// an empty sorted set.
+#[derive(Debug, PartialEq)]
+pub struct ZCountCmd {
+    /// The key of the sorted set.
+    key: String,
+    /// The lowest score.
+    min: Bound<f64>,
+    /// The highest score.
+    max: Bound<f64>,
+}
+
+// Scores are never `NaN`, as they are rejected while parsing.
+impl Eq for ZCountCmd {}
+
+impl ZCardCmd {
+    /// Creates a new [`ZCardCmd`] command.
+    pub fn new(key: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl ZCountCmd {
+    /// Creates a new [`ZCountCmd`] command.
+    pub fn new(key: impl ToString, min: Bound<f64>, max: Bound<f64>) -> Self {
+        Self {
+            key: key.to_string(),
+            min,
+            max,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the lowest score.
+    pub fn min(&self) -> Bound<f64> {
+        self.min
+    }
+
+    /// Get the highest score.
+    pub fn max(&self) -> Bound<f64> {
+        self.max
+    }
+}
+
+impl Command for ZCardCmd {
+    /// Parse a [`ZCardCmd`] instance from a received frame.
+    ///
+    /// The `ZCARD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZCARD key
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        Ok(Self { key })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = Frame::Integer(db.zcard(&self.key)? as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zcard"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        Ok(frame)
+    }
+}
+
+impl Command for ZCountCmd {
+    /// Parse a [`ZCountCmd`] instance from a received frame.
+    ///
+    /// The `ZCOUNT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZCOUNT key min max
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let (min, max) = parse_score_range(parse)?;
+        Ok(Self { key, min, max })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let count = db.zcount(&self.key, self.min, self.max)?;
+        let response = Frame::Integer(count as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zcount"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_bulk(Bytes::from(score_bound_arg(self.min, "-inf")))?;
+        frame.push_bulk(Bytes::from(score_bound_arg(self.max, "+inf")))?;
+        Ok(frame)
+    }
+}
+
+impl Display for ZCountCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let min = score_bound_arg(self.min, "-inf");
+        let max = score_bound_arg(self.max, "+inf");
+        write!(f, "ZCOUNT {} {min} {max}", self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommandVariant, Connection, Db};
+
+    #[tokio::test]
+    async fn test_zcount_bounds() {
+        let db = Db::default();
+        let members = [(1.0, "a"), (2.0, "b"), (2.0, "c"), (3.0, "d")]
+            .map(|(score, member)| (score, Bytes::from(member)))
+            .to_vec();
+        db.zadd("foo", members).unwrap();
+        assert_eq!(db.zcard("foo").unwrap(), 4);
+        assert_eq!(db.zcard("missing").unwrap(), 0);
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b":3\r\n")
+            .write(b":2\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        // Inclusive bounds count the members on the boundaries.
+        ZCountCmd::new("foo", Bound::Included(1.0), Bound::Included(2.0))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        // An exclusive bound leaves them out.
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("ZCOUNT")),
+            Frame::BulkString(Bytes::from("foo")),
+            Frame::BulkString(Bytes::from("(1")),
+            Frame::BulkString(Bytes::from("(3")),
+        ]);
+        let CommandVariant::ZCount(cmd) = CommandVariant::from_frame(frame).unwrap() else {
+            panic!("unexpected command");
+        };
+        cmd.apply(&db, &mut conn).await.unwrap();
+
+        let count = db.zcount("foo", Bound::Excluded(2.0), Bound::Unbounded);
+        assert_eq!(count.unwrap(), 1);
+    }
+}
@@ -0,0 +1,177 @@
+//! Implementation of the `ZRANDMEMBER` command.
+use super::{hrandfield::parse_count, Command};
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Get random members of the sorted set stored at `key`.
+///
+/// Without a count, replies with a single member, or null if the key does not
+/// exist. With a positive count, replies with up to `count` distinct members.
+/// With a negative count, replies with exactly `-count` members, which may
+/// repeat. A missing key is an empty sorted set.
+///
+/// # Options
+///
+/// * WITHSCORES -- Reply with the score of each member after the member.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ZRandMemberCmd {
+    /// The key of the sorted set.
+    key: String,
+    /// Number of members to pick, if any was given.
+    count: Option<i64>,
+    /// Whether to reply with the scores.
+    with_scores: bool,
+}
+
+impl ZRandMemberCmd {
+    /// Creates a new [`ZRandMemberCmd`] command, picking a single member.
+    pub fn new(key: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+            count: None,
+            with_scores: false,
+        }
+    }
+
+    /// Pick `count` members, replying with an array.
+    pub fn count(mut self, count: i64) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Reply with the score of each member.
+    ///
+    /// The scores are only part of the reply when a count is given too.
+    pub fn with_scores(mut self) -> Self {
+        self.with_scores = true;
+        self
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Command for ZRandMemberCmd {
+    /// Parse a [`ZRandMemberCmd`] instance from a received frame.
+    ///
+    /// The `ZRANDMEMBER` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZRANDMEMBER key [count [WITHSCORES]]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let (count, with_scores) = parse_count(parse, "WITHSCORES")?;
+        Ok(Self {
+            key,
+            count,
+            with_scores,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = match self.count {
+            Some(count) => {
+                let mut response = Frame::array();
+                for (member, score) in db.zrandmember(&self.key, count)? {
+                    response.push_bulk(member)?;
+                    if self.with_scores {
+                        response.push_bulk(Bytes::from(score.to_string()))?;
+                    }
+                }
+                response
+            }
+            None => match db.zrandmember(&self.key, 1)?.pop() {
+                Some((member, _)) => Frame::BulkString(member),
+                None => Frame::NullBulkString,
+            },
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrandmember"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        if let Some(count) = self.count {
+            frame.push_int(count)?;
+            if self.with_scores {
+                frame.push_bulk(Bytes::from("withscores"))?;
+            }
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for ZRandMemberCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ZRANDMEMBER {}", self.key)?;
+        if let Some(count) = self.count {
+            write!(f, " {count}")?;
+            if self.with_scores {
+                write!(f, " WITHSCORES")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db, Error};
+
+    #[tokio::test]
+    async fn test_zrandmember_reply_shape() {
+        let db = Db::default();
+        db.zadd("foo", vec![(1.5, Bytes::from("a"))]).unwrap();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*4\r\n$1\r\na\r\n$3\r\n1.5\r\n$1\r\na\r\n$3\r\n1.5\r\n")
+            .write(b"*1\r\n$1\r\na\r\n")
+            .write(b"$-1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        // The only member is repeated as asked.
+        ZRandMemberCmd::new("foo")
+            .count(-2)
+            .with_scores()
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        ZRandMemberCmd::new("foo")
+            .count(5)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        ZRandMemberCmd::new("missing")
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+
+        let picked = db.zrandmember("foo", -3).unwrap();
+        assert_eq!(picked, vec![(Bytes::from("a"), 1.5); 3]);
+        db.set("str".to_string(), Bytes::from("bar"), None).unwrap();
+        assert!(matches!(db.zrandmember("str", 1), Err(Error::WrongType)));
+    }
+}
@@ -0,0 +1,619 @@
+//! Implementation of the `ZRANGE`, `ZRANGEBYSCORE` and `ZRANGEBYLEX`
+//! commands.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use std::{fmt::Display, ops::Bound};
+use tracing::debug;
+
+pub use crate::sorted_set::LexBound;
+
+/// Get the members of the sorted set stored at `key` whose rank is between
+/// `start` and `stop`, inclusive.
+///
+/// Members are ordered from the lowest to the highest score. Negative ranks
+/// count from the end of the sorted set, `-1` being the last member.
+///
+/// # Options
+///
+/// * WITHSCORES -- Reply with the score of each member after the member.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ZRangeCmd {
+    /// The key of the sorted set.
+    key: String,
+    /// The rank of the first member.
+    start: i64,
+    /// The rank of the last member.
+    stop: i64,
+    /// Whether to reply with the scores.
+    with_scores: bool,
+}
+
+impl ZRangeCmd {
+    /// Creates a new [`ZRangeCmd`] command.
+    pub fn new(key: impl ToString, start: i64, stop: i64, with_scores: bool) -> Self {
+        Self {
+            key: key.to_string(),
+            start,
+            stop,
+            with_scores,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the rank of the first member.
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    /// Get the rank of the last member.
+    pub fn stop(&self) -> i64 {
+        self.stop
+    }
+
+    /// Whether the scores are included in the reply.
+    pub fn with_scores(&self) -> bool {
+        self.with_scores
+    }
+}
+
+impl Command for ZRangeCmd {
+    /// Parse a [`ZRangeCmd`] instance from a received frame.
+    ///
+    /// The `ZRANGE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZRANGE key start stop [WITHSCORES]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let start = parse.next_int_signed()?;
+        let stop = parse.next_int_signed()?;
+
+        let with_scores = match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "WITHSCORES" => true,
+            Ok(_) => return Err(Error::Syntax),
+            Err(Error::EndOfStream) => false,
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self {
+            key,
+            start,
+            stop,
+            with_scores,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let mut response = Frame::array();
+        for (member, score) in db.zrange(&self.key, self.start, self.stop)? {
+            response.push_bulk(member)?;
+            if self.with_scores {
+                response.push_bulk(Bytes::from(score.to_string()))?;
+            }
+        }
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrange"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_int(self.start)?;
+        frame.push_int(self.stop)?;
+        if self.with_scores {
+            frame.push_bulk(Bytes::from("withscores"))?;
+        }
+        Ok(frame)
+    }
+}
+
+/// Get the members of the sorted set stored at `key` whose score is between
+/// `min` and `max`.
+///
+/// Members are ordered from the lowest to the highest score. An unbounded
+/// `min` or `max` stands for an infinite score.
+///
+/// # Options
+///
+/// * WITHSCORES -- Reply with the score of each member after the member.
+/// * LIMIT offset count -- Reply with `count` members after skipping `offset`
+///   ones. A negative `count` replies with every member after `offset`.
+#[derive(Debug, PartialEq)]
+pub struct ZRangeByScoreCmd {
+    /// The key of the sorted set.
+    key: String,
+    /// The lowest score.
+    min: Bound<f64>,
+    /// The highest score.
+    max: Bound<f64>,
+    /// Whether to reply with the scores.
+    with_scores: bool,
+    /// The offset and count of the members to reply with.
+    limit: Option<(i64, i64)>,
+}
+
+// Scores are never `NaN`, as they are rejected while parsing.
+impl Eq for ZRangeByScoreCmd {}
+
+impl ZRangeByScoreCmd {
+    /// Creates a new [`ZRangeByScoreCmd`] command.
+    pub fn new(key: impl ToString, min: Bound<f64>, max: Bound<f64>, with_scores: bool) -> Self {
+        Self {
+            key: key.to_string(),
+            min,
+            max,
+            with_scores,
+            limit: None,
+        }
+    }
+
+    /// Reply with `count` members after skipping `offset` ones.
+    pub fn limit(mut self, offset: i64, count: i64) -> Self {
+        self.limit = Some((offset, count));
+        self
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the lowest score.
+    pub fn min(&self) -> Bound<f64> {
+        self.min
+    }
+
+    /// Get the highest score.
+    pub fn max(&self) -> Bound<f64> {
+        self.max
+    }
+
+    /// Whether the scores are included in the reply.
+    pub fn with_scores(&self) -> bool {
+        self.with_scores
+    }
+}
+
+impl Command for ZRangeByScoreCmd {
+    /// Parse a [`ZRangeByScoreCmd`] instance from a received frame.
+    ///
+    /// The `ZRANGEBYSCORE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZRANGEBYSCORE key min max [WITHSCORES] [LIMIT offset count]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let (min, max) = parse_score_range(parse)?;
+        let mut cmd = Self::new(key, min, max, false);
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "WITHSCORES" => cmd.with_scores = true,
+                Ok(s) if s.to_uppercase() == "LIMIT" => cmd.limit = Some(parse_limit(parse)?),
+                Ok(_) => return Err(Error::Syntax),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(cmd)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let mut response = Frame::array();
+        for (member, score) in db.zrange_by_score(&self.key, self.min, self.max, self.limit)? {
+            response.push_bulk(member)?;
+            if self.with_scores {
+                response.push_bulk(Bytes::from(score.to_string()))?;
+            }
+        }
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrangebyscore"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_bulk(Bytes::from(score_bound_arg(self.min, "-inf")))?;
+        frame.push_bulk(Bytes::from(score_bound_arg(self.max, "+inf")))?;
+        if self.with_scores {
+            frame.push_bulk(Bytes::from("withscores"))?;
+        }
+        if let Some((offset, count)) = self.limit {
+            frame.push_bulk(Bytes::from("limit"))?;
+            frame.push_int(offset)?;
+            frame.push_int(count)?;
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for ZRangeByScoreCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let min = score_bound_arg(self.min, "-inf");
+        let max = score_bound_arg(self.max, "+inf");
+        write!(f, "ZRANGEBYSCORE {} {min} {max}", self.key)?;
+        if self.with_scores {
+            write!(f, " WITHSCORES")?;
+        }
+        if let Some((offset, count)) = self.limit {
+            write!(f, " LIMIT {offset} {count}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Get the members of the sorted set stored at `key` which are between `min`
+/// and `max` lexicographically.
+///
+/// The members are expected to all have the same score, so that they are
+/// ordered lexicographically.
+///
+/// # Options
+///
+/// * LIMIT offset count -- Like for [`ZRangeByScoreCmd`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct ZRangeByLexCmd {
+    /// The key of the sorted set.
+    key: String,
+    /// The lowest member.
+    min: LexBound,
+    /// The highest member.
+    max: LexBound,
+    /// The offset and count of the members to reply with.
+    limit: Option<(i64, i64)>,
+}
+
+impl ZRangeByLexCmd {
+    /// Creates a new [`ZRangeByLexCmd`] command.
+    pub fn new(key: impl ToString, min: LexBound, max: LexBound) -> Self {
+        Self {
+            key: key.to_string(),
+            min,
+            max,
+            limit: None,
+        }
+    }
+
+    /// Reply with `count` members after skipping `offset` ones.
+    pub fn limit(mut self, offset: i64, count: i64) -> Self {
+        self.limit = Some((offset, count));
+        self
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the lowest member.
+    pub fn min(&self) -> &LexBound {
+        &self.min
+    }
+
+    /// Get the highest member.
+    pub fn max(&self) -> &LexBound {
+        &self.max
+    }
+}
+
+impl Command for ZRangeByLexCmd {
+    /// Parse a [`ZRangeByLexCmd`] instance from a received frame.
+    ///
+    /// The `ZRANGEBYLEX` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZRANGEBYLEX key min max [LIMIT offset count]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let min = parse_lex_bound(parse)?;
+        let max = parse_lex_bound(parse)?;
+        let mut cmd = Self::new(key, min, max);
+
+        match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "LIMIT" => cmd.limit = Some(parse_limit(parse)?),
+            Ok(_) => return Err(Error::Syntax),
+            Err(Error::EndOfStream) => return Ok(cmd),
+            Err(err) => return Err(err),
+        }
+        if parse.remaining() > 0 {
+            return Err(Error::Syntax);
+        }
+
+        Ok(cmd)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let mut response = Frame::array();
+        for member in db.zrange_by_lex(&self.key, &self.min, &self.max, self.limit)? {
+            response.push_bulk(member)?;
+        }
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrangebylex"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_bulk(lex_bound_arg(self.min))?;
+        frame.push_bulk(lex_bound_arg(self.max))?;
+        if let Some((offset, count)) = self.limit {
+            frame.push_bulk(Bytes::from("limit"))?;
+            frame.push_int(offset)?;
+            frame.push_int(count)?;
+        }
+        Ok(frame)
+    }
+}
+
+impl Display for ZRangeByLexCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let min = lex_bound_arg(self.min.clone());
+        let max = lex_bound_arg(self.max.clone());
+        write!(f, "ZRANGEBYLEX {} {min:?} {max:?}", self.key)?;
+        if let Some((offset, count)) = self.limit {
+            write!(f, " LIMIT {offset} {count}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the `min` and `max` scores of a range.
+///
+/// Scores are inclusive, unless prefixed with `(`. `-inf` and `+inf` stand
+/// for infinite scores.
+pub(crate) fn parse_score_range(parse: &mut Parse) -> Result<(Bound<f64>, Bound<f64>)> {
+    let min = parse_score_bound(&parse.next_string()?)?;
+    let max = parse_score_bound(&parse.next_string()?)?;
+    Ok((min, max))
+}
+
+/// Parse a single score bound, see [`parse_score_range`].
+fn parse_score_bound(arg: &str) -> Result<Bound<f64>> {
+    let (bound, score): (fn(f64) -> Bound<f64>, _) = match arg.strip_prefix('(') {
+        Some(score) => (Bound::Excluded, score),
+        None => (Bound::Included, arg),
+    };
+    score
+        .parse::<f64>()
+        .ok()
+        .filter(|score| !score.is_nan())
+        .map(bound)
+        .ok_or_else(|| Error::Protocol("min or max is not a float".to_string()))
+}
+
+/// Render a score bound as an argument, an unbounded score being rendered as
+/// `infinite`.
+pub(crate) fn score_bound_arg(bound: Bound<f64>, infinite: &str) -> String {
+    match bound {
+        Bound::Included(score) => score.to_string(),
+        Bound::Excluded(score) => format!("({score}"),
+        Bound::Unbounded => infinite.to_string(),
+    }
+}
+
+/// Parse a lexicographical bound, `-`, `+`, `[member` or `(member`.
+fn parse_lex_bound(parse: &mut Parse) -> Result<LexBound> {
+    let arg = parse.next_bytes()?;
+    match arg.first() {
+        Some(b'-') if arg.len() == 1 => Ok(LexBound::Min),
+        Some(b'+') if arg.len() == 1 => Ok(LexBound::Max),
+        Some(b'[') => Ok(LexBound::Inclusive(arg.slice(1..))),
+        Some(b'(') => Ok(LexBound::Exclusive(arg.slice(1..))),
+        _ => Err(Error::Protocol(
+            "min or max not valid string range item".to_string(),
+        )),
+    }
+}
+
+/// Render a lexicographical bound as an argument.
+fn lex_bound_arg(bound: LexBound) -> Bytes {
+    let (prefix, member) = match bound {
+        LexBound::Min => return Bytes::from("-"),
+        LexBound::Max => return Bytes::from("+"),
+        LexBound::Inclusive(member) => (b'[', member),
+        LexBound::Exclusive(member) => (b'(', member),
+    };
+    let mut arg = Vec::with_capacity(member.len() + 1);
+    arg.push(prefix);
+    arg.extend_from_slice(&member);
+    Bytes::from(arg)
+}
+
+/// Parse the `offset` and `count` of a `LIMIT` option.
+fn parse_limit(parse: &mut Parse) -> Result<(i64, i64)> {
+    let offset = parse.next_int_signed().map_err(|_| Error::Syntax)?;
+    let count = parse.next_int_signed().map_err(|_| Error::Syntax)?;
+    Ok((offset, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommandVariant, Connection, Db};
+
+    #[tokio::test]
+    async fn test_zrange_orders_by_score_then_member() {
+        let db = Db::default();
+        let members = vec![
+            (2.0, Bytes::from("c")),
+            (1.0, Bytes::from("b")),
+            (2.0, Bytes::from("a")),
+        ];
+        assert_eq!(db.zadd("foo", members).unwrap(), 3);
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*3\r\n$1\r\nb\r\n$1\r\na\r\n$1\r\nc\r\n")
+            .write(b"*4\r\n$1\r\na\r\n$1\r\n2\r\n$1\r\nc\r\n$1\r\n2\r\n")
+            .write(b"*0\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        ZRangeCmd::new("foo", 0, -1, false)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        ZRangeCmd::new("foo", -2, 10, true)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        ZRangeCmd::new("missing", 0, -1, false)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_zscore_and_wrong_type() {
+        let db = Db::default();
+
+        db.zadd("foo", vec![(1.5, Bytes::from("a"))]).unwrap();
+        assert_eq!(db.zadd("foo", vec![(3.0, Bytes::from("a"))]).unwrap(), 0);
+        assert_eq!(db.zscore("foo", b"a").unwrap(), Some(3.0));
+        assert_eq!(db.zscore("foo", b"b").unwrap(), None);
+
+        db.set("bar".to_string(), Bytes::from("baz"), None).unwrap();
+        assert!(matches!(db.zscore("bar", b"a"), Err(Error::WrongType)));
+        assert!(matches!(db.zrange("bar", 0, -1), Err(Error::WrongType)));
+    }
+
+    #[tokio::test]
+    async fn test_zrangebyscore_bounds_and_limit() {
+        let db = Db::default();
+        let members = [(1.0, "a"), (2.0, "b"), (2.0, "c"), (3.0, "d")]
+            .map(|(score, member)| (score, Bytes::from(member)));
+        db.zadd("foo", members.to_vec()).unwrap();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b"*2\r\n$1\r\nb\r\n$1\r\nc\r\n")
+            .write(b"*2\r\n$1\r\nc\r\n$1\r\n2\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        // Both bounds are exclusive.
+        ZRangeByScoreCmd::new("foo", Bound::Excluded(1.0), Bound::Excluded(3.0), false)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        // The second page of one member, with its score.
+        ZRangeByScoreCmd::new("foo", Bound::Included(2.0), Bound::Unbounded, true)
+            .limit(1, 1)
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+
+        let page = |offset, count| {
+            db.zrange_by_score(
+                "foo",
+                Bound::Unbounded,
+                Bound::Unbounded,
+                Some((offset, count)),
+            )
+            .unwrap()
+            .len()
+        };
+        assert_eq!(page(1, -1), 3);
+        assert_eq!(page(3, 5), 1);
+        assert_eq!(page(-1, 5), 0);
+
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("ZRANGEBYSCORE")),
+            Frame::BulkString(Bytes::from("foo")),
+            Frame::BulkString(Bytes::from("(1")),
+            Frame::BulkString(Bytes::from("+inf")),
+            Frame::BulkString(Bytes::from("LIMIT")),
+            Frame::BulkString(Bytes::from("0")),
+            Frame::BulkString(Bytes::from("2")),
+        ]);
+        let expected = ZRangeByScoreCmd::new(
+            "foo",
+            Bound::Excluded(1.0),
+            Bound::Included(f64::INFINITY),
+            false,
+        )
+        .limit(0, 2);
+        assert_eq!(
+            CommandVariant::from_frame(frame).unwrap(),
+            CommandVariant::ZRangeByScore(expected)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_zrangebylex_bounds() {
+        let db = Db::default();
+        let members = ["a", "b", "c", "d"].map(|member| (0.0, Bytes::from(member)));
+        db.zadd("foo", members.to_vec()).unwrap();
+
+        let range = |min, max| db.zrange_by_lex("foo", &min, &max, None).unwrap();
+        let inclusive = |member| LexBound::Inclusive(Bytes::from(member));
+        let exclusive = |member| LexBound::Exclusive(Bytes::from(member));
+        assert_eq!(range(LexBound::Min, exclusive("c")), ["a", "b"]);
+        assert_eq!(range(inclusive("b"), LexBound::Max), ["b", "c", "d"]);
+        assert_eq!(range(exclusive("a"), inclusive("c")), ["b", "c"]);
+        assert!(range(LexBound::Max, LexBound::Min).is_empty());
+
+        let frame = Frame::Array(vec![
+            Frame::BulkString(Bytes::from("ZRANGEBYLEX")),
+            Frame::BulkString(Bytes::from("foo")),
+            Frame::BulkString(Bytes::from("a")),
+            Frame::BulkString(Bytes::from("+")),
+        ]);
+        let err = CommandVariant::from_frame(frame).unwrap_err();
+        assert!(
+            matches!(err, Error::Protocol(msg) if msg == "min or max not valid string range item")
+        );
+    }
+}
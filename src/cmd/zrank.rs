@@ -0,0 +1,210 @@
+//! Implementation of the `ZRANK` and `ZREVRANK` commands.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Get the rank of `member` in the sorted set stored at `key`, ranking the
+/// members from the lowest score.
+///
+/// The rank is 0 based. If the key or the member do not exist, a `Null` RESP
+/// type is returned.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ZRankCmd {
+    /// The key of the sorted set.
+    key: String,
+    /// The member to get the rank of.
+    member: Bytes,
+}
+
+/// Get the rank of `member` in the sorted set stored at `key`, ranking the
+/// members from the highest score.
+///
+/// Replies like [`ZRankCmd`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct ZRevRankCmd {
+    /// The key of the sorted set.
+    key: String,
+    /// The member to get the rank of.
+    member: Bytes,
+}
+
+impl ZRankCmd {
+    /// Creates a new [`ZRankCmd`] command.
+    pub fn new(key: impl ToString, member: Bytes) -> Self {
+        Self {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the member.
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+}
+
+impl ZRevRankCmd {
+    /// Creates a new [`ZRevRankCmd`] command.
+    pub fn new(key: impl ToString, member: Bytes) -> Self {
+        Self {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the member.
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+}
+
+impl Command for ZRankCmd {
+    /// Parse a [`ZRankCmd`] instance from a received frame.
+    ///
+    /// The `ZRANK` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZRANK key member
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+        Ok(Self { key, member })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = rank_frame(db.zrank(&self.key, &self.member, false)?);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrank"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_bulk(self.member)?;
+        Ok(frame)
+    }
+}
+
+impl Command for ZRevRankCmd {
+    /// Parse a [`ZRevRankCmd`] instance from a received frame.
+    ///
+    /// The `ZREVRANK` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZREVRANK key member
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+        Ok(Self { key, member })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = rank_frame(db.zrank(&self.key, &self.member, true)?);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrevrank"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_bulk(self.member)?;
+        Ok(frame)
+    }
+}
+
+/// Creates the response holding `rank`, or null if there is none.
+fn rank_frame(rank: Option<usize>) -> Frame {
+    match rank {
+        Some(rank) => Frame::Integer(rank as i64),
+        None => Frame::NullBulkString,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db, Error};
+
+    #[tokio::test]
+    async fn test_zrank_of_missing_member_is_null() {
+        let db = Db::default();
+        let members = [(1.0, "a"), (2.0, "b"), (3.0, "c")]
+            .map(|(score, member)| (score, Bytes::from(member)))
+            .to_vec();
+        db.zadd("foo", members).unwrap();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b":1\r\n")
+            .write(b":0\r\n")
+            .write(b"$-1\r\n")
+            .write(b"$-1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        ZRankCmd::new("foo", Bytes::from("b"))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        ZRevRankCmd::new("foo", Bytes::from("c"))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        ZRankCmd::new("foo", Bytes::from("missing"))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        ZRevRankCmd::new("missing", Bytes::from("a"))
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+
+        db.set("bar".to_string(), Bytes::from("baz"), None).unwrap();
+        assert!(matches!(
+            db.zrank("bar", b"a", false),
+            Err(Error::WrongType)
+        ));
+    }
+}
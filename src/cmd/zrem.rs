@@ -0,0 +1,121 @@
+//! Implementation of the `ZREM` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Error, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Remove `members` from the sorted set stored at `key`.
+///
+/// Replies with the number of members that were removed, not counting the
+/// ones which were not in the sorted set. The key is deleted once empty.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ZRemCmd {
+    /// The key of the sorted set.
+    key: String,
+    /// The members to remove.
+    members: Vec<Bytes>,
+}
+
+impl ZRemCmd {
+    /// Creates a new [`ZRemCmd`] command.
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> Self {
+        Self {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the members.
+    pub fn members(&self) -> &[Bytes] {
+        &self.members
+    }
+}
+
+impl Command for ZRemCmd {
+    /// Parse a [`ZRemCmd`] instance from a received frame.
+    ///
+    /// The `ZREM` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZREM key member [member ...]
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        // At least one member must be provided.
+        let mut members = vec![parse.next_bytes()?];
+        loop {
+            match parse.next_bytes() {
+                Ok(member) => members.push(member),
+                Err(Error::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(Self { key, members })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let removed = db.zrem(&self.key, &self.members)?;
+        let response = Frame::Integer(removed as i64);
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrem"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        for member in self.members {
+            frame.push_bulk(member)?;
+        }
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    #[tokio::test]
+    async fn test_zrem_deletes_emptied_key() {
+        let db = Db::default();
+        let members = vec![(1.0, Bytes::from("a")), (2.0, Bytes::from("b"))];
+        db.zadd("foo", members).unwrap();
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b":1\r\n")
+            .write(b":1\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        let cmd = ZRemCmd::new("foo", vec![Bytes::from("a"), Bytes::from("missing")]);
+        cmd.apply(&db, &mut conn).await.unwrap();
+        assert_eq!(db.zcard("foo").unwrap(), 1);
+
+        ZRemCmd::new("foo", vec![Bytes::from("b"), Bytes::from("b")])
+            .apply(&db, &mut conn)
+            .await
+            .unwrap();
+        assert!(!db.exists("foo"));
+        assert_eq!(db.zrem("foo", &[Bytes::from("b")]).unwrap(), 0);
+    }
+}
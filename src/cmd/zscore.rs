@@ -0,0 +1,82 @@
+//! Implementation of the `ZSCORE` command.
+use super::Command;
+use crate::{parse::Parse, ConnectionStream, Frame, Result};
+use bytes::Bytes;
+use tracing::debug;
+
+/// Get the score of `member` in the sorted set stored at `key`.
+///
+/// If the key or the member do not exist, a `Null` RESP type is returned.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ZScoreCmd {
+    /// The key of the sorted set.
+    key: String,
+    /// The member to get the score of.
+    member: Bytes,
+}
+
+impl ZScoreCmd {
+    /// Creates a new [`ZScoreCmd`] command.
+    pub fn new(key: impl ToString, member: Bytes) -> Self {
+        Self {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    /// Get the key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the member.
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+}
+
+impl Command for ZScoreCmd {
+    /// Parse a [`ZScoreCmd`] instance from a received frame.
+    ///
+    /// The `ZSCORE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZSCORE key member
+    /// ```
+    fn parse_frames(parse: &mut Parse) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+        Ok(Self { key, member })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn apply<S: ConnectionStream>(
+        self,
+        db: &crate::Db,
+        dst: &mut crate::Connection<S>,
+    ) -> Result<()> {
+        let response = match db.zscore(&self.key, &self.member)? {
+            Some(score) => Frame::BulkString(Bytes::from(score.to_string())),
+            None => Frame::NullBulkString,
+        };
+
+        debug!(?response);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn into_frame(self) -> Result<Frame> {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zscore"))?;
+        frame.push_bulk(Bytes::from(self.key))?;
+        frame.push_bulk(self.member)?;
+        Ok(frame)
+    }
+}
@@ -0,0 +1,269 @@
+//! A [`tokio_util::codec`] implementation of the RESP wire format.
+use crate::{frame::Protocol, Error, Frame, Result};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::io::Cursor;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Decodes [`Frame`] values from bytes, and encodes them back.
+///
+/// Used by [`Connection`](crate::Connection) through a
+/// [`Framed`](tokio_util::codec::Framed) stream, which owns the read and
+/// write buffers.
+///
+/// Frames of every protocol version are decoded, while encoding depends on
+/// the negotiated [`Protocol`].
+///
+/// Lines not starting with a RESP type byte are decoded as inline commands,
+/// whose arguments are separated by whitespace, as typed in a `nc` session.
+/// Once one is decoded, frames are encoded as plain text instead, see
+/// [`Frame::to_text`].
+#[derive(Debug, Default)]
+pub(crate) struct RespCodec {
+    /// Protocol the frames are encoded with.
+    pub(crate) protocol: Protocol,
+    /// Maximum number of bytes buffered while waiting for a frame to be
+    /// complete, unbounded if `None`.
+    pub(crate) max_frame_size: Option<usize>,
+    /// Whether an inline command was decoded, replies are then encoded as
+    /// plain text.
+    pub(crate) inline_mode: bool,
+}
+
+impl RespCodec {
+    /// Decodes an inline command, a line of whitespace separated arguments,
+    /// into an array of bulk strings.
+    ///
+    /// Blank lines are skipped.
+    fn decode_inline(&mut self, src: &mut BytesMut) -> Result<Option<Frame>> {
+        loop {
+            let Some(end) = src.iter().position(|byte| *byte == b'\n') else {
+                return self.incomplete(src);
+            };
+            let line = src.split_to(end + 1);
+            let args: Vec<_> = line[..]
+                .split(|byte| byte.is_ascii_whitespace())
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| Frame::BulkString(Bytes::copy_from_slice(arg)))
+                .collect();
+            if !args.is_empty() {
+                self.inline_mode = true;
+                return Ok(Some(Frame::Array(args)));
+            }
+            if src.is_empty() || is_type_byte(src[0]) {
+                return self.decode(src);
+            }
+        }
+    }
+
+    /// Handles a frame which is not fully buffered yet.
+    fn incomplete(&self, src: &BytesMut) -> Result<Option<Frame>> {
+        match self.max_frame_size {
+            // the peer keeps sending a frame too large to be buffered
+            Some(max) if src.len() > max => Err(Error::Protocol(format!(
+                "frame exceeds the maximum size of {max} bytes"
+            ))),
+            // not enough data has been buffered
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Whether `byte` opens a RESP frame.
+fn is_type_byte(byte: u8) -> bool {
+    matches!(byte, b'+' | b'-' | b':' | b'$' | b'*' | b'>' | b'%' | b'_')
+}
+
+impl Decoder for RespCodec {
+    type Item = Frame;
+    type Error = Error;
+
+    /// Tries to decode a frame from the buffered data, if enough data has been
+    /// buffered.
+    ///
+    /// If there isn't enough data, i.e. `Error::IncompleteFrame` occurs,
+    /// `Ok(None)` is returned. Any other errors are returned as is.
+    ///
+    /// A protocol error is returned once more than the maximum frame size is
+    /// buffered without forming a frame, instead of buffering indefinitely.
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Frame>> {
+        if src.first().is_some_and(|byte| !is_type_byte(*byte)) {
+            return self.decode_inline(src);
+        }
+
+        let mut buf = Cursor::new(&src[..]);
+
+        match Frame::check(&mut buf) {
+            Ok(_) => {
+                // get the byte length of the frame
+                let len = buf.position() as usize;
+                // reset the cursor in order to call `parse`
+                buf.set_position(0);
+                let frame = Frame::parse(&mut buf)?;
+                // discard the frame from the buffer
+                src.advance(len);
+
+                Ok(Some(frame))
+            }
+            Err(Error::IncompleteFrame) => self.incomplete(src),
+            // an actual error has occurred
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Decodes the last frames once the remote closed the stream.
+    ///
+    /// For this to be a clean shutdown, there should be no data left in the
+    /// buffer. If there is, the peer closed the stream while sending a frame.
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Frame>> {
+        match self.decode(src)? {
+            Some(frame) => Ok(Some(frame)),
+            None if src.is_empty() => Ok(None),
+            None => Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "connection was closed mid frame",
+            ))),
+        }
+    }
+}
+
+impl Encoder<&Frame> for RespCodec {
+    type Error = Error;
+
+    /// Encodes `frame` for the negotiated protocol, see
+    /// [`Frame::encode_as`], or as a line of plain text in inline mode.
+    fn encode(&mut self, frame: &Frame, dst: &mut BytesMut) -> Result<()> {
+        if self.inline_mode {
+            dst.put_slice(frame.to_text().as_bytes());
+            dst.put_u8(b'\n');
+        } else {
+            frame.encode_as(self.protocol, dst);
+        }
+        Ok(())
+    }
+}
+
+impl Encoder<Frame> for RespCodec {
+    type Error = Error;
+
+    fn encode(&mut self, frame: Frame, dst: &mut BytesMut) -> Result<()> {
+        self.encode(&frame, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_partial_frames() {
+        let mut codec = RespCodec::default();
+        let mut buf = BytesMut::from(&b"*2\r\n$4\r\nping"[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        // Nothing is consumed until a full frame is buffered.
+        assert_eq!(buf.len(), 12);
+
+        buf.extend_from_slice(b"\r\n:1\r\n+OK\r\n");
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Frame::Array(vec![
+                Frame::BulkString(Bytes::from("ping")),
+                Frame::Integer(1),
+            ]))
+        );
+        assert_eq!(&buf[..], b"+OK\r\n");
+
+        buf.truncate(3);
+        assert!(matches!(
+            codec.decode_eof(&mut buf),
+            Err(Error::Io(err)) if err.kind() == std::io::ErrorKind::ConnectionReset
+        ));
+    }
+
+    #[test]
+    fn test_decode_inline_commands() {
+        let mut codec = RespCodec::default();
+        let mut buf = BytesMut::from(&b"SET foo  bar\r\n\r\n  \nGET"[..]);
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Frame::Array(vec![
+                Frame::BulkString(Bytes::from("SET")),
+                Frame::BulkString(Bytes::from("foo")),
+                Frame::BulkString(Bytes::from("bar")),
+            ]))
+        );
+        assert!(codec.inline_mode);
+        // Blank lines are skipped, and incomplete lines are kept buffered.
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(&buf[..], b"GET");
+
+        buf.extend_from_slice(b" foo\n*1\r\n$4\r\nPING\r\n");
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Frame::Array(vec![
+                Frame::BulkString(Bytes::from("GET")),
+                Frame::BulkString(Bytes::from("foo")),
+            ]))
+        );
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Frame::Array(vec![Frame::BulkString(Bytes::from("PING"))]))
+        );
+
+        buf.clear();
+        codec
+            .encode(&Frame::SimpleString("OK".to_string()), &mut buf)
+            .unwrap();
+        assert_eq!(&buf[..], b"OK\n");
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let frame = Frame::Array(vec![
+            Frame::SimpleString("OK".to_string()),
+            Frame::SimpleError("ERR oops".to_string()),
+            Frame::Integer(-42),
+            Frame::BulkString(Bytes::from("foobar")),
+            Frame::NullBulkString,
+            Frame::Array(vec![Frame::Null]),
+        ]);
+
+        let mut codec = RespCodec::default();
+        let mut buf = BytesMut::new();
+        codec.encode(&frame, &mut buf).unwrap();
+        assert_eq!(
+            &buf[..],
+            b"*6\r\n+OK\r\n-ERR oops\r\n:-42\r\n$6\r\nfoobar\r\n$-1\r\n*1\r\n_\r\n"
+        );
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(frame));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_encode_depends_on_protocol() {
+        let map = Frame::Map(vec![(
+            Frame::BulkString(Bytes::from("proto")),
+            Frame::Integer(3),
+        )]);
+        let push = Frame::Push(vec![Frame::BulkString(Bytes::from("message"))]);
+
+        let mut codec = RespCodec::default();
+        let mut buf = BytesMut::new();
+        codec.encode(&map, &mut buf).unwrap();
+        codec.encode(&push, &mut buf).unwrap();
+        assert_eq!(
+            &buf[..],
+            b"*2\r\n$5\r\nproto\r\n:3\r\n*1\r\n$7\r\nmessage\r\n"
+        );
+
+        codec.protocol = Protocol::Resp3;
+        buf.clear();
+        codec.encode(&map, &mut buf).unwrap();
+        codec.encode(&push, &mut buf).unwrap();
+        assert_eq!(
+            &buf[..],
+            b"%1\r\n$5\r\nproto\r\n:3\r\n>1\r\n$7\r\nmessage\r\n"
+        );
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(map));
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(push));
+    }
+}
@@ -1,28 +1,44 @@
 //! A module for handling the connection to a stream, usually a remote peer via a [`TcpStream`].
-use crate::frame::Frame;
+use crate::codec::RespCodec;
+use crate::frame::{Frame, Protocol};
+use crate::server::OutputBufferLimit;
 use crate::{Error, Result};
-use bytes::{Buf, BytesMut};
-use std::io::Cursor;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
-use tracing::{debug, error};
+use bytes::BufMut;
+use futures::{Sink, SinkExt, Stream};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
+use tokio_stream::StreamExt;
+use tokio_util::codec::Framed;
+use tracing::debug;
 
 const DEFAULT_BUFFER_SIZE: usize = 16 * 1024;
 
 /// Send and receive `Frame` values from a remote peer.
 ///
-/// To read frames, the `Connection` uses an internal buffer, which is filled
-/// up until there are enough bytes to create a full frame. Once this happens,
-/// the `Connection` creates the frame and returns it to the caller.
+/// The byte level encoding and decoding is done by [`RespCodec`], while
+/// [`Framed`] owns the read and write buffers. To read frames, the read buffer
+/// is filled up until there are enough bytes to create a full frame.
 ///
 /// When sending frames, the frame is first encoded into the write buffer.
-/// The contents of the write buffer are then written to the socket.
+/// The contents of the write buffer are then written to the stream.
+///
+/// Frames can also be read through the [`Stream`] implementation, which
+/// yields `None` once the peer cleanly closes the stream.
+///
+/// With an [`OutputBufferLimit`], writes never wait for the stream. Whatever
+/// it does not accept right away stays in the write buffer, and is written
+/// while waiting for the next frame to read.
 #[derive(Debug)]
 pub(crate) struct Connection<S> {
-    /// Stream wrapped with a [`BufWriter`] for buffering writes.
-    stream: BufWriter<S>,
-    /// Buffer used for reading frames.
-    // TODO: Look into `tokio_util::codec` and implementing my own codec for decoding and enco
-    buffer: BytesMut,
+    /// Stream wrapped with the RESP codec.
+    framed: Framed<S, RespCodec>,
+    /// Limits on the bytes left in the write buffer.
+    output_buffer_limit: OutputBufferLimit,
+    /// Since when the write buffer has been over the soft limit, if it still
+    /// is.
+    over_soft_limit_since: Option<Instant>,
 }
 
 /// A trait for types that can be used as a connection stream.
@@ -37,11 +53,61 @@ impl<S: ConnectionStream> Connection<S> {
     /// The connection is internally buffered, with a default buffer size of 16KB.
     pub fn new(stream: S) -> Self {
         Self {
-            stream: BufWriter::new(stream),
-            buffer: BytesMut::with_capacity(DEFAULT_BUFFER_SIZE),
+            framed: Framed::with_capacity(stream, RespCodec::default(), DEFAULT_BUFFER_SIZE),
+            output_buffer_limit: OutputBufferLimit::default(),
+            over_soft_limit_since: None,
         }
     }
 
+    /// Get the protocol replies are encoded with.
+    pub fn protocol(&self) -> Protocol {
+        self.framed.codec().protocol
+    }
+
+    /// Set the protocol replies are encoded with, as negotiated by `HELLO`.
+    ///
+    /// Frames only available in RESP3, such as maps and pushes, are encoded
+    /// as arrays under RESP2.
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.framed.codec_mut().protocol = protocol;
+    }
+
+    /// Whether the peer sent an inline command, in which case replies are
+    /// written as plain text rather than RESP.
+    pub fn inline_mode(&self) -> bool {
+        self.framed.codec().inline_mode
+    }
+
+    /// Limit the number of bytes buffered while waiting for a frame to be
+    /// complete.
+    ///
+    /// Once more than `max` bytes are buffered without forming a frame,
+    /// reading fails with a protocol error, and the connection should be
+    /// closed.
+    pub fn set_max_frame_size(&mut self, max: usize) {
+        self.framed.codec_mut().max_frame_size = Some(max);
+    }
+
+    /// Limit the number of bytes left in the write buffer, as the stream does
+    /// not accept them.
+    ///
+    /// Writes no longer wait for the stream, and fail with
+    /// [`Error::OutputBufferLimit`] once the limit is exceeded, in which case
+    /// the connection should be closed.
+    pub fn set_output_buffer_limit(&mut self, limit: OutputBufferLimit) {
+        self.output_buffer_limit = limit;
+        if !limit.is_unlimited() {
+            // Encoding frames must not wait for the buffer to be written out.
+            self.framed.set_backpressure_boundary(usize::MAX);
+        }
+    }
+
+    /// Get the number of bytes in the write buffer, not yet written to the
+    /// stream.
+    pub fn buffered(&self) -> usize {
+        self.framed.write_buffer().len()
+    }
+
     /// Read a single `Frame` from the connection.
     ///
     /// The function waits until it has retrieved enough data to parse a frame.
@@ -55,141 +121,122 @@ impl<S: ConnectionStream> Connection<S> {
     /// `None`. Otherwise, an error is returned.
     #[tracing::instrument(skip_all)]
     pub async fn read_frame(&mut self) -> Result<Option<Frame>> {
-        loop {
-            // Attempt to parse a frame from the buffered data. If enough data
-            // has been buffered, the frame is returned.
-            if let Some(frame) = self.parse_frame()? {
-                debug!(?frame, "frame received");
-                return Ok(Some(frame));
-            }
+        let frame = self.next().await.transpose()?;
+        match &frame {
+            Some(frame) => debug!(?frame, "frame received"),
+            None => debug!("no more frames to read from the buffer"),
+        }
+        Ok(frame)
+    }
 
-            // There is not enough buffered data to read a frame. Attempt to
-            // read more data from the socket.
-            //
-            // On success, the number of bytes is returned. `0` indicates "end
-            // of stream".
-            if 0 == self.stream.read_buf(&mut self.buffer).await? {
-                // The remote closed the connection. For this to be a clean
-                // shutdown, there should be no data in the read buffer. If
-                // there is, this means that the peer closed the socket while
-                // sending a frame.
-                if self.buffer.is_empty() {
-                    debug!("no more frames to read from the buffer");
-                    return Ok(None);
-                } else {
-                    error!("connection was closed mid frame");
-                    return Err(Error::Io(std::io::Error::new(
-                        std::io::ErrorKind::ConnectionReset,
-                        "connection was closed mid frame",
-                    )));
-                }
-            }
+    /// Write a frame to the connection's underlying stream.
+    ///
+    /// The `Frame` value is encoded into the write buffer, which is then
+    /// flushed to the stream.
+    pub async fn write_frame(&mut self, frame: &Frame) -> Result<()> {
+        self.framed.feed(frame).await?;
+        self.flush().await
+    }
+
+    /// Encode a frame into the write buffer, without flushing it.
+    ///
+    /// This allows coalescing several frames into a single write to the
+    /// stream. The buffer is only written out early if it grows too large.
+    /// [`Connection::flush`] must be called once the frames are buffered.
+    pub async fn write_frame_buffered(&mut self, frame: &Frame) -> Result<()> {
+        self.framed.feed(frame).await
+    }
+
+    /// Write several frames to the connection's underlying stream, with a
+    /// single flush once all of them are encoded.
+    ///
+    /// The write buffer is only written out early if it grows too large.
+    pub async fn write_frames(&mut self, frames: &[Frame]) -> Result<()> {
+        for frame in frames {
+            self.framed.feed(frame).await?;
         }
+        self.flush().await
     }
 
-    /// Tries to parse a frame from the buffered data, if enough data has been buffered.
+    /// Encode the header of an array of `len` frames into the write buffer,
+    /// without flushing it.
     ///
-    /// If there isn't enough data, i.e. `Error::IncompleteFrame` occurs,
-    /// `Ok(None)` is returned.
+    /// The `len` elements of the array must be written next, with any of the
+    /// write methods. In inline mode there is no header, the elements are
+    /// written one per line.
+    pub fn write_array_len(&mut self, len: usize) {
+        if self.inline_mode() {
+            return;
+        }
+        self.framed
+            .write_buffer_mut()
+            .put_slice(format!("*{len}\r\n").as_bytes());
+    }
+
+    /// Flush the frames buffered by [`Connection::write_frame_buffered`] to
+    /// the stream.
     ///
-    /// Any other errors are returned as is.
-    fn parse_frame(&mut self) -> Result<Option<Frame>> {
-        let mut buf = Cursor::new(&self.buffer[..]);
-
-        match Frame::check(&mut buf) {
-            Ok(_) => {
-                // get the byte length of the frame
-                let len = buf.position() as usize;
-                // reset the cursor in order to call `parse`
-                buf.set_position(0);
-                let frame = Frame::parse(&mut buf)?;
-                // discard the frame from the buffer
-                self.buffer.advance(len);
-
-                Ok(Some(frame))
-            }
-            // not enough data has been buffered
-            Err(Error::IncompleteFrame) => Ok(None),
-            // an actual error has occurred
-            Err(e) => Err(e),
+    /// With an [`OutputBufferLimit`], only what the stream accepts right away
+    /// is written, and the limit is checked against the rest.
+    pub async fn flush(&mut self) -> Result<()> {
+        if self.output_buffer_limit.is_unlimited() {
+            return SinkExt::<&Frame>::flush(&mut self.framed).await;
         }
+        std::future::poll_fn(|cx| match self.poll_flush_buffer(cx) {
+            Poll::Pending => Poll::Ready(Ok(())),
+            ready => ready,
+        })
+        .await?;
+        self.check_output_buffer_limit()
     }
 
-    /// Write a frame to the connection's underlying stream.
+    /// Wait until the whole write buffer is written out to the stream.
     ///
-    /// The `Frame` value is written to the socket using the various `write_*`
-    /// functions provided by `AsyncWrite`. Calling these functions directly on
-    /// a [`TcpStream`], for example, is **not** advised,
-    /// as this will result in a large number of syscalls.
-    /// However, it is fine to call these functions on a *buffered*
-    /// write stream. The data will be written to the buffer. Once the buffer is
-    /// full, it is flushed to the underlying socket.
-    pub async fn write_frame(&mut self, frame: &Frame) -> Result<()> {
-        self.write_value(frame).await?;
-        self.stream.flush().await.map_err(Error::from)
+    /// With an [`OutputBufferLimit`], what [`Connection::flush`] leaves in the
+    /// write buffer is written while waiting for the next frame. This writes
+    /// it out when no frame is read anymore.
+    pub async fn drain(&mut self) -> Result<()> {
+        std::future::poll_fn(|cx| self.poll_flush_buffer(cx)).await?;
+        self.check_output_buffer_limit()
     }
 
-    #[tracing::instrument(skip(self))]
-    #[async_recursion::async_recursion]
-    async fn write_value(&mut self, frame: &Frame) -> std::io::Result<()> {
-        debug!(?frame);
-        match frame {
-            Frame::SimpleString(val) => {
-                self.stream.write_u8(b'+').await?;
-                self.stream.write_all(val.as_bytes()).await?;
-                self.stream.write_all(b"\r\n").await?;
-            }
-            Frame::SimpleError(val) => {
-                self.stream.write_u8(b'-').await?;
-                self.stream.write_all(val.as_bytes()).await?;
-                self.stream.write_all(b"\r\n").await?;
-            }
-            Frame::Integer(val) => {
-                self.stream.write_u8(b':').await?;
-                self.write_decimal(*val).await?;
-            }
-            Frame::BulkString(val) => {
-                let len = val.len();
+    /// Write out the write buffer to the stream.
+    fn poll_flush_buffer(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Sink::<&Frame>::poll_flush(Pin::new(&mut self.framed), cx)
+    }
 
-                self.stream.write_u8(b'$').await?;
-                self.write_decimal(len as i64).await?;
-                self.stream.write_all(val).await?;
-                self.stream.write_all(b"\r\n").await?;
-            }
-            Frame::NullBulkString => {
-                self.stream.write_all(b"$-1\r\n").await?;
-            }
-            Frame::NullArray => {
-                self.stream.write_all(b"*-1\r\n").await?;
-            }
-            Frame::Array(frames) => {
-                self.stream.write_u8(b'*').await?;
-                self.write_decimal(frames.len() as i64).await?;
-                for frame in frames {
-                    self.write_value(frame).await?;
+    /// Fail if the write buffer is over the hard limit, or has been over the
+    /// soft limit for too long.
+    fn check_output_buffer_limit(&mut self) -> Result<()> {
+        let buffered = self.buffered();
+        let limit = self.output_buffer_limit;
+        if limit.hard.is_some_and(|hard| buffered > hard) {
+            return Err(Error::OutputBufferLimit(buffered));
+        }
+        match limit.soft {
+            Some((soft, duration)) if buffered > soft => {
+                let since = *self.over_soft_limit_since.get_or_insert_with(Instant::now);
+                if since.elapsed() >= duration {
+                    return Err(Error::OutputBufferLimit(buffered));
                 }
             }
-            Frame::Null => {
-                self.stream.write_all(b"_\r\n").await?;
-            }
-        };
-
+            _ => self.over_soft_limit_since = None,
+        }
         Ok(())
     }
+}
 
-    async fn write_decimal(&mut self, val: i64) -> std::io::Result<()> {
-        use std::io::Write;
-
-        let mut buf = [0u8; 12];
-
-        let mut buf = Cursor::new(&mut buf[..]);
-        write!(&mut buf, "{}", val)?;
-
-        let pos = buf.position() as usize;
-        self.stream.write_all(&buf.get_ref()[..pos]).await?;
-        self.stream.write_all(b"\r\n").await?;
+impl<S: ConnectionStream> Stream for Connection<S> {
+    type Item = Result<Frame>;
 
-        Ok(())
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // Frames left in the write buffer are written out while waiting.
+        if self.buffered() > 0 {
+            if let Poll::Ready(Err(err)) = self.poll_flush_buffer(cx) {
+                return Poll::Ready(Some(Err(err)));
+            }
+        }
+        Pin::new(&mut self.framed).poll_next(cx)
     }
 }
 
@@ -197,6 +244,44 @@ impl<S: ConnectionStream> Connection<S> {
 mod tests {
     use super::*;
     use bytes::Bytes;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::AsyncReadExt;
+
+    /// A write-only stream recording the data of every write to it.
+    #[derive(Debug, Default)]
+    struct RecordingStream {
+        writes: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl AsyncRead for RecordingStream {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl AsyncWrite for RecordingStream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.writes.lock().unwrap().push(buf.to_vec());
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
 
     #[tokio::test]
     #[tracing_test::traced_test]
@@ -258,4 +343,86 @@ mod tests {
             conn.write_frame(frame).await.unwrap();
         }
     }
+
+    #[tokio::test]
+    async fn test_stream_frames() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"+OK\r\n:1\r\n")
+            .read(b"$4\r\nping\r\n")
+            .build();
+        let mut conn = Connection::new(stream);
+
+        assert_eq!(
+            conn.next().await.unwrap().unwrap(),
+            Frame::SimpleString("OK".to_string())
+        );
+        let rest: Vec<Frame> = conn.map(|frame| frame.unwrap()).collect().await;
+        assert_eq!(
+            rest,
+            vec![Frame::Integer(1), Frame::BulkString(Bytes::from("ping"))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_buffered_writes_are_flushed_together() {
+        let stream = RecordingStream::default();
+        let writes = stream.writes.clone();
+        let mut conn = Connection::new(stream);
+
+        conn.write_frame_buffered(&Frame::Integer(1)).await.unwrap();
+        conn.write_frame_buffered(&Frame::Integer(2)).await.unwrap();
+        assert!(writes.lock().unwrap().is_empty());
+
+        conn.flush().await.unwrap();
+        assert_eq!(*writes.lock().unwrap(), vec![b":1\r\n:2\r\n".to_vec()]);
+
+        // Batches are flushed once, after the last frame.
+        writes.lock().unwrap().clear();
+        let frames = [Frame::Integer(1), Frame::Null, Frame::Integer(3)];
+        conn.write_frames(&frames).await.unwrap();
+        assert_eq!(*writes.lock().unwrap(), vec![b":1\r\n_\r\n:3\r\n".to_vec()]);
+
+        // Unbuffered writes are flushed one by one.
+        writes.lock().unwrap().clear();
+        conn.write_frame(&Frame::Integer(1)).await.unwrap();
+        conn.write_frame(&Frame::Integer(2)).await.unwrap();
+        assert_eq!(writes.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_output_buffer_over_soft_limit_for_too_long() {
+        // The peer never reads, so the stream accepts no more than 4 bytes.
+        let (mut peer, stream) = tokio::io::duplex(4);
+        let mut conn = Connection::new(stream);
+        conn.set_output_buffer_limit(OutputBufferLimit {
+            hard: None,
+            soft: Some((8, Duration::from_secs(10))),
+        });
+        let frame = Frame::BulkString(Bytes::from("foo"));
+
+        // 5 of the 9 bytes written so far are left buffered.
+        conn.write_frame(&frame).await.unwrap();
+        assert_eq!(conn.buffered(), 5);
+        conn.write_frame(&frame).await.unwrap();
+        assert_eq!(conn.buffered(), 14);
+        tokio::time::advance(Duration::from_secs(5)).await;
+        conn.write_frame(&Frame::Integer(1)).await.unwrap();
+
+        // Once the peer catches up, the buffer is drained while reading.
+        let mut received = vec![0; 22];
+        let (read, _) = tokio::join!(peer.read_exact(&mut received), async {
+            let _ = tokio::time::timeout(Duration::from_millis(1), conn.read_frame()).await;
+        });
+        read.unwrap();
+        assert_eq!(received, b"$3\r\nfoo\r\n$3\r\nfoo\r\n:1\r\n");
+        assert_eq!(conn.buffered(), 0);
+
+        // Staying over the soft limit for too long closes the connection.
+        for _ in 0..2 {
+            conn.write_frame(&frame).await.unwrap();
+        }
+        tokio::time::advance(Duration::from_secs(10)).await;
+        let err = conn.write_frame(&frame).await.unwrap_err();
+        assert!(matches!(err, Error::OutputBufferLimit(23)));
+    }
 }
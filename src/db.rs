@@ -1,23 +1,323 @@
 use std::{
-    collections::{BTreeSet, HashMap},
-    sync::{Arc, RwLock},
-    time::Duration,
+    collections::{BTreeSet, HashMap, VecDeque},
+    ops::Bound,
+    sync::{
+        atomic::{AtomicU64, AtomicU8, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, SystemTime},
 };
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use tokio::{
     sync::{broadcast, Notify},
     time::Instant,
 };
 use tracing::debug;
 
+use crate::{
+    cmd::{BitFieldOp, ExpireCondition, ScoreDirection, ScoredMember, SetCondition},
+    dump, glob,
+    hash::Hash,
+    hyperloglog::HyperLogLog,
+    server::EvictionPolicy,
+    sorted_set::{LexBound, SortedSet},
+    Error, Result,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A value stored in the database.
+pub(crate) enum Value {
+    /// A binary safe string.
+    String(Bytes),
+    /// A sequence of strings, in insertion order.
+    List(VecDeque<Bytes>),
+    /// A collection of unique members.
+    ///
+    /// Members are kept sorted, so replies listing them are deterministic.
+    Set(BTreeSet<Bytes>),
+    /// A collection of unique members, ordered by their score.
+    ZSet(SortedSet),
+    /// A map of fields to values, each field with an optional time to live.
+    Hash(Hash),
+}
+
+impl Value {
+    /// Borrow the value as a `T`.
+    ///
+    /// [`Error::WrongType`] is returned if the value holds another type.
+    fn typed<T: Typed>(&self) -> Result<&T> {
+        T::cast(self).ok_or(Error::WrongType)
+    }
+
+    /// Mutably borrow the value as a `T`.
+    ///
+    /// [`Error::WrongType`] is returned if the value holds another type.
+    fn typed_mut<T: Typed>(&mut self) -> Result<&mut T> {
+        T::cast_mut(self).ok_or(Error::WrongType)
+    }
+
+    /// Approximate memory used by the value, as its byte length.
+    fn size(&self) -> usize {
+        match self {
+            Value::String(data) => data.len(),
+            Value::List(items) => items.iter().map(Bytes::len).sum(),
+            Value::Set(members) => members.iter().map(Bytes::len).sum(),
+            Value::ZSet(zset) => zset.size(),
+            Value::Hash(hash) => hash.size(),
+        }
+    }
+
+    /// Name of the representation Redis would pick for the value, with its
+    /// default configuration.
+    ///
+    /// Values are always stored the same way here, so this only serves to
+    /// report which representation a value would get.
+    fn encoding(&self) -> &'static str {
+        match self {
+            Value::String(data) if is_int(data) => "int",
+            Value::String(data) if data.len() <= MAX_EMBSTR_LEN => "embstr",
+            Value::String(_) => "raw",
+            Value::List(_) => "quicklist",
+            Value::Set(members)
+                if members.len() <= MAX_INTSET_ENTRIES && members.iter().all(|m| is_int(m)) =>
+            {
+                "intset"
+            }
+            Value::Set(members) if is_small(members.len(), members.iter()) => "listpack",
+            Value::Set(_) => "hashtable",
+            Value::ZSet(zset) if is_small(zset.len(), zset.iter().map(|(m, _)| m)) => "listpack",
+            Value::ZSet(_) => "skiplist",
+            Value::Hash(hash)
+                if is_small(hash.len(), hash.iter_all().flat_map(|(f, v)| [f, v])) =>
+            {
+                "listpack"
+            }
+            Value::Hash(_) => "hashtable",
+        }
+    }
+}
+
+/// Longest string Redis embeds in its object header.
+const MAX_EMBSTR_LEN: usize = 44;
+
+/// Most integer members of a set Redis stores as an intset.
+const MAX_INTSET_ENTRIES: usize = 512;
+
+/// Most entries of a set, sorted set or hash Redis stores as a listpack.
+const MAX_SMALL_ENTRIES: usize = 128;
+
+/// Longest entry of a set, sorted set or hash Redis stores as a listpack.
+const MAX_SMALL_VALUE: usize = 64;
+
+/// Size of a quicklist node, in bytes, as Redis splits lists by default.
+const QUICKLIST_NODE_SIZE: usize = 8 * 1024;
+
+/// Returns `true` if `data` is the canonical representation of an `i64`, so
+/// Redis would store it as an integer.
+fn is_int(data: &[u8]) -> bool {
+    std::str::from_utf8(data)
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok().filter(|n| n.to_string() == s))
+        .is_some()
+}
+
+/// Returns `true` if a collection of `len` entries, made of `items`, is small
+/// enough for Redis to store it as a listpack.
+fn is_small<'a>(len: usize, mut items: impl Iterator<Item = &'a Bytes>) -> bool {
+    len <= MAX_SMALL_ENTRIES && items.all(|item| item.len() <= MAX_SMALL_VALUE)
+}
+
+/// A string value along with its remaining TTL, as read by [`Db::get_entry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EntrySnapshot {
+    /// The value.
+    pub(crate) value: Bytes,
+    /// The remaining TTL, or `None` if the key does not expire.
+    pub(crate) ttl: Option<Duration>,
+}
+
+/// Internals of a value, as reported by `DEBUG OBJECT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ObjectDebug {
+    /// Name of the representation Redis would pick for the value.
+    pub(crate) encoding: &'static str,
+    /// Length of the value serialized by `DUMP`.
+    pub(crate) serialized_length: usize,
+    /// Time since the value was last accessed.
+    pub(crate) idle: Duration,
+    /// Number of quicklist nodes Redis would split a list into, or `None` for
+    /// the other types.
+    pub(crate) list_nodes: Option<usize>,
+}
+
+/// A type held by one of the [`Value`] variants.
+trait Typed {
+    /// Borrow `value` as `Self`, if it holds this type.
+    fn cast(value: &Value) -> Option<&Self>;
+    /// Mutably borrow `value` as `Self`, if it holds this type.
+    fn cast_mut(value: &mut Value) -> Option<&mut Self>;
+}
+
+/// Implements [`Typed`] for the type held by a [`Value`] variant.
+macro_rules! impl_typed {
+    ($variant:ident, $ty:ty) => {
+        impl Typed for $ty {
+            fn cast(value: &Value) -> Option<&Self> {
+                match value {
+                    Value::$variant(inner) => Some(inner),
+                    _ => None,
+                }
+            }
+
+            fn cast_mut(value: &mut Value) -> Option<&mut Self> {
+                match value {
+                    Value::$variant(inner) => Some(inner),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl_typed!(String, Bytes);
+impl_typed!(List, VecDeque<Bytes>);
+impl_typed!(Set, BTreeSet<Bytes>);
+impl_typed!(ZSet, SortedSet);
+impl_typed!(Hash, Hash);
+
+/// An end of a list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ListEnd {
+    /// The head of the list.
+    Left,
+    /// The tail of the list.
+    Right,
+}
+
+/// An operation combining sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SetOp {
+    /// The members of all the sets.
+    Inter,
+    /// The members of any of the sets.
+    Union,
+    /// The members of the first set which are not in any of the following
+    /// sets.
+    Diff,
+}
+
+impl SetOp {
+    /// Combine `sets`.
+    fn combine(self, sets: &[&BTreeSet<Bytes>]) -> BTreeSet<Bytes> {
+        let Some((first, rest)) = sets.split_first() else {
+            return BTreeSet::new();
+        };
+        match self {
+            SetOp::Inter => first
+                .iter()
+                .filter(|member| rest.iter().all(|set| set.contains(*member)))
+                .cloned()
+                .collect(),
+            SetOp::Union => sets.iter().flat_map(|set| set.iter()).cloned().collect(),
+            SetOp::Diff => first
+                .iter()
+                .filter(|member| !rest.iter().any(|set| set.contains(*member)))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// The keyspace event published when storing the result.
+    fn store_event(self) -> &'static str {
+        match self {
+            SetOp::Inter => "sinterstore",
+            SetOp::Union => "sunionstore",
+            SetOp::Diff => "sdiffstore",
+        }
+    }
+}
+
+/// A change to the time to live of a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Expiry {
+    /// Expire the key after the duration.
+    After(Duration),
+    /// Remove the time to live of the key.
+    Persist,
+}
+
+/// Why [`Db::incr_by`] failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IncrError {
+    /// The value stored at the key is not the string of an integer.
+    NotAnInteger,
+    /// The result does not fit in an `i64`.
+    Overflow,
+    /// The key holds a value other than a string.
+    WrongType,
+    /// The new value does not fit in the memory budget.
+    OutOfMemory,
+}
+
+/// Fixed number of bytes accounted for every entry by
+/// [`Db::memory_usage`], on top of the key and value lengths.
+///
+/// This is the inline size of the key and entry structures, not counting the
+/// allocator overhead or the expiration tracking.
+const ENTRY_OVERHEAD: usize = std::mem::size_of::<(String, Entry)>();
+
 #[derive(Debug)]
 /// A single database entry.
 struct Entry {
-    /// Stored data
-    data: Bytes,
+    /// Stored value
+    value: Value,
     /// Instant at which the data expires and should be removed from the database
     expires_at: Option<Instant>,
+    /// Value of [`DbState::clock`] when the entry was last accessed.
+    ///
+    /// Atomic, so it can be updated while only holding the read lock.
+    last_access: AtomicU64,
+    /// Logarithmic counter of the accesses to the entry, decaying over time,
+    /// as of `last_access`. Used by the `allkeys-lfu` eviction policy.
+    ///
+    /// Atomic, so it can be updated while only holding the read lock.
+    freq: AtomicU8,
+}
+
+/// Access frequency of new entries, so they are not evicted right away.
+const LFU_INIT_VAL: u8 = 5;
+
+/// The higher, the more accesses are needed to increment the access frequency.
+const LFU_LOG_FACTOR: f64 = 10.0;
+
+/// The access frequency is decremented once per period of inactivity, in
+/// microseconds of the access clock.
+const LFU_DECAY_TIME: u64 = 60 * 1_000_000;
+
+impl Entry {
+    /// Marks the entry as accessed at `access`, a time of the access clock.
+    fn touch(&self, access: u64) {
+        let freq = self.frequency(access);
+        // The counter is logarithmic: the higher it is, the less likely an
+        // access increments it.
+        let p = 1.0 / (f64::from(freq.saturating_sub(LFU_INIT_VAL)) * LFU_LOG_FACTOR + 1.0);
+        let freq = if freq < u8::MAX && fastrand::f64() < p {
+            freq + 1
+        } else {
+            freq
+        };
+        self.freq.store(freq, Ordering::Relaxed);
+        self.last_access.store(access, Ordering::Relaxed);
+    }
+
+    /// The access frequency at `now`, a time of the access clock, decayed
+    /// by the time elapsed since the last access.
+    fn frequency(&self, now: u64) -> u8 {
+        let idle = now.saturating_sub(self.last_access.load(Ordering::Relaxed));
+        let decay = u8::try_from(idle / LFU_DECAY_TIME).unwrap_or(u8::MAX);
+        self.freq.load(Ordering::Relaxed).saturating_sub(decay)
+    }
 }
 
 #[derive(Debug)]
@@ -40,6 +340,53 @@ struct DbState {
     /// This happens when all `Db` values drop.
     /// Also, setting this to `true` signals the background task to exit.
     shutdown: bool,
+    /// Approximate memory used by the entries, as the sum of their key and
+    /// value byte lengths.
+    used_memory: usize,
+    /// Clock ticked on every entry access, used to find the least recently
+    /// used entry, and how long an entry has been idle.
+    ///
+    /// It counts the microseconds elapsed since `epoch`, but strictly
+    /// increases on every tick, so accesses within the same microsecond are
+    /// still ordered.
+    clock: AtomicU64,
+    /// Instant the clock counts from.
+    epoch: Instant,
+    /// Notified when values are pushed to the list stored at the key, waking
+    /// up the clients blocked on it.
+    list_waiters: HashMap<String, Arc<Notify>>,
+}
+
+#[derive(Debug, Clone)]
+/// Configuration of a [`Db`] instance.
+pub(crate) struct DbConfig {
+    /// Publish keyspace notifications when keys are modified or expire.
+    ///
+    /// For every event, the event name is published to the
+    /// `__keyspace@0__:<key>` channel and the key name is published to the
+    /// `__keyevent@0__:<event>` channel.
+    pub(crate) notify_keyspace_events: bool,
+    /// Memory budget in bytes. `None` means unbounded.
+    pub(crate) maxmemory: Option<usize>,
+    /// How to make room for new values once `maxmemory` is reached.
+    pub(crate) maxmemory_policy: EvictionPolicy,
+    /// Number of messages buffered per pub/sub channel. Subscribers lagging
+    /// further behind miss messages.
+    pub(crate) pubsub_channel_capacity: usize,
+    /// Maximum number of channels a single connection may subscribe to.
+    /// `None` means unbounded.
+    pub(crate) max_subscriptions: Option<usize>,
+    /// Purge expired keys from a background task. When disabled, expired
+    /// keys are only removed once accessed.
+    pub(crate) active_expiration: bool,
+}
+
+impl Default for DbConfig {
+    /// The configuration of a database served with the default
+    /// [`ServerConfig`](crate::server::ServerConfig).
+    fn default() -> Self {
+        crate::server::ServerConfig::default().db_config()
+    }
 }
 
 #[derive(Debug)]
@@ -56,6 +403,32 @@ struct DbSharedState {
     /// The background task waits on this to be notified,
     /// then checks for expired values or the shutdown signal.
     background_task: Notify,
+    /// Configuration the database was created with.
+    config: DbConfig,
+    /// Callback invoked with the key name whenever the purge task removes an
+    /// expired key.
+    on_expire: RwLock<Option<ExpireCallback>>,
+    /// Callback invoked with the time taken by every purge of the expired
+    /// keys.
+    on_expire_cycle: RwLock<Option<ExpireCycleCallback>>,
+}
+
+/// A callback registered through [`Db::on_expire`].
+struct ExpireCallback(Box<dyn Fn(&str) + Send + Sync>);
+
+impl std::fmt::Debug for ExpireCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ExpireCallback").finish_non_exhaustive()
+    }
+}
+
+/// A callback registered through [`Db::on_expire_cycle`].
+struct ExpireCycleCallback(Box<dyn Fn(Duration) + Send + Sync>);
+
+impl std::fmt::Debug for ExpireCycleCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ExpireCycleCallback").finish_non_exhaustive()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -92,8 +465,10 @@ impl DbDropGuard {
     /// Create a new `DbDropGuard`, wrapping a new `Db` instance.
     ///
     /// When this is dropped, the `Db`'s purge task will be shutdown.
-    pub(crate) fn new() -> Self {
-        DbDropGuard { db: Db::new() }
+    pub(crate) fn new(config: DbConfig) -> Self {
+        DbDropGuard {
+            db: Db::new(config),
+        }
     }
 
     /// Get the shared database.
@@ -112,46 +487,303 @@ impl Drop for DbDropGuard {
 }
 
 impl Db {
-    /// Create a new empty `Db` instance.
+    /// Create a new empty `Db` instance with the given `config`.
     ///
-    /// Allocates the shared state and spawns a background task
-    /// to manage key expiration.
-    pub(crate) fn new() -> Self {
+    /// Allocates the shared state and, unless active expiration is disabled,
+    /// spawns a background task to manage key expiration.
+    pub(crate) fn new(config: DbConfig) -> Self {
         let shared = Arc::new(DbSharedState {
             state: RwLock::new(DbState {
                 entries: HashMap::new(),
                 pub_sub: HashMap::new(),
                 expirations: BTreeSet::new(),
                 shutdown: false,
+                used_memory: 0,
+                clock: AtomicU64::new(0),
+                epoch: Instant::now(),
+                list_waiters: HashMap::new(),
             }),
             background_task: Notify::new(),
+            config,
+            on_expire: RwLock::new(None),
+            on_expire_cycle: RwLock::new(None),
         });
 
         // Start the background task.
-        tokio::spawn(purge_expired_tasks(shared.clone()));
+        if shared.config.active_expiration {
+            tokio::spawn(purge_expired_tasks(shared.clone()));
+        }
 
         Self { shared }
     }
 
+    /// Maximum number of channels a single connection may subscribe to, if
+    /// bounded.
+    pub(crate) fn max_subscriptions(&self) -> Option<usize> {
+        self.shared.config.max_subscriptions
+    }
+
+    /// Registers a callback invoked with the key name each time the
+    /// background task purges an expired key.
+    ///
+    /// Only one callback is kept, registering a new one replaces the previous.
+    /// The callback runs on the purge task, outside of the database lock.
+    pub(crate) fn on_expire(&self, f: impl Fn(&str) + Send + Sync + 'static) {
+        *self.shared.on_expire.write().unwrap() = Some(ExpireCallback(Box::new(f)));
+    }
+
+    /// Registers a callback invoked with the time taken by each purge of the
+    /// expired keys by the background task.
+    ///
+    /// Only one callback is kept, registering a new one replaces the previous.
+    pub(crate) fn on_expire_cycle(&self, f: impl Fn(Duration) + Send + Sync + 'static) {
+        *self.shared.on_expire_cycle.write().unwrap() = Some(ExpireCycleCallback(Box::new(f)));
+    }
+
     /// Get the value associated with a key.
     ///
     /// Returns `None` if there is no value associated with the key.
     /// This may be because no value was assigned to this key,
     /// or because a previously assigned value has expired.
-    pub(crate) fn get(&self, key: &str) -> Option<Bytes> {
+    ///
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// string.
+    pub(crate) fn get(&self, key: &str) -> Result<Option<Bytes>> {
         // Acquire a read lock, get the entry and clone the value.
         // Because we use `Bytes` to store the data,
         // cloning is a shallow clone, the data itself is not copied.
         let state = self.shared.state.read().unwrap();
-        state.entries.get(key).map(|e| e.data.clone())
+        let Some(entry) = state.entries.get(key) else {
+            return Ok(None);
+        };
+        if !entry.is_expired(Instant::now()) {
+            entry.touch(state.tick());
+            return entry.value.typed().cloned().map(Some);
+        }
+
+        // The key expired but the background task did not purge it yet.
+        // Expiration is observed on access, so remove the entry right away,
+        // which requires upgrading to a write lock.
+        drop(state);
+        let mut state = self.shared.state.write().unwrap();
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+        Ok(None)
+    }
+
+    /// Get the value associated with a key, along with its remaining TTL.
+    ///
+    /// Both are read under the same lock, so the TTL is the one of the
+    /// returned value even if the key is being modified concurrently. Returns
+    /// `None` if the key does not exist or has expired. [`Error::WrongType`]
+    /// is returned if the key holds a value other than a string.
+    ///
+    /// Methods which also modify the key, like [`Db::expire`], or which read
+    /// values of any type, like [`Db::dump`], read the entry under their own
+    /// lock instead.
+    pub(crate) fn get_entry(&self, key: &str) -> Result<Option<EntrySnapshot>> {
+        let state = self.shared.state.read().unwrap();
+        let now = Instant::now();
+        let Some(entry) = state.live_entry(key, now) else {
+            return Ok(None);
+        };
+        entry.touch(state.tick());
+        let value = entry.value.typed::<Bytes>()?.clone();
+        Ok(Some(EntrySnapshot {
+            value,
+            ttl: entry.ttl(now),
+        }))
+    }
+
+    /// Get the value associated with a key, and update its TTL.
+    ///
+    /// Without an `expiry`, the value is read like [`Db::get_entry`] does. The
+    /// TTL is only updated if the key holds a string. [`Error::WrongType`] is
+    /// returned if the key holds a value other than a string.
+    pub(crate) fn get_ex(&self, key: &str, expiry: Option<Expiry>) -> Result<Option<Bytes>> {
+        let Some(expiry) = expiry else {
+            return Ok(self.get_entry(key)?.map(|entry| entry.value));
+        };
+
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        let now = Instant::now();
+        if state.remove_if_expired(key, now) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        let tick = state.tick();
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(None);
+        };
+        let value = entry.value.typed::<Bytes>()?.clone();
+        entry.touch(tick);
+
+        let expires_at = match expiry {
+            Expiry::After(duration) => Some(now + duration),
+            Expiry::Persist => None,
+        };
+        let previous = std::mem::replace(&mut entry.expires_at, expires_at);
+        if previous == expires_at {
+            return Ok(Some(value));
+        }
+
+        if let Some(when) = previous {
+            state.expirations.remove(&(when, key.to_string()));
+        }
+
+        // The key may become the key that expires **next**, like in `set`.
+        let notify = expires_at.is_some_and(|when| {
+            state
+                .next_expiration()
+                .map(|expiration| expiration > when)
+                .unwrap_or(true)
+        });
+        if let Some(when) = expires_at {
+            state.expirations.insert((when, key.to_string()));
+        }
+
+        let event = match expiry {
+            Expiry::After(_) => "expire",
+            Expiry::Persist => "persist",
+        };
+        self.shared.notify_keyspace_event(&state, event, key);
+
+        // Release the lock before notifying the background task.
+        drop(state);
+
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+
+        Ok(Some(value))
+    }
+
+    /// Set the TTL of `key` to `ttl`, if `condition` is met.
+    ///
+    /// Returns `true` if the TTL was set, or `false` if the key does not exist
+    /// or the condition is not met. A key without a TTL counts as having an
+    /// infinite one, for [`ExpireCondition::Gt`] and [`ExpireCondition::Lt`].
+    pub(crate) fn expire(
+        &self,
+        key: &str,
+        ttl: Duration,
+        condition: Option<ExpireCondition>,
+    ) -> Result<bool> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        let now = Instant::now();
+        if state.remove_if_expired(key, now) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        let tick = state.tick();
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(false);
+        };
+        let when = now + ttl;
+        let previous = entry.expires_at;
+        if !expire_condition_met(condition, previous, when) {
+            return Ok(false);
+        }
+        entry.touch(tick);
+        entry.expires_at = Some(when);
+
+        if let Some(previous) = previous {
+            state.expirations.remove(&(previous, key.to_string()));
+        }
+
+        // The key may become the key that expires **next**, like in `set`.
+        let notify = state
+            .next_expiration()
+            .map(|expiration| expiration > when)
+            .unwrap_or(true);
+        state.expirations.insert((when, key.to_string()));
+
+        self.shared.notify_keyspace_event(&state, "expire", key);
+
+        // Release the lock before notifying the background task.
+        drop(state);
+
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+
+        Ok(true)
     }
 
     /// Set the value associated with a key along with an optional TTL.
     ///
     /// if a value is already associated with the key, it will be replaced.
-    pub(crate) fn set(&self, key: String, value: Bytes, expire: Option<Duration>) {
+    ///
+    /// If a memory budget is configured, other keys are evicted according to
+    /// the eviction policy to make room for the value. [`Error::OutOfMemory`]
+    /// is returned if not enough room can be made.
+    pub(crate) fn set(&self, key: String, value: Bytes, expire: Option<Duration>) -> Result<()> {
+        self.set_with_options(key, value, expire, None, false)
+            .map(drop)
+    }
+
+    /// Like [`Db::set`], but also returns the value previously associated
+    /// with the key, if any, reading and replacing it under the same lock.
+    ///
+    /// [`Error::WrongType`] is returned, and nothing is stored, if the key
+    /// holds a value other than a string.
+    pub(crate) fn set_returning_previous(
+        &self,
+        key: String,
+        value: Bytes,
+        expire: Option<Duration>,
+    ) -> Result<Option<Bytes>> {
+        self.set_with_options(key, value, expire, None, true)
+            .map(|(_, previous)| previous)
+    }
+
+    /// The core logic of [`Db::set`] and [`Db::set_returning_previous`].
+    ///
+    /// The value is only stored if the existence of the key satisfies
+    /// `condition`, if any. The previous value is only returned if `previous`
+    /// is set, even if the value was not stored, along with whether the value
+    /// was stored.
+    pub(crate) fn set_with_options(
+        &self,
+        key: String,
+        value: Bytes,
+        expire: Option<Duration>,
+        condition: Option<SetCondition>,
+        previous: bool,
+    ) -> Result<(bool, Option<Bytes>)> {
         let mut state = self.shared.state.write().unwrap();
 
+        let previous = if previous || condition.is_some() {
+            // An expired key counts as missing, even if it was not purged yet.
+            if state.remove_if_expired(&key, Instant::now()) {
+                self.shared.notify_keyspace_event(&state, "expired", &key);
+            }
+            let exists = state.entries.get(&key);
+            let skip = match condition {
+                Some(SetCondition::Nx) => exists.is_some(),
+                Some(SetCondition::Xx) => exists.is_none(),
+                None => false,
+            };
+            let previous = match exists {
+                Some(entry) if previous => Some(entry.value.typed::<Bytes>()?.clone()),
+                _ => None,
+            };
+            if skip {
+                return Ok((false, previous));
+            }
+            previous
+        } else {
+            None
+        };
+
+        self.shared
+            .make_room(&mut state, &key, key.len() + value.len())?;
+
         // If this `set` becomes the key that expires **next**, the background
         // task needs to be notified so it can update its state.
         //
@@ -172,30 +804,28 @@ impl Db {
             when
         });
 
-        // Insert the value into the database, and get the previous value if it existed.
-        let prev = state.entries.insert(
-            key.clone(),
-            Entry {
-                data: value,
-                expires_at,
-            },
-        );
+        // Remove the value previously associated with the key, if any. This also
+        // removes the associated entry in the `expirations` set, to avoid
+        // leaking data.
+        state.remove_entry(&key);
 
-        // If there was a value previously associated with the key,
-        // **and** it had an expiration date, the associated entry in the `expirations`
-        // set must be removed to avoid leaking data.
-        if let Some(prev) = prev {
-            if let Some(when) = prev.expires_at {
-                state.expirations.remove(&(when, key.clone()));
-            }
-        }
+        let entry = Entry {
+            value: Value::String(value),
+            expires_at,
+            last_access: AtomicU64::new(state.tick()),
+            freq: AtomicU8::new(LFU_INIT_VAL),
+        };
+        state.used_memory += key.len() + entry.value.size();
+        state.entries.insert(key.clone(), entry);
 
         // Track the expiration. If we insert before the remove that will cause
         // on the remote case when the current `(when, key)` is equal to the previous.
         if let Some(when) = expires_at {
-            state.expirations.insert((when, key));
+            state.expirations.insert((when, key.clone()));
         }
 
+        self.shared.notify_keyspace_event(&state, "set", &key);
+
         // Release the lock before notifying the background task.
         // This helps reduce contention by avoiding the background task waking up
         // only to be unable to acquire the lock due to this function still holding it,
@@ -207,94 +837,2322 @@ impl Db {
         if notify {
             self.shared.background_task.notify_one();
         }
-    }
 
-    /// Publishes a message to a given channel.
-    ///
-    /// # Returns
-    /// The number of subscribers listening on the channel at this exact times.
-    /// This should only be used as a hint, because a subscriber could drop
-    /// the channel before the message is actually delivered.
-    pub(crate) fn publish(&self, channel: &str, message: Bytes) -> usize {
-        let state = self.shared.state.read().unwrap();
-        state
-            .pub_sub
-            .get(channel)
-            // On a successful message send on the broadcast channel,
-            // the number of subscribers is returned. An error indicates there are
-            // no receivers, in which case, `0` should be returned.
-            .map(|tx| tx.send(message).unwrap_or(0))
-            // If there is no entry for the channel key, there are no subscribers.
-            // So return `0`.
-            .unwrap_or(0)
+        Ok((true, previous))
     }
 
-    /// Signals the purge background task to shutdown.
+    /// Copy the value stored at `source` to `destination`, along with its
+    /// remaining time to live.
     ///
-    /// This is called by the [`DbDropGuard`]'s [`Drop`] implementation.
-    fn shutdown_purge_task(&self) {
-        // The background task must be signaled to shutdown. This is done by
-        // setting `DbState::shutdown` to `true` and signalling the task.
-        let mut state = self.shared.state.write().unwrap();
-        state.shutdown = true;
-        drop(state);
-        self.shared.background_task.notify_one();
-    }
-}
+    /// Nothing is copied if `destination` already exists, unless `replace` is
+    /// set. Returns `true` if the value was copied. [`Error::SameObject`] is
+    /// returned if both keys are the same.
+    pub(crate) fn copy(&self, source: &str, destination: &str, replace: bool) -> Result<bool> {
+        if source == destination {
+            return Err(Error::SameObject);
+        }
 
-impl DbSharedState {
-    /// Returns `true` if the database is shutting down
-    ///
-    /// The `shutdown` flag is set when all `Db` values have dropped, indicating
-    /// that the shared state can no longer be accessed.
-    fn is_shutdown(&self) -> bool {
-        self.state.read().unwrap().shutdown
-    }
+        let mut state = self.shared.state.write().unwrap();
 
-    /// Purge all expired keys and return the `Instant` at which the **next** key will expire.
-    ///
-    /// The background task will sleep until this instant.
-    #[tracing::instrument(skip_all)]
-    fn purge_expired_keys(&self) -> Option<Instant> {
-        debug!("starting purge of expired keys");
-        let mut state = self.state.write().unwrap();
+        // An expired key counts as missing, even if it was not purged yet.
+        let now = Instant::now();
+        for key in [source, destination] {
+            if state.remove_if_expired(key, now) {
+                self.shared.notify_keyspace_event(&state, "expired", key);
+            }
+        }
 
-        if state.shutdown {
-            // The database is shutting down. All handles to the shared state
-            // have been dropped. The background task should exit.
-            return None;
+        let Some(entry) = state.entries.get(source) else {
+            return Ok(false);
+        };
+        if !replace && state.entries.contains_key(destination) {
+            return Ok(false);
         }
+        let value = entry.value.clone();
+        let expires_at = entry.expires_at;
 
-        // This is needed to make the borrow checker happy. In short, `write()`
-        // returns a `RwLockWriteGuard` and not a `&mut DbState`. The borrow checker is
-        // not able to see "through" the lock guard and determine that it is
-        // safe to access both `state.expirations` and `state.entries` mutably,
-        // so we get a "real" mutable reference to `DbState` outside of the loop.
-        let state = &mut *state;
+        self.shared
+            .make_room(&mut state, destination, destination.len() + value.size())?;
 
-        // Find all keys scheduled to expire **before** now.
-        let now = Instant::now();
+        // The copy may become the key that expires **next**, like in `set`.
+        let notify = expires_at.is_some_and(|when| {
+            state
+                .next_expiration()
+                .map(|expiration| expiration > when)
+                .unwrap_or(true)
+        });
 
-        while let Some(&(when, ref key)) = state.expirations.iter().next() {
-            if when > now {
-                debug!("next expiration is in the future, done purging");
-                // Done purging, `when` is the instant at which the next key expires.
-                // The works task will wait until this instant.
-                return Some(when);
-            }
+        state.remove_entry(destination);
+        let entry = Entry {
+            value,
+            expires_at,
+            last_access: AtomicU64::new(state.tick()),
+            freq: AtomicU8::new(LFU_INIT_VAL),
+        };
+        state.used_memory += destination.len() + entry.value.size();
+        state.entries.insert(destination.to_string(), entry);
+        if let Some(when) = expires_at {
+            state.expirations.insert((when, destination.to_string()));
+        }
 
-            // The key has expired, remove it.
-            debug!("removing expired {key:?}");
-            state.entries.remove(key);
-            state.expirations.remove(&(when, key.clone()));
+        self.shared
+            .notify_keyspace_event(&state, "copy_to", destination);
+
+        // Release the lock before notifying the background task.
+        drop(state);
+
+        if notify {
+            self.shared.background_task.notify_one();
         }
 
-        debug!("no keys to purge");
-        None
+        Ok(true)
     }
-}
+
+    /// Serialize the value associated with a key, see the [`dump`] module.
+    ///
+    /// Returns `None` if the key does not exist.
+    ///
+    /// [`dump`]: crate::dump
+    pub(crate) fn dump(&self, key: &str) -> Option<Bytes> {
+        let state = self.shared.state.read().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        state
+            .live_entry(key, Instant::now())
+            .map(|entry| dump::serialize(&entry.value))
+    }
+
+    /// Serialize every key, with its value and expiration, into a snapshot
+    /// which [`Db::load_snapshot`] recreates the keyspace from.
+    ///
+    /// Keys that already expired, but were not purged yet, are skipped.
+    pub(crate) fn snapshot(&self) -> Bytes {
+        let state = self.shared.state.read().unwrap();
+        let (now, wall_now) = (Instant::now(), SystemTime::now());
+        let entries: Vec<_> = state
+            .entries
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired(now))
+            .map(|(key, entry)| {
+                // Expirations are anchored to the wall clock, as the instants
+                // are only meaningful to this process.
+                let expires_at = entry
+                    .expires_at
+                    .map(|when| wall_now + when.saturating_duration_since(now));
+                (key.as_str(), &entry.value, expires_at)
+            })
+            .collect();
+        dump::serialize_snapshot(entries.into_iter())
+    }
+
+    /// Returns every string key, with its value and remaining time to live.
+    ///
+    /// The entries are cloned under the read lock, which is released before
+    /// returning, so callers may process them for as long as they like.
+    /// Keys that already expired, but were not purged yet, are skipped, as
+    /// are keys holding other types. Unlike [`Db::snapshot`], this is not a
+    /// snapshot of the whole keyspace.
+    pub(crate) fn iter_string_snapshot(&self) -> Vec<(String, Bytes, Option<Duration>)> {
+        let state = self.shared.state.read().unwrap();
+        let now = Instant::now();
+        state
+            .entries
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired(now))
+            .filter_map(|(key, entry)| match &entry.value {
+                Value::String(value) => Some((key.clone(), value.clone(), entry.ttl(now))),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Replace every key with the ones of a snapshot produced by
+    /// [`Db::snapshot`].
+    ///
+    /// Keys which expired since the snapshot was taken are not loaded. If the
+    /// snapshot is invalid, [`Error::BadDumpPayload`] is returned and the
+    /// keyspace is left untouched.
+    pub(crate) fn load_snapshot(&self, snapshot: &[u8]) -> Result<()> {
+        let entries = dump::deserialize_snapshot(snapshot)?;
+
+        let mut state = self.shared.state.write().unwrap();
+        state.entries.clear();
+        state.expirations.clear();
+        state.used_memory = 0;
+
+        let (now, wall_now) = (Instant::now(), SystemTime::now());
+        for (key, value, expires_at) in entries {
+            let expires_at = match expires_at {
+                Some(when) => match when.duration_since(wall_now) {
+                    Ok(ttl) if !ttl.is_zero() => Some(now + ttl),
+                    _ => continue,
+                },
+                None => None,
+            };
+            let entry = Entry {
+                value,
+                expires_at,
+                last_access: AtomicU64::new(state.tick()),
+                freq: AtomicU8::new(LFU_INIT_VAL),
+            };
+            state.used_memory += key.len() + entry.value.size();
+            if let Some(when) = expires_at {
+                state.expirations.insert((when, key.clone()));
+            }
+            state.entries.insert(key, entry);
+        }
+
+        // Release the lock before notifying the background task.
+        drop(state);
+
+        // The key expiring next likely changed.
+        self.shared.background_task.notify_one();
+
+        Ok(())
+    }
+
+    /// Recreate a key from a payload produced by [`Db::dump`], expiring after
+    /// `ttl` if set.
+    ///
+    /// [`Error::BusyKey`] is returned if the key already exists, unless
+    /// `replace` is set.
+    pub(crate) fn restore(
+        &self,
+        key: String,
+        payload: &[u8],
+        ttl: Option<Duration>,
+        replace: bool,
+    ) -> Result<()> {
+        let value = dump::deserialize(payload)?;
+
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(&key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", &key);
+        }
+        if !replace && state.entries.contains_key(&key) {
+            return Err(Error::BusyKey);
+        }
+
+        self.shared
+            .make_room(&mut state, &key, key.len() + value.size())?;
+
+        // The restored key may become the key that expires **next**, like in
+        // `set`.
+        let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+        let notify = expires_at.is_some_and(|when| {
+            state
+                .next_expiration()
+                .map(|expiration| expiration > when)
+                .unwrap_or(true)
+        });
+
+        state.remove_entry(&key);
+        let entry = Entry {
+            value,
+            expires_at,
+            last_access: AtomicU64::new(state.tick()),
+            freq: AtomicU8::new(LFU_INIT_VAL),
+        };
+        state.used_memory += key.len() + entry.value.size();
+        if let Some(when) = expires_at {
+            state.expirations.insert((when, key.clone()));
+        }
+        state.entries.insert(key.clone(), entry);
+
+        self.shared.notify_keyspace_event(&state, "restore", &key);
+
+        // Release the lock before notifying the background task.
+        drop(state);
+
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+
+        Ok(())
+    }
+
+    /// Remove the value associated with a key.
+    ///
+    /// Returns `true` if the key existed, and was thus removed.
+    pub(crate) fn del(&self, key: &str) -> bool {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+            return false;
+        }
+
+        if state.remove_entry(key).is_none() {
+            return false;
+        }
+
+        self.shared.notify_keyspace_event(&state, "del", key);
+        true
+    }
+
+    /// Returns `true` if a value is associated with the key.
+    pub(crate) fn exists(&self, key: &str) -> bool {
+        let state = self.shared.state.read().unwrap();
+        state.live_entry(key, Instant::now()).is_some()
+    }
+
+    /// Remove every expired key right away, instead of waiting for the
+    /// background task, returning the number of keys removed.
+    pub(crate) fn sweep_expired(&self) -> usize {
+        let (_, removed) = self.shared.purge_expired_keys();
+        // The next expiration may have changed.
+        self.shared.background_task.notify_one();
+        removed
+    }
+
+    /// Returns the number of keys, counting the expired keys which were not
+    /// purged yet.
+    pub(crate) fn len(&self) -> usize {
+        let state = self.shared.state.read().unwrap();
+        state.entries.len()
+    }
+
+    /// Returns the approximate number of bytes used by the key and value
+    /// stored at `key`, or `None` if the key does not exist.
+    ///
+    /// This is the length of the key and value, plus [`ENTRY_OVERHEAD`].
+    pub(crate) fn memory_usage(&self, key: &str) -> Option<usize> {
+        let state = self.shared.state.read().unwrap();
+        let entry = state.live_entry(key, Instant::now())?;
+        Some(ENTRY_OVERHEAD + key.len() + entry.value.size())
+    }
+
+    /// Returns the internals of the value stored at `key`, or `None` if the
+    /// key does not exist.
+    ///
+    /// This does not count as an access.
+    pub(crate) fn debug_object(&self, key: &str) -> Option<ObjectDebug> {
+        let state = self.shared.state.read().unwrap();
+        let entry = state.live_entry(key, Instant::now())?;
+        let last_access = entry.last_access.load(Ordering::Relaxed);
+        let list_nodes = match &entry.value {
+            Value::List(_) => Some(entry.value.size().div_ceil(QUICKLIST_NODE_SIZE).max(1)),
+            _ => None,
+        };
+        Some(ObjectDebug {
+            encoding: entry.value.encoding(),
+            serialized_length: dump::serialize(&entry.value).len(),
+            idle: Duration::from_micros(state.now().saturating_sub(last_access)),
+            list_nodes,
+        })
+    }
+
+    /// Returns how long the value stored at `key` was not accessed for, or
+    /// `None` if the key does not exist.
+    ///
+    /// This does not count as an access.
+    pub(crate) fn idle_time(&self, key: &str) -> Option<Duration> {
+        let state = self.shared.state.read().unwrap();
+        let entry = state.live_entry(key, Instant::now())?;
+        let last_access = entry.last_access.load(Ordering::Relaxed);
+        Some(Duration::from_micros(
+            state.now().saturating_sub(last_access),
+        ))
+    }
+
+    /// Returns the access frequency of the value stored at `key`, or `None`
+    /// if the key does not exist.
+    ///
+    /// This does not count as an access.
+    pub(crate) fn frequency(&self, key: &str) -> Option<u8> {
+        let state = self.shared.state.read().unwrap();
+        let entry = state.live_entry(key, Instant::now())?;
+        Some(entry.frequency(state.now()))
+    }
+
+    /// Returns the names of all keys holding a value.
+    ///
+    /// Keys that already expired, but were not purged yet, are skipped.
+    pub(crate) fn keys(&self) -> Vec<String> {
+        let state = self.shared.state.read().unwrap();
+        let now = Instant::now();
+        state
+            .entries
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired(now))
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Increment the float value stored at `key` by `delta`.
+    ///
+    /// If the key does not exist, it is set to `0` before performing the
+    /// operation. Any TTL associated with the key is kept.
+    ///
+    /// # Returns
+    ///
+    /// The value of the key after the increment. [`Error::NotAFloat`] is
+    /// returned if the stored value cannot be interpreted as a float, and
+    /// [`Error::NanOrInfinity`] if the result is not a finite number.
+    pub(crate) fn incr_by_float(&self, key: &str, delta: f64) -> Result<f64> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        let current = match state.entries.get(key) {
+            Some(entry) => std::str::from_utf8(entry.value.typed::<Bytes>()?)
+                .ok()
+                .and_then(|s| s.parse::<f64>().ok())
+                .filter(|value| !value.is_nan())
+                .ok_or(Error::NotAFloat)?,
+            None => 0.0,
+        };
+
+        let value = current + delta;
+        if !value.is_finite() {
+            return Err(Error::NanOrInfinity);
+        }
+
+        // `f64`'s `Display` renders the shortest representation that
+        // round-trips, without trailing zeros or an exponent, like Redis does.
+        let data = Value::String(Bytes::from(value.to_string()));
+        self.shared
+            .make_room(&mut state, key, key.len() + data.size())?;
+        let access = state.tick();
+        let state = &mut *state;
+        match state.entries.get_mut(key) {
+            Some(entry) => {
+                state.used_memory = state.used_memory - entry.value.size() + data.size();
+                entry.value = data;
+                entry.touch(access);
+            }
+            None => {
+                state.used_memory += key.len() + data.size();
+                state.entries.insert(
+                    key.to_string(),
+                    Entry {
+                        value: data,
+                        expires_at: None,
+                        last_access: AtomicU64::new(access),
+                        freq: AtomicU8::new(LFU_INIT_VAL),
+                    },
+                );
+            }
+        }
+
+        self.shared.notify_keyspace_event(state, "incrbyfloat", key);
+
+        Ok(value)
+    }
+
+    /// Increment the integer value stored at `key` by `delta`, which may be
+    /// negative.
+    ///
+    /// If the key does not exist, it is set to `0` before performing the
+    /// operation. Any TTL associated with the key is kept. Returns the value
+    /// of the key after the increment, the value is left untouched on error.
+    pub(crate) fn incr_by(&self, key: &str, delta: i64) -> std::result::Result<i64, IncrError> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        let current = match state.entries.get(key) {
+            Some(entry) => {
+                let data: &Bytes = entry.value.typed().map_err(|_| IncrError::WrongType)?;
+                std::str::from_utf8(data)
+                    .ok()
+                    // Like Redis, only the canonical representation is accepted.
+                    .filter(|s| !s.starts_with('+'))
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .ok_or(IncrError::NotAnInteger)?
+            }
+            None => 0,
+        };
+        let value = current.checked_add(delta).ok_or(IncrError::Overflow)?;
+
+        let new = Bytes::from(value.to_string());
+        let new_len = new.len();
+        self.shared
+            .make_room(&mut state, key, key.len() + new_len)
+            .map_err(|_| IncrError::OutOfMemory)?;
+        let entry = state.get_or_insert(key, || Value::String(Bytes::new()));
+        let Value::String(data) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+        let old_len = std::mem::replace(data, new).len();
+        state.used_memory = state.used_memory - old_len + new_len;
+
+        // Redis publishes the same event for decrements.
+        self.shared.notify_keyspace_event(&state, "incrby", key);
+
+        Ok(value)
+    }
+
+    /// Set the bit at `offset` in the string stored at `key` to `bit`.
+    ///
+    /// Bits are numbered from the most significant bit of the first byte. The
+    /// string is grown with zero bytes if `offset` is past its end, and a new
+    /// string is created if the key does not exist. Returns the previous value
+    /// of the bit. [`Error::WrongType`] is returned if the key holds a value
+    /// other than a string.
+    pub(crate) fn setbit(&self, key: &str, offset: usize, bit: bool) -> Result<bool> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        let (byte, mask) = (offset / 8, 0x80 >> (offset % 8));
+        let len = match state.entries.get(key) {
+            Some(entry) => entry.value.typed::<Bytes>()?.len(),
+            None => 0,
+        };
+        let grown = (byte + 1).saturating_sub(len);
+        self.shared.make_room_to_grow(&mut state, key, grown)?;
+
+        let entry = state.get_or_insert(key, || Value::String(Bytes::new()));
+        let Value::String(data) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+        let mut bytes = take_mut(data);
+        if grown > 0 {
+            bytes.resize(byte + 1, 0);
+        }
+        let prev = bytes[byte] & mask != 0;
+        if bit {
+            bytes[byte] |= mask;
+        } else {
+            bytes[byte] &= !mask;
+        }
+        *data = bytes.freeze();
+        state.used_memory += grown;
+
+        self.shared.notify_keyspace_event(&state, "setbit", key);
+
+        Ok(prev)
+    }
+
+    /// Apply the `BITFIELD` operations `ops` to the string stored at `key`,
+    /// returning the result of every operation.
+    ///
+    /// The string is grown with zero bytes as needed, and created if the key
+    /// does not exist, unless all operations are reads. [`Error::WrongType`]
+    /// is returned if the key holds a value other than a string.
+    pub(crate) fn bitfield(&self, key: &str, ops: &[BitFieldOp]) -> Result<Vec<i64>> {
+        // Reads neither create the key nor need the write lock.
+        if ops.iter().all(BitFieldOp::is_get) {
+            return self
+                .with_typed(key, |data: &Bytes| {
+                    ops.iter().map(|op| op.read(data)).collect()
+                })
+                .map(|values| values.unwrap_or_else(|| vec![0; ops.len()]));
+        }
+
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        let len = match state.entries.get(key) {
+            Some(entry) => entry.value.typed::<Bytes>()?.len(),
+            None => 0,
+        };
+        let written = ops.iter().map(BitFieldOp::written_len).max();
+        let grown = written.unwrap_or(0).saturating_sub(len);
+        self.shared.make_room_to_grow(&mut state, key, grown)?;
+
+        let entry = state.get_or_insert(key, || Value::String(Bytes::new()));
+        let Value::String(data) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+        let mut bytes = take_mut(data);
+        let values = ops.iter().map(|op| op.apply(&mut bytes)).collect();
+        *data = bytes.freeze();
+        state.used_memory += grown;
+
+        self.shared.notify_keyspace_event(&state, "setbit", key);
+
+        Ok(values)
+    }
+
+    /// Add `elements` to the HyperLogLog stored at `key`.
+    ///
+    /// A new HyperLogLog is created if the key does not exist. Returns `true`
+    /// if the HyperLogLog was created or its estimate may have changed.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// string, and [`Error::InvalidHyperLogLog`] if the string is not a
+    /// HyperLogLog.
+    pub(crate) fn pfadd(&self, key: &str, elements: &[Bytes]) -> Result<bool> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        let created = match state.entries.get(key) {
+            Some(entry) => {
+                HyperLogLog::from_bytes(entry.value.typed::<Bytes>()?)?;
+                false
+            }
+            None => true,
+        };
+        // The serialized length is fixed, so only creating the key takes more
+        // memory.
+        let empty = HyperLogLog::new().to_bytes();
+        let growth = if created { empty.len() } else { 0 };
+        self.shared.make_room_to_grow(&mut state, key, growth)?;
+
+        let entry = state.get_or_insert(key, || Value::String(empty));
+        let Value::String(data) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+        let mut bytes = take_mut(data);
+        let mut updated = created;
+        for element in elements {
+            updated |= HyperLogLog::add_to_bytes(&mut bytes, element)?;
+        }
+        *data = bytes.freeze();
+        if !updated {
+            return Ok(false);
+        }
+
+        self.shared.notify_keyspace_event(&state, "pfadd", key);
+
+        Ok(true)
+    }
+
+    /// Estimate the number of distinct elements added to the union of the
+    /// HyperLogLogs stored at `keys`.
+    ///
+    /// Missing keys count as empty HyperLogLogs. [`Error::WrongType`] is
+    /// returned if a key holds a value other than a string, and
+    /// [`Error::InvalidHyperLogLog`] if the string is not a HyperLogLog.
+    pub(crate) fn pfcount(&self, keys: &[String]) -> Result<u64> {
+        let state = self.shared.state.read().unwrap();
+        let now = Instant::now();
+        let mut union = HyperLogLog::new();
+        for key in keys {
+            let Some(entry) = state.live_entry(key, now) else {
+                continue;
+            };
+            entry.touch(state.tick());
+            let data: &Bytes = entry.value.typed()?;
+            union.merge(&HyperLogLog::from_bytes(data)?);
+        }
+        Ok(union.count())
+    }
+
+    /// Get the bit at `offset` in the string stored at `key`.
+    ///
+    /// Bits past the end of the string, or of a missing key, are `false`.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// string.
+    pub(crate) fn getbit(&self, key: &str, offset: usize) -> Result<bool> {
+        self.with_typed(key, |data: &Bytes| {
+            data.get(offset / 8)
+                .is_some_and(|byte| byte & (0x80 >> (offset % 8)) != 0)
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Count the bits set in the string stored at `key`, restricted to the
+    /// inclusive byte `range` if given.
+    ///
+    /// Negative offsets of the range count from the end of the string. A
+    /// missing key counts as an empty string. [`Error::WrongType`] is returned
+    /// if the key holds a value other than a string.
+    pub(crate) fn bitcount(&self, key: &str, range: Option<(i64, i64)>) -> Result<u64> {
+        self.with_typed(key, |data: &Bytes| {
+            let bytes = match range {
+                Some((start, stop)) => match rank_range(start, stop, data.len()) {
+                    Some(range) => &data[range],
+                    None => &[],
+                },
+                None => &data[..],
+            };
+            bytes.iter().map(|byte| u64::from(byte.count_ones())).sum()
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Add `members` to the set stored at `key`.
+    ///
+    /// A new set is created if the key does not exist. Returns the number of
+    /// members that were added, not counting the ones already in the set.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// set.
+    pub(crate) fn sadd(&self, key: &str, members: Vec<Bytes>) -> Result<usize> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        // Check the type before making room, so a failing command evicts
+        // nothing. Members already in the set take no more memory.
+        let set = state
+            .entries
+            .get(key)
+            .map(|entry| entry.value.typed::<BTreeSet<Bytes>>())
+            .transpose()?;
+        let mut new_members = BTreeSet::new();
+        let growth = members
+            .iter()
+            .filter(|member| !set.is_some_and(|set| set.contains(*member)))
+            .filter(|member| new_members.insert(*member))
+            .map(|member| member.len())
+            .sum();
+        self.shared.make_room_to_grow(&mut state, key, growth)?;
+
+        let entry = state.get_or_insert(key, || Value::Set(BTreeSet::new()));
+        let Value::Set(set) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+
+        let mut added = 0;
+        let mut size = 0;
+        for member in members {
+            let len = member.len();
+            if set.insert(member) {
+                size += len;
+                added += 1;
+            }
+        }
+        state.used_memory += size;
+
+        if added > 0 {
+            self.shared.notify_keyspace_event(&state, "sadd", key);
+        }
+
+        Ok(added)
+    }
+
+    /// Push `values` one after the other onto the `end` of the list stored at
+    /// `key`.
+    ///
+    /// A new list is created if the key does not exist. Returns the length of
+    /// the list after the push. [`Error::WrongType`] is returned if the key
+    /// holds a value other than a list.
+    pub(crate) fn push(&self, key: &str, values: Vec<Bytes>, end: ListEnd) -> Result<usize> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        // Check the type before making room, so a failing command evicts
+        // nothing.
+        if let Some(entry) = state.entries.get(key) {
+            entry.value.typed::<VecDeque<Bytes>>()?;
+        }
+        let size: usize = values.iter().map(Bytes::len).sum();
+        self.shared.make_room_to_grow(&mut state, key, size)?;
+
+        let entry = state.get_or_insert(key, || Value::List(VecDeque::new()));
+        let Value::List(list) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+
+        for value in values {
+            match end {
+                ListEnd::Left => list.push_front(value),
+                ListEnd::Right => list.push_back(value),
+            }
+        }
+        let len = list.len();
+        state.used_memory += size;
+
+        let event = match end {
+            ListEnd::Left => "lpush",
+            ListEnd::Right => "rpush",
+        };
+        self.shared.notify_keyspace_event(&state, event, key);
+        state.wake_list_waiters(key);
+
+        Ok(len)
+    }
+
+    /// Get the length of the list stored at `key`.
+    ///
+    /// A missing key is an empty list. [`Error::WrongType`] is returned if the
+    /// key holds a value other than a list.
+    pub(crate) fn llen(&self, key: &str) -> Result<usize> {
+        self.with_typed(key, |list: &VecDeque<Bytes>| list.len())
+            .map(Option::unwrap_or_default)
+    }
+
+    /// Get the element at `index` in the list stored at `key`.
+    ///
+    /// Negative indices count from the tail of the list, `-1` being the last
+    /// element. Returns `None` if the key does not exist or the index is out
+    /// of range. [`Error::WrongType`] is returned if the key holds a value
+    /// other than a list.
+    pub(crate) fn lindex(&self, key: &str, index: i64) -> Result<Option<Bytes>> {
+        self.with_typed(key, |list: &VecDeque<Bytes>| {
+            list_index(index, list.len()).map(|index| list[index].clone())
+        })
+        .map(Option::flatten)
+    }
+
+    /// Set the element at `index` in the list stored at `key` to `value`.
+    ///
+    /// Negative indices count from the tail of the list. [`Error::NoSuchKey`]
+    /// is returned if the key does not exist, [`Error::IndexOutOfRange`] if
+    /// the index is out of range, and [`Error::WrongType`] if the key holds a
+    /// value other than a list.
+    pub(crate) fn lset(&self, key: &str, index: i64, value: Bytes) -> Result<()> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        let Some(entry) = state.entries.get(key) else {
+            return Err(Error::NoSuchKey);
+        };
+        let list: &VecDeque<Bytes> = entry.value.typed()?;
+        let index = list_index(index, list.len()).ok_or(Error::IndexOutOfRange)?;
+        let growth = value.len().saturating_sub(list[index].len());
+        self.shared.make_room_to_grow(&mut state, key, growth)?;
+
+        let access = state.tick();
+        let state = &mut *state;
+        let entry = state.entries.get_mut(key).expect("the key was checked");
+        let Value::List(list) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+
+        let len = value.len();
+        let prev = std::mem::replace(&mut list[index], value);
+        entry.touch(access);
+        state.used_memory = state.used_memory - prev.len() + len;
+
+        self.shared.notify_keyspace_event(state, "lset", key);
+
+        Ok(())
+    }
+
+    /// Get the indices of the elements equal to `value` in the list stored at
+    /// `key`.
+    ///
+    /// The `rank` selects the first match to report, `1` being the first one.
+    /// A negative `rank` searches from tail to head instead, `-1` being the
+    /// last match. Up to `count` indices are returned, zero meaning every
+    /// match. [`Error::WrongType`] is returned if the key holds a value other
+    /// than a list.
+    pub(crate) fn lpos(
+        &self,
+        key: &str,
+        value: &[u8],
+        rank: i64,
+        count: usize,
+    ) -> Result<Vec<usize>> {
+        let skip = usize::try_from(rank.unsigned_abs().saturating_sub(1)).unwrap_or(usize::MAX);
+        let count = if count == 0 { usize::MAX } else { count };
+        self.with_typed(key, |list: &VecDeque<Bytes>| {
+            let matches = list
+                .iter()
+                .enumerate()
+                .filter(|(_, element)| element.as_ref() == value)
+                .map(|(index, _)| index);
+            if rank < 0 {
+                matches.rev().skip(skip).take(count).collect()
+            } else {
+                matches.skip(skip).take(count).collect()
+            }
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Remove up to `count` occurrences of `value` from the list stored at
+    /// `key`.
+    ///
+    /// A positive `count` removes from head to tail, a negative one from tail
+    /// to head, and zero removes every occurrence. The key is deleted once the
+    /// list is empty. Returns the number of removed elements.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// list.
+    pub(crate) fn lrem(&self, key: &str, count: i64, value: &Bytes) -> Result<usize> {
+        self.modify_list(key, "lrem", |list| {
+            let limit = match count.unsigned_abs() {
+                0 => usize::MAX,
+                limit => usize::try_from(limit).unwrap_or(usize::MAX),
+            };
+            let mut removed = 0;
+            if count < 0 {
+                let mut index = list.len();
+                while index > 0 && removed < limit {
+                    index -= 1;
+                    if list[index] == value {
+                        list.remove(index);
+                        removed += 1;
+                    }
+                }
+            } else {
+                let mut index = 0;
+                while index < list.len() && removed < limit {
+                    if list[index] == value {
+                        list.remove(index);
+                        removed += 1;
+                    } else {
+                        index += 1;
+                    }
+                }
+            }
+            removed
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Trim the list stored at `key` to the inclusive range from `start` to
+    /// `stop`.
+    ///
+    /// Negative indices count from the tail of the list. The key is deleted if
+    /// the range is empty. [`Error::WrongType`] is returned if the key holds a
+    /// value other than a list.
+    pub(crate) fn ltrim(&self, key: &str, start: i64, stop: i64) -> Result<()> {
+        self.modify_list(key, "ltrim", |list| {
+            match rank_range(start, stop, list.len()) {
+                Some(range) => {
+                    list.truncate(range.end() + 1);
+                    list.drain(..range.start());
+                }
+                None => list.clear(),
+            }
+        })?;
+        Ok(())
+    }
+
+    /// Atomically pop the last element of the list stored at `source` and
+    /// push it at the head of the list stored at `destination`.
+    ///
+    /// Both keys are accessed under a single write lock, so no other client
+    /// observes the element in neither or both lists. Returns the moved
+    /// element, or `None` if the source does not exist. [`Error::WrongType`]
+    /// is returned if either key holds a value other than a list.
+    pub(crate) fn rpoplpush(&self, source: &str, destination: &str) -> Result<Option<Bytes>> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        let now = Instant::now();
+        for key in [source, destination] {
+            if state.remove_if_expired(key, now) {
+                self.shared.notify_keyspace_event(&state, "expired", key);
+            }
+        }
+
+        // Check both types before modifying anything.
+        for key in [source, destination] {
+            if let Some(entry) = state.entries.get(key) {
+                entry.value.typed::<VecDeque<Bytes>>()?;
+            }
+        }
+        // The value moves between lists, only creating `destination` might
+        // take more memory.
+        self.shared.make_room_to_grow(&mut state, destination, 0)?;
+
+        let access = state.tick();
+        let Some(entry) = state.entries.get_mut(source) else {
+            return Ok(None);
+        };
+        entry.touch(access);
+        let Value::List(list) = &mut entry.value else {
+            unreachable!("the source type was checked");
+        };
+        let Some(value) = list.pop_back() else {
+            return Ok(None);
+        };
+        let emptied = list.is_empty();
+        state.used_memory -= value.len();
+        self.shared.notify_keyspace_event(&state, "rpop", source);
+        if emptied {
+            state.remove_entry(source);
+            self.shared.notify_keyspace_event(&state, "del", source);
+        }
+
+        let entry = state.get_or_insert(destination, || Value::List(VecDeque::new()));
+        let Value::List(list) = &mut entry.value else {
+            unreachable!("the destination type was checked");
+        };
+        list.push_front(value.clone());
+        state.used_memory += value.len();
+        self.shared
+            .notify_keyspace_event(&state, "lpush", destination);
+        state.wake_list_waiters(destination);
+
+        Ok(Some(value))
+    }
+
+    /// Atomically move `member` from the set stored at `source` to the set
+    /// stored at `destination`.
+    ///
+    /// Returns `true` if `member` was in `source`, in which case it is added
+    /// to `destination` even if it was already there. The emptied source is
+    /// deleted. [`Error::WrongType`] is returned if either key holds a value
+    /// other than a set, before modifying anything.
+    pub(crate) fn smove(&self, source: &str, destination: &str, member: Bytes) -> Result<bool> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        let now = Instant::now();
+        for key in [source, destination] {
+            if state.remove_if_expired(key, now) {
+                self.shared.notify_keyspace_event(&state, "expired", key);
+            }
+        }
+
+        // Check both types before modifying anything.
+        for key in [source, destination] {
+            if let Some(entry) = state.entries.get(key) {
+                entry.value.typed::<BTreeSet<Bytes>>()?;
+            }
+        }
+        // The member moves between sets, only creating `destination` might
+        // take more memory.
+        self.shared.make_room_to_grow(&mut state, destination, 0)?;
+
+        let access = state.tick();
+        let Some(entry) = state.entries.get_mut(source) else {
+            return Ok(false);
+        };
+        entry.touch(access);
+        let Value::Set(set) = &mut entry.value else {
+            unreachable!("the source type was checked");
+        };
+        if !set.contains(&member) {
+            return Ok(false);
+        }
+        // Moving to the same set leaves it unchanged.
+        if source == destination {
+            return Ok(true);
+        }
+        set.remove(&member);
+        let emptied = set.is_empty();
+        state.used_memory -= member.len();
+        self.shared.notify_keyspace_event(&state, "srem", source);
+        if emptied {
+            state.remove_entry(source);
+            self.shared.notify_keyspace_event(&state, "del", source);
+        }
+
+        let entry = state.get_or_insert(destination, || Value::Set(BTreeSet::new()));
+        let Value::Set(set) = &mut entry.value else {
+            unreachable!("the destination type was checked");
+        };
+        let len = member.len();
+        if set.insert(member) {
+            state.used_memory += len;
+        }
+        self.shared
+            .notify_keyspace_event(&state, "sadd", destination);
+
+        Ok(true)
+    }
+
+    /// Pop up to `count` values from the `end` of the first non empty list
+    /// stored at one of `keys`, returning the key along with the values, in
+    /// the order they were popped.
+    ///
+    /// Returns `None` if every list is empty or missing. [`Error::WrongType`]
+    /// is returned if a key holds a value other than a list, before reaching
+    /// a non empty list.
+    pub(crate) fn pop_first(
+        &self,
+        keys: &[String],
+        end: ListEnd,
+        count: usize,
+    ) -> Result<Option<(String, Vec<Bytes>)>> {
+        let mut state = self.shared.state.write().unwrap();
+
+        let now = Instant::now();
+        for key in keys {
+            // An expired key counts as missing, even if it was not purged yet.
+            if state.remove_if_expired(key, now) {
+                self.shared.notify_keyspace_event(&state, "expired", key);
+            }
+            let access = state.tick();
+            let Some(entry) = state.entries.get_mut(key) else {
+                continue;
+            };
+            let list: &mut VecDeque<Bytes> = entry.value.typed_mut()?;
+            let count = count.min(list.len());
+            let (values, event): (Vec<_>, _) = match end {
+                ListEnd::Left => (list.drain(..count).collect(), "lpop"),
+                ListEnd::Right => (list.drain(list.len() - count..).rev().collect(), "rpop"),
+            };
+            if values.is_empty() {
+                continue;
+            }
+            let emptied = list.is_empty();
+            entry.touch(access);
+            state.used_memory -= values.iter().map(Bytes::len).sum::<usize>();
+            self.shared.notify_keyspace_event(&state, event, key);
+            if emptied {
+                state.remove_entry(key);
+                self.shared.notify_keyspace_event(&state, "del", key);
+            }
+            return Ok(Some((key.clone(), values)));
+        }
+
+        Ok(None)
+    }
+
+    /// Get the notifiers woken up when values are pushed to the lists stored
+    /// at `keys`.
+    ///
+    /// They must be released with [`Db::release_list_waiters`] once done
+    /// waiting.
+    pub(crate) fn list_waiters(&self, keys: &[String]) -> Vec<Arc<Notify>> {
+        let mut state = self.shared.state.write().unwrap();
+        keys.iter()
+            .map(|key| state.list_waiters.entry(key.clone()).or_default().clone())
+            .collect()
+    }
+
+    /// Release `waiters`, obtained from [`Db::list_waiters`] for `keys`.
+    ///
+    /// The notifier of a key is dropped once no client waits on it anymore.
+    pub(crate) fn release_list_waiters(&self, keys: &[String], waiters: Vec<Arc<Notify>>) {
+        let mut state = self.shared.state.write().unwrap();
+        drop(waiters);
+        for key in keys {
+            if state
+                .list_waiters
+                .get(key)
+                .is_some_and(|notify| Arc::strong_count(notify) == 1)
+            {
+                state.list_waiters.remove(key);
+            }
+        }
+    }
+
+    /// Calls `f` with the list stored at `key` for modification, under a write
+    /// lock.
+    ///
+    /// The memory accounting is updated, `event` is notified, and the key is
+    /// deleted if `f` leaves the list empty. Returns `None` if the key does
+    /// not exist.
+    fn modify_list<T>(
+        &self,
+        key: &str,
+        event: &str,
+        f: impl FnOnce(&mut VecDeque<Bytes>) -> T,
+    ) -> Result<Option<T>> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        let access = state.tick();
+        let state = &mut *state;
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(None);
+        };
+        entry.value.typed::<VecDeque<Bytes>>()?;
+        entry.touch(access);
+
+        let before = entry.value.size();
+        let Value::List(list) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+        let res = f(list);
+        let emptied = list.is_empty();
+        state.used_memory = state.used_memory - before + entry.value.size();
+
+        self.shared.notify_keyspace_event(state, event, key);
+        if emptied {
+            state.remove_entry(key);
+            self.shared.notify_keyspace_event(state, "del", key);
+        }
+
+        Ok(Some(res))
+    }
+
+    /// Calls `f` with the value stored at `key` as a `T`, under a read lock.
+    ///
+    /// The expected type is selected by `T`, usually through the type of the
+    /// argument of `f`. Returns `None` if the key does not exist.
+    /// [`Error::WrongType`] is returned if the key holds another type.
+    fn with_typed<T: Typed, R>(&self, key: &str, f: impl FnOnce(&T) -> R) -> Result<Option<R>> {
+        let state = self.shared.state.read().unwrap();
+        let Some(entry) = state.live_entry(key, Instant::now()) else {
+            return Ok(None);
+        };
+        entry.touch(state.tick());
+        entry.value.typed().map(f).map(Some)
+    }
+
+    /// Add `members` with their scores to the sorted set stored at `key`.
+    ///
+    /// The score of members already in the sorted set is updated. A new sorted
+    /// set is created if the key does not exist. Returns the number of members
+    /// that were added, not counting the ones whose score was updated.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// sorted set.
+    pub(crate) fn zadd(&self, key: &str, members: Vec<(f64, Bytes)>) -> Result<usize> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        // Check the type before making room, so a failing command evicts
+        // nothing. Each new member is accounted for along with its score, see
+        // `SortedSet::size`, while updating a score takes no more memory.
+        let zset = state
+            .entries
+            .get(key)
+            .map(|entry| entry.value.typed::<SortedSet>())
+            .transpose()?;
+        let mut new_members = BTreeSet::new();
+        let growth = members
+            .iter()
+            .map(|(_, member)| member)
+            .filter(|member| zset.is_none_or(|zset| zset.score(member).is_none()))
+            .filter(|member| new_members.insert(*member))
+            .map(|member| member.len() + std::mem::size_of::<f64>())
+            .sum();
+        self.shared.make_room_to_grow(&mut state, key, growth)?;
+
+        let entry = state.get_or_insert(key, || Value::ZSet(SortedSet::new()));
+        let Value::ZSet(zset) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+
+        let prev_size = zset.size();
+        let added = members
+            .into_iter()
+            .filter(|(score, member)| zset.insert(member.clone(), *score))
+            .count();
+        let size = zset.size();
+        state.used_memory = state.used_memory - prev_size + size;
+
+        self.shared.notify_keyspace_event(&state, "zadd", key);
+
+        Ok(added)
+    }
+
+    /// Get the score of `member` in the sorted set stored at `key`.
+    ///
+    /// Returns `None` if the key or the member do not exist.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// sorted set.
+    pub(crate) fn zscore(&self, key: &str, member: &[u8]) -> Result<Option<f64>> {
+        self.with_typed(key, |zset: &SortedSet| zset.score(member))
+            .map(Option::flatten)
+    }
+
+    /// Pick random members of the sorted set stored at `key`, along with their
+    /// scores.
+    ///
+    /// `count` is like for [`Db::hrandfield`]. A missing key is an empty sorted
+    /// set. [`Error::WrongType`] is returned if the key holds a value other
+    /// than a sorted set.
+    pub(crate) fn zrandmember(&self, key: &str, count: i64) -> Result<Vec<ScoredMember>> {
+        self.with_typed(key, |zset: &SortedSet| {
+            random_sample(zset.iter().collect(), count)
+                .into_iter()
+                .map(|(member, score)| (member.clone(), score))
+                .collect()
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Get the rank of `member` in the sorted set stored at `key`.
+    ///
+    /// Members are ranked from the lowest score, or from the highest one if
+    /// `rev` is `true`. Returns `None` if the key or the member do not exist.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// sorted set.
+    pub(crate) fn zrank(&self, key: &str, member: &[u8], rev: bool) -> Result<Option<usize>> {
+        self.with_typed(key, |zset: &SortedSet| {
+            let rank = zset.rank(member)?;
+            Some(if rev { zset.len() - 1 - rank } else { rank })
+        })
+        .map(Option::flatten)
+    }
+
+    /// Get the number of members of the sorted set stored at `key`.
+    ///
+    /// A missing key is an empty sorted set. [`Error::WrongType`] is returned
+    /// if the key holds a value other than a sorted set.
+    pub(crate) fn zcard(&self, key: &str) -> Result<usize> {
+        self.with_typed(key, SortedSet::len)
+            .map(Option::unwrap_or_default)
+    }
+
+    /// Count the members of the sorted set stored at `key` whose score is
+    /// between `min` and `max`.
+    ///
+    /// A missing key is an empty sorted set. [`Error::WrongType`] is returned
+    /// if the key holds a value other than a sorted set.
+    pub(crate) fn zcount(&self, key: &str, min: Bound<f64>, max: Bound<f64>) -> Result<usize> {
+        self.with_typed(key, |zset: &SortedSet| {
+            zset.range_by_score(min, max).count()
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Remove `members` from the sorted set stored at `key`.
+    ///
+    /// Returns the number of members that were removed. The key is deleted
+    /// once empty. [`Error::WrongType`] is returned if the key holds a value
+    /// other than a sorted set.
+    pub(crate) fn zrem(&self, key: &str, members: &[Bytes]) -> Result<usize> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        if state.remove_if_expired(key, Instant::now()) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        let access = state.tick();
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(0);
+        };
+        entry.value.typed::<SortedSet>()?;
+        entry.touch(access);
+        let Value::ZSet(zset) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+
+        let prev_size = zset.size();
+        let removed = members.iter().filter(|member| zset.remove(member)).count();
+        let emptied = zset.len() == 0;
+        let size = zset.size();
+        state.used_memory = state.used_memory - prev_size + size;
+
+        if removed > 0 {
+            self.shared.notify_keyspace_event(&state, "zrem", key);
+        }
+        if emptied {
+            state.remove_entry(key);
+            self.shared.notify_keyspace_event(&state, "del", key);
+        }
+
+        Ok(removed)
+    }
+
+    /// Pop up to `count` members, with the lowest or highest scores depending
+    /// on `direction`, from the first non empty sorted set stored at one of
+    /// `keys`. Returns the key along with the members and their scores, in
+    /// the order they were popped.
+    ///
+    /// Returns `None` if every sorted set is empty or missing.
+    /// [`Error::WrongType`] is returned if a key holds a value other than a
+    /// sorted set, before reaching a non empty sorted set.
+    pub(crate) fn zpop_first(
+        &self,
+        keys: &[String],
+        direction: ScoreDirection,
+        count: usize,
+    ) -> Result<Option<(String, Vec<ScoredMember>)>> {
+        let mut state = self.shared.state.write().unwrap();
+
+        let now = Instant::now();
+        for key in keys {
+            // An expired key counts as missing, even if it was not purged yet.
+            if state.remove_if_expired(key, now) {
+                self.shared.notify_keyspace_event(&state, "expired", key);
+            }
+            let access = state.tick();
+            let Some(entry) = state.entries.get_mut(key) else {
+                continue;
+            };
+            let zset: &mut SortedSet = entry.value.typed_mut()?;
+            let prev_size = zset.size();
+            let (members, event) = match direction {
+                ScoreDirection::Min => (zset.pop_min(count), "zpopmin"),
+                ScoreDirection::Max => (zset.pop_max(count), "zpopmax"),
+            };
+            if members.is_empty() {
+                continue;
+            }
+            let emptied = zset.len() == 0;
+            let size = zset.size();
+            entry.touch(access);
+            state.used_memory = state.used_memory - prev_size + size;
+            self.shared.notify_keyspace_event(&state, event, key);
+            if emptied {
+                state.remove_entry(key);
+                self.shared.notify_keyspace_event(&state, "del", key);
+            }
+            return Ok(Some((key.clone(), members)));
+        }
+
+        Ok(None)
+    }
+
+    /// Get the members of the sorted set stored at `key` whose rank is in the
+    /// inclusive range from `start` to `stop`, along with their scores.
+    ///
+    /// Negative ranks count from the end of the sorted set, `-1` being the
+    /// member with the highest score. A missing key is an empty sorted set.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// sorted set.
+    pub(crate) fn zrange(&self, key: &str, start: i64, stop: i64) -> Result<Vec<(Bytes, f64)>> {
+        self.with_typed(key, |zset: &SortedSet| {
+            let Some(range) = rank_range(start, stop, zset.len()) else {
+                return vec![];
+            };
+            zset.iter()
+                .skip(*range.start())
+                .take(range.count())
+                .map(|(member, score)| (member.clone(), score))
+                .collect()
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Get the members of the sorted set stored at `key` whose score is
+    /// between `min` and `max`, along with their scores.
+    ///
+    /// `limit` restricts the result to `count` members after skipping
+    /// `offset` ones, see [`limit`]. A missing key is an empty sorted set.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// sorted set.
+    pub(crate) fn zrange_by_score(
+        &self,
+        key: &str,
+        min: Bound<f64>,
+        max: Bound<f64>,
+        limit: Option<(i64, i64)>,
+    ) -> Result<Vec<(Bytes, f64)>> {
+        self.with_typed(key, |zset: &SortedSet| {
+            let members = zset
+                .range_by_score(min, max)
+                .map(|(member, score)| (member.clone(), score));
+            self::limit(members, limit)
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Get the members of the sorted set stored at `key` which are between
+    /// `min` and `max` lexicographically.
+    ///
+    /// The members are expected to all have the same score. `limit` and
+    /// errors are like in [`Db::zrange_by_score`].
+    pub(crate) fn zrange_by_lex(
+        &self,
+        key: &str,
+        min: &LexBound,
+        max: &LexBound,
+        limit: Option<(i64, i64)>,
+    ) -> Result<Vec<Bytes>> {
+        self.with_typed(key, |zset: &SortedSet| {
+            let members = zset
+                .range_by_lex(min, max)
+                .map(|(member, _)| member.clone());
+            self::limit(members, limit)
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Set `fields` to their values in the hash stored at `key`.
+    ///
+    /// A new hash is created if the key does not exist. Returns the number of
+    /// fields that were added, not counting the ones whose value was updated.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// hash.
+    pub(crate) fn hset(&self, key: &str, fields: Vec<(Bytes, Bytes)>) -> Result<usize> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        let now = Instant::now();
+        if state.remove_if_expired(key, now) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        // Check the type before making room, so a failing command evicts
+        // nothing. Overwritten fields free the size of their current value.
+        let hash = state
+            .entries
+            .get(key)
+            .map(|entry| entry.value.typed::<Hash>())
+            .transpose()?;
+        let (added, freed) =
+            fields
+                .iter()
+                .fold((0, 0), |(added, freed), (field, value)| {
+                    match hash.and_then(|hash| hash.get(field, now)) {
+                        Some(current) => (added + value.len(), freed + current.len()),
+                        None => (added + field.len() + value.len(), freed),
+                    }
+                });
+        let growth = usize::saturating_sub(added, freed);
+        self.shared.make_room_to_grow(&mut state, key, growth)?;
+
+        let entry = state.get_or_insert(key, || Value::Hash(Hash::new()));
+        let prev_size = entry.value.size();
+        let Value::Hash(hash) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+
+        // Likewise, expired fields count as missing.
+        hash.remove_expired(now);
+        let added = fields
+            .into_iter()
+            .filter(|(field, value)| hash.insert(field.clone(), value.clone()))
+            .count();
+        let size = entry.value.size();
+        state.used_memory = state.used_memory - prev_size + size;
+
+        self.shared.notify_keyspace_event(&state, "hset", key);
+
+        Ok(added)
+    }
+
+    /// Get the value of `field` in the hash stored at `key`.
+    ///
+    /// Returns `None` if the key or the field do not exist.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// hash.
+    pub(crate) fn hget(&self, key: &str, field: &[u8]) -> Result<Option<Bytes>> {
+        let now = Instant::now();
+        self.with_typed(key, |hash: &Hash| hash.get(field, now).cloned())
+            .map(Option::flatten)
+    }
+
+    /// Get the values of `fields` in the hash stored at `key`.
+    ///
+    /// Missing fields, or all of them if the key does not exist, are `None`.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// hash.
+    pub(crate) fn hmget(&self, key: &str, fields: &[Bytes]) -> Result<Vec<Option<Bytes>>> {
+        let now = Instant::now();
+        let values = self.with_typed(key, |hash: &Hash| {
+            fields
+                .iter()
+                .map(|field| hash.get(field, now).cloned())
+                .collect()
+        })?;
+        Ok(values.unwrap_or_else(|| vec![None; fields.len()]))
+    }
+
+    /// Get the fields of the hash stored at `key`.
+    ///
+    /// A missing key is an empty hash. [`Error::WrongType`] is returned if the
+    /// key holds a value other than a hash.
+    pub(crate) fn hkeys(&self, key: &str) -> Result<Vec<Bytes>> {
+        let now = Instant::now();
+        self.with_typed(key, |hash: &Hash| {
+            hash.iter(now).map(|(field, _)| field.clone()).collect()
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Get the values of the hash stored at `key`, ordered by their field.
+    ///
+    /// A missing key is an empty hash. [`Error::WrongType`] is returned if the
+    /// key holds a value other than a hash.
+    pub(crate) fn hvals(&self, key: &str) -> Result<Vec<Bytes>> {
+        let now = Instant::now();
+        self.with_typed(key, |hash: &Hash| {
+            hash.iter(now).map(|(_, value)| value.clone()).collect()
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Pick random fields of the hash stored at `key`, along with their values.
+    ///
+    /// A positive `count` picks distinct fields, at most every one of them,
+    /// while a negative `count` picks `-count` fields which may repeat. A
+    /// missing key is an empty hash. [`Error::WrongType`] is returned if the
+    /// key holds a value other than a hash.
+    pub(crate) fn hrandfield(&self, key: &str, count: i64) -> Result<Vec<(Bytes, Bytes)>> {
+        let now = Instant::now();
+        self.with_typed(key, |hash: &Hash| {
+            random_sample(hash.iter(now).collect(), count)
+                .into_iter()
+                .map(|(field, value)| (field.clone(), value.clone()))
+                .collect()
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Increment the integer stored at `field` in the hash stored at `key` by
+    /// `delta`.
+    ///
+    /// A missing key or field counts as `0`. Returns the value after the
+    /// increment. [`Error::HashNotAnInteger`] is returned if the field does not
+    /// hold an integer, [`Error::Overflow`] if the increment overflows, and
+    /// [`Error::WrongType`] if the key holds a value other than a hash.
+    pub(crate) fn hincr_by(&self, key: &str, field: Bytes, delta: i64) -> Result<i64> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        let now = Instant::now();
+        if state.remove_if_expired(key, now) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        // Check the current value before inserting anything, so a failed
+        // increment leaves no empty hash behind.
+        let hash = match state.entries.get(key) {
+            Some(entry) => Some(entry.value.typed::<Hash>()?),
+            None => None,
+        };
+        let current = hash.and_then(|hash| hash.get(&field, now));
+        let value = match current {
+            Some(data) => std::str::from_utf8(data)
+                .ok()
+                .and_then(|s| s.parse::<i64>().ok())
+                .ok_or(Error::HashNotAnInteger)?,
+            None => 0,
+        };
+        let value = value.checked_add(delta).ok_or(Error::Overflow)?;
+        let data = Bytes::from(value.to_string());
+
+        let growth = match current {
+            Some(current) => data.len().saturating_sub(current.len()),
+            None => field.len() + data.len(),
+        };
+        self.shared.make_room_to_grow(&mut state, key, growth)?;
+
+        let entry = state.get_or_insert(key, || Value::Hash(Hash::new()));
+        let prev_size = entry.value.size();
+        let Value::Hash(hash) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+        // Unlike `HSET`, an increment keeps the time to live of the field.
+        hash.remove_expired(now);
+        let expires_at = hash.expires_at(&field);
+        hash.insert(field.clone(), data);
+        if let Some(when) = expires_at {
+            hash.expire(&field, when);
+        }
+        let size = entry.value.size();
+        state.used_memory = state.used_memory - prev_size + size;
+
+        self.shared.notify_keyspace_event(&state, "hincrby", key);
+
+        Ok(value)
+    }
+
+    /// Iterate the fields of the hash stored at `key`, starting at `cursor`.
+    ///
+    /// See [`scan`] for the cursor semantics. [`Error::WrongType`] is returned
+    /// if the key holds a value other than a hash.
+    pub(crate) fn hscan(
+        &self,
+        key: &str,
+        cursor: u64,
+        count: usize,
+        pattern: Option<&[u8]>,
+    ) -> Result<(u64, Vec<(Bytes, Bytes)>)> {
+        let now = Instant::now();
+        self.with_typed(key, |hash: &Hash| {
+            let fields = hash
+                .iter(now)
+                .map(|(field, value)| (field.clone(), value.clone()));
+            scan(fields, cursor, count, pattern, |(field, _)| field)
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Set a time to live on `fields` of the hash stored at `key`.
+    ///
+    /// Returns, for each field, `-2` if it does not exist, `0` if `condition`
+    /// is not met, `1` if the time to live was set, or `2` if the field was
+    /// deleted as `ttl` is zero. The hash is deleted along with its last
+    /// field. [`Error::WrongType`] is returned if the key holds a value other
+    /// than a hash.
+    pub(crate) fn hexpire(
+        &self,
+        key: &str,
+        ttl: Duration,
+        condition: Option<ExpireCondition>,
+        fields: &[Bytes],
+    ) -> Result<Vec<i64>> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        let now = Instant::now();
+        if state.remove_if_expired(key, now) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        let tick = state.tick();
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(vec![-2; fields.len()]);
+        };
+        entry.value.typed::<Hash>()?;
+        entry.touch(tick);
+        let prev_size = entry.value.size();
+        let Value::Hash(hash) = &mut entry.value else {
+            unreachable!("the type was checked");
+        };
+
+        hash.remove_expired(now);
+        let when = now + ttl;
+        let results: Vec<i64> = fields
+            .iter()
+            .map(|field| {
+                if hash.get(field, now).is_none() {
+                    -2
+                } else if !expire_condition_met(condition, hash.expires_at(field), when) {
+                    0
+                } else if ttl.is_zero() {
+                    hash.remove(field);
+                    2
+                } else {
+                    hash.expire(field, when);
+                    1
+                }
+            })
+            .collect();
+        let emptied = hash.len() == 0;
+        let size = entry.value.size();
+        state.used_memory = state.used_memory - prev_size + size;
+
+        if results.contains(&1) {
+            self.shared.notify_keyspace_event(&state, "hexpire", key);
+        }
+        if results.contains(&2) {
+            self.shared.notify_keyspace_event(&state, "hexpired", key);
+        }
+        if emptied {
+            state.remove_entry(key);
+            self.shared.notify_keyspace_event(&state, "del", key);
+        }
+
+        Ok(results)
+    }
+
+    /// Get the remaining time to live of `fields` of the hash stored at `key`,
+    /// in seconds.
+    ///
+    /// Returns, for each field, `-2` if it does not exist, `-1` if it has no
+    /// time to live, or the time to live otherwise. [`Error::WrongType`] is
+    /// returned if the key holds a value other than a hash.
+    pub(crate) fn httl(&self, key: &str, fields: &[Bytes]) -> Result<Vec<i64>> {
+        let now = Instant::now();
+        let ttls = self.with_typed(key, |hash: &Hash| {
+            fields
+                .iter()
+                .map(
+                    |field| match (hash.get(field, now), hash.expires_at(field)) {
+                        (None, _) => -2,
+                        (Some(_), None) => -1,
+                        // Rounded to the nearest second, like `TTL`.
+                        (Some(_), Some(when)) => ((when - now).as_millis() as i64 + 500) / 1000,
+                    },
+                )
+                .collect()
+        })?;
+        Ok(ttls.unwrap_or_else(|| vec![-2; fields.len()]))
+    }
+
+    /// Remove the time to live of `fields` of the hash stored at `key`.
+    ///
+    /// Returns, for each field, `-2` if it does not exist, `-1` if it has no
+    /// time to live, or `1` if its time to live was removed.
+    /// [`Error::WrongType`] is returned if the key holds a value other than a
+    /// hash.
+    pub(crate) fn hpersist(&self, key: &str, fields: &[Bytes]) -> Result<Vec<i64>> {
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        let now = Instant::now();
+        if state.remove_if_expired(key, now) {
+            self.shared.notify_keyspace_event(&state, "expired", key);
+        }
+
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(vec![-2; fields.len()]);
+        };
+        let hash: &mut Hash = entry.value.typed_mut()?;
+        let results: Vec<i64> = fields
+            .iter()
+            .map(|field| {
+                if hash.get(field, now).is_none() {
+                    -2
+                } else if hash.persist(field) {
+                    1
+                } else {
+                    -1
+                }
+            })
+            .collect();
+
+        if results.contains(&1) {
+            self.shared.notify_keyspace_event(&state, "hpersist", key);
+        }
+
+        Ok(results)
+    }
+
+    /// Iterate the members of the set stored at `key`, starting at `cursor`.
+    ///
+    /// See [`scan`] for the cursor semantics. [`Error::WrongType`] is returned
+    /// if the key holds a value other than a set.
+    pub(crate) fn sscan(
+        &self,
+        key: &str,
+        cursor: u64,
+        count: usize,
+        pattern: Option<&[u8]>,
+    ) -> Result<(u64, Vec<Bytes>)> {
+        self.with_sets(&[key.to_string()], |sets| {
+            scan(sets[0].iter().cloned(), cursor, count, pattern, |member| {
+                member
+            })
+        })
+    }
+
+    /// Iterate the members of the sorted set stored at `key`, along with their
+    /// scores, starting at `cursor`.
+    ///
+    /// See [`scan`] for the cursor semantics. [`Error::WrongType`] is returned
+    /// if the key holds a value other than a sorted set.
+    pub(crate) fn zscan(
+        &self,
+        key: &str,
+        cursor: u64,
+        count: usize,
+        pattern: Option<&[u8]>,
+    ) -> Result<(u64, Vec<(Bytes, f64)>)> {
+        self.with_typed(key, |zset: &SortedSet| {
+            let members = zset.iter().map(|(member, score)| (member.clone(), score));
+            scan(members, cursor, count, pattern, |(member, _)| member)
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Returns the members of the intersection of the sets stored at `keys`.
+    ///
+    /// Missing keys count as empty sets, so the result is empty if any of the
+    /// keys is missing. [`Error::WrongType`] is returned if any of the keys
+    /// holds a value other than a set.
+    pub(crate) fn sinter(&self, keys: &[String]) -> Result<BTreeSet<Bytes>> {
+        self.with_sets(keys, |sets| SetOp::Inter.combine(sets))
+    }
+
+    /// Returns the number of members of the intersection of the sets stored at
+    /// `keys`, counting no further than `limit`.
+    ///
+    /// The smallest set is scanned, so the intersection is never built, and
+    /// scanning stops as soon as `limit` members are found. Missing keys count
+    /// as empty sets. [`Error::WrongType`] is returned if any of the keys holds
+    /// a value other than a set.
+    pub(crate) fn sintercard(&self, keys: &[String], limit: usize) -> Result<usize> {
+        self.with_sets(keys, |sets| {
+            let Some(smallest) = sets.iter().min_by_key(|set| set.len()) else {
+                return 0;
+            };
+            smallest
+                .iter()
+                .filter(|member| sets.iter().all(|set| set.contains(*member)))
+                .take(limit)
+                .count()
+        })
+    }
+
+    /// Returns the members of the union of the sets stored at `keys`.
+    ///
+    /// Missing keys count as empty sets. [`Error::WrongType`] is returned if
+    /// any of the keys holds a value other than a set.
+    pub(crate) fn sunion(&self, keys: &[String]) -> Result<BTreeSet<Bytes>> {
+        self.with_sets(keys, |sets| SetOp::Union.combine(sets))
+    }
+
+    /// Returns the members of the first set stored at `keys` which are not in
+    /// any of the following sets.
+    ///
+    /// Missing keys count as empty sets. [`Error::WrongType`] is returned if
+    /// any of the keys holds a value other than a set.
+    pub(crate) fn sdiff(&self, keys: &[String]) -> Result<BTreeSet<Bytes>> {
+        self.with_sets(keys, |sets| SetOp::Diff.combine(sets))
+    }
+
+    /// Store the result of `op` over the sets stored at `keys` as a set at
+    /// `destination`, overwriting any value it holds.
+    ///
+    /// Returns the number of members of the result. An empty result deletes
+    /// `destination`. Missing keys count as empty sets.
+    /// [`Error::WrongType`] is returned if any of the `keys` holds a value
+    /// other than a set, in which case `destination` is left untouched.
+    pub(crate) fn set_op_store(
+        &self,
+        op: SetOp,
+        destination: &str,
+        keys: &[String],
+    ) -> Result<usize> {
+        static EMPTY: BTreeSet<Bytes> = BTreeSet::new();
+
+        let mut state = self.shared.state.write().unwrap();
+
+        // An expired key counts as missing, even if it was not purged yet.
+        let now = Instant::now();
+        for key in keys.iter().map(String::as_str).chain([destination]) {
+            if state.remove_if_expired(key, now) {
+                self.shared.notify_keyspace_event(&state, "expired", key);
+            }
+        }
+
+        let access = state.tick();
+        let sets = keys
+            .iter()
+            .map(|key| match state.entries.get(key) {
+                Some(entry) => {
+                    entry.touch(access);
+                    entry.value.typed()
+                }
+                None => Ok(&EMPTY),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let result = op.combine(&sets);
+
+        if result.is_empty() {
+            if state.remove_entry(destination).is_some() {
+                self.shared
+                    .notify_keyspace_event(&state, "del", destination);
+            }
+            return Ok(0);
+        }
+
+        let len = result.len();
+        let value = Value::Set(result);
+        self.shared
+            .make_room(&mut state, destination, destination.len() + value.size())?;
+
+        state.remove_entry(destination);
+        state.get_or_insert(destination, || value);
+        self.shared
+            .notify_keyspace_event(&state, op.store_event(), destination);
+
+        Ok(len)
+    }
+
+    /// Calls `f` with the sets stored at `keys`, under a single read lock.
+    ///
+    /// Missing keys are passed as empty sets.
+    fn with_sets<T>(&self, keys: &[String], f: impl FnOnce(&[&BTreeSet<Bytes>]) -> T) -> Result<T> {
+        static EMPTY: BTreeSet<Bytes> = BTreeSet::new();
+
+        let state = self.shared.state.read().unwrap();
+        let now = Instant::now();
+        let sets = keys
+            .iter()
+            .map(|key| match state.live_entry(key, now) {
+                Some(entry) => {
+                    entry.touch(state.tick());
+                    entry.value.typed()
+                }
+                None => Ok(&EMPTY),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(f(&sets))
+    }
+
+    /// Returns a `broadcast::Receiver` for the requested channel.
+    ///
+    /// The returned `Receiver` is used to receive values broadcast by `PUBLISH`
+    /// commands.
+    pub(crate) fn subscribe(&self, channel: String) -> broadcast::Receiver<Bytes> {
+        use std::collections::hash_map::Entry;
+
+        let mut state = self.shared.state.write().unwrap();
+
+        // If there is no entry for the requested channel, then create a new
+        // broadcast channel and associate it with the key. If one already
+        // exists, return an associated receiver.
+        match state.pub_sub.entry(channel) {
+            Entry::Occupied(e) => e.get().subscribe(),
+            Entry::Vacant(e) => {
+                // No broadcast channel exists yet, so create one.
+                //
+                // The channel is created with a capacity of `1024` messages. A
+                // message is stored in the channel until **all** subscribers
+                // have seen it. This means that a slow subscriber could result
+                // in messages being held indefinitely.
+                //
+                // When the channel's capacity fills up, publishing will result
+                // in old messages being dropped. This prevents slow consumers
+                // from blocking the entire system.
+                let (tx, rx) = broadcast::channel(self.shared.config.pubsub_channel_capacity);
+                e.insert(tx);
+                rx
+            }
+        }
+    }
+
+    /// Publishes a message to a given channel.
+    ///
+    /// Once every subscriber of the channel is gone, the channel is removed,
+    /// so channels do not pile up as clients come and go.
+    ///
+    /// # Returns
+    /// The number of subscribers listening on the channel at this exact times.
+    /// This should only be used as a hint, because a subscriber could drop
+    /// the channel before the message is actually delivered.
+    pub(crate) fn publish(&self, channel: &str, message: Bytes) -> usize {
+        let state = self.shared.state.read().unwrap();
+        let receivers = state.publish(channel, message);
+        if receivers > 0 || !state.pub_sub.contains_key(channel) {
+            return receivers;
+        }
+        drop(state);
+
+        // A client may have subscribed while the lock was released, so the
+        // subscribers are counted again.
+        let mut state = self.shared.state.write().unwrap();
+        if state
+            .pub_sub
+            .get(channel)
+            .is_some_and(|tx| tx.receiver_count() == 0)
+        {
+            state.pub_sub.remove(channel);
+        }
+        0
+    }
+
+    /// Signals the purge background task to shutdown.
+    ///
+    /// This is called by the [`DbDropGuard`]'s [`Drop`] implementation.
+    fn shutdown_purge_task(&self) {
+        // The background task must be signaled to shutdown. This is done by
+        // setting `DbState::shutdown` to `true` and signalling the task.
+        let mut state = self.shared.state.write().unwrap();
+        state.shutdown = true;
+        drop(state);
+        self.shared.background_task.notify_one();
+    }
+}
+
+impl Default for Db {
+    /// Create a new empty `Db` instance with the default configuration.
+    fn default() -> Self {
+        Self::new(DbConfig::default())
+    }
+}
+
+impl DbSharedState {
+    /// Publishes a keyspace notification for `event` on `key`, if enabled.
+    ///
+    /// Takes the already locked `state`, so it can be called from within the
+    /// critical section of the operation that triggered the event.
+    fn notify_keyspace_event(&self, state: &DbState, event: &str, key: &str) {
+        if !self.config.notify_keyspace_events {
+            return;
+        }
+
+        state.publish(
+            &format!("__keyspace@0__:{key}"),
+            Bytes::from(event.to_string()),
+        );
+        state.publish(
+            &format!("__keyevent@0__:{event}"),
+            Bytes::from(key.to_string()),
+        );
+    }
+
+    /// Checks that storing `size` bytes at `key` fits in the memory budget,
+    /// evicting keys as the configured policy allows.
+    ///
+    /// Every write goes through this check, or
+    /// [`DbSharedState::make_room_to_grow`], before storing anything. See
+    /// [`DbState::make_room`].
+    fn make_room(&self, state: &mut DbState, key: &str, size: usize) -> Result<()> {
+        for evicted in state.make_room(&self.config, key, size)? {
+            self.notify_keyspace_event(state, "evicted", &evicted);
+        }
+        Ok(())
+    }
+
+    /// Checks that the value stored at `key` can grow by `growth` bytes, or
+    /// that `key` can be created with a value of `growth` bytes if it does
+    /// not exist.
+    fn make_room_to_grow(&self, state: &mut DbState, key: &str, growth: usize) -> Result<()> {
+        for evicted in state.make_room_to_grow(&self.config, key, growth)? {
+            self.notify_keyspace_event(state, "evicted", &evicted);
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the database is shutting down
+    ///
+    /// The `shutdown` flag is set when all `Db` values have dropped, indicating
+    /// that the shared state can no longer be accessed.
+    fn is_shutdown(&self) -> bool {
+        self.state.read().unwrap().shutdown
+    }
+
+    /// Purge all expired keys and return the `Instant` at which the **next** key will expire,
+    /// alongside the number of keys purged.
+    ///
+    /// The background task will sleep until this instant.
+    #[tracing::instrument(skip_all)]
+    fn purge_expired_keys(&self) -> (Option<Instant>, usize) {
+        debug!("starting purge of expired keys");
+        let mut state = self.state.write().unwrap();
+
+        if state.shutdown {
+            // The database is shutting down. All handles to the shared state
+            // have been dropped. The background task should exit.
+            return (None, 0);
+        }
+
+        // This is needed to make the borrow checker happy. In short, `write()`
+        // returns a `RwLockWriteGuard` and not a `&mut DbState`. The borrow checker is
+        // not able to see "through" the lock guard and determine that it is
+        // safe to access both `state.expirations` and `state.entries` mutably,
+        // so we get a "real" mutable reference to `DbState` outside of the loop.
+        let state_ref = &mut *state;
+
+        // Find all keys scheduled to expire **before** now.
+        let now = Instant::now();
+        let mut next = None;
+        let mut expired = vec![];
+
+        while let Some(&(when, ref key)) = state_ref.expirations.iter().next() {
+            if when > now {
+                debug!("next expiration is in the future, done purging");
+                // Done purging, `when` is the instant at which the next key expires.
+                // The works task will wait until this instant.
+                next = Some(when);
+                break;
+            }
+
+            // The key has expired, remove it.
+            debug!("removing expired {key:?}");
+            let key = key.clone();
+            state_ref.remove_entry(&key);
+            self.notify_keyspace_event(state_ref, "expired", &key);
+            expired.push(key);
+        }
+
+        // Release the lock before running the callback, so it is free to
+        // access the database.
+        drop(state);
+
+        if let Some(ExpireCallback(callback)) = &*self.on_expire.read().unwrap() {
+            for key in &expired {
+                callback(key);
+            }
+        }
+
+        if next.is_none() {
+            debug!("no keys to purge");
+        }
+        (next, expired.len())
+    }
+}
+
+impl Entry {
+    /// Returns `true` if the entry has a TTL which lapsed at `now`.
+    ///
+    /// A hash whose fields all expired counts as expired as well, as Redis
+    /// deletes a hash along with its last field.
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|when| when <= now)
+            || matches!(&self.value, Value::Hash(hash) if hash.is_expired(now))
+    }
+
+    /// Returns the TTL remaining at `now`, or `None` if the entry does not
+    /// expire.
+    fn ttl(&self, now: Instant) -> Option<Duration> {
+        self.expires_at
+            .map(|when| when.saturating_duration_since(now))
+    }
+}
 
 impl DbState {
+    /// Get the entry at `key`, unless it has expired at `now`.
+    fn live_entry(&self, key: &str, now: Instant) -> Option<&Entry> {
+        self.entries.get(key).filter(|entry| !entry.is_expired(now))
+    }
+
+    /// Removes the entry at `key` if it has expired at `now`, along with its
+    /// expiration tracking.
+    ///
+    /// Returns `true` if an entry was removed.
+    fn remove_if_expired(&mut self, key: &str, now: Instant) -> bool {
+        if !self
+            .entries
+            .get(key)
+            .is_some_and(|entry| entry.is_expired(now))
+        {
+            return false;
+        }
+
+        debug!("removing expired {key:?} on access");
+        self.remove_entry(key);
+        true
+    }
+
+    /// Get the entry at `key` for modification, inserting a new entry holding
+    /// the `empty` value if it does not exist.
+    ///
+    /// The entry is marked as accessed.
+    fn get_or_insert(&mut self, key: &str, empty: impl FnOnce() -> Value) -> &mut Entry {
+        let access = self.tick();
+        let entry = self.entries.entry(key.to_string()).or_insert_with(|| {
+            let value = empty();
+            self.used_memory += key.len() + value.size();
+            Entry {
+                value,
+                expires_at: None,
+                last_access: AtomicU64::new(access),
+                freq: AtomicU8::new(LFU_INIT_VAL),
+            }
+        });
+        entry.touch(access);
+        entry
+    }
+
+    /// Wake up the clients blocked on the list stored at `key`.
+    fn wake_list_waiters(&self, key: &str) {
+        if let Some(notify) = self.list_waiters.get(key) {
+            notify.notify_waiters();
+        }
+    }
+
+    /// Removes the entry at `key`, along with its expiration tracking and
+    /// memory accounting.
+    fn remove_entry(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        if let Some(when) = entry.expires_at {
+            self.expirations.remove(&(when, key.to_string()));
+        }
+        self.used_memory -= key.len() + entry.value.size();
+        Some(entry)
+    }
+
+    /// Advances the access clock, returning the new time.
+    fn tick(&self) -> u64 {
+        let now = self.now();
+        let next = |clock: u64| (clock + 1).max(now);
+        let prev = self
+            .clock
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |clock| {
+                Some(next(clock))
+            })
+            .unwrap_or_else(|clock| clock);
+        next(prev)
+    }
+
+    /// Reads the current time of the access clock, without advancing it.
+    fn now(&self) -> u64 {
+        self.epoch.elapsed().as_micros() as u64
+    }
+
+    /// Evicts entries according to the configured policy until storing
+    /// `size` bytes at `key` fits in the memory budget.
+    ///
+    /// The value currently stored at `key` is about to be replaced, so it is
+    /// neither counted against the budget nor evicted.
+    ///
+    /// Returns the evicted keys, or [`Error::OutOfMemory`] if no room can be
+    /// made. Nothing is evicted in that case.
+    fn make_room(&mut self, config: &DbConfig, key: &str, size: usize) -> Result<Vec<String>> {
+        let Some(maxmemory) = config.maxmemory else {
+            return Ok(vec![]);
+        };
+
+        let replaced = self
+            .entries
+            .get(key)
+            .map_or(0, |entry| key.len() + entry.value.size());
+        let mut used = self.used_memory - replaced;
+        if used + size <= maxmemory {
+            return Ok(vec![]);
+        }
+        if size > maxmemory || config.maxmemory_policy == EvictionPolicy::NoEviction {
+            return Err(Error::OutOfMemory);
+        }
+
+        let mut evicted = vec![];
+        while used + size > maxmemory {
+            let mut candidates = self.entries.iter().filter(|(k, _)| k.as_str() != key);
+            let victim = match config.maxmemory_policy {
+                EvictionPolicy::NoEviction => unreachable!("checked above"),
+                EvictionPolicy::AllKeysLru => candidates
+                    .min_by_key(|(_, entry)| entry.last_access.load(Ordering::Relaxed))
+                    .map(|(k, _)| k.clone()),
+                EvictionPolicy::AllKeysLfu => {
+                    let now = self.now();
+                    // Among the least frequently used keys, the least recently
+                    // used one is evicted.
+                    candidates
+                        .min_by_key(|(_, entry)| {
+                            (
+                                entry.frequency(now),
+                                entry.last_access.load(Ordering::Relaxed),
+                            )
+                        })
+                        .map(|(k, _)| k.clone())
+                }
+                EvictionPolicy::AllKeysRandom => {
+                    let count = self.entries.len() - usize::from(replaced > 0);
+                    candidates
+                        .nth(fastrand::usize(..count))
+                        .map(|(k, _)| k.clone())
+                }
+            };
+            // As `size` fits in the budget on its own, there is always a
+            // candidate left while over budget.
+            let victim = victim.expect("over budget without eviction candidates");
+
+            debug!("evicting {victim:?}");
+            self.remove_entry(&victim);
+            used = self.used_memory - replaced;
+            evicted.push(victim);
+        }
+
+        Ok(evicted)
+    }
+
+    /// Like [`DbState::make_room`], for growing the value stored at `key` by
+    /// `growth` bytes, or creating `key` with a value of `growth` bytes.
+    fn make_room_to_grow(
+        &mut self,
+        config: &DbConfig,
+        key: &str,
+        growth: usize,
+    ) -> Result<Vec<String>> {
+        // Sizing the current value walks the whole collection, so it is only
+        // done once the budget is at stake.
+        let fits = config
+            .maxmemory
+            .is_none_or(|maxmemory| self.used_memory + key.len() + growth <= maxmemory);
+        if fits {
+            return Ok(vec![]);
+        }
+
+        let current = self.entries.get(key).map_or(0, |entry| entry.value.size());
+        self.make_room(config, key, key.len() + current + growth)
+    }
+
+    /// Publishes a message to a given channel, returning the number of
+    /// subscribers listening on it.
+    fn publish(&self, channel: &str, message: Bytes) -> usize {
+        self.pub_sub
+            .get(channel)
+            // On a successful message send on the broadcast channel,
+            // the number of subscribers is returned. An error indicates there are
+            // no receivers, in which case, `0` should be returned.
+            .map(|tx| tx.send(message).unwrap_or(0))
+            // If there is no entry for the channel key, there are no subscribers.
+            // So return `0`.
+            .unwrap_or(0)
+    }
+
     fn next_expiration(&self) -> Option<Instant> {
         self.expirations
             .iter()
@@ -303,6 +3161,127 @@ impl DbState {
     }
 }
 
+/// Takes the string out of `data` to edit it in place.
+///
+/// The buffer is only copied if it is still shared, for instance with a reply
+/// which was not written out yet.
+fn take_mut(data: &mut Bytes) -> BytesMut {
+    BytesMut::from(std::mem::take(data))
+}
+
+/// Returns `true` if a time to live expiring at `when` may replace the one
+/// expiring at `previous`, if any, under `condition`.
+///
+/// A missing time to live counts as an infinite one.
+fn expire_condition_met(
+    condition: Option<ExpireCondition>,
+    previous: Option<Instant>,
+    when: Instant,
+) -> bool {
+    match condition {
+        None => true,
+        Some(ExpireCondition::Nx) => previous.is_none(),
+        Some(ExpireCondition::Xx) => previous.is_some(),
+        Some(ExpireCondition::Gt) => previous.is_some_and(|previous| when > previous),
+        Some(ExpireCondition::Lt) => previous.is_none_or(|previous| when < previous),
+    }
+}
+
+/// Resolves the inclusive rank range from `start` to `stop` over a collection
+/// of `len` elements.
+///
+/// Negative ranks count from the end of the collection. Out of range ranks are
+/// clamped, and `None` is returned if the range is empty.
+fn rank_range(start: i64, stop: i64, len: usize) -> Option<std::ops::RangeInclusive<usize>> {
+    let len = len as i64;
+    let start = if start < 0 { start + len } else { start }.max(0);
+    let stop = if stop < 0 { stop + len } else { stop }.min(len - 1);
+    (start <= stop).then_some(start as usize..=stop as usize)
+}
+
+/// Collects `count` of `items` after skipping `offset` ones, as given by
+/// `limit`, or every item if `limit` is `None`.
+///
+/// A negative `count` collects every item after `offset`, while a negative
+/// `offset` collects nothing.
+fn limit<T>(items: impl Iterator<Item = T>, limit: Option<(i64, i64)>) -> Vec<T> {
+    let Some((offset, count)) = limit else {
+        return items.collect();
+    };
+    let Ok(offset) = usize::try_from(offset) else {
+        return vec![];
+    };
+    let count = usize::try_from(count).unwrap_or(usize::MAX);
+    items.skip(offset).take(count).collect()
+}
+
+/// Picks `count` random items out of `items`.
+///
+/// A positive `count` picks distinct items, at most every one of them, in a
+/// random order. A negative `count` picks exactly `-count` items, which may
+/// repeat.
+fn random_sample<T: Copy>(mut items: Vec<T>, count: i64) -> Vec<T> {
+    if items.is_empty() {
+        return vec![];
+    }
+    match usize::try_from(count) {
+        Ok(count) => {
+            fastrand::shuffle(&mut items);
+            items.truncate(count);
+            items
+        }
+        Err(_) => std::iter::repeat_with(|| items[fastrand::usize(..items.len())])
+            .take(count.unsigned_abs() as usize)
+            .collect(),
+    }
+}
+
+/// Returns one page of a cursor based iteration over `items`.
+///
+/// The cursor is the position of the next item to visit, starting at `0`. Up to
+/// `count` items are visited, and the ones whose name, as given by `name`, match
+/// the glob `pattern` are returned. The returned cursor is `0` once every item
+/// was visited.
+///
+/// Collections are iterated in order, so every item present for the whole
+/// iteration is returned, as long as no item before it is removed meanwhile.
+fn scan<T>(
+    items: impl Iterator<Item = T>,
+    cursor: u64,
+    count: usize,
+    pattern: Option<&[u8]>,
+    name: impl Fn(&T) -> &Bytes,
+) -> (u64, Vec<T>) {
+    let start = usize::try_from(cursor).unwrap_or(usize::MAX);
+    let mut items = items.skip(start).peekable();
+    let mut visited = 0;
+    let mut page = vec![];
+    while visited < count {
+        let Some(item) = items.next() else {
+            break;
+        };
+        visited += 1;
+        if pattern.is_none_or(|pattern| glob::matches(pattern, name(&item))) {
+            page.push(item);
+        }
+    }
+
+    let cursor = match items.peek() {
+        Some(_) => (start + visited) as u64,
+        None => 0,
+    };
+    (cursor, page)
+}
+
+/// Resolves `index` into a position in a collection of `len` elements.
+///
+/// Negative indices count from the end of the collection. Returns `None` if
+/// the index is out of range.
+fn list_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { index + len as i64 } else { index };
+    usize::try_from(index).ok().filter(|index| *index < len)
+}
+
 /// Routine executed by the background task.
 ///
 /// Wait to be notified. On notification, purge any expired keys from the shared
@@ -314,7 +3293,12 @@ async fn purge_expired_tasks(shared: Arc<DbSharedState>) {
         // Purge all keys that are expired. The function returns the instant at
         // which the **next** key will expire. The worker should wait until the
         // instant has passed then purge again.
-        if let Some(when) = shared.purge_expired_keys() {
+        let start = Instant::now();
+        let (next, _) = shared.purge_expired_keys();
+        if let Some(ExpireCycleCallback(callback)) = &*shared.on_expire_cycle.read().unwrap() {
+            callback(start.elapsed());
+        }
+        if let Some(when) = next {
             // Wait until the next key expires **or** until the background task
             // is notified. If the task is notified, then it must reload its
             // state as new keys have been set to expire early. This is done by
@@ -339,3 +3323,557 @@ async fn purge_expired_tasks(shared: Arc<DbSharedState>) {
 
     debug!("purge background task shutdown");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cmd::BitFieldType, Frame};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_keyspace_expired_event() {
+        let db = Db::new(DbConfig {
+            notify_keyspace_events: true,
+            ..Default::default()
+        });
+        let mut events = db.subscribe("__keyevent@0__:expired".to_string());
+        let mut keyspace = db.subscribe("__keyspace@0__:foo".to_string());
+
+        db.set(
+            "foo".to_string(),
+            Bytes::from("bar"),
+            Some(Duration::from_secs(1)),
+        )
+        .unwrap();
+        assert_eq!(keyspace.recv().await.unwrap(), "set");
+
+        // The purge task publishes the event once the TTL lapses.
+        assert_eq!(events.recv().await.unwrap(), "foo");
+        assert_eq!(keyspace.recv().await.unwrap(), "expired");
+        assert_eq!(db.get("foo").unwrap(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_iter_string_snapshot() {
+        let db = Db::new(DbConfig {
+            active_expiration: false,
+            ..Default::default()
+        });
+        db.set("foo".to_string(), Bytes::from("a"), None).unwrap();
+        db.set(
+            "bar".to_string(),
+            Bytes::from("b"),
+            Some(Duration::from_secs(10)),
+        )
+        .unwrap();
+        db.set(
+            "baz".to_string(),
+            Bytes::from("c"),
+            Some(Duration::from_secs(1)),
+        )
+        .unwrap();
+        db.sadd("set", vec![Bytes::from("d")]).unwrap();
+        db.set("foo".to_string(), Bytes::from("e"), None).unwrap();
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+
+        let mut snapshot = db.iter_string_snapshot();
+        snapshot.sort();
+        assert_eq!(
+            snapshot,
+            vec![
+                (
+                    "bar".to_string(),
+                    Bytes::from("b"),
+                    Some(Duration::from_secs(8))
+                ),
+                ("foo".to_string(), Bytes::from("e"), None),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_get_entry() {
+        let db = Db::default();
+        db.set(
+            "foo".to_string(),
+            Bytes::from("a"),
+            Some(Duration::from_secs(10)),
+        )
+        .unwrap();
+        db.set("bar".to_string(), Bytes::from("b"), None).unwrap();
+        db.sadd("set", vec![Bytes::from("c")]).unwrap();
+
+        tokio::time::advance(Duration::from_secs(4)).await;
+        let entry = db.get_entry("foo").unwrap().unwrap();
+        assert_eq!(entry.value, Bytes::from("a"));
+        assert_eq!(entry.ttl, Some(Duration::from_secs(6)));
+        assert_eq!(db.get_entry("bar").unwrap().unwrap().ttl, None);
+        assert_eq!(db.get_entry("missing").unwrap(), None);
+        assert!(matches!(db.get_entry("set"), Err(Error::WrongType)));
+
+        tokio::time::advance(Duration::from_secs(6)).await;
+        assert_eq!(db.get_entry("foo").unwrap(), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_entry_is_consistent_under_concurrent_writes() {
+        let db = Db::default();
+        db.set("foo".to_string(), Bytes::from("0"), None).unwrap();
+
+        // Each value records the TTL it is set with, in hours, with only the
+        // odd ones expiring, so a reader can tell whether the TTL it got
+        // belongs to the value.
+        let writer = std::thread::spawn({
+            let db = db.clone();
+            move || {
+                for hours in 1..=2000u64 {
+                    let ttl = (hours % 2 == 1).then(|| Duration::from_secs(hours * 3600));
+                    db.set("foo".to_string(), Bytes::from(hours.to_string()), ttl)
+                        .unwrap();
+                }
+            }
+        });
+
+        while !writer.is_finished() {
+            let entry = db.get_entry("foo").unwrap().unwrap();
+            let hours: u64 = std::str::from_utf8(&entry.value).unwrap().parse().unwrap();
+            match entry.ttl {
+                None => assert_eq!(hours % 2, 0, "{hours} has no TTL"),
+                Some(ttl) => {
+                    assert_eq!(hours % 2, 1, "{hours} has a TTL");
+                    let set = Duration::from_secs(hours * 3600);
+                    assert!(ttl <= set && ttl > set - Duration::from_secs(60));
+                }
+            }
+        }
+        writer.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_publish_prunes_channels_without_subscribers() {
+        let db = Db::default();
+        let subscriber = db.subscribe("foo".to_string());
+        assert_eq!(db.publish("foo", Bytes::from("a")), 1);
+
+        drop(subscriber);
+        assert_eq!(db.publish("foo", Bytes::from("b")), 0);
+        assert!(!db.shared.state.read().unwrap().pub_sub.contains_key("foo"));
+
+        // Subscribing again creates a new channel.
+        let _subscriber = db.subscribe("foo".to_string());
+        assert_eq!(db.publish("foo", Bytes::from("c")), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_on_expire_callback() {
+        let db = Db::default();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        db.on_expire(move |key| tx.send(key.to_string()).unwrap());
+
+        db.set(
+            "foo".to_string(),
+            Bytes::from("bar"),
+            Some(Duration::from_secs(1)),
+        )
+        .unwrap();
+        db.set("baz".to_string(), Bytes::from("qux"), None).unwrap();
+
+        assert_eq!(rx.recv().await.unwrap(), "foo");
+        assert_eq!(db.get("foo").unwrap(), None);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_observes_expiration_before_purge() {
+        let db = Db::default();
+        db.set(
+            "foo".to_string(),
+            Bytes::from("bar"),
+            Some(Duration::from_millis(1)),
+        )
+        .unwrap();
+
+        // Blocking the current-thread runtime keeps the purge task from
+        // running, so only `get` itself can observe the expiration.
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(db.get("foo").unwrap(), None);
+        let state = db.shared.state.read().unwrap();
+        assert!(state.entries.is_empty());
+        assert!(state.expirations.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_lazy_expiration_without_purge_task() {
+        let db = Db::new(DbConfig {
+            active_expiration: false,
+            ..Default::default()
+        });
+        let ttl = Some(Duration::from_secs(1));
+        db.set("foo".to_string(), Bytes::from("a"), ttl).unwrap();
+        db.set("bar".to_string(), Bytes::from("b"), ttl).unwrap();
+
+        // Time passes and other tasks get to run, but nothing purges the
+        // expired keys.
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        tokio::task::yield_now().await;
+        assert_eq!(db.len(), 2);
+
+        // Accessing a key still observes its expiration.
+        assert_eq!(db.get("foo").unwrap(), None);
+        assert_eq!(db.len(), 1);
+        assert_eq!(db.sweep_expired(), 1);
+        assert_eq!(db.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_set_returning_previous() {
+        let db = Db::default();
+        let ttl = Some(Duration::from_secs(10));
+
+        let previous = db
+            .set_returning_previous("foo".to_string(), Bytes::from("a"), ttl)
+            .unwrap();
+        assert_eq!(previous, None);
+
+        // Overwriting drops the expiration of the previous value.
+        let previous = db
+            .set_returning_previous("foo".to_string(), Bytes::from("b"), ttl)
+            .unwrap();
+        assert_eq!(previous, Some(Bytes::from("a")));
+        assert_eq!(db.shared.state.read().unwrap().expirations.len(), 1);
+        let previous = db
+            .set_returning_previous("foo".to_string(), Bytes::from("c"), None)
+            .unwrap();
+        assert_eq!(previous, Some(Bytes::from("b")));
+        {
+            let state = db.shared.state.read().unwrap();
+            assert!(state.expirations.is_empty());
+            assert!(state.entries["foo"].expires_at.is_none());
+        }
+
+        // Other types are not replaced.
+        db.push("list", vec![Bytes::from("a")], ListEnd::Left)
+            .unwrap();
+        let err = db
+            .set_returning_previous("list".to_string(), Bytes::from("a"), None)
+            .unwrap_err();
+        assert!(matches!(err, Error::WrongType));
+        assert_eq!(db.llen("list").unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_allkeys_lru_evicts_least_recently_used() {
+        let db = Db::new(DbConfig {
+            maxmemory: Some(12),
+            maxmemory_policy: EvictionPolicy::AllKeysLru,
+            ..Default::default()
+        });
+
+        // Each entry accounts for 4 bytes, filling the budget.
+        for key in ["a", "b", "c"] {
+            db.set(key.to_string(), Bytes::from("val"), None).unwrap();
+        }
+        // Touch `a`, making `b` the least recently used key.
+        assert!(db.get("a").unwrap().is_some());
+
+        db.set("d".to_string(), Bytes::from("val"), None).unwrap();
+        assert_eq!(db.get("b").unwrap(), None);
+        assert_eq!(db.keys().len(), 3);
+
+        // Replacing a value does not evict anything else.
+        db.set("d".to_string(), Bytes::from("new"), None).unwrap();
+        assert_eq!(db.keys().len(), 3);
+        assert_eq!(db.shared.state.read().unwrap().used_memory, 12);
+    }
+
+    #[tokio::test]
+    async fn test_incr_by_float_respects_memory_budget() {
+        let db = Db::new(DbConfig {
+            maxmemory: Some(4),
+            ..Default::default()
+        });
+        assert_eq!(db.incr_by_float("f", 1.5).unwrap(), 1.5);
+
+        let err = db.incr_by_float("f", 0.25).unwrap_err();
+        assert!(matches!(err, Error::OutOfMemory));
+        assert_eq!(db.get("f").unwrap(), Some(Bytes::from("1.5")));
+    }
+
+    #[tokio::test]
+    async fn test_allkeys_lfu_evicts_least_frequently_used() {
+        let db = Db::new(DbConfig {
+            maxmemory: Some(12),
+            maxmemory_policy: EvictionPolicy::AllKeysLfu,
+            ..Default::default()
+        });
+
+        db.set("hot".to_string(), Bytes::from("v"), None).unwrap();
+        for _ in 0..200 {
+            assert!(db.get("hot").unwrap().is_some());
+        }
+        db.set("one".to_string(), Bytes::from("v"), None).unwrap();
+        assert!(db.get("one").unwrap().is_some());
+        // `new` is the most recently used key, but was never read.
+        db.set("new".to_string(), Bytes::from("v"), None).unwrap();
+        assert!(db.frequency("hot").unwrap() > db.frequency("one").unwrap());
+        assert!(db.frequency("one").unwrap() > db.frequency("new").unwrap());
+
+        db.set("foo".to_string(), Bytes::from("v"), None).unwrap();
+        assert_eq!(db.get("new").unwrap(), None);
+        assert!(db.get("hot").unwrap().is_some());
+        assert!(db.get("one").unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_noeviction_rejects_writes_over_budget() {
+        let db = Db::new(DbConfig {
+            maxmemory: Some(8),
+            ..Default::default()
+        });
+
+        db.set("a".to_string(), Bytes::from("val"), None).unwrap();
+        db.set("b".to_string(), Bytes::from("val"), None).unwrap();
+        let err = db
+            .set("c".to_string(), Bytes::from("val"), None)
+            .unwrap_err();
+        assert!(matches!(err, Error::OutOfMemory));
+        assert_eq!(
+            err.to_frame(),
+            Some(Frame::SimpleError(
+                "OOM command not allowed when used memory > 'maxmemory'.".to_string()
+            ))
+        );
+        assert_eq!(db.keys().len(), 2);
+
+        // Removing a key frees its memory.
+        assert!(db.del("a"));
+        db.set("c".to_string(), Bytes::from("val"), None).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sadd_respects_memory_budget() {
+        let db = Db::new(DbConfig {
+            maxmemory: Some(12),
+            maxmemory_policy: EvictionPolicy::AllKeysLru,
+            ..Default::default()
+        });
+        db.set("a".to_string(), Bytes::from("val"), None).unwrap();
+        db.set("b".to_string(), Bytes::from("val"), None).unwrap();
+        assert_eq!(
+            db.sadd("s", vec![Bytes::from("x"), Bytes::from("y")])
+                .unwrap(),
+            2
+        );
+
+        // Growing the set past the budget evicts the least recently used key.
+        assert_eq!(db.sadd("s", vec![Bytes::from("zzz")]).unwrap(), 1);
+        assert_eq!(db.get("a").unwrap(), None);
+        assert!(db.get("b").unwrap().is_some());
+        assert_eq!(db.sunion(&["s".to_string()]).unwrap().len(), 3);
+
+        let db = Db::new(DbConfig {
+            maxmemory: Some(8),
+            ..Default::default()
+        });
+        db.set("a".to_string(), Bytes::from("val"), None).unwrap();
+        assert_eq!(db.sadd("s", vec![Bytes::from("xyz")]).unwrap(), 1);
+
+        let err = db.sadd("s", vec![Bytes::from("w")]).unwrap_err();
+        assert!(matches!(err, Error::OutOfMemory));
+        assert_eq!(db.sunion(&["s".to_string()]).unwrap().len(), 1);
+        assert!(db.get("a").unwrap().is_some());
+
+        // Adding members already in the set takes no more memory.
+        assert_eq!(db.sadd("s", vec![Bytes::from("xyz")]).unwrap(), 0);
+
+        // A key of another type is rejected before anything is evicted.
+        let err = db.sadd("a", vec![Bytes::from("x")]).unwrap_err();
+        assert!(matches!(err, Error::WrongType));
+        for policy in [EvictionPolicy::AllKeysLru, EvictionPolicy::AllKeysRandom] {
+            let db = Db::new(DbConfig {
+                maxmemory: Some(8),
+                maxmemory_policy: policy,
+                ..Default::default()
+            });
+            db.set("a".to_string(), Bytes::from("val"), None).unwrap();
+            db.set("b".to_string(), Bytes::from("val"), None).unwrap();
+            let err = db.sadd("a", vec![Bytes::from("xyz")]).unwrap_err();
+            assert!(matches!(err, Error::WrongType));
+            assert_eq!(db.keys().len(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_zadd_respects_memory_budget() {
+        let db = Db::new(DbConfig {
+            maxmemory: Some(20),
+            maxmemory_policy: EvictionPolicy::AllKeysLru,
+            ..Default::default()
+        });
+        db.set("a".to_string(), Bytes::from("val"), None).unwrap();
+        assert_eq!(db.zadd("z", vec![(1.0, Bytes::from("m"))]).unwrap(), 1);
+
+        // Adding a member to the sorted set evicts `a`.
+        assert_eq!(db.zadd("z", vec![(2.0, Bytes::from("n"))]).unwrap(), 1);
+        assert_eq!(db.get("a").unwrap(), None);
+        assert_eq!(db.zrange("z", 0, -1).unwrap().len(), 2);
+
+        // The sorted set alone does not fit with a third member.
+        let err = db.zadd("z", vec![(3.0, Bytes::from("o"))]).unwrap_err();
+        assert!(matches!(err, Error::OutOfMemory));
+        assert_eq!(db.zrange("z", 0, -1).unwrap().len(), 2);
+
+        // Updating the score of a member takes no more memory.
+        assert_eq!(db.zadd("z", vec![(3.0, Bytes::from("n"))]).unwrap(), 0);
+        assert_eq!(db.zscore("z", b"n").unwrap(), Some(3.0));
+
+        // Adding to a key of another type evicts nothing.
+        let db = Db::new(DbConfig {
+            maxmemory: Some(8),
+            maxmemory_policy: EvictionPolicy::AllKeysLru,
+            ..Default::default()
+        });
+        db.set("a".to_string(), Bytes::from("val"), None).unwrap();
+        db.set("b".to_string(), Bytes::from("val"), None).unwrap();
+        let err = db.zadd("a", vec![(1.0, Bytes::from("m"))]).unwrap_err();
+        assert!(matches!(err, Error::WrongType));
+        assert_eq!(db.keys().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_list_writes_respect_memory_budget() {
+        let db = Db::new(DbConfig {
+            maxmemory: Some(8),
+            ..Default::default()
+        });
+        db.push("l", vec![Bytes::from("abc")], ListEnd::Right)
+            .unwrap();
+
+        let err = db
+            .push("l", vec![Bytes::from("defgh")], ListEnd::Left)
+            .unwrap_err();
+        assert!(matches!(err, Error::OutOfMemory));
+        let err = db.lset("l", 0, Bytes::from("abcdefgh")).unwrap_err();
+        assert!(matches!(err, Error::OutOfMemory));
+        assert_eq!(db.lindex("l", 0).unwrap(), Some(Bytes::from("abc")));
+
+        // Replacing an element with a shorter one always fits.
+        db.lset("l", 0, Bytes::from("a")).unwrap();
+        assert_eq!(db.shared.state.read().unwrap().used_memory, 2);
+
+        // Pushing onto a key of another type evicts nothing.
+        let db = Db::new(DbConfig {
+            maxmemory: Some(8),
+            maxmemory_policy: EvictionPolicy::AllKeysLru,
+            ..Default::default()
+        });
+        db.set("a".to_string(), Bytes::from("val"), None).unwrap();
+        db.set("b".to_string(), Bytes::from("val"), None).unwrap();
+        for end in [ListEnd::Left, ListEnd::Right] {
+            let err = db.push("a", vec![Bytes::from("xyz")], end).unwrap_err();
+            assert!(matches!(err, Error::WrongType));
+        }
+        assert_eq!(db.keys().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_hash_writes_respect_memory_budget() {
+        let db = Db::new(DbConfig {
+            maxmemory: Some(8),
+            ..Default::default()
+        });
+        db.hset("h", vec![(Bytes::from("a"), Bytes::from("1"))])
+            .unwrap();
+
+        let err = db
+            .hset("h", vec![(Bytes::from("bcd"), Bytes::from("efg"))])
+            .unwrap_err();
+        assert!(matches!(err, Error::OutOfMemory));
+        let err = db.hincr_by("h", Bytes::from("a"), 999_999).unwrap_err();
+        assert!(matches!(err, Error::OutOfMemory));
+        assert_eq!(db.hget("h", b"a").unwrap(), Some(Bytes::from("1")));
+
+        assert_eq!(db.hincr_by("h", Bytes::from("a"), 9_999).unwrap(), 10_000);
+        assert_eq!(db.shared.state.read().unwrap().used_memory, 7);
+
+        // Overwriting a field only takes the difference in size.
+        db.hset("h", vec![(Bytes::from("a"), Bytes::from("abcdef"))])
+            .unwrap();
+        assert_eq!(db.shared.state.read().unwrap().used_memory, 8);
+
+        // Setting a field of a key of another type evicts nothing.
+        let db = Db::new(DbConfig {
+            maxmemory: Some(8),
+            maxmemory_policy: EvictionPolicy::AllKeysLru,
+            ..Default::default()
+        });
+        db.set("a".to_string(), Bytes::from("val"), None).unwrap();
+        db.set("b".to_string(), Bytes::from("val"), None).unwrap();
+        let err = db
+            .hset("a", vec![(Bytes::from("f"), Bytes::from("v"))])
+            .unwrap_err();
+        assert!(matches!(err, Error::WrongType));
+        assert_eq!(db.keys().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_setbit_respects_memory_budget() {
+        let db = Db::new(DbConfig {
+            maxmemory: Some(8),
+            ..Default::default()
+        });
+        db.setbit("b", 0, true).unwrap();
+
+        // Growing the string to 8 bytes does not fit along with the key.
+        let err = db.setbit("b", 63, true).unwrap_err();
+        assert!(matches!(err, Error::OutOfMemory));
+        assert!(db.getbit("b", 0).unwrap());
+        assert!(!db.getbit("b", 63).unwrap());
+
+        assert!(!db.setbit("b", 55, true).unwrap());
+        assert_eq!(db.shared.state.read().unwrap().used_memory, 8);
+    }
+
+    #[tokio::test]
+    async fn test_bitfield_respects_memory_budget() {
+        let db = Db::new(DbConfig {
+            maxmemory: Some(8),
+            ..Default::default()
+        });
+        let set = |offset, value| BitFieldOp::Set {
+            ty: BitFieldType::Unsigned(8),
+            offset,
+            value,
+        };
+        assert_eq!(db.bitfield("b", &[set(0, 1)]).unwrap(), vec![0]);
+
+        let err = db.bitfield("b", &[set(8, 2), set(56, 3)]).unwrap_err();
+        assert!(matches!(err, Error::OutOfMemory));
+        assert_eq!(db.get("b").unwrap(), Some(Bytes::from_static(&[1])));
+
+        assert_eq!(db.bitfield("b", &[set(48, 3)]).unwrap(), vec![0]);
+        assert_eq!(db.shared.state.read().unwrap().used_memory, 8);
+    }
+
+    #[tokio::test]
+    async fn test_pfadd_respects_memory_budget() {
+        let db = Db::new(DbConfig {
+            maxmemory: Some(1024),
+            ..Default::default()
+        });
+
+        let err = db.pfadd("hll", &[Bytes::from("a")]).unwrap_err();
+        assert!(matches!(err, Error::OutOfMemory));
+        assert!(!db.exists("hll"));
+    }
+
+    #[tokio::test]
+    async fn test_keyspace_events_disabled() {
+        let db = Db::default();
+        let mut keyspace = db.subscribe("__keyspace@0__:foo".to_string());
+
+        db.set("foo".to_string(), Bytes::from("bar"), None).unwrap();
+        assert!(keyspace.try_recv().is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! Binary serialization of a single value, produced by `DUMP` and consumed by
+//! `RESTORE`.
+//!
+//! # Format
+//!
+//! ```text
+//! type:u8 body version:u16 checksum:u64
+//! ```
+//!
+//! Integers are little endian. The body depends on the type:
+//!
+//! * string -- `bytes`
+//! * list, set -- `len:u64` followed by `len` times `bytes`
+//! * sorted set -- `len:u64` followed by `len` times `bytes score:f64`
+//! * hash -- `len:u64` followed by `len` times `bytes bytes`
+//!
+//! Where `bytes` is `len:u64` followed by `len` bytes. The checksum is the
+//! FNV-1a hash of everything preceding it.
+//!
+//! # Snapshot format
+//!
+//! Snapshots of the whole keyspace, taken by `DEBUG RELOAD`, are made of the
+//! payloads of every value:
+//!
+//! ```text
+//! len:u64 entries version:u16 checksum:u64
+//! ```
+//!
+//! Where every entry is `key:bytes expires_at:u64 payload:bytes`, and
+//! `expires_at` is the expiration of the key in milliseconds since the unix
+//! epoch, or `0` if the key does not expire.
+use std::{
+    collections::{BTreeSet, VecDeque},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use tokio::time::Instant;
+
+use crate::{db::Value, hash::Hash, sorted_set::SortedSet, Error, Result};
+
+/// Version of the format, bumped on incompatible changes.
+const VERSION: u16 = 1;
+
+/// Length of the `version` and `checksum` trailer.
+const TRAILER_LEN: usize = 2 + 8;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_LIST: u8 = 1;
+const TYPE_SET: u8 = 2;
+const TYPE_ZSET: u8 = 3;
+const TYPE_HASH: u8 = 4;
+
+/// Serialize `value` into a payload understood by [`deserialize`].
+pub(crate) fn serialize(value: &Value) -> Bytes {
+    let mut buf = BytesMut::new();
+    match value {
+        Value::String(data) => {
+            buf.put_u8(TYPE_STRING);
+            put_bytes(&mut buf, data);
+        }
+        Value::List(items) => {
+            buf.put_u8(TYPE_LIST);
+            buf.put_u64_le(items.len() as u64);
+            items.iter().for_each(|item| put_bytes(&mut buf, item));
+        }
+        Value::Set(members) => {
+            buf.put_u8(TYPE_SET);
+            buf.put_u64_le(members.len() as u64);
+            members
+                .iter()
+                .for_each(|member| put_bytes(&mut buf, member));
+        }
+        Value::ZSet(zset) => {
+            buf.put_u8(TYPE_ZSET);
+            buf.put_u64_le(zset.len() as u64);
+            for (member, score) in zset.iter() {
+                put_bytes(&mut buf, member);
+                buf.put_f64_le(score);
+            }
+        }
+        Value::Hash(hash) => {
+            // Times to live of fields are not serialized, and expired fields
+            // are left out.
+            let fields: Vec<_> = hash.iter(Instant::now()).collect();
+            buf.put_u8(TYPE_HASH);
+            buf.put_u64_le(fields.len() as u64);
+            for (field, value) in fields {
+                put_bytes(&mut buf, field);
+                put_bytes(&mut buf, value);
+            }
+        }
+    }
+    buf.put_u16_le(VERSION);
+    let checksum = fnv1a(&buf);
+    buf.put_u64_le(checksum);
+    buf.freeze()
+}
+
+/// Deserialize a payload produced by [`serialize`].
+///
+/// [`Error::BadDumpPayload`] is returned if the payload was not produced by
+/// this version of the format, or is corrupted.
+pub(crate) fn deserialize(payload: &[u8]) -> Result<Value> {
+    let Some(body_len) = payload.len().checked_sub(TRAILER_LEN) else {
+        return Err(Error::BadDumpPayload);
+    };
+    let (data, mut checksum) = payload.split_at(body_len + 2);
+    if checksum.get_u64_le() != fnv1a(data) {
+        return Err(Error::BadDumpPayload);
+    }
+    let (mut body, mut version) = data.split_at(body_len);
+    if version.get_u16_le() != VERSION {
+        return Err(Error::BadDumpPayload);
+    }
+
+    let value = match get_u8(&mut body)? {
+        TYPE_STRING => Value::String(get_bytes(&mut body)?),
+        TYPE_LIST => {
+            let len = get_u64(&mut body)?;
+            let mut items = VecDeque::new();
+            for _ in 0..len {
+                items.push_back(get_bytes(&mut body)?);
+            }
+            Value::List(items)
+        }
+        TYPE_SET => {
+            let len = get_u64(&mut body)?;
+            let mut members = BTreeSet::new();
+            for _ in 0..len {
+                members.insert(get_bytes(&mut body)?);
+            }
+            Value::Set(members)
+        }
+        TYPE_ZSET => {
+            let len = get_u64(&mut body)?;
+            let mut zset = SortedSet::new();
+            for _ in 0..len {
+                let member = get_bytes(&mut body)?;
+                let score = f64::from_bits(get_u64(&mut body)?);
+                if score.is_nan() {
+                    return Err(Error::BadDumpPayload);
+                }
+                zset.insert(member, score);
+            }
+            Value::ZSet(zset)
+        }
+        TYPE_HASH => {
+            let len = get_u64(&mut body)?;
+            let mut hash = Hash::new();
+            for _ in 0..len {
+                hash.insert(get_bytes(&mut body)?, get_bytes(&mut body)?);
+            }
+            Value::Hash(hash)
+        }
+        _ => return Err(Error::BadDumpPayload),
+    };
+
+    // The whole body must make up the value.
+    if body.has_remaining() {
+        return Err(Error::BadDumpPayload);
+    }
+    Ok(value)
+}
+
+/// A key of a snapshot, with its value and expiration.
+pub(crate) type SnapshotEntry = (String, Value, Option<SystemTime>);
+
+/// Serialize `entries` into a snapshot understood by
+/// [`deserialize_snapshot`].
+///
+/// Expirations are stored as wall clock times, so they keep counting down
+/// while the snapshot is not loaded.
+pub(crate) fn serialize_snapshot<'a>(
+    entries: impl ExactSizeIterator<Item = (&'a str, &'a Value, Option<SystemTime>)>,
+) -> Bytes {
+    let mut buf = BytesMut::new();
+    buf.put_u64_le(entries.len() as u64);
+    for (key, value, expires_at) in entries {
+        put_bytes(&mut buf, key.as_bytes());
+        let expires_at = expires_at.map_or(0, |when| {
+            when.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64
+        });
+        buf.put_u64_le(expires_at);
+        put_bytes(&mut buf, &serialize(value));
+    }
+    buf.put_u16_le(VERSION);
+    let checksum = fnv1a(&buf);
+    buf.put_u64_le(checksum);
+    buf.freeze()
+}
+
+/// Deserialize a snapshot produced by [`serialize_snapshot`].
+///
+/// [`Error::BadDumpPayload`] is returned if the snapshot was not produced by
+/// this version of the format, or is corrupted.
+pub(crate) fn deserialize_snapshot(snapshot: &[u8]) -> Result<Vec<SnapshotEntry>> {
+    let Some(body_len) = snapshot.len().checked_sub(TRAILER_LEN) else {
+        return Err(Error::BadDumpPayload);
+    };
+    let (data, mut checksum) = snapshot.split_at(body_len + 2);
+    if checksum.get_u64_le() != fnv1a(data) {
+        return Err(Error::BadDumpPayload);
+    }
+    let (mut body, mut version) = data.split_at(body_len);
+    if version.get_u16_le() != VERSION {
+        return Err(Error::BadDumpPayload);
+    }
+
+    let len = get_u64(&mut body)?;
+    let mut entries = vec![];
+    for _ in 0..len {
+        let key =
+            String::from_utf8(get_bytes(&mut body)?.to_vec()).map_err(|_| Error::BadDumpPayload)?;
+        let expires_at = match get_u64(&mut body)? {
+            0 => None,
+            millis => Some(UNIX_EPOCH + Duration::from_millis(millis)),
+        };
+        let value = deserialize(&get_bytes(&mut body)?)?;
+        entries.push((key, value, expires_at));
+    }
+
+    // The whole body must make up the entries.
+    if body.has_remaining() {
+        return Err(Error::BadDumpPayload);
+    }
+    Ok(entries)
+}
+
+fn put_bytes(buf: &mut BytesMut, data: &[u8]) {
+    buf.put_u64_le(data.len() as u64);
+    buf.put_slice(data);
+}
+
+fn get_u8(buf: &mut &[u8]) -> Result<u8> {
+    if !buf.has_remaining() {
+        return Err(Error::BadDumpPayload);
+    }
+    Ok(buf.get_u8())
+}
+
+fn get_u64(buf: &mut &[u8]) -> Result<u64> {
+    if buf.remaining() < 8 {
+        return Err(Error::BadDumpPayload);
+    }
+    Ok(buf.get_u64_le())
+}
+
+fn get_bytes(buf: &mut &[u8]) -> Result<Bytes> {
+    let len = usize::try_from(get_u64(buf)?).map_err(|_| Error::BadDumpPayload)?;
+    if buf.remaining() < len {
+        return Err(Error::BadDumpPayload);
+    }
+    Ok(buf.copy_to_bytes(len))
+}
+
+/// The 64 bits FNV-1a hash of `data`.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_corruption() {
+        let mut zset = SortedSet::new();
+        zset.insert(Bytes::from("a"), 1.5);
+        zset.insert(Bytes::from("b"), -2.0);
+        let values = [
+            Value::String(Bytes::from("bar")),
+            Value::List(VecDeque::from([Bytes::from("a"), Bytes::from("b")])),
+            Value::Set(BTreeSet::from([Bytes::from("a"), Bytes::from("b")])),
+            Value::ZSet(zset),
+            Value::Hash([(Bytes::from("f"), Bytes::from("v"))].into_iter().collect()),
+        ];
+        for value in values {
+            let payload = serialize(&value);
+            assert_eq!(deserialize(&payload).unwrap(), value);
+
+            let mut corrupted = payload.to_vec();
+            corrupted[1] ^= 1;
+            assert!(matches!(
+                deserialize(&corrupted),
+                Err(Error::BadDumpPayload)
+            ));
+            assert!(matches!(
+                deserialize(&payload[..payload.len() - 1]),
+                Err(Error::BadDumpPayload)
+            ));
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::Frame;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -15,10 +16,209 @@ pub enum Error {
     #[error("end of stream error")]
     /// Attempting to extract a value failed due to the frame being fully consumed
     EndOfStream,
-    #[error("unknown command `{0}`")]
-    UnknownCommand(String),
+    #[error(
+        "unknown command '{name}', with args beginning with: {}",
+        quote_args(.args)
+    )]
+    /// The command name is not supported, `args` are the arguments it was sent with
+    UnknownCommand { name: String, args: Vec<String> },
+    #[error("unknown subcommand '{subcommand}'. Try {command} HELP.")]
+    /// The subcommand is not supported by `command`
+    UnknownSubcommand { command: String, subcommand: String },
+    #[error("wrong number of arguments for '{0}' command")]
+    /// A command was given the wrong number of arguments
+    WrongNumberOfArguments(String),
     #[error("wrong frame type: {0}")]
     WrongFrameType(String),
     #[error("response error: {0}")]
     Response(String),
+    #[error("value is not a valid float")]
+    /// The value stored at a key cannot be interpreted as a float
+    NotAFloat,
+    #[error("increment would produce NaN or Infinity")]
+    /// A floating point operation resulted in a non-finite number
+    NanOrInfinity,
+    #[error("command not allowed when used memory > 'maxmemory'.")]
+    /// Storing a value would exceed the memory budget, and no room can be made
+    OutOfMemory,
+    #[error("Operation against a key holding the wrong kind of value")]
+    /// The key holds a value of a different type than the command expects
+    WrongType,
+    #[error("syntax error")]
+    /// The arguments of a command are malformed
+    Syntax,
+    #[error("no such key")]
+    /// The command requires the key to exist
+    NoSuchKey,
+    #[error("index out of range")]
+    /// The index is outside of the collection
+    IndexOutOfRange,
+    #[error("value is not an integer or out of range")]
+    /// The value stored at a key, or an argument, is not an integer, or an
+    /// increment of it overflows
+    NotAnInteger,
+    #[error("hash value is not an integer")]
+    /// The value stored at a hash field cannot be interpreted as an integer
+    HashNotAnInteger,
+    #[error("increment or decrement would overflow")]
+    /// An integer operation overflowed
+    Overflow,
+    #[error("source and destination objects are the same")]
+    /// A command operating on two keys was given the same key twice
+    SameObject,
+    #[error("DB index is out of range")]
+    /// The database index does not exist
+    DbIndexOutOfRange,
+    #[error("Authentication required.")]
+    /// The connection must authenticate before issuing commands
+    NoAuth,
+    #[error("invalid username-password pair or user is disabled.")]
+    /// The credentials given to `AUTH` do not match
+    WrongPass,
+    #[error(
+        "AUTH <password> called without any password configured for the default user. \
+         Are you sure your configuration is correct?"
+    )]
+    /// `AUTH` was called while no password is configured
+    NoPasswordSet,
+    #[error("MULTI calls can not be nested")]
+    /// `MULTI` was called inside a transaction
+    NestedMulti,
+    #[error("EXEC without MULTI")]
+    /// `EXEC` was called outside of a transaction
+    ExecWithoutMulti,
+    #[error("DISCARD without MULTI")]
+    /// `DISCARD` was called outside of a transaction
+    DiscardWithoutMulti,
+    #[error("Transaction discarded because of previous errors.")]
+    /// A command failed to be queued, so the transaction was discarded
+    ExecAbort,
+    #[error("Command not allowed inside a transaction")]
+    /// The command cannot be queued in a transaction
+    NotAllowedInTransaction,
+    #[error(
+        "Can't execute '{0}': only SUBSCRIBE / UNSUBSCRIBE / PING / RESET are allowed in this context"
+    )]
+    /// The command cannot be issued while subscribed to channels
+    NotAllowedInSubscribe(String),
+    #[error("bit offset is not an integer or out of range")]
+    /// The bit offset is negative or past the maximum string length
+    BitOffsetOutOfRange,
+    #[error("output buffer limit exceeded, {0} bytes pending")]
+    /// Too many bytes are buffered for a connection, as its peer does not
+    /// read them fast enough
+    OutputBufferLimit(usize),
+    #[error("bit is not an integer or out of range")]
+    /// The bit value is neither `0` nor `1`
+    BitOutOfRange,
+    #[error("Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.")]
+    /// A `BITFIELD` type is not a signed or unsigned integer of a supported width
+    InvalidBitFieldType,
+    #[error("subscriber lagged behind, {skipped} messages dropped on channel '{channel}'")]
+    /// A subscriber did not keep up with the messages published on a channel
+    Lagged { channel: String, skipped: u64 },
+    #[error("DUMP payload version or checksum are wrong")]
+    /// The payload given to `RESTORE` was not produced by `DUMP`
+    BadDumpPayload,
+    #[error("Target key name already exists.")]
+    /// `RESTORE` would overwrite an existing key without `REPLACE`
+    BusyKey,
+    #[error("Key is not a valid HyperLogLog string value.")]
+    /// The string stored at the key was not created by `PFADD`
+    InvalidHyperLogLog,
+    #[error("unsupported protocol version")]
+    /// `HELLO` was given a protocol version other than `2` or `3`
+    NoProto,
+    #[error("cannot subscribe to '{0}', maximum subscriptions exceeded")]
+    /// The connection is subscribed to as many channels as it may be
+    TooManySubscriptions(String),
+    #[error("Invalid command specified")]
+    /// `COMMAND GETKEYS` was given an unknown command
+    InvalidCommand,
+    #[error("Invalid number of arguments specified for command")]
+    /// `COMMAND GETKEYS` was given a command with the wrong number of
+    /// arguments
+    InvalidCommandArguments,
+    #[error("The command has no key arguments")]
+    /// `COMMAND GETKEYS` was given a command not operating on keys
+    NoKeyArguments,
+    #[error("numkeys should be greater than 0")]
+    /// The number of keys given to a command is not positive
+    NumKeysNotPositive,
+    #[error("count should be greater than 0")]
+    /// The `COUNT` option of a command is not positive
+    CountNotPositive,
+    #[error("LIMIT can't be negative")]
+    /// The `LIMIT` option of a command is negative
+    LimitNegative,
+}
+
+/// Render arguments like Redis does in error messages, e.g. `'foo', 'bar'`.
+fn quote_args(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| format!("'{arg}'"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Error {
+    /// Convert the error into the `SimpleError` frame reported to the peer.
+    ///
+    /// Returns `None` if the error is not recoverable, meaning the connection
+    /// must be terminated instead, such as for IO errors.
+    pub(crate) fn to_frame(&self) -> Option<Frame> {
+        match self {
+            Error::Io(_) | Error::IncompleteFrame | Error::OutputBufferLimit(_) => None,
+            err @ Error::OutOfMemory => Some(Frame::SimpleError(format!("OOM {err}"))),
+            err @ (Error::WrongType | Error::InvalidHyperLogLog) => {
+                Some(Frame::SimpleError(format!("WRONGTYPE {err}")))
+            }
+            err @ Error::NoAuth => Some(Frame::SimpleError(format!("NOAUTH {err}"))),
+            err @ Error::WrongPass => Some(Frame::SimpleError(format!("WRONGPASS {err}"))),
+            err @ Error::ExecAbort => Some(Frame::SimpleError(format!("EXECABORT {err}"))),
+            err @ Error::BusyKey => Some(Frame::SimpleError(format!("BUSYKEY {err}"))),
+            err @ Error::NoProto => Some(Frame::SimpleError(format!("NOPROTO {err}"))),
+            // These carry internal details, the peer gets the error Redis
+            // would reply with instead.
+            Error::EndOfStream => Some(Frame::SimpleError(
+                "ERR wrong number of arguments".to_string(),
+            )),
+            Error::WrongFrameType(_) => Some(Frame::SimpleError("ERR syntax error".to_string())),
+            Error::Conversion(_) | Error::Utf8(_) => Some(Frame::SimpleError(
+                "ERR value is not an integer or out of range".to_string(),
+            )),
+            err => Some(Frame::SimpleError(format!("ERR {err}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_internal_errors_are_not_leaked() {
+        let conversion = u8::try_from(256).unwrap_err();
+        let utf8 = String::from_utf8(vec![0xff]).unwrap_err();
+        for (err, expected) in [
+            (Error::EndOfStream, "ERR wrong number of arguments"),
+            (
+                Error::WrongFrameType("expected array".to_string()),
+                "ERR syntax error",
+            ),
+            (
+                Error::Conversion(conversion),
+                "ERR value is not an integer or out of range",
+            ),
+            (
+                Error::Utf8(utf8),
+                "ERR value is not an integer or out of range",
+            ),
+        ] {
+            assert_eq!(
+                err.to_frame(),
+                Some(Frame::SimpleError(expected.to_string()))
+            );
+        }
+    }
 }
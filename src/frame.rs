@@ -1,6 +1,6 @@
 use crate::{Error, Result};
-use bytes::{Buf, Bytes};
-use std::io::Cursor;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::{fmt::Write, io::Cursor};
 
 /// A frame in Redis Serialization Protocol (RESP).
 ///
@@ -8,18 +8,57 @@ use std::io::Cursor;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Frame {
     // RESP 2
+    /// A string without line breaks, such as `OK`.
     SimpleString(String),
+    /// An error reply, starting with its error code.
     SimpleError(String),
+    /// A signed 64 bits integer.
     Integer(i64),
+    /// A binary safe string.
     BulkString(Bytes),
+    /// An ordered collection of frames.
     Array(Vec<Frame>),
+    /// The RESP2 null, as a bulk string.
     NullBulkString,
+    /// The RESP2 null, as an array.
     NullArray,
     // RESP 3
+    /// The RESP3 null.
     Null,
+    /// An ordered collection of key and value pairs.
+    Map(Vec<(Frame, Frame)>),
+    /// Out of band data, such as pub/sub messages.
+    Push(Vec<Frame>),
+}
+
+/// Version of the protocol negotiated by a connection with `HELLO`.
+///
+/// Frames only available in RESP3 are encoded with their closest RESP2
+/// equivalent on a [`Protocol::Resp2`] connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Protocol {
+    /// RESP2, which every connection starts with.
+    #[default]
+    Resp2,
+    /// RESP3.
+    Resp3,
+}
+
+impl Protocol {
+    /// The version number of the protocol, as given to `HELLO`.
+    pub fn version(&self) -> i64 {
+        match self {
+            Protocol::Resp2 => 2,
+            Protocol::Resp3 => 3,
+        }
+    }
 }
 
 impl Frame {
+    /// Check whether a whole frame can be parsed from `src`, advancing it past
+    /// the frame.
+    ///
+    /// [`Error::IncompleteFrame`] is returned if more data is needed.
     pub fn check(src: &mut Cursor<&[u8]>) -> Result<()> {
         match get_u8(src)? {
             b'+' | b'-' => {
@@ -42,12 +81,30 @@ impl Frame {
                 }
             }
             b'*' => {
-                let len = get_decimal_signed(src)?;
+                let len = get_array_len(src)?.unwrap_or_default();
                 for _ in 0..len {
                     Frame::check(src)?;
                 }
                 Ok(())
             }
+            b'>' => {
+                let len = get_decimal_unsigned(src)?;
+                for _ in 0..len {
+                    Frame::check(src)?;
+                }
+                Ok(())
+            }
+            b'%' => {
+                let len = get_decimal_unsigned(src)?;
+                // Each entry of a map is a key frame followed by a value frame.
+                let frames = len
+                    .checked_mul(2)
+                    .ok_or_else(|| Error::Protocol(format!("invalid map length {len}")))?;
+                for _ in 0..frames {
+                    Frame::check(src)?;
+                }
+                Ok(())
+            }
             b'_' => {
                 let line = get_line(src)?;
                 if line != b"" {
@@ -62,6 +119,7 @@ impl Frame {
         }
     }
 
+    /// Parse a frame from `src`, which was validated with [`Frame::check`].
     pub fn parse(src: &mut Cursor<&[u8]>) -> Result<Frame> {
         // The first byte of the frame indicates the data type.
         match get_u8(src)? {
@@ -100,17 +158,31 @@ impl Frame {
                 }
             }
             b'*' => {
-                let decimal = get_decimal_signed(src)?;
-                if decimal == -1 {
+                let Some(len) = get_array_len(src)? else {
                     return Ok(Frame::NullArray);
-                }
-                let len: usize = decimal.try_into()?;
+                };
                 let mut out = Vec::with_capacity(len);
                 for _ in 0..len {
                     out.push(Frame::parse(src)?);
                 }
                 Ok(Frame::Array(out))
             }
+            b'%' => {
+                let len: usize = get_decimal_unsigned(src)?.try_into()?;
+                let mut out = Vec::with_capacity(len);
+                for _ in 0..len {
+                    out.push((Frame::parse(src)?, Frame::parse(src)?));
+                }
+                Ok(Frame::Map(out))
+            }
+            b'>' => {
+                let len: usize = get_decimal_unsigned(src)?.try_into()?;
+                let mut out = Vec::with_capacity(len);
+                for _ in 0..len {
+                    out.push(Frame::parse(src)?);
+                }
+                Ok(Frame::Push(out))
+            }
             b'_' => {
                 let line = get_line(src)?;
                 if line != b"" {
@@ -127,13 +199,123 @@ impl Frame {
         }
     }
 
+    /// Serialize the frame into `dst`, in its RESP3 representation.
+    ///
+    /// Frames of every type are written as they are, see
+    /// [`Frame::encode_as`] to serialize them for a given [`Protocol`].
+    pub fn encode(&self, dst: &mut BytesMut) {
+        self.encode_as(Protocol::Resp3, dst)
+    }
+
+    /// Serialize the frame into a new buffer, in its RESP3 representation.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut buf = BytesMut::new();
+        self.encode(&mut buf);
+        buf.freeze()
+    }
+
+    /// Render the frame as human readable text, the way `redis-cli` prints
+    /// replies.
+    ///
+    /// Used for connections sending inline commands, such as `nc` sessions.
+    /// Nested frames are rendered over several lines, without a trailing
+    /// newline.
+    pub fn to_text(&self) -> String {
+        match self {
+            Frame::SimpleString(val) => val.clone(),
+            Frame::SimpleError(val) => format!("(error) {val}"),
+            Frame::Integer(val) => format!("(integer) {val}"),
+            Frame::BulkString(val) => quote(val),
+            Frame::NullBulkString | Frame::NullArray | Frame::Null => "(nil)".to_string(),
+            Frame::Array(frames) | Frame::Push(frames) => {
+                let items = frames.iter().map(|frame| (String::new(), frame.to_text()));
+                numbered_text(items.collect(), ")")
+            }
+            Frame::Map(pairs) => {
+                let items = pairs
+                    .iter()
+                    .map(|(key, value)| (key.to_text() + " => ", value.to_text()));
+                numbered_text(items.collect(), "#")
+            }
+        }
+    }
+
+    /// Serialize the frame into `dst`, as sent on a connection which
+    /// negotiated `protocol`.
+    ///
+    /// Under RESP2, frames only available in RESP3 are written as their
+    /// closest RESP2 equivalent.
+    pub fn encode_as(&self, protocol: Protocol, dst: &mut BytesMut) {
+        match self {
+            Frame::SimpleString(val) => {
+                dst.put_u8(b'+');
+                dst.put_slice(val.as_bytes());
+                dst.put_slice(b"\r\n");
+            }
+            Frame::SimpleError(val) => {
+                dst.put_u8(b'-');
+                dst.put_slice(val.as_bytes());
+                dst.put_slice(b"\r\n");
+            }
+            Frame::Integer(val) => {
+                dst.put_u8(b':');
+                write_decimal(*val, dst);
+            }
+            Frame::BulkString(val) => {
+                dst.put_u8(b'$');
+                write_decimal(val.len() as i64, dst);
+                dst.put_slice(val);
+                dst.put_slice(b"\r\n");
+            }
+            Frame::NullBulkString => dst.put_slice(b"$-1\r\n"),
+            Frame::NullArray => dst.put_slice(b"*-1\r\n"),
+            Frame::Array(frames) => {
+                dst.put_u8(b'*');
+                write_decimal(frames.len() as i64, dst);
+                for frame in frames {
+                    frame.encode_as(protocol, dst);
+                }
+            }
+            Frame::Null => dst.put_slice(b"_\r\n"),
+            Frame::Map(pairs) => {
+                // RESP2 has no maps, they are flattened into an array of
+                // alternating keys and values.
+                match protocol {
+                    Protocol::Resp2 => {
+                        dst.put_u8(b'*');
+                        write_decimal(pairs.len() as i64 * 2, dst);
+                    }
+                    Protocol::Resp3 => {
+                        dst.put_u8(b'%');
+                        write_decimal(pairs.len() as i64, dst);
+                    }
+                }
+                for (key, value) in pairs {
+                    key.encode_as(protocol, dst);
+                    value.encode_as(protocol, dst);
+                }
+            }
+            Frame::Push(frames) => {
+                // RESP2 has no out of band data, pushes are sent as arrays.
+                match protocol {
+                    Protocol::Resp2 => dst.put_u8(b'*'),
+                    Protocol::Resp3 => dst.put_u8(b'>'),
+                }
+                write_decimal(frames.len() as i64, dst);
+                for frame in frames {
+                    frame.encode_as(protocol, dst);
+                }
+            }
+        }
+    }
+
     pub(crate) fn array() -> Self {
         Frame::Array(vec![])
     }
 
     pub(crate) fn push_bulk(&mut self, bytes: Bytes) -> Result<()> {
         match self {
-            Frame::Array(vec) => {
+            Frame::Array(vec) | Frame::Push(vec) => {
                 vec.push(Frame::BulkString(bytes));
                 Ok(())
             }
@@ -144,9 +326,22 @@ impl Frame {
         }
     }
 
+    pub(crate) fn push_frame(&mut self, frame: Frame) -> Result<()> {
+        match self {
+            Frame::Array(vec) | Frame::Push(vec) => {
+                vec.push(frame);
+                Ok(())
+            }
+            ty => Err(Error::WrongFrameType(format!(
+                "cannot push to non-array frame type, type was {:?}",
+                ty
+            ))),
+        }
+    }
+
     pub(crate) fn push_int(&mut self, value: i64) -> Result<()> {
         match self {
-            Frame::Array(vec) => {
+            Frame::Array(vec) | Frame::Push(vec) => {
                 vec.push(Frame::Integer(value));
                 Ok(())
             }
@@ -158,6 +353,54 @@ impl Frame {
     }
 }
 
+/// Render `val` as a double quoted string, escaping quotes, backslashes and
+/// non printable bytes.
+fn quote(val: &[u8]) -> String {
+    let mut text = String::from("\"");
+    for byte in val {
+        match byte {
+            b'"' => text.push_str("\\\""),
+            b'\\' => text.push_str("\\\\"),
+            b'\n' => text.push_str("\\n"),
+            b'\r' => text.push_str("\\r"),
+            b'\t' => text.push_str("\\t"),
+            0x20..=0x7e => text.push(char::from(*byte)),
+            _ => write!(text, "\\x{byte:02x}").expect("writing to a String is infallible"),
+        }
+    }
+    text.push('"');
+    text
+}
+
+/// Render numbered `items`, each made of a prefix and its rendered text.
+///
+/// Lines following the first one of an item are indented past its number,
+/// so nested aggregates line up.
+fn numbered_text(items: Vec<(String, String)>, separator: &str) -> String {
+    if items.is_empty() {
+        return "(empty array)".to_string();
+    }
+    let width = items.len().to_string().len();
+    let mut lines = vec![];
+    for (i, (prefix, text)) in items.into_iter().enumerate() {
+        let number = format!("{:>width$}{separator} {prefix}", i + 1);
+        let indent = " ".repeat(width + separator.len() + 1);
+        for (j, line) in text.lines().enumerate() {
+            match j {
+                0 => lines.push(format!("{number}{line}")),
+                _ => lines.push(format!("{indent}{line}")),
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// Writes `val` as a decimal number followed by a CRLF.
+fn write_decimal(val: i64, dst: &mut BytesMut) {
+    // Writing into a `BytesMut` never fails, it grows as needed.
+    write!(dst, "{val}\r\n").expect("writing to a BytesMut is infallible");
+}
+
 fn get_u8(src: &mut Cursor<&[u8]>) -> Result<u8> {
     if !src.has_remaining() {
         return Err(Error::IncompleteFrame);
@@ -196,6 +439,20 @@ fn get_decimal_unsigned(src: &mut Cursor<&[u8]>) -> Result<u64> {
     atoi(line).ok_or(Error::Protocol("invalid frame format".into()))
 }
 
+/// Reads the length of an array, `None` standing for the null array.
+///
+/// Like bulk string lengths, a leading `+` is tolerated, while `-1` is the
+/// only valid negative length.
+fn get_array_len(src: &mut Cursor<&[u8]>) -> Result<Option<usize>> {
+    match get_decimal_signed(src)? {
+        -1 => Ok(None),
+        len if len < 0 => Err(Error::Protocol(format!(
+            "invalid frame format, only valid negative length is -1, got `{len}`"
+        ))),
+        len => Ok(Some(len.try_into()?)),
+    }
+}
+
 fn get_line<'a>(src: &'a mut Cursor<&[u8]>) -> Result<&'a [u8]> {
     let start = src.position() as usize;
     let end = src.get_ref().len() - 1;
@@ -227,6 +484,8 @@ mod tests {
             b"*-1\r\n",
             b"*0\r\n",
             b"_\r\n",
+            b"%1\r\n+key\r\n:1\r\n",
+            b">2\r\n+message\r\n$3\r\nfoo\r\n",
         ];
         for frame in frames {
             match_frame(frame);
@@ -247,6 +506,8 @@ mod tests {
             Frame::NullBulkString => (),
             Frame::NullArray => (),
             Frame::Null => (),
+            Frame::Map(_) => (),
+            Frame::Push(_) => (),
         }
     }
 
@@ -362,6 +623,46 @@ mod tests {
         assert!(frame.is_err());
     }
 
+    #[test]
+    fn test_array_len_with_plus_sign() {
+        let src = b"*+2\r\n+OK\r\n$6\r\nfoobar\r\n".as_slice();
+        let mut buf = Cursor::new(src);
+        Frame::check(&mut buf).unwrap();
+        assert_eq!(buf.position() as usize, src.len());
+
+        let mut buf = Cursor::new(src);
+        let frame = Frame::parse(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            Frame::Array(vec![
+                Frame::SimpleString("OK".to_string()),
+                Frame::BulkString(Bytes::from("foobar")),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_negative_array_len() {
+        for src in [&b"*-2\r\n"[..], b"*-100\r\n+OK\r\n"] {
+            let err = Frame::check(&mut Cursor::new(src)).unwrap_err();
+            assert!(matches!(err, Error::Protocol(_)), "{err}");
+            let err = Frame::parse(&mut Cursor::new(src)).unwrap_err();
+            assert!(matches!(err, Error::Protocol(_)), "{err}");
+        }
+        // Pushes and maps have no null form.
+        for src in [&b">-1\r\n"[..], b"%-1\r\n"] {
+            assert!(Frame::check(&mut Cursor::new(src)).is_err());
+            assert!(Frame::parse(&mut Cursor::new(src)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_overflowing_map_len() {
+        let mut src = Cursor::new(&b"%9223372036854775808\r\n"[..]);
+        let err = Frame::check(&mut src).unwrap_err();
+        assert!(matches!(err, Error::Protocol(_)), "{err}");
+    }
+
     #[test]
     fn test_null_array() {
         let mut buf = Cursor::new(b"*-1\r\n".as_slice());
@@ -403,6 +704,68 @@ mod tests {
         assert!(frame.is_err());
     }
 
+    #[test]
+    fn test_encode_every_variant() {
+        let bulk = || Frame::BulkString(Bytes::from("foo"));
+        let cases: &[(Frame, &[u8])] = &[
+            (Frame::SimpleString("OK".to_string()), b"+OK\r\n"),
+            (Frame::SimpleError("ERR oops".to_string()), b"-ERR oops\r\n"),
+            (Frame::Integer(-42), b":-42\r\n"),
+            (bulk(), b"$3\r\nfoo\r\n"),
+            (Frame::BulkString(Bytes::new()), b"$0\r\n\r\n"),
+            (
+                Frame::Array(vec![bulk(), Frame::Integer(1)]),
+                b"*2\r\n$3\r\nfoo\r\n:1\r\n",
+            ),
+            (Frame::Array(vec![]), b"*0\r\n"),
+            (Frame::NullBulkString, b"$-1\r\n"),
+            (Frame::NullArray, b"*-1\r\n"),
+            (Frame::Null, b"_\r\n"),
+            (
+                Frame::Map(vec![(bulk(), Frame::Integer(1))]),
+                b"%1\r\n$3\r\nfoo\r\n:1\r\n",
+            ),
+            (Frame::Push(vec![bulk()]), b">1\r\n$3\r\nfoo\r\n"),
+        ];
+        for (frame, expected) in cases {
+            let mut buf = BytesMut::new();
+            frame.encode(&mut buf);
+            assert_eq!(&buf[..], *expected, "{frame:?}");
+            assert_eq!(&frame.to_bytes()[..], *expected);
+            // The encoded frame parses back to itself.
+            let mut src = Cursor::new(*expected);
+            assert_eq!(&Frame::parse(&mut src).unwrap(), frame);
+        }
+
+        // RESP3 types are downgraded for RESP2.
+        let mut buf = BytesMut::new();
+        Frame::Map(vec![(bulk(), Frame::Push(vec![Frame::Integer(1)]))])
+            .encode_as(Protocol::Resp2, &mut buf);
+        assert_eq!(&buf[..], b"*2\r\n$3\r\nfoo\r\n*1\r\n:1\r\n");
+    }
+
+    #[test]
+    fn test_to_text() {
+        let bulk = |val: &'static str| Frame::BulkString(Bytes::from(val));
+        assert_eq!(Frame::SimpleString("OK".to_string()).to_text(), "OK");
+        assert_eq!(
+            Frame::SimpleError("ERR oops".to_string()).to_text(),
+            "(error) ERR oops"
+        );
+        assert_eq!(Frame::Integer(-1).to_text(), "(integer) -1");
+        assert_eq!(bulk("say \"hi\"\n").to_text(), r#""say \"hi\"\n""#);
+        assert_eq!(Frame::NullBulkString.to_text(), "(nil)");
+        assert_eq!(Frame::array().to_text(), "(empty array)");
+        assert_eq!(
+            Frame::Array(vec![bulk("a"), Frame::Array(vec![bulk("b"), bulk("c")])]).to_text(),
+            "1) \"a\"\n2) 1) \"b\"\n   2) \"c\""
+        );
+        assert_eq!(
+            Frame::Map(vec![(bulk("proto"), Frame::Integer(3))]).to_text(),
+            "1# \"proto\" => (integer) 3"
+        );
+    }
+
     #[test]
     fn test_invalid_frame() {
         let mut buf = Cursor::new(b"invalid frame\r\n".as_slice());
@@ -0,0 +1,138 @@
+//! Glob-style pattern matching, used by the `MATCH` option of the scan
+//! commands.
+//!
+//! Supported patterns:
+//!
+//! * `?` matches any single byte.
+//! * `*` matches any sequence of bytes, including the empty one.
+//! * `[abc]` matches one of the listed bytes, `[^abc]` any byte not listed,
+//!   and `[a-z]` a range of bytes.
+//! * `\x` matches the byte `x` literally.
+
+/// Returns `true` if `string` matches the glob `pattern`.
+pub(crate) fn matches(pattern: &[u8], string: &[u8]) -> bool {
+    // Position to resume from after the last `*`: the pattern right after the
+    // star, and the next string position it should be tried against.
+    let mut backtrack = None;
+    let (mut p, mut s) = (0, 0);
+
+    while s < string.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p += 1;
+                backtrack = Some((p, s + 1));
+                continue;
+            }
+            Some(b'?') => {
+                p += 1;
+                s += 1;
+                continue;
+            }
+            Some(b'[') => {
+                if let Some((true, len)) = match_class(&pattern[p + 1..], string[s]) {
+                    p += 1 + len;
+                    s += 1;
+                    continue;
+                }
+            }
+            Some(b'\\') if pattern.get(p + 1) == Some(&string[s]) => {
+                p += 2;
+                s += 1;
+                continue;
+            }
+            // An escaped byte which does not match.
+            Some(b'\\') if p + 1 < pattern.len() => {}
+            Some(&c) if c == string[s] => {
+                p += 1;
+                s += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        // Mismatch, let the last `*` absorb one more byte.
+        match backtrack {
+            Some((star_p, star_s)) => {
+                p = star_p;
+                s = star_s;
+                backtrack = Some((star_p, star_s + 1));
+            }
+            None => return false,
+        }
+    }
+
+    // The remaining pattern must only match the empty string.
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// Matches `c` against the character class at the start of `class`, right
+/// after the opening `[`.
+///
+/// Returns whether `c` is in the class, and the length of the class including
+/// the closing `]`. Returns `None` if the class is not terminated.
+fn match_class(class: &[u8], c: u8) -> Option<(bool, usize)> {
+    let (negated, mut i) = match class.first() {
+        Some(b'^') => (true, 1),
+        _ => (false, 0),
+    };
+
+    let mut matched = false;
+    loop {
+        match *class.get(i)? {
+            b']' => return Some((matched != negated, i + 1)),
+            b'\\' => {
+                matched |= *class.get(i + 1)? == c;
+                i += 2;
+            }
+            start if class.get(i + 1) == Some(&b'-') && class.get(i + 2) != Some(&b']') => {
+                let end = *class.get(i + 2)?;
+                let (low, high) = if start <= end {
+                    (start, end)
+                } else {
+                    (end, start)
+                };
+                matched |= (low..=high).contains(&c);
+                i += 3;
+            }
+            other => {
+                matched |= other == c;
+                i += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_matches() {
+        let cases: &[(&str, &str, bool)] = &[
+            ("*", "", true),
+            ("*", "anything", true),
+            ("h?llo", "hello", true),
+            ("h?llo", "hllo", false),
+            ("h*llo", "heeeello", true),
+            ("h*llo", "hello world", false),
+            ("*o*d", "hello world", true),
+            ("h[ae]llo", "hallo", true),
+            ("h[ae]llo", "hillo", false),
+            ("h[^e]llo", "hallo", true),
+            ("h[^e]llo", "hello", false),
+            ("h[a-c]llo", "hbllo", true),
+            ("h[a-c]llo", "hdllo", false),
+            ("h\\*llo", "h*llo", true),
+            ("h\\*llo", "hello", false),
+            ("field:*", "field:1", true),
+            ("field:*", "other:1", false),
+        ];
+        for (pattern, string, expected) in cases {
+            assert_eq!(
+                matches(pattern.as_bytes(), string.as_bytes()),
+                *expected,
+                "{pattern:?} against {string:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! The hash data structure backing the `H*` commands.
+use bytes::Bytes;
+use std::collections::BTreeMap;
+use tokio::time::Instant;
+
+/// A map of fields to values, where each field may have a time to live.
+///
+/// Expired fields are not removed on their own: the read methods skip them,
+/// and [`Hash::remove_expired`] drops them for good.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Hash {
+    /// The value of each field. Fields are kept sorted, so replies listing
+    /// them are deterministic.
+    fields: BTreeMap<Bytes, Bytes>,
+    /// The instant at which each field with a time to live expires.
+    expirations: BTreeMap<Bytes, Instant>,
+}
+
+impl Hash {
+    /// Creates an empty hash.
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of fields, including the expired ones.
+    pub(crate) fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Approximate memory used by the hash, as the byte length of its fields
+    /// and values.
+    pub(crate) fn size(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|(field, value)| field.len() + value.len())
+            .sum()
+    }
+
+    /// Returns `true` if the hash has fields, all of which expired at `now`.
+    ///
+    /// Such a hash counts as a missing key.
+    pub(crate) fn is_expired(&self, now: Instant) -> bool {
+        !self.fields.is_empty()
+            && self.expirations.len() == self.fields.len()
+            && self.expirations.values().all(|&when| when <= now)
+    }
+
+    /// Get the value of `field`, unless it expired at `now`.
+    pub(crate) fn get(&self, field: &[u8], now: Instant) -> Option<&Bytes> {
+        self.fields
+            .get(field)
+            .filter(|_| !self.is_field_expired(field, now))
+    }
+
+    /// Iterate the fields which have not expired at `now`, with their values.
+    pub(crate) fn iter(&self, now: Instant) -> impl Iterator<Item = (&Bytes, &Bytes)> {
+        self.fields
+            .iter()
+            .filter(move |(field, _)| !self.is_field_expired(field, now))
+    }
+
+    /// Iterate every field with its value, including the expired ones.
+    pub(crate) fn iter_all(&self) -> impl Iterator<Item = (&Bytes, &Bytes)> {
+        self.fields.iter()
+    }
+
+    /// Sets `field` to `value`, clearing its time to live.
+    ///
+    /// Returns `true` if `field` was added.
+    pub(crate) fn insert(&mut self, field: Bytes, value: Bytes) -> bool {
+        self.expirations.remove(&field);
+        self.fields.insert(field, value).is_none()
+    }
+
+    /// Removes `field`, along with its time to live.
+    ///
+    /// Returns `true` if `field` was in the hash.
+    pub(crate) fn remove(&mut self, field: &[u8]) -> bool {
+        self.expirations.remove(field);
+        self.fields.remove(field).is_some()
+    }
+
+    /// Removes the fields which expired at `now`.
+    ///
+    /// Returns the number of removed fields.
+    pub(crate) fn remove_expired(&mut self, now: Instant) -> usize {
+        let expired: Vec<Bytes> = self
+            .expirations
+            .iter()
+            .filter(|(_, &when)| when <= now)
+            .map(|(field, _)| field.clone())
+            .collect();
+        for field in &expired {
+            self.remove(field);
+        }
+        expired.len()
+    }
+
+    /// Returns the instant at which `field` expires, if it has a time to live.
+    pub(crate) fn expires_at(&self, field: &[u8]) -> Option<Instant> {
+        self.expirations.get(field).copied()
+    }
+
+    /// Sets `field` to expire at `when`.
+    ///
+    /// Returns `false` if `field` is not in the hash.
+    pub(crate) fn expire(&mut self, field: &Bytes, when: Instant) -> bool {
+        if !self.fields.contains_key(field) {
+            return false;
+        }
+        self.expirations.insert(field.clone(), when);
+        true
+    }
+
+    /// Removes the time to live of `field`.
+    ///
+    /// Returns `true` if `field` had a time to live.
+    pub(crate) fn persist(&mut self, field: &[u8]) -> bool {
+        self.expirations.remove(field).is_some()
+    }
+
+    /// Returns `true` if `field` has a time to live which lapsed at `now`.
+    fn is_field_expired(&self, field: &[u8], now: Instant) -> bool {
+        self.expirations.get(field).is_some_and(|&when| when <= now)
+    }
+}
+
+impl FromIterator<(Bytes, Bytes)> for Hash {
+    fn from_iter<I: IntoIterator<Item = (Bytes, Bytes)>>(iter: I) -> Self {
+        Self {
+            fields: iter.into_iter().collect(),
+            expirations: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_expired_fields_are_skipped() {
+        let now = Instant::now();
+        let mut hash: Hash = [
+            (Bytes::from("a"), Bytes::from("1")),
+            (Bytes::from("b"), Bytes::from("2")),
+        ]
+        .into_iter()
+        .collect();
+
+        assert!(hash.expire(&Bytes::from("a"), now + Duration::from_secs(1)));
+        assert!(!hash.expire(&Bytes::from("c"), now));
+        assert_eq!(hash.get(b"a", now), Some(&Bytes::from("1")));
+
+        let later = now + Duration::from_secs(1);
+        assert_eq!(hash.get(b"a", later), None);
+        assert_eq!(hash.get(b"b", later), Some(&Bytes::from("2")));
+        assert_eq!(hash.iter(later).count(), 1);
+        assert!(!hash.is_expired(later));
+
+        // Setting the field again clears its time to live.
+        hash.insert(Bytes::from("a"), Bytes::from("3"));
+        assert_eq!(hash.expires_at(b"a"), None);
+        assert_eq!(hash.get(b"a", later), Some(&Bytes::from("3")));
+
+        hash.expire(&Bytes::from("a"), now);
+        hash.expire(&Bytes::from("b"), now);
+        assert!(hash.is_expired(now));
+        assert_eq!(hash.remove_expired(now), 2);
+        assert_eq!(hash.len(), 0);
+    }
+}
@@ -0,0 +1,134 @@
+//! Monitor of the latency spikes of the server, reported by `LATENCY`.
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Maximum number of samples kept per event.
+const MAX_SAMPLES: usize = 160;
+
+/// Event of the time taken to apply a command.
+pub(crate) const EVENT_COMMAND: &str = "command";
+
+/// Event of the time taken by a cycle of the expired keys purge.
+pub(crate) const EVENT_EXPIRE_CYCLE: &str = "expire-cycle";
+
+/// Records, per event, the latencies exceeding a threshold.
+///
+/// Samples recorded within the same second are merged, keeping the maximum
+/// latency. Once an event has too many samples, the oldest ones are dropped.
+#[derive(Debug)]
+pub(crate) struct LatencyMonitor {
+    /// Latencies at or above this are recorded. `None` disables the monitor.
+    threshold: Option<Duration>,
+    /// The history of every event, keyed by the event name.
+    events: Mutex<BTreeMap<&'static str, EventHistory>>,
+}
+
+/// The samples recorded for an event.
+#[derive(Debug, Default)]
+struct EventHistory {
+    /// The samples, oldest first.
+    samples: VecDeque<LatencySample>,
+    /// The highest latency ever recorded.
+    max: Duration,
+}
+
+/// The maximum latency of an event during a second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LatencySample {
+    /// Unix time of the second, in seconds.
+    pub(crate) timestamp: u64,
+    /// The latency.
+    pub(crate) latency: Duration,
+}
+
+/// The latest sample of an event, along with its all time maximum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LatestSample {
+    /// Name of the event.
+    pub(crate) event: &'static str,
+    /// The latest sample.
+    pub(crate) sample: LatencySample,
+    /// The highest latency ever recorded.
+    pub(crate) max: Duration,
+}
+
+impl LatencyMonitor {
+    /// Create a monitor recording latencies of at least `threshold`.
+    pub(crate) fn new(threshold: Option<Duration>) -> Self {
+        Self {
+            threshold,
+            events: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Record the `latency` of `event`, if it reaches the threshold.
+    pub(crate) fn record(&self, event: &'static str, latency: Duration) {
+        if self.threshold.is_none_or(|threshold| latency < threshold) {
+            return;
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut events = self.events.lock().unwrap();
+        let history = events.entry(event).or_default();
+        history.max = history.max.max(latency);
+        match history.samples.back_mut() {
+            Some(last) if last.timestamp == timestamp => {
+                last.latency = last.latency.max(latency);
+            }
+            _ => {
+                history
+                    .samples
+                    .push_back(LatencySample { timestamp, latency });
+                if history.samples.len() > MAX_SAMPLES {
+                    history.samples.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Returns the latest sample of every event, sorted by event name.
+    pub(crate) fn latest(&self) -> Vec<LatestSample> {
+        let events = self.events.lock().unwrap();
+        events
+            .iter()
+            .filter_map(|(event, history)| {
+                Some(LatestSample {
+                    event,
+                    sample: *history.samples.back()?,
+                    max: history.max,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the samples of `event`, oldest first.
+    pub(crate) fn history(&self, event: &str) -> Vec<LatencySample> {
+        let events = self.events.lock().unwrap();
+        events
+            .get(event)
+            .map(|history| history.samples.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Removes the samples of `events`, or of every event if it is empty.
+    ///
+    /// Returns the number of events which had samples.
+    pub(crate) fn reset(&self, events: &[String]) -> usize {
+        let mut guard = self.events.lock().unwrap();
+        if events.is_empty() {
+            let len = guard.len();
+            guard.clear();
+            return len;
+        }
+        events
+            .iter()
+            .filter(|event| guard.remove(event.as_str()).is_some())
+            .count()
+    }
+}
@@ -2,6 +2,8 @@
 //! A simple Redis clone written in Rust.
 mod parse;
 
+pub(crate) mod codec;
+
 pub(crate) mod connection;
 pub(crate) use connection::Connection;
 pub(crate) use connection::ConnectionStream;
@@ -9,15 +11,34 @@ pub(crate) use connection::ConnectionStream;
 pub(crate) mod db;
 pub(crate) use db::Db;
 
+pub(crate) mod dump;
+
 pub(crate) mod error;
 pub(crate) use error::Error;
 
 pub(crate) mod frame;
-pub(crate) use frame::Frame;
+pub use frame::Frame;
+pub use frame::Protocol;
+
+pub(crate) mod glob;
+
+pub(crate) mod hash;
+
+pub(crate) mod hyperloglog;
+
+pub(crate) mod latency;
+
+pub(crate) mod pause;
 
 pub(crate) mod shutdown;
 pub(crate) use shutdown::Shutdown;
 
+pub(crate) mod slowlog;
+
+pub(crate) mod sorted_set;
+
+pub(crate) mod stats;
+
 pub mod clients;
 pub use clients::Client;
 
@@ -26,6 +47,9 @@ pub use cmd::CommandVariant;
 
 pub mod server;
 
+pub mod store;
+pub use store::Store;
+
 /// The default port for the server to bind to.
 pub const DEFAULT_PORT: u16 = 6379;
 /// The default host/interface for the server to bind to.
@@ -1,7 +1,7 @@
 use crate::{Error, Frame, Result};
 use bytes::Bytes;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Parse {
     parts: std::vec::IntoIter<Frame>,
 }
@@ -18,10 +18,27 @@ impl Parse {
         })
     }
 
+    /// Returns the number of entries left to consume.
+    pub(crate) fn remaining(&self) -> usize {
+        self.parts.len()
+    }
+
     fn next(&mut self) -> Result<Frame> {
         self.parts.next().ok_or(Error::EndOfStream)
     }
 
+    /// Peek at the next entry as a string, without consuming it.
+    ///
+    /// Returns `None` if there are no entries left, or if the next entry is not
+    /// a simple or bulk frame holding valid UTF-8.
+    pub(crate) fn peek_string(&self) -> Option<&str> {
+        match self.parts.as_slice().first()? {
+            Frame::SimpleString(s) => Some(s),
+            Frame::BulkString(data) => std::str::from_utf8(data).ok(),
+            _ => None,
+        }
+    }
+
     #[tracing::instrument(skip_all, level = "debug")]
     pub(crate) fn next_string(&mut self) -> Result<String> {
         match self.next()? {
@@ -45,12 +62,17 @@ impl Parse {
         }
     }
 
+    /// Return the next entry as an unsigned integer.
+    ///
+    /// `Integer` frames are accepted as long as they are not negative. Simple
+    /// and bulk strings are accepted if they represent a valid unsigned
+    /// integer. Any other entry results in an error.
     pub(crate) fn next_int_unsigned(&mut self) -> Result<u64> {
         use atoi::atoi;
         let invalid_number_err = Error::Protocol("invalid number".to_string());
 
         match self.next()? {
-            Frame::Integer(v) => Ok(v as u64),
+            Frame::Integer(v) => u64::try_from(v).map_err(|_| invalid_number_err),
             Frame::SimpleString(data) => atoi::<_>(data.as_bytes()).ok_or(invalid_number_err),
             Frame::BulkString(data) => atoi::<_>(&data).ok_or(invalid_number_err),
             frame => Err(Error::Protocol(format!(
@@ -59,6 +81,70 @@ impl Parse {
         }
     }
 
+    /// Return the next entry as a signed integer.
+    ///
+    /// `Integer` frames are accepted as is. Simple and bulk strings are
+    /// accepted if they represent a valid, possibly negative, integer. Any
+    /// other entry results in an error.
+    pub(crate) fn next_int_signed(&mut self) -> Result<i64> {
+        use atoi::atoi;
+        let invalid_number_err = Error::Protocol("invalid number".to_string());
+
+        match self.next()? {
+            Frame::Integer(v) => Ok(v),
+            Frame::SimpleString(data) => atoi::<_>(data.as_bytes()).ok_or(invalid_number_err),
+            Frame::BulkString(data) => atoi::<_>(&data).ok_or(invalid_number_err),
+            frame => Err(Error::Protocol(format!(
+                "expected int frame, got {frame:?}"
+            ))),
+        }
+    }
+
+    /// Return the next entry as a float.
+    ///
+    /// `Integer` frames are converted to a float. Simple and bulk strings are
+    /// accepted if they represent a valid number, including exponent notation
+    /// and `inf`. `NaN` and any other entry result in an error.
+    pub(crate) fn next_float(&mut self) -> Result<f64> {
+        let invalid_float_err = || Error::Protocol("value is not a valid float".to_string());
+
+        let value = match self.next()? {
+            Frame::Integer(v) => v as f64,
+            Frame::SimpleString(data) => data.parse().map_err(|_| invalid_float_err())?,
+            Frame::BulkString(data) => std::str::from_utf8(&data)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or_else(invalid_float_err)?,
+            frame => {
+                return Err(Error::Protocol(format!(
+                    "expected float frame, got {frame:?}"
+                )))
+            }
+        };
+
+        if value.is_nan() {
+            return Err(invalid_float_err());
+        }
+
+        Ok(value)
+    }
+
+    /// Consume every remaining entry, rendering each of them as a string.
+    ///
+    /// Invalid UTF-8 is replaced, and non string entries are rendered with
+    /// their debug representation. This is intended for error reporting.
+    pub(crate) fn remaining_lossy(&mut self) -> Vec<String> {
+        self.parts
+            .by_ref()
+            .map(|frame| match frame {
+                Frame::SimpleString(s) => s,
+                Frame::BulkString(data) => String::from_utf8_lossy(&data).into_owned(),
+                Frame::Integer(v) => v.to_string(),
+                frame => format!("{frame:?}"),
+            })
+            .collect()
+    }
+
     pub(crate) fn finish(&mut self) -> Result<()> {
         if self.parts.next().is_none() {
             Ok(())
@@ -67,3 +153,119 @@ impl Parse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_of(parts: Vec<Frame>) -> Parse {
+        Parse::new(Frame::Array(parts)).unwrap()
+    }
+
+    #[test]
+    fn test_peek_string() {
+        let mut parse = parse_of(vec![
+            Frame::BulkString(Bytes::from("EX")),
+            Frame::Integer(10),
+            Frame::SimpleString("NX".to_string()),
+        ]);
+
+        // Peeking does not consume the entry.
+        assert_eq!(parse.peek_string(), Some("EX"));
+        assert_eq!(parse.peek_string(), Some("EX"));
+        if parse.peek_string() == Some("EX") {
+            assert_eq!(parse.next_string().unwrap(), "EX");
+            assert_eq!(parse.next_int_unsigned().unwrap(), 10);
+        }
+
+        // An unexpected option is left in place for other parsing.
+        assert_eq!(parse.peek_string(), Some("NX"));
+        if parse.peek_string() == Some("EX") {
+            panic!("unexpected option");
+        }
+        assert_eq!(parse.next_string().unwrap(), "NX");
+        assert_eq!(parse.peek_string(), None);
+        assert!(parse.finish().is_ok());
+
+        // Non string entries cannot be peeked as strings.
+        let parse = parse_of(vec![Frame::Integer(10)]);
+        assert_eq!(parse.peek_string(), None);
+    }
+
+    #[test]
+    fn test_next_int_unsigned() {
+        let mut parse = parse_of(vec![
+            Frame::Integer(42),
+            Frame::SimpleString("42".to_string()),
+            Frame::BulkString(Bytes::from("42")),
+        ]);
+        assert_eq!(parse.next_int_unsigned().unwrap(), 42);
+        assert_eq!(parse.next_int_unsigned().unwrap(), 42);
+        assert_eq!(parse.next_int_unsigned().unwrap(), 42);
+        assert!(matches!(parse.next_int_unsigned(), Err(Error::EndOfStream)));
+
+        let mut parse = parse_of(vec![
+            Frame::Integer(-42),
+            Frame::SimpleString("-42".to_string()),
+            Frame::BulkString(Bytes::from("-42")),
+            Frame::BulkString(Bytes::from("abc")),
+        ]);
+        assert!(parse.next_int_unsigned().is_err());
+        assert!(parse.next_int_unsigned().is_err());
+        assert!(parse.next_int_unsigned().is_err());
+        assert!(parse.next_int_unsigned().is_err());
+    }
+
+    #[test]
+    fn test_next_int_signed() {
+        let mut parse = parse_of(vec![
+            Frame::Integer(42),
+            Frame::Integer(-42),
+            Frame::SimpleString("42".to_string()),
+            Frame::SimpleString("-42".to_string()),
+            Frame::BulkString(Bytes::from("42")),
+            Frame::BulkString(Bytes::from("-42")),
+        ]);
+        assert_eq!(parse.next_int_signed().unwrap(), 42);
+        assert_eq!(parse.next_int_signed().unwrap(), -42);
+        assert_eq!(parse.next_int_signed().unwrap(), 42);
+        assert_eq!(parse.next_int_signed().unwrap(), -42);
+        assert_eq!(parse.next_int_signed().unwrap(), 42);
+        assert_eq!(parse.next_int_signed().unwrap(), -42);
+        assert!(matches!(parse.next_int_signed(), Err(Error::EndOfStream)));
+
+        let mut parse = parse_of(vec![
+            Frame::BulkString(Bytes::from("abc")),
+            Frame::BulkString(Bytes::from("9223372036854775808")),
+            Frame::NullBulkString,
+        ]);
+        assert!(parse.next_int_signed().is_err());
+        assert!(parse.next_int_signed().is_err());
+        assert!(parse.next_int_signed().is_err());
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn test_next_float() {
+        let mut parse = parse_of(vec![
+            Frame::BulkString(Bytes::from("3.14")),
+            Frame::SimpleString("1e3".to_string()),
+            Frame::Integer(42),
+            Frame::BulkString(Bytes::from("-inf")),
+        ]);
+        assert_eq!(parse.next_float().unwrap(), 3.14);
+        assert_eq!(parse.next_float().unwrap(), 1000.0);
+        assert_eq!(parse.next_float().unwrap(), 42.0);
+        assert_eq!(parse.next_float().unwrap(), f64::NEG_INFINITY);
+        assert!(matches!(parse.next_float(), Err(Error::EndOfStream)));
+
+        let mut parse = parse_of(vec![
+            Frame::BulkString(Bytes::from("abc")),
+            Frame::BulkString(Bytes::from("nan")),
+            Frame::NullBulkString,
+        ]);
+        assert!(matches!(parse.next_float(), Err(Error::Protocol(_))));
+        assert!(matches!(parse.next_float(), Err(Error::Protocol(_))));
+        assert!(matches!(parse.next_float(), Err(Error::Protocol(_))));
+    }
+}
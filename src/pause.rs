@@ -0,0 +1,91 @@
+//! Pausing of the command processing across connections, controlled by
+//! `CLIENT PAUSE` and `CLIENT UNPAUSE`.
+use crate::cmd::PauseMode;
+use std::{sync::Mutex, time::Duration};
+use tokio::{sync::Notify, time::Instant};
+
+/// The pause shared by every connection.
+#[derive(Debug, Default)]
+pub(crate) struct ClientPause {
+    /// When the current pause ends, and which commands it affects. `None`
+    /// when the commands are not paused.
+    state: Mutex<Option<(Instant, PauseMode)>>,
+    /// Wakes up the paused connections when the pause is changed.
+    notify: Notify,
+}
+
+impl ClientPause {
+    /// Pause the commands affected by `mode` for `duration`.
+    ///
+    /// This replaces any previous pause.
+    pub(crate) fn pause(&self, duration: Duration, mode: PauseMode) {
+        *self.state.lock().unwrap() = Some((Instant::now() + duration, mode));
+        self.notify.notify_waiters();
+    }
+
+    /// Lift the current pause, if any.
+    pub(crate) fn unpause(&self) {
+        *self.state.lock().unwrap() = None;
+        self.notify.notify_waiters();
+    }
+
+    /// Waits until a command may be applied.
+    ///
+    /// `is_write` tells whether the command may modify the keyspace, only
+    /// those are paused by [`PauseMode::Write`].
+    pub(crate) async fn wait(&self, is_write: bool) {
+        loop {
+            // Register for notifications before reading the state, so an
+            // update in between is not missed.
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let Some((deadline, mode)) = *self.state.lock().unwrap() else {
+                return;
+            };
+            if deadline <= Instant::now() || (mode == PauseMode::Write && !is_write) {
+                return;
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => {}
+                _ = notified => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pause_modes_and_unpause() {
+        let pause = Arc::new(ClientPause::default());
+        let timeout = Duration::from_millis(500);
+
+        // Reads flow during a write pause, writes wait for it to end.
+        pause.pause(timeout, PauseMode::Write);
+        let start = Instant::now();
+        pause.wait(false).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        pause.wait(true).await;
+        assert_eq!(start.elapsed(), timeout);
+
+        // Unpausing releases the waiting commands early.
+        pause.pause(timeout, PauseMode::All);
+        let start = Instant::now();
+        let unpause = {
+            let pause = pause.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                pause.unpause();
+            })
+        };
+        pause.wait(false).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(100));
+        unpause.await.unwrap();
+    }
+}
@@ -3,14 +3,375 @@
 //! Provides an async `run` function that listens for inbound connections,
 //! spawning a task per connection.
 
-use crate::{db::DbDropGuard, CommandVariant, Connection, Db, Result, Shutdown};
-use std::{future::Future, sync::Arc, time::Duration};
+use crate::{
+    cmd::{monitor::monitor_line, CommandRegistry},
+    db::{DbConfig, DbDropGuard},
+    latency::{self, LatencyMonitor},
+    pause::ClientPause,
+    slowlog::SlowLog,
+    stats::CommandStats,
+    CommandVariant, Connection, ConnectionStream, Db, Error, Frame, Result, Shutdown,
+};
+use std::{
+    collections::HashSet,
+    future::Future,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock, RwLock,
+    },
+    time::Duration,
+};
 use tokio::{
-    net::{TcpListener, TcpStream},
+    net::{TcpListener, TcpSocket, TcpStream},
     sync::{broadcast, mpsc, Semaphore},
 };
+#[cfg(any(test, feature = "test-util"))]
+use tokio::{sync::oneshot, task::JoinHandle};
 use tracing::{debug, error, info, warn};
 
+/// Server configuration.
+///
+/// Passed to [`run_with_config`] to tune the server behavior. The [`Default`]
+/// implementation matches the behavior of [`run`].
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Close connections that have not sent a complete frame for this long.
+    ///
+    /// `None` disables the idle timeout, keeping idle connections open
+    /// indefinitely.
+    pub idle_timeout: Option<Duration>,
+    /// Publish keyspace notifications when keys are modified or expire.
+    ///
+    /// Events are published to the `__keyspace@0__:<key>` and
+    /// `__keyevent@0__:<event>` channels.
+    pub notify_keyspace_events: bool,
+    /// Memory budget in bytes, approximated as the sum of key and value
+    /// lengths.
+    ///
+    /// `None` leaves memory usage unbounded.
+    pub maxmemory: Option<usize>,
+    /// How to make room for new values once `maxmemory` is reached.
+    pub maxmemory_policy: EvictionPolicy,
+    /// Require clients to authenticate with this password, using `AUTH`,
+    /// before issuing any other command.
+    ///
+    /// `None` disables authentication.
+    pub requirepass: Option<String>,
+    /// Record the commands taking longer than this to apply in the slow log,
+    /// reported by `SLOWLOG GET`.
+    ///
+    /// `None` disables the slow log.
+    pub slowlog_log_slower_than: Option<Duration>,
+    /// Maximum number of entries kept in the slow log, the oldest ones are
+    /// dropped first.
+    pub slowlog_max_len: usize,
+    /// Record the commands and purges of expired keys taking at least this
+    /// long, reported by `LATENCY`.
+    ///
+    /// `None` disables the latency monitor.
+    pub latency_monitor_threshold: Option<Duration>,
+    /// Emit an event for every applied command, with the `loja::access`
+    /// target, carrying the command name, key and latency. The id of the
+    /// connection is recorded on the enclosing connection span.
+    ///
+    /// The events can be filtered separately from the other logs, e.g. with
+    /// `RUST_LOG=loja::access=info`.
+    pub access_log: bool,
+    /// Number of messages buffered per pub/sub channel.
+    ///
+    /// Subscribers falling further behind the publishers miss the oldest
+    /// messages, and are sent an error reporting how many were dropped.
+    ///
+    /// # Panics
+    ///
+    /// The server panics on the first subscription if this is `0`.
+    pub pubsub_channel_capacity: usize,
+    /// Maximum size in bytes of a frame received from a client.
+    ///
+    /// A connection buffering more than this without receiving a complete
+    /// frame is closed with a protocol error.
+    pub max_frame_size: usize,
+    /// Maximum number of consecutive requests a client may send which cannot
+    /// be parsed into a command, such as malformed frames or unknown
+    /// commands.
+    ///
+    /// Every such request is replied to with an error, and the connection is
+    /// closed once this many were received without a valid command in
+    /// between.
+    pub max_protocol_errors: usize,
+    /// Maximum number of channels a single connection may subscribe to.
+    ///
+    /// Subscribing to more channels is replied to with an error, leaving the
+    /// existing subscriptions untouched. `None` leaves the number of
+    /// subscriptions unbounded.
+    pub max_subscriptions: Option<usize>,
+    /// Purge expired keys from a background task.
+    ///
+    /// When disabled, no task is spawned and expired keys are only removed
+    /// once accessed, so they keep counting against `maxmemory` until then.
+    pub active_expiration: bool,
+    /// Limits on the replies buffered for a connection but not yet written
+    /// to its socket, protecting the server from clients too slow to read
+    /// them, typically subscribers.
+    ///
+    /// Connections going over the limits are closed.
+    pub output_buffer_limit: OutputBufferLimit,
+    /// Names of the commands clients may not use, such as `DEBUG`, ignoring
+    /// case.
+    ///
+    /// Disabled commands are replied to as unknown commands, without being
+    /// applied.
+    pub disabled_commands: HashSet<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout: None,
+            notify_keyspace_events: false,
+            maxmemory: None,
+            maxmemory_policy: EvictionPolicy::default(),
+            requirepass: None,
+            slowlog_log_slower_than: None,
+            slowlog_max_len: DEFAULT_SLOWLOG_MAX_LEN,
+            latency_monitor_threshold: None,
+            access_log: false,
+            pubsub_channel_capacity: DEFAULT_PUBSUB_CHANNEL_CAPACITY,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            max_protocol_errors: DEFAULT_MAX_PROTOCOL_ERRORS,
+            max_subscriptions: None,
+            active_expiration: true,
+            output_buffer_limit: OutputBufferLimit::default(),
+            disabled_commands: HashSet::new(),
+        }
+    }
+}
+
+/// Default maximum number of entries kept in the slow log.
+pub const DEFAULT_SLOWLOG_MAX_LEN: usize = 128;
+
+/// Default number of messages buffered per pub/sub channel.
+pub const DEFAULT_PUBSUB_CHANNEL_CAPACITY: usize = 1024;
+
+/// Default maximum size of a received frame, 512MB like the maximum length of
+/// a string.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 512 * 1024 * 1024;
+
+/// Default maximum number of consecutive requests which cannot be parsed into
+/// a command.
+pub const DEFAULT_MAX_PROTOCOL_ERRORS: usize = 100;
+
+/// Default size of the queue of connections waiting to be accepted, like
+/// [`TcpListener::bind`].
+pub const DEFAULT_TCP_BACKLOG: u32 = 1024;
+
+impl ServerConfig {
+    /// The commands clients may use, every builtin command but the disabled
+    /// ones.
+    pub(crate) fn command_registry(&self) -> CommandRegistry {
+        let mut registry = CommandRegistry::builtin().clone();
+        for name in &self.disabled_commands {
+            registry.unregister(name);
+        }
+        registry
+    }
+
+    /// The configuration of the database served with this configuration.
+    pub(crate) fn db_config(&self) -> DbConfig {
+        DbConfig {
+            notify_keyspace_events: self.notify_keyspace_events,
+            maxmemory: self.maxmemory,
+            maxmemory_policy: self.maxmemory_policy,
+            pubsub_channel_capacity: self.pubsub_channel_capacity,
+            max_subscriptions: self.max_subscriptions,
+            active_expiration: self.active_expiration,
+        }
+    }
+}
+
+/// Policy applied when storing a value would exceed the memory budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Reject writes with an `OOM` error.
+    #[default]
+    NoEviction,
+    /// Evict the least recently used keys.
+    AllKeysLru,
+    /// Evict the least frequently used keys.
+    AllKeysLfu,
+    /// Evict random keys.
+    AllKeysRandom,
+}
+
+impl std::str::FromStr for EvictionPolicy {
+    type Err = String;
+
+    /// Parses the policy from its Redis name, e.g. `allkeys-lru`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "noeviction" => Ok(Self::NoEviction),
+            "allkeys-lru" => Ok(Self::AllKeysLru),
+            "allkeys-lfu" => Ok(Self::AllKeysLfu),
+            "allkeys-random" => Ok(Self::AllKeysRandom),
+            _ => Err(format!("unknown eviction policy '{s}'")),
+        }
+    }
+}
+
+/// Limits on the number of bytes buffered for a connection but not yet
+/// written to its socket.
+///
+/// Without any limit, writing a reply waits for the socket to accept it.
+/// Otherwise, writes only send what the socket accepts right away, and the
+/// rest stays buffered until the socket is ready, while the connection keeps
+/// being served.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputBufferLimit {
+    /// Close the connection as soon as more than this many bytes are
+    /// buffered.
+    pub hard: Option<usize>,
+    /// Close the connection once more than this many bytes stayed buffered
+    /// for the given duration.
+    pub soft: Option<(usize, Duration)>,
+}
+
+impl OutputBufferLimit {
+    /// Whether no limit is set.
+    pub fn is_unlimited(&self) -> bool {
+        self.hard.is_none() && self.soft.is_none()
+    }
+}
+
+/// State of the server shared by every connection, as opposed to the
+/// keyspace held by [`Db`].
+///
+/// Owned by the [`Listener`], and passed to the commands reporting on or
+/// controlling the server.
+#[derive(Debug)]
+pub(crate) struct ServerState {
+    /// Password clients must authenticate with. `None` disables
+    /// authentication.
+    requirepass: Option<String>,
+    /// Emit an access log event for every applied command.
+    access_log: bool,
+    /// Call statistics of every command applied.
+    stats: CommandStats,
+    /// Log of the commands which were slow to apply.
+    slowlog: SlowLog,
+    /// Latency spikes of the commands and of the purge task.
+    latency: LatencyMonitor,
+    /// Pause of the command processing, set by `CLIENT PAUSE`.
+    client_pause: ClientPause,
+    /// Random identifier of this server instance, reported by `INFO`.
+    run_id: RwLock<String>,
+    /// Feeds the commands received by the server to the `MONITOR`
+    /// connections.
+    monitors: broadcast::Sender<String>,
+}
+
+impl ServerState {
+    /// Create the state of a server started with `config`.
+    pub(crate) fn new(config: &ServerConfig) -> Self {
+        Self {
+            requirepass: config.requirepass.clone(),
+            access_log: config.access_log,
+            stats: CommandStats::new(),
+            slowlog: SlowLog::new(config.slowlog_log_slower_than, config.slowlog_max_len),
+            latency: LatencyMonitor::new(config.latency_monitor_threshold),
+            client_pause: ClientPause::default(),
+            run_id: RwLock::new(new_run_id()),
+            monitors: broadcast::channel(config.pubsub_channel_capacity).0,
+        }
+    }
+
+    /// Call statistics of the commands applied by the server.
+    pub(crate) fn stats(&self) -> &CommandStats {
+        &self.stats
+    }
+
+    /// Log of the commands which were slow to apply.
+    pub(crate) fn slowlog(&self) -> &SlowLog {
+        &self.slowlog
+    }
+
+    /// Latency spikes of the commands and of the purge task.
+    pub(crate) fn latency(&self) -> &LatencyMonitor {
+        &self.latency
+    }
+
+    /// Pause of the command processing, shared by every connection.
+    pub(crate) fn client_pause(&self) -> &ClientPause {
+        &self.client_pause
+    }
+
+    /// Random identifier of this server instance, 40 hexadecimal characters
+    /// generated when the server starts.
+    pub(crate) fn run_id(&self) -> String {
+        self.run_id.read().unwrap().clone()
+    }
+
+    /// Replace the identifier of this server instance, see
+    /// [`ServerState::run_id`].
+    pub(crate) fn set_run_id(&self, run_id: String) {
+        *self.run_id.write().unwrap() = run_id;
+    }
+
+    /// Whether an access log event is emitted for every applied command.
+    pub(crate) fn access_log(&self) -> bool {
+        self.access_log
+    }
+
+    /// Check the credentials of a client against the configured password.
+    ///
+    /// The only user is `default`, which is assumed when `username` is `None`.
+    /// [`Error::NoPasswordSet`] is returned if no password is configured, and
+    /// [`Error::WrongPass`] if the credentials do not match.
+    pub(crate) fn authenticate(&self, username: Option<&str>, password: &str) -> Result<()> {
+        let Some(requirepass) = &self.requirepass else {
+            return Err(Error::NoPasswordSet);
+        };
+        if username.is_some_and(|username| username != "default") || password != requirepass {
+            return Err(Error::WrongPass);
+        }
+        Ok(())
+    }
+
+    /// Receive the commands fed to the monitors, see
+    /// [`ServerState::feed_monitors`].
+    pub(crate) fn monitor(&self) -> broadcast::Receiver<String> {
+        self.monitors.subscribe()
+    }
+
+    /// Returns `true` if a connection is monitoring the commands, so they
+    /// are worth rendering.
+    pub(crate) fn has_monitors(&self) -> bool {
+        self.monitors.receiver_count() > 0
+    }
+
+    /// Send a command, rendered as `line`, to every monitor.
+    pub(crate) fn feed_monitors(&self, line: String) {
+        // Failing only means there is no monitor left.
+        let _ = self.monitors.send(line);
+    }
+}
+
+impl Default for ServerState {
+    /// The state of a server started with the default [`ServerConfig`].
+    fn default() -> Self {
+        Self::new(&ServerConfig::default())
+    }
+}
+
+/// Generate a random identifier of 40 hexadecimal characters, like the run
+/// ids of Redis.
+fn new_run_id() -> String {
+    std::iter::repeat_with(|| fastrand::u8(..))
+        .take(20)
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
 /// Server listener state. Created in the `run` call. It includes a `run` method
 /// which performs the TCP listening and initialization of per-connection state.
 #[derive(Debug)]
@@ -23,8 +384,15 @@ struct Listener {
     /// This holds a wrapper around an `Arc`. The internal `Db` can be
     /// retrieved and passed into the per connection state (`Handler`).
     db_holder: DbDropGuard,
-    /// TCP listener supplied by the `run` caller.
-    listener: TcpListener,
+    /// TCP listeners supplied by the `run` caller, connections are accepted on
+    /// all of them.
+    listeners: Vec<TcpListener>,
+    /// Configuration supplied by the `run` caller, shared with every handler.
+    config: Arc<ServerConfig>,
+    /// State of the server, shared with every handler.
+    server: Arc<ServerState>,
+    /// The commands clients may use, shared with every handler.
+    commands: Arc<CommandRegistry>,
     /// Limit the max number of connections.
     ///
     /// A `Semaphore` is used to limit the max number of connections. Before
@@ -34,6 +402,14 @@ struct Listener {
     /// When handlers complete processing a connection, the permit is returned
     /// to the semaphore.
     limit_connections: Arc<Semaphore>,
+    /// Number of connections currently open.
+    ///
+    /// This is not derived from `limit_connections`, as the listener also
+    /// holds a permit while waiting for the next connection.
+    active_connections: Arc<AtomicUsize>,
+    /// Id assigned to the next accepted connection, identifying it in the
+    /// logs.
+    next_conn_id: u64,
     /// Broadcasts a shutdown signal to all active connections.
     ///
     /// The initial `shutdown` trigger is provided by the `run` caller. The
@@ -62,6 +438,10 @@ struct Listener {
 /// commands to `db`.
 #[derive(Debug)]
 struct Handler<S> {
+    /// Id of the connection, unique for the lifetime of the server.
+    conn_id: u64,
+    /// Address of the peer, if known, as reported by `MONITOR`.
+    peer_addr: Option<SocketAddr>,
     /// Shared database handle.
     ///
     /// When a command is received from `connection`, it is applied with `db`.
@@ -75,6 +455,26 @@ struct Handler<S> {
     /// [`Connection`] allows the handler to operate at the "frame" level and keep
     /// the byte level protocol parsing details encapsulated in [`Connection`].
     connection: Connection<S>,
+    /// Server configuration shared by all connections.
+    config: Arc<ServerConfig>,
+    /// State of the server shared by all connections, passed to the commands
+    /// along with `db`.
+    server: Arc<ServerState>,
+    /// Parses received frames into the commands clients may use.
+    commands: Arc<CommandRegistry>,
+    /// Whether the peer is allowed to issue commands.
+    ///
+    /// Starts out `false` when [`ServerConfig::requirepass`] is set, and
+    /// becomes `true` once the peer successfully authenticates with `AUTH`.
+    authenticated: bool,
+    /// The transaction started by `MULTI`, if any.
+    transaction: Option<Transaction>,
+    /// Number of requests which could not be parsed into a command since the
+    /// last valid one.
+    ///
+    /// The connection is closed once this reaches
+    /// [`ServerConfig::max_protocol_errors`].
+    protocol_errors: usize,
     /// Listen for shutdown notifications.
     ///
     /// A wrapper around the `broadcast::Receiver` paired with the sender in
@@ -92,6 +492,54 @@ struct Handler<S> {
     _shutdown_complete: mpsc::Sender<()>,
 }
 
+/// Commands queued since `MULTI`, waiting for `EXEC`.
+#[derive(Debug, Default)]
+struct Transaction {
+    /// The queued commands, in order.
+    commands: Vec<CommandVariant>,
+    /// Whether a command failed to be queued, which aborts the transaction.
+    dirty: bool,
+}
+
+/// Options of the listening socket created by [`bind`].
+///
+/// The [`Default`] implementation matches [`TcpListener::bind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BindOptions {
+    /// Maximum number of connections waiting to be accepted. The operating
+    /// system may cap it, e.g. to `somaxconn` on Linux.
+    pub backlog: u32,
+    /// Set `SO_REUSEADDR`, so the address can be bound again right away,
+    /// even while connections of a previous listener linger in `TIME_WAIT`.
+    ///
+    /// Enabled by default on Unix.
+    pub reuse_addr: bool,
+}
+
+impl Default for BindOptions {
+    fn default() -> Self {
+        Self {
+            backlog: DEFAULT_TCP_BACKLOG,
+            reuse_addr: cfg!(unix),
+        }
+    }
+}
+
+/// Bind a [`TcpListener`] to `addr`, with the given [`BindOptions`].
+///
+/// # Panics
+///
+/// Panics if called outside of a Tokio runtime.
+pub fn bind(addr: SocketAddr, options: BindOptions) -> Result<TcpListener> {
+    let socket = match addr {
+        SocketAddr::V4(_) => TcpSocket::new_v4()?,
+        SocketAddr::V6(_) => TcpSocket::new_v6()?,
+    };
+    socket.set_reuseaddr(options.reuse_addr)?;
+    socket.bind(addr)?;
+    Ok(socket.listen(options.backlog)?)
+}
+
 /// Maximum number of concurrent connections the redis server will accept.
 ///
 /// When this limit is reached, the server will stop accepting connections until
@@ -116,6 +564,51 @@ const MAX_CONNECTIONS: usize = 250;
 /// `tokio::signal::ctrl_c()` can be used as the `shutdown` argument. This will
 /// listen for a SIGINT signal.
 pub async fn run(listener: TcpListener, shutdown: impl Future) {
+    run_with_config(listener, ServerConfig::default(), shutdown).await
+}
+
+/// Run the redis server with the given [`ServerConfig`].
+///
+/// Behaves exactly like [`run`], except that the server behavior is tuned by
+/// `config`.
+pub async fn run_with_config(listener: TcpListener, config: ServerConfig, shutdown: impl Future) {
+    run_multi(vec![listener], config, shutdown).await
+}
+
+/// Run the redis server on several listeners with the given [`ServerConfig`].
+///
+/// Behaves exactly like [`run_with_config`], except that connections are
+/// accepted on every listener, e.g. bound to both an IPv4 and an IPv6
+/// address. All the connections share the same database.
+///
+/// # Panics
+///
+/// Panics if `listeners` is empty.
+pub async fn run_multi(listeners: Vec<TcpListener>, config: ServerConfig, shutdown: impl Future) {
+    run_with_admin(listeners, config, AdminHandle::new(), shutdown).await
+}
+
+/// Run the redis server, exposing its metrics through `admin`.
+///
+/// Behaves exactly like [`run_multi`], except that `admin` reports the
+/// metrics of this server once it starts, which lets a process embedding the
+/// server scrape them without going through the protocol.
+///
+/// # Panics
+///
+/// Panics if `listeners` is empty, or if `admin` was already passed to
+/// another server.
+pub async fn run_with_admin(
+    listeners: Vec<TcpListener>,
+    config: ServerConfig,
+    admin: AdminHandle,
+    shutdown: impl Future,
+) {
+    assert!(
+        !listeners.is_empty(),
+        "no listener to accept connections on"
+    );
+
     // When the provided `shutdown` future completes, we must send a shutdown
     // message to all active connections. We use a broadcast channel for this
     // purpose. The call below ignores the receiver of the broadcast pair, and when
@@ -125,10 +618,23 @@ pub async fn run(listener: TcpListener, shutdown: impl Future) {
     let (shutdown_complete_tx, mut shutdown_complete_rx) = mpsc::channel(1);
 
     // Initialize the listener state
+    let db_holder = DbDropGuard::new(config.db_config());
+    let state = Arc::new(ServerState::new(&config));
+    db_holder.db().on_expire_cycle({
+        let state = state.clone();
+        move |elapsed| state.latency().record(latency::EVENT_EXPIRE_CYCLE, elapsed)
+    });
+    let active_connections = Arc::new(AtomicUsize::new(0));
+    admin.attach(db_holder.db(), state.clone(), active_connections.clone());
     let mut server = Listener {
-        listener,
-        db_holder: DbDropGuard::new(),
+        listeners,
+        db_holder,
+        server: state,
+        commands: Arc::new(config.command_registry()),
+        config: Arc::new(config),
         limit_connections: Arc::new(Semaphore::new(MAX_CONNECTIONS)),
+        active_connections,
+        next_conn_id: 0,
         notify_shutdown,
         shutdown_complete_tx,
     };
@@ -177,6 +683,122 @@ pub async fn run(listener: TcpListener, shutdown: impl Future) {
     let _ = shutdown_complete_rx.recv().await;
 }
 
+/// Handle reading the metrics of a server started with [`run_with_admin`].
+///
+/// Handles are cheap to clone, and all the clones report the metrics of the
+/// same server. Until the server starts, every metric is zero.
+#[derive(Debug, Clone, Default)]
+pub struct AdminHandle {
+    /// The state of the server, set once it starts.
+    server: Arc<OnceLock<AdminState>>,
+}
+
+/// The state of a running server the metrics are read from.
+#[derive(Debug)]
+struct AdminState {
+    /// The database of the server.
+    db: Db,
+    /// The state of the server.
+    state: Arc<ServerState>,
+    /// Number of connections currently open.
+    active_connections: Arc<AtomicUsize>,
+}
+
+/// A snapshot of the metrics of a server, taken by [`AdminHandle::metrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Number of connections currently open.
+    pub active_connections: usize,
+    /// Number of commands applied since the server started.
+    pub commands_processed: u64,
+    /// Number of keys in the database, counting the expired keys which were
+    /// not purged yet.
+    pub keys: usize,
+}
+
+impl AdminHandle {
+    /// Creates a new [`AdminHandle`], not attached to any server yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a snapshot of the metrics of the server.
+    pub fn metrics(&self) -> Metrics {
+        let Some(server) = self.server.get() else {
+            return Metrics::default();
+        };
+        Metrics {
+            active_connections: server.active_connections.load(Ordering::Relaxed),
+            commands_processed: server.state.stats().total_calls(),
+            keys: server.db.len(),
+        }
+    }
+
+    /// Report the metrics of the server owning `db` and `state`, and
+    /// counting its open connections in `active_connections`.
+    fn attach(&self, db: Db, state: Arc<ServerState>, active_connections: Arc<AtomicUsize>) {
+        let state = AdminState {
+            db,
+            state,
+            active_connections,
+        };
+        assert!(
+            self.server.set(state).is_ok(),
+            "the admin handle is already attached to a server"
+        );
+    }
+}
+
+/// Handle to a server spawned by [`test_harness`].
+///
+/// The server shuts down gracefully once the handle is dropped, or when
+/// [`ShutdownHandle::shutdown`] is called, which also waits for the shutdown
+/// to complete.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug)]
+pub struct ShutdownHandle {
+    /// Dropping or sending on this completes the `shutdown` future of the
+    /// server.
+    notify: oneshot::Sender<()>,
+    /// The task running the server.
+    server: JoinHandle<()>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl ShutdownHandle {
+    /// Shut the server down, waiting for every connection to be closed.
+    pub async fn shutdown(self) {
+        let _ = self.notify.send(());
+        let _ = self.server.await;
+    }
+}
+
+/// Spawn a server with the default configuration on an ephemeral local port.
+///
+/// Returns the address the server listens on, and a handle shutting the
+/// server down once dropped.
+#[cfg(any(test, feature = "test-util"))]
+pub async fn test_harness() -> (SocketAddr, ShutdownHandle) {
+    test_harness_with_config(ServerConfig::default()).await
+}
+
+/// Spawn a server with the given [`ServerConfig`] on an ephemeral local port.
+///
+/// Behaves exactly like [`test_harness`], except that the server behavior is
+/// tuned by `config`.
+#[cfg(any(test, feature = "test-util"))]
+pub async fn test_harness_with_config(config: ServerConfig) -> (SocketAddr, ShutdownHandle) {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("failed to bind an ephemeral port");
+    let addr = listener
+        .local_addr()
+        .expect("failed to get the local address");
+    let (notify, shutdown) = oneshot::channel::<()>();
+    let server = tokio::spawn(run_with_config(listener, config, shutdown));
+    (addr, ShutdownHandle { notify, server })
+}
+
 impl Listener {
     /// Run the server
     ///
@@ -216,25 +838,46 @@ impl Listener {
             // The `accept` method internally attempts to recover errors, so an
             // error here is non-recoverable.
             let socket = self.accept().await?;
+            let peer_addr = socket.peer_addr().ok();
+
+            // Initialize the connection state.
+            // This allocates read/write buffers to perform RESP frame parsing.
+            let mut connection = Connection::new(socket);
+            connection.set_max_frame_size(self.config.max_frame_size);
+            connection.set_output_buffer_limit(self.config.output_buffer_limit);
 
             // Create the necessary per-connection handler state.
+            self.next_conn_id += 1;
             let mut handler = Handler {
+                conn_id: self.next_conn_id,
+                peer_addr,
                 // Get a handle to the shared database.
                 db: self.db_holder.db(),
-                // Initialize the connection state.
-                // This allocates read/write buffers to perform RESP frame parsing.
-                connection: Connection::new(socket),
+                connection,
+                // Share the server configuration.
+                config: self.config.clone(),
+                // Share the server state.
+                server: self.server.clone(),
+                commands: self.commands.clone(),
+                // Connections only need to authenticate if a password is set.
+                authenticated: self.config.requirepass.is_none(),
+                transaction: None,
+                protocol_errors: 0,
                 // Receive shutdown notifications.
                 shutdown: Shutdown::new(self.notify_shutdown.subscribe()),
                 // Notifies the receiver half once all clones are dropped.
                 _shutdown_complete: self.shutdown_complete_tx.clone(),
             };
 
+            let active_connections = self.active_connections.clone();
+            active_connections.fetch_add(1, Ordering::Relaxed);
+
             tokio::spawn(async move {
                 if let Err(err) = handler.run().await {
                     error!(cause = %err, "connection error");
                 }
 
+                active_connections.fetch_sub(1, Ordering::Relaxed);
                 drop(permit);
             });
         }
@@ -254,7 +897,13 @@ impl Listener {
         loop {
             // Perform the accept operation. If a socket is successfully
             // accepted, return it. Otherwise, save the error.
-            match self.listener.accept().await {
+            // Every listener is polled, the first connection is taken. As
+            // accepting is cancel safe, the others are not lost.
+            let accepts = self
+                .listeners
+                .iter()
+                .map(|listener| Box::pin(listener.accept()));
+            match futures::future::select_all(accepts).await.0 {
                 Ok((socket, _)) => {
                     debug!("successfully accepted inbound connection");
                     return Ok(socket);
@@ -278,7 +927,7 @@ impl Listener {
     }
 }
 
-impl Handler<TcpStream> {
+impl<S: ConnectionStream> Handler<S> {
     /// Process a single connection.
     ///
     /// Request frames are read from the socket and processed. Responses are
@@ -291,13 +940,30 @@ impl Handler<TcpStream> {
     ///
     /// When the shutdown signal is received, the connection is processed until
     /// it reaches a safe state, at which point it is terminated.
-    #[tracing::instrument(skip_all)]
+    ///
+    /// If [`ServerConfig::idle_timeout`] is set and no complete frame is
+    /// received within it, the connection is terminated.
+    ///
+    /// Requests which are not valid commands are replied to with an error,
+    /// until [`ServerConfig::max_protocol_errors`] of them were received in a
+    /// row, at which point the connection is terminated.
+    #[tracing::instrument(skip_all, fields(conn_id = self.conn_id))]
     async fn run(&mut self) -> Result<()> {
+        let idle_timeout = self.config.idle_timeout;
+
         // As long as the shutdown signal has not been received,
         // try to process a new request frame.
         while !self.shutdown.is_shutdown() {
             let maybe_frame = tokio::select! {
-                res = self.connection.read_frame() => res?,
+                res = read_frame_timeout(&mut self.connection, idle_timeout) => match res {
+                    Some(res) => res?,
+                    None => {
+                        // No complete frame was received in time. Returning
+                        // from `run` drops the connection.
+                        info!(?idle_timeout, "closing idle connection");
+                        return Ok(());
+                    }
+                },
                 _ = self.shutdown.recv() => {
                     // If shutdown signal is received, return from `run`.
                     // This will result in the task terminating.
@@ -314,11 +980,66 @@ impl Handler<TcpStream> {
 
             // Convert the RESP frame into a `CommandVariant` struct. This returns an
             // error if the frame is not a valid redis command or it is an
-            // unsupported command.
-            let cmd = CommandVariant::from_frame(frame)?;
+            // unsupported command. Such errors are reported to the peer, and
+            // the connection keeps processing requests, unless the peer keeps
+            // sending garbage.
+            // Render the command for the monitors before it is consumed,
+            // only if there are any.
+            let monitor_line = self
+                .server
+                .has_monitors()
+                .then(|| monitor_line(self.peer_addr, &frame));
+
+            let cmd = match self.commands.parse(frame) {
+                Ok(cmd) => cmd,
+                Err(err) => {
+                    // A malformed command aborts the pending transaction.
+                    if let Some(transaction) = &mut self.transaction {
+                        transaction.dirty = true;
+                    }
+                    self.write_error(err).await?;
+                    self.protocol_errors += 1;
+                    if self.protocol_errors >= self.config.max_protocol_errors {
+                        info!(
+                            protocol_errors = self.protocol_errors,
+                            "closing connection after too many protocol errors"
+                        );
+                        return Ok(());
+                    }
+                    continue;
+                }
+            };
+            self.protocol_errors = 0;
 
             debug!(?cmd);
 
+            // Until the peer authenticates, only `AUTH` and `QUIT` are allowed.
+            let is_auth = matches!(cmd, CommandVariant::Auth(_));
+            let is_quit = matches!(cmd, CommandVariant::Quit(_));
+            if !self.authenticated && !is_auth && !is_quit {
+                self.write_error(Error::NoAuth).await?;
+                continue;
+            }
+
+            // `AUTH` is not fed to the monitors, so passwords are not leaked.
+            if let Some(line) = monitor_line.filter(|_| !is_auth) {
+                self.server.feed_monitors(line);
+            }
+
+            // Inside a transaction, commands are queued instead of applied.
+            if self.transaction.is_some() {
+                if self.queue(cmd).await? {
+                    return Ok(());
+                }
+                continue;
+            }
+            let is_multi = matches!(cmd, CommandVariant::Multi(_));
+
+            // `CLIENT` commands are never paused, so a pause can be lifted.
+            if !matches!(cmd, CommandVariant::Client(_)) && !self.wait_pause(cmd.is_write()).await {
+                return Ok(());
+            }
+
             // Perform the work needed to apply the command. This may mutate the
             // database state as a result.
             //
@@ -326,10 +1047,528 @@ impl Handler<TcpStream> {
             // command to write response frames directly to the connection. In
             // the case of pub/sub, multiple frames may be send back to the
             // peer.
-            cmd.apply(&self.db, &mut self.connection, &mut self.shutdown)
+            match cmd
+                .apply(
+                    &self.db,
+                    &self.server,
+                    &mut self.connection,
+                    &mut self.shutdown,
+                )
+                .await
+            {
+                Ok(()) if is_auth => self.authenticated = true,
+                Ok(()) if is_multi => self.transaction = Some(Transaction::default()),
+                // The reply to `QUIT` was written, close the connection.
+                Ok(()) if is_quit => return Ok(()),
+                Ok(()) => {}
+                Err(err) => self.write_error(err).await?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle a command received inside a transaction.
+    ///
+    /// Regular commands are queued, while the ones controlling the transaction
+    /// or the connection are applied right away. Returns `true` if the
+    /// connection must be closed.
+    async fn queue(&mut self, cmd: CommandVariant) -> Result<bool> {
+        let Some(transaction) = &mut self.transaction else {
+            return Ok(false);
+        };
+        match cmd {
+            CommandVariant::Multi(_) => self.write_error(Error::NestedMulti).await?,
+            CommandVariant::Exec(_) => self.exec().await?,
+            CommandVariant::Discard(_) => {
+                self.transaction = None;
+                let response = Frame::SimpleString("OK".to_string());
+                self.connection.write_frame(&response).await?;
+            }
+            // Commands taking over the connection cannot be queued.
+            CommandVariant::Subscribe(_) | CommandVariant::Monitor(_) => {
+                transaction.dirty = true;
+                self.write_error(Error::NotAllowedInTransaction).await?;
+            }
+            // These are about the connection itself, so they are applied right
+            // away, dropping the transaction.
+            cmd @ (CommandVariant::Reset(_) | CommandVariant::Quit(_)) => {
+                self.transaction = None;
+                let is_quit = matches!(cmd, CommandVariant::Quit(_));
+                cmd.apply(
+                    &self.db,
+                    &self.server,
+                    &mut self.connection,
+                    &mut self.shutdown,
+                )
                 .await?;
+                return Ok(is_quit);
+            }
+            cmd => {
+                transaction.commands.push(cmd);
+                let response = Frame::SimpleString("QUEUED".to_string());
+                self.connection.write_frame(&response).await?;
+            }
+        }
+        Ok(false)
+    }
+
+    /// Apply the commands queued in the pending transaction.
+    ///
+    /// The replies of the commands are written as a single array. If the
+    /// transaction is dirty, nothing is applied and an `EXECABORT` error is
+    /// reported instead.
+    async fn exec(&mut self) -> Result<()> {
+        let Some(transaction) = self.transaction.take() else {
+            return self.write_error(Error::ExecWithoutMulti).await;
+        };
+        if transaction.dirty {
+            return self.write_error(Error::ExecAbort).await;
+        }
+
+        let is_write = transaction.commands.iter().any(CommandVariant::is_write);
+        if !self.wait_pause(is_write).await {
+            return Ok(());
+        }
+
+        // Every command replies with a single frame, errors included, which
+        // makes up the elements of the array.
+        self.connection.write_array_len(transaction.commands.len());
+        for cmd in transaction.commands {
+            // A transaction cannot wait for other clients to push values.
+            if let Err(err) = cmd
+                .without_blocking()
+                .apply(
+                    &self.db,
+                    &self.server,
+                    &mut self.connection,
+                    &mut self.shutdown,
+                )
+                .await
+            {
+                self.write_error(err).await?;
+            }
         }
 
         Ok(())
     }
+
+    /// Waits until `CLIENT PAUSE` allows applying a command, `is_write`
+    /// telling whether it may modify the keyspace.
+    ///
+    /// Returns `false` if the shutdown signal was received in the meantime.
+    async fn wait_pause(&mut self, is_write: bool) -> bool {
+        tokio::select! {
+            _ = self.server.client_pause().wait(is_write) => true,
+            _ = self.shutdown.recv() => false,
+        }
+    }
+
+    /// Report a command error to the peer as a `SimpleError` frame.
+    ///
+    /// Errors which are not recoverable, such as IO errors, are returned
+    /// instead, which terminates the connection.
+    async fn write_error(&mut self, err: Error) -> Result<()> {
+        match err.to_frame() {
+            Some(frame) => {
+                debug!(%err, "command failed");
+                self.connection.write_frame(&frame).await
+            }
+            None => Err(err),
+        }
+    }
+}
+
+/// Read a frame from `connection`, giving up once `idle_timeout` elapses.
+///
+/// Returns `None` if the timeout elapsed before a complete frame was read.
+/// Partially read frames are kept in the connection buffer, as reading is
+/// cancel safe. When `idle_timeout` is `None`, this waits indefinitely.
+async fn read_frame_timeout<S: ConnectionStream>(
+    connection: &mut Connection<S>,
+    idle_timeout: Option<Duration>,
+) -> Option<Result<Option<crate::Frame>>> {
+    match idle_timeout {
+        Some(duration) => tokio::time::timeout(duration, connection.read_frame())
+            .await
+            .ok(),
+        None => Some(connection.read_frame().await),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        time::Instant,
+    };
+
+    /// Create a [`Handler`] over `stream`, returning it alongside the sender
+    /// half of the shutdown channel, which must be kept alive.
+    fn handler<S: ConnectionStream>(
+        stream: S,
+        config: ServerConfig,
+    ) -> (Handler<S>, broadcast::Sender<()>, DbDropGuard) {
+        let (notify_shutdown, _) = broadcast::channel(1);
+        let (shutdown_complete_tx, _) = mpsc::channel(1);
+        let db_holder = DbDropGuard::new(config.db_config());
+        let mut connection = Connection::new(stream);
+        connection.set_max_frame_size(config.max_frame_size);
+        connection.set_output_buffer_limit(config.output_buffer_limit);
+        let handler = Handler {
+            conn_id: 1,
+            peer_addr: None,
+            db: db_holder.db(),
+            connection,
+            authenticated: config.requirepass.is_none(),
+            server: Arc::new(ServerState::new(&config)),
+            transaction: None,
+            protocol_errors: 0,
+            commands: Arc::new(config.command_registry()),
+            config: Arc::new(config),
+            shutdown: Shutdown::new(notify_shutdown.subscribe()),
+            _shutdown_complete: shutdown_complete_tx,
+        };
+        (handler, notify_shutdown, db_holder)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_connection_is_closed() {
+        // The peer half is kept alive but never sends anything.
+        let (_peer, stream) = tokio::io::duplex(64);
+        let idle_timeout = Duration::from_secs(5);
+        let config = ServerConfig {
+            idle_timeout: Some(idle_timeout),
+            ..Default::default()
+        };
+        let (mut handler, _notify_shutdown, _db_holder) = handler(stream, config);
+
+        let start = Instant::now();
+        handler.run().await.unwrap();
+        assert!(start.elapsed() >= idle_timeout);
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_past_hard_output_limit_is_closed() {
+        // The peer never reads its replies, so at most 64 bytes of them are
+        // accepted by the stream.
+        let (mut peer, stream) = tokio::io::duplex(64);
+        peer.write_all(b"*2\r\n$9\r\nSUBSCRIBE\r\n$3\r\nfoo\r\n")
+            .await
+            .unwrap();
+        let config = ServerConfig {
+            output_buffer_limit: OutputBufferLimit {
+                hard: Some(256),
+                soft: None,
+            },
+            ..Default::default()
+        };
+        let (mut handler, _notify_shutdown, db_holder) = handler(stream, config);
+        let db = db_holder.db();
+        let subscriber = tokio::spawn(async move { handler.run().await });
+
+        // Messages pile up in the output buffer, as the peer never reads them.
+        while !subscriber.is_finished() {
+            db.publish("foo", Bytes::from("a message of some length"));
+            tokio::task::yield_now().await;
+        }
+        let err = subscriber.await.unwrap().unwrap_err();
+        assert!(matches!(err, Error::OutputBufferLimit(len) if len > 256));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_subscriber_survives_write_half_close() {
+        let (peer, stream) = tokio::io::duplex(1024);
+        let (mut peer_read, mut peer_write) = tokio::io::split(peer);
+        let (mut handler, _notify_shutdown, db_holder) = handler(stream, ServerConfig::default());
+        let db = db_holder.db();
+        let subscriber = tokio::spawn(async move { handler.run().await });
+
+        peer_write
+            .write_all(b"*2\r\n$9\r\nSUBSCRIBE\r\n$3\r\nfoo\r\n")
+            .await
+            .unwrap();
+        let confirmation = b"*3\r\n$9\r\nsubscribe\r\n$3\r\nfoo\r\n:1\r\n";
+        let mut buf = vec![0; confirmation.len()];
+        peer_read.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, confirmation);
+
+        // The time only advances once the handler is idle, so it saw the EOF.
+        peer_write.shutdown().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(!subscriber.is_finished());
+
+        assert_eq!(db.publish("foo", Bytes::from("bar")), 1);
+        let message = b"*3\r\n$7\r\nmessage\r\n$3\r\nfoo\r\n$3\r\nbar\r\n";
+        let mut buf = vec![0; message.len()];
+        peer_read.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, message);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_half_closed_subscriber_drains_output_buffer() {
+        // The stream only accepts 64 bytes at a time, so most of a long
+        // message stays in the output buffer.
+        let (peer, stream) = tokio::io::duplex(64);
+        let (mut peer_read, mut peer_write) = tokio::io::split(peer);
+        let config = ServerConfig {
+            output_buffer_limit: OutputBufferLimit {
+                hard: Some(4096),
+                soft: None,
+            },
+            ..Default::default()
+        };
+        let (mut handler, _notify_shutdown, db_holder) = handler(stream, config);
+        let db = db_holder.db();
+        tokio::spawn(async move { handler.run().await });
+
+        peer_write
+            .write_all(b"*2\r\n$9\r\nSUBSCRIBE\r\n$3\r\nfoo\r\n")
+            .await
+            .unwrap();
+        let confirmation = b"*3\r\n$9\r\nsubscribe\r\n$3\r\nfoo\r\n:1\r\n";
+        let mut buf = vec![0; confirmation.len()];
+        peer_read.read_exact(&mut buf).await.unwrap();
+        peer_write.shutdown().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let payload = "x".repeat(1000);
+        assert_eq!(db.publish("foo", Bytes::from(payload.clone())), 1);
+        let message = format!("*3\r\n$7\r\nmessage\r\n$3\r\nfoo\r\n$1000\r\n{payload}\r\n");
+
+        // The peer reads slowly, the rest of the message is written out as it
+        // makes room, even though the subscriber reads no more frames.
+        let mut received = vec![];
+        while received.len() < message.len() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let mut chunk = [0; 32];
+            let n = tokio::time::timeout(Duration::from_secs(1), peer_read.read(&mut chunk))
+                .await
+                .expect("the output buffer is not drained")
+                .unwrap();
+            assert!(n > 0);
+            received.extend_from_slice(&chunk[..n]);
+        }
+        assert_eq!(received, message.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_partial_frame_closes_connection() {
+        // A huge array is declared, but its elements never stop coming.
+        let (mut peer, stream) = tokio::io::duplex(1024);
+        peer.write_all(b"*1000000\r\n").await.unwrap();
+        for _ in 0..20 {
+            peer.write_all(b"$3\r\nfoo\r\n").await.unwrap();
+        }
+        let config = ServerConfig {
+            max_frame_size: 64,
+            ..Default::default()
+        };
+        let (mut handler, _notify_shutdown, _db_holder) = handler(stream, config);
+
+        let err = handler.run().await.unwrap_err();
+        assert!(matches!(err, Error::Protocol(msg) if msg.contains("maximum size of 64 bytes")));
+    }
+
+    #[tokio::test]
+    async fn test_command_error_keeps_connection() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"*2\r\n$3\r\nFOO\r\n$3\r\nbar\r\n")
+            .write(b"-ERR unknown command 'FOO', with args beginning with: 'bar'\r\n")
+            .read(b"*1\r\n$3\r\nGET\r\n")
+            .write(b"-ERR wrong number of arguments for 'get' command\r\n")
+            .read(b"*2\r\n$6\r\nCLIENT\r\n$5\r\nPAUSE\r\n")
+            .write(b"-ERR wrong number of arguments for 'client' command\r\n")
+            .read(b"*1\r\n$4\r\nPING\r\n")
+            .write(b"+PONG\r\n")
+            .build();
+        let (mut handler, _notify_shutdown, _db_holder) = handler(stream, ServerConfig::default());
+
+        handler.run().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_repeated_protocol_errors_close_connection() {
+        use tokio::io::AsyncReadExt;
+
+        let (mut peer, stream) = tokio::io::duplex(1024);
+        let config = ServerConfig {
+            max_protocol_errors: 3,
+            ..Default::default()
+        };
+        let (mut handler, _notify_shutdown, _db_holder) = handler(stream, config);
+
+        // A valid command resets the count, the `PING` sent after the third
+        // error in a row must never be answered.
+        peer.write_all(b"+garbage\r\n*1\r\n$3\r\nFOO\r\n*1\r\n$4\r\nPING\r\n")
+            .await
+            .unwrap();
+        peer.write_all(b":1\r\n*1\r\n$3\r\nFOO\r\n*1\r\n$3\r\nGET\r\n*1\r\n$4\r\nPING\r\n")
+            .await
+            .unwrap();
+        handler.run().await.unwrap();
+        drop(handler);
+
+        let mut reply = String::new();
+        peer.read_to_string(&mut reply).await.unwrap();
+        let replies: Vec<&str> = reply.split_terminator("\r\n").collect();
+        assert_eq!(replies.len(), 6, "{reply}");
+        assert_eq!(replies[2], "+PONG");
+        assert!(replies[..2].iter().all(|reply| reply.starts_with("-ERR")));
+        assert!(replies[3..].iter().all(|reply| reply.starts_with("-ERR")));
+    }
+
+    #[tokio::test]
+    async fn test_requirepass_rejects_until_authenticated() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"*2\r\n$3\r\nGET\r\n$3\r\nfoo\r\n")
+            .write(b"-NOAUTH Authentication required.\r\n")
+            .read(b"*2\r\n$4\r\nAUTH\r\n$5\r\nwrong\r\n")
+            .write(b"-WRONGPASS invalid username-password pair or user is disabled.\r\n")
+            .read(b"*2\r\n$3\r\nGET\r\n$3\r\nfoo\r\n")
+            .write(b"-NOAUTH Authentication required.\r\n")
+            .read(b"*3\r\n$4\r\nAUTH\r\n$7\r\ndefault\r\n$6\r\nsecret\r\n")
+            .write(b"+OK\r\n")
+            .read(b"*2\r\n$3\r\nGET\r\n$3\r\nfoo\r\n")
+            .write(b"$-1\r\n")
+            .build();
+        let config = ServerConfig {
+            requirepass: Some("secret".to_string()),
+            ..Default::default()
+        };
+        let (mut handler, _notify_shutdown, _db_holder) = handler(stream, config);
+
+        handler.run().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_disabled_commands_are_unknown() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"*2\r\n$5\r\ndebug\r\n$4\r\nhelp\r\n")
+            .write(b"-ERR unknown command 'debug', with args beginning with: 'help'\r\n")
+            .read(b"*3\r\n$3\r\nSET\r\n$3\r\nfoo\r\n$3\r\nbar\r\n")
+            .write(b"+OK\r\n")
+            .build();
+        let config = ServerConfig {
+            disabled_commands: HashSet::from(["DEBUG".to_string()]),
+            ..Default::default()
+        };
+        let (mut handler, _notify_shutdown, db_holder) = handler(stream, config);
+
+        handler.run().await.unwrap();
+        assert_eq!(db_holder.db().get("foo").unwrap(), Some(Bytes::from("bar")));
+    }
+
+    #[tokio::test]
+    async fn test_auth_without_requirepass() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"*2\r\n$4\r\nAUTH\r\n$6\r\nsecret\r\n")
+            .write(
+                b"-ERR AUTH <password> called without any password configured for the default \
+                  user. Are you sure your configuration is correct?\r\n",
+            )
+            .build();
+        let (mut handler, _notify_shutdown, _db_holder) = handler(stream, ServerConfig::default());
+
+        handler.run().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_quit_closes_connection() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut peer, stream) = tokio::io::duplex(64);
+        let config = ServerConfig {
+            requirepass: Some("secret".to_string()),
+            ..Default::default()
+        };
+        let (mut handler, _notify_shutdown, _db_holder) = handler(stream, config);
+
+        // The `PING` sent after `QUIT` must never be answered.
+        peer.write_all(b"*1\r\n$4\r\nQUIT\r\n*1\r\n$4\r\nPING\r\n")
+            .await
+            .unwrap();
+        handler.run().await.unwrap();
+        drop(handler);
+
+        let mut reply = vec![];
+        peer.read_to_end(&mut reply).await.unwrap();
+        assert_eq!(reply, b"+OK\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_inline_commands_get_plain_text_replies() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"SET foo bar\r\n")
+            .write(b"OK\n")
+            .read(b"GET foo\r\n")
+            .write(b"\"bar\"\n")
+            .read(b"GET missing\n")
+            .write(b"(nil)\n")
+            .build();
+        let (mut handler, _notify_shutdown, _db_holder) = handler(stream, ServerConfig::default());
+
+        handler.run().await.unwrap();
+        assert!(handler.connection.inline_mode());
+    }
+
+    #[tokio::test]
+    async fn test_exec_applies_queued_commands() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"*1\r\n$5\r\nMULTI\r\n")
+            .write(b"+OK\r\n")
+            .read(b"*3\r\n$3\r\nSET\r\n$3\r\nfoo\r\n$3\r\nbar\r\n")
+            .write(b"+QUEUED\r\n")
+            .read(b"*2\r\n$4\r\nLLEN\r\n$3\r\nfoo\r\n")
+            .write(b"+QUEUED\r\n")
+            .read(b"*2\r\n$3\r\nGET\r\n$3\r\nfoo\r\n")
+            .write(b"+QUEUED\r\n")
+            .read(b"*1\r\n$4\r\nEXEC\r\n")
+            // Errors of queued commands are part of the reply.
+            .write(b"*3\r\n+OK\r\n-WRONGTYPE Operation against a key holding the wrong kind of value\r\n$3\r\nbar\r\n")
+            .read(b"*1\r\n$4\r\nEXEC\r\n")
+            .write(b"-ERR EXEC without MULTI\r\n")
+            .build();
+        let (mut handler, _notify_shutdown, _db_holder) = handler(stream, ServerConfig::default());
+
+        handler.run().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_exec_aborts_after_queuing_error() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"*1\r\n$5\r\nMULTI\r\n")
+            .write(b"+OK\r\n")
+            .read(b"*3\r\n$3\r\nSET\r\n$3\r\nfoo\r\n$3\r\nbar\r\n")
+            .write(b"+QUEUED\r\n")
+            .read(b"*1\r\n$3\r\nGET\r\n")
+            .write(b"-ERR wrong number of arguments for 'get' command\r\n")
+            .read(b"*1\r\n$4\r\nEXEC\r\n")
+            .write(b"-EXECABORT Transaction discarded because of previous errors.\r\n")
+            .build();
+        let (mut handler, _notify_shutdown, db_holder) = handler(stream, ServerConfig::default());
+
+        handler.run().await.unwrap();
+        // The valid command was not applied either.
+        assert_eq!(db_holder.db().get("foo").unwrap(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_client_pause_delays_commands() {
+        let pause = Duration::from_millis(500);
+        let stream = tokio_test::io::Builder::new()
+            .read(b"*4\r\n$6\r\nCLIENT\r\n$5\r\nPAUSE\r\n$3\r\n500\r\n$3\r\nALL\r\n")
+            .write(b"+OK\r\n")
+            .read(b"*2\r\n$3\r\nGET\r\n$3\r\nfoo\r\n")
+            .write(b"$-1\r\n")
+            .build();
+        let (mut handler, _notify_shutdown, _db_holder) = handler(stream, ServerConfig::default());
+
+        let start = Instant::now();
+        handler.run().await.unwrap();
+        let elapsed = start.elapsed();
+        assert!(elapsed >= pause && elapsed < pause * 2, "{elapsed:?}");
+    }
 }
@@ -0,0 +1,101 @@
+//! Log of the commands which took longer than a threshold to apply, reported
+//! by `SLOWLOG GET`.
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+/// Maximum number of arguments kept in the preview of an entry.
+const MAX_ARGS: usize = 32;
+
+/// Maximum length of an argument kept in the preview of an entry.
+const MAX_ARG_LEN: usize = 128;
+
+/// A bounded log of slow commands.
+///
+/// Once the log is full, recording a new entry drops the oldest one.
+#[derive(Debug)]
+pub(crate) struct SlowLog {
+    /// Commands taking longer than this are logged. `None` disables the log.
+    threshold: Option<Duration>,
+    /// Maximum number of entries kept.
+    max_len: usize,
+    /// The entries, newest first, along with the id of the next entry.
+    entries: Mutex<(VecDeque<SlowLogEntry>, u64)>,
+}
+
+/// A command which took longer than the threshold to apply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SlowLogEntry {
+    /// Unique, increasing, identifier of the entry.
+    pub(crate) id: u64,
+    /// When the command was logged.
+    pub(crate) timestamp: SystemTime,
+    /// How long the command took to apply.
+    pub(crate) duration: Duration,
+    /// The command name and arguments, possibly truncated.
+    pub(crate) args: Vec<String>,
+}
+
+impl SlowLog {
+    /// Create an empty log, recording commands slower than `threshold` and
+    /// keeping up to `max_len` entries.
+    pub(crate) fn new(threshold: Option<Duration>, max_len: usize) -> Self {
+        Self {
+            threshold,
+            max_len,
+            entries: Mutex::new((VecDeque::new(), 0)),
+        }
+    }
+
+    /// Returns `true` if commands may be logged.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.threshold.is_some()
+    }
+
+    /// Log the command rendered as `preview`, if `duration` exceeds the
+    /// threshold.
+    pub(crate) fn record(&self, duration: Duration, preview: &str) {
+        if self.threshold.is_none_or(|threshold| duration <= threshold) {
+            return;
+        }
+
+        let args = preview
+            .split_whitespace()
+            .take(MAX_ARGS)
+            .map(|arg| match arg.char_indices().nth(MAX_ARG_LEN) {
+                Some((end, _)) => format!("{}...", &arg[..end]),
+                None => arg.to_string(),
+            })
+            .collect();
+
+        let mut guard = self.entries.lock().unwrap();
+        let (entries, next_id) = &mut *guard;
+        entries.push_front(SlowLogEntry {
+            id: *next_id,
+            timestamp: SystemTime::now(),
+            duration,
+            args,
+        });
+        entries.truncate(self.max_len);
+        *next_id += 1;
+    }
+
+    /// Returns up to `count` entries, newest first. `None` returns them all.
+    pub(crate) fn get(&self, count: Option<usize>) -> Vec<SlowLogEntry> {
+        let guard = self.entries.lock().unwrap();
+        let count = count.unwrap_or(usize::MAX);
+        guard.0.iter().take(count).cloned().collect()
+    }
+
+    /// Returns the number of entries.
+    pub(crate) fn len(&self) -> usize {
+        self.entries.lock().unwrap().0.len()
+    }
+
+    /// Removes every entry.
+    pub(crate) fn reset(&self) {
+        self.entries.lock().unwrap().0.clear();
+    }
+}
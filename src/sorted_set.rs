@@ -0,0 +1,258 @@
+//! The sorted set data structure backing the `Z*` commands.
+use bytes::Bytes;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+
+/// A score of a [`SortedSet`] member.
+///
+/// Scores are never `NaN`, which makes them totally ordered.
+#[derive(Debug, Clone, Copy)]
+struct Score(f64);
+
+impl Score {
+    fn new(score: f64) -> Self {
+        debug_assert!(!score.is_nan(), "scores are never NaN");
+        // `-0.0` and `0.0` compare equal, but not under `total_cmp`.
+        Self(if score == 0.0 { 0.0 } else { score })
+    }
+}
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// A bound of a range of members, compared lexicographically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexBound {
+    /// Lower than any member, written `-`.
+    Min,
+    /// Greater than any member, written `+`.
+    Max,
+    /// Includes the member, written `[member`.
+    Inclusive(Bytes),
+    /// Excludes the member, written `(member`.
+    Exclusive(Bytes),
+}
+
+impl LexBound {
+    /// Returns `true` if `member` is above this lower bound.
+    fn is_above(&self, member: &[u8]) -> bool {
+        match self {
+            LexBound::Min => true,
+            LexBound::Max => false,
+            LexBound::Inclusive(min) => member >= min,
+            LexBound::Exclusive(min) => member > min,
+        }
+    }
+
+    /// Returns `true` if `member` is below this upper bound.
+    fn is_below(&self, member: &[u8]) -> bool {
+        match self {
+            LexBound::Min => false,
+            LexBound::Max => true,
+            LexBound::Inclusive(max) => member <= max,
+            LexBound::Exclusive(max) => member < max,
+        }
+    }
+}
+
+/// A collection of unique members, each associated with a score.
+///
+/// Members are ordered by score, and members with the same score are ordered
+/// lexicographically.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SortedSet {
+    /// The score of each member.
+    scores: HashMap<Bytes, Score>,
+    /// Every `(score, member)` pair, in order.
+    index: BTreeSet<(Score, Bytes)>,
+}
+
+impl SortedSet {
+    /// Creates an empty sorted set.
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of members.
+    pub(crate) fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// Approximate memory used by the sorted set, as the byte length of its
+    /// members and scores.
+    pub(crate) fn size(&self) -> usize {
+        self.scores
+            .keys()
+            .map(|member| member.len() + std::mem::size_of::<f64>())
+            .sum()
+    }
+
+    /// Sets the score of `member`, adding it if it is not in the set.
+    ///
+    /// Returns `true` if `member` was added.
+    pub(crate) fn insert(&mut self, member: Bytes, score: f64) -> bool {
+        let score = Score::new(score);
+        match self.scores.insert(member.clone(), score) {
+            Some(prev) => {
+                if prev != score {
+                    self.index.remove(&(prev, member.clone()));
+                    self.index.insert((score, member));
+                }
+                false
+            }
+            None => {
+                self.index.insert((score, member));
+                true
+            }
+        }
+    }
+
+    /// Removes `member` from the set.
+    ///
+    /// Returns `true` if `member` was in the set.
+    pub(crate) fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove_entry(member) {
+            Some((member, score)) => {
+                self.index.remove(&(score, member));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes up to `count` members with the lowest scores, returning them
+    /// along with their scores, in order.
+    pub(crate) fn pop_min(&mut self, count: usize) -> Vec<(Bytes, f64)> {
+        let popped: Vec<_> = std::iter::from_fn(|| self.index.pop_first())
+            .take(count)
+            .collect();
+        self.forget(popped)
+    }
+
+    /// Removes up to `count` members with the highest scores, returning them
+    /// along with their scores, from the highest score.
+    pub(crate) fn pop_max(&mut self, count: usize) -> Vec<(Bytes, f64)> {
+        let popped: Vec<_> = std::iter::from_fn(|| self.index.pop_last())
+            .take(count)
+            .collect();
+        self.forget(popped)
+    }
+
+    /// Removes the scores of the members `popped` out of the index.
+    fn forget(&mut self, popped: Vec<(Score, Bytes)>) -> Vec<(Bytes, f64)> {
+        popped
+            .into_iter()
+            .map(|(score, member)| {
+                self.scores.remove(&member);
+                (member, score.0)
+            })
+            .collect()
+    }
+
+    /// Returns the rank of `member`, its position in the set ordered from the
+    /// lowest score, if it is in the set.
+    pub(crate) fn rank(&self, member: &[u8]) -> Option<usize> {
+        let (member, score) = self.scores.get_key_value(member)?;
+        Some(self.index.range(..(*score, member.clone())).count())
+    }
+
+    /// Returns the score of `member`, if it is in the set.
+    pub(crate) fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).map(|score| score.0)
+    }
+
+    /// Iterates over the members and their scores, in order.
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = (&Bytes, f64)> {
+        self.index.iter().map(|(score, member)| (member, score.0))
+    }
+
+    /// Iterates over the members whose score is between `min` and `max`, and
+    /// their scores, in order.
+    ///
+    /// An unbounded `min` or `max` stands for an infinite score.
+    pub(crate) fn range_by_score(
+        &self,
+        min: Bound<f64>,
+        max: Bound<f64>,
+    ) -> impl Iterator<Item = (&Bytes, f64)> {
+        let start = match min {
+            Bound::Included(min) | Bound::Excluded(min) => Score::new(min),
+            Bound::Unbounded => Score(f64::NEG_INFINITY),
+        };
+        // The empty member is the lowest member of any score.
+        self.index
+            .range((start, Bytes::new())..)
+            .map(|(score, member)| (member, score.0))
+            .skip_while(move |(_, score)| matches!(min, Bound::Excluded(min) if *score == min))
+            .take_while(move |(_, score)| match max {
+                Bound::Included(max) => *score <= max,
+                Bound::Excluded(max) => *score < max,
+                Bound::Unbounded => true,
+            })
+    }
+
+    /// Iterates over the members between `min` and `max`, and their scores,
+    /// in order.
+    ///
+    /// The members are expected to all have the same score, so that they are
+    /// ordered lexicographically. Otherwise, the members are scanned in order
+    /// from the first one above `min`, up to the first one past `max`.
+    pub(crate) fn range_by_lex<'a>(
+        &'a self,
+        min: &'a LexBound,
+        max: &'a LexBound,
+    ) -> impl Iterator<Item = (&'a Bytes, f64)> {
+        self.iter()
+            .skip_while(|(member, _)| !min.is_above(member))
+            .take_while(|(member, _)| max.is_below(member))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_by_score_then_member() {
+        let mut zset = SortedSet::new();
+        assert!(zset.insert(Bytes::from("c"), 1.0));
+        assert!(zset.insert(Bytes::from("b"), 2.0));
+        assert!(zset.insert(Bytes::from("a"), 1.0));
+        assert!(zset.insert(Bytes::from("d"), -0.0));
+        assert!(zset.insert(Bytes::from("e"), f64::INFINITY));
+
+        // Updating a score moves the member.
+        assert!(!zset.insert(Bytes::from("b"), 0.0));
+
+        let members: Vec<_> = zset.iter().map(|(member, _)| member.clone()).collect();
+        assert_eq!(members, ["b", "d", "a", "c", "e"]);
+        assert_eq!(zset.score(b"b"), Some(0.0));
+        assert_eq!(zset.score(b"z"), None);
+        assert_eq!(zset.len(), 5);
+        assert_eq!(zset.rank(b"a"), Some(2));
+
+        assert!(zset.remove(b"d"));
+        assert!(!zset.remove(b"d"));
+        assert_eq!(zset.rank(b"a"), Some(1));
+        assert_eq!(zset.rank(b"d"), None);
+        assert_eq!(zset.len(), 4);
+    }
+}
@@ -0,0 +1,122 @@
+//! Per-command call statistics, reported by `INFO commandstats`, and totals
+//! reported by `INFO stats`.
+use crate::cmd::COMMAND_TABLE;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Call counters of every command supported by the server.
+///
+/// The set of commands is fixed at creation, so recording a call only updates
+/// atomic counters, without taking any lock.
+#[derive(Debug)]
+pub(crate) struct CommandStats {
+    /// Counters keyed by the command name, as registered in [`COMMAND_TABLE`].
+    commands: HashMap<&'static str, CommandStat>,
+    /// Number of calls of commands reading keys without modifying them.
+    reads: AtomicU64,
+    /// Number of calls of commands which may modify the keyspace.
+    writes: AtomicU64,
+}
+
+/// Call counters of a single command.
+#[derive(Debug, Default)]
+struct CommandStat {
+    /// Number of calls.
+    calls: AtomicU64,
+    /// Cumulative time spent applying the command, in microseconds.
+    usec: AtomicU64,
+}
+
+impl CommandStats {
+    /// Create counters for every command in [`COMMAND_TABLE`], all at zero.
+    pub(crate) fn new() -> Self {
+        let commands = COMMAND_TABLE
+            .iter()
+            .map(|spec| (spec.name, CommandStat::default()))
+            .collect();
+        Self {
+            commands,
+            reads: AtomicU64::new(0),
+            writes: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a call of the command `name` which took `elapsed`.
+    pub(crate) fn record(&self, name: &str, elapsed: Duration) {
+        let Some(stat) = self.commands.get(name) else {
+            return;
+        };
+        let usec = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        stat.calls.fetch_add(1, Ordering::Relaxed);
+        stat.usec.fetch_add(usec, Ordering::Relaxed);
+    }
+
+    /// Record a call of a command reading keys without modifying them.
+    pub(crate) fn record_read(&self) {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a call of a command which may modify the keyspace.
+    pub(crate) fn record_write(&self) {
+        self.writes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Total number of calls of commands reading keys.
+    pub(crate) fn total_reads(&self) -> u64 {
+        self.reads.load(Ordering::Relaxed)
+    }
+
+    /// Total number of calls of commands which may modify the keyspace.
+    pub(crate) fn total_writes(&self) -> u64 {
+        self.writes.load(Ordering::Relaxed)
+    }
+
+    /// Render the `stats` section of `INFO`.
+    pub(crate) fn stats_info(&self) -> String {
+        format!(
+            "# Stats\r\ntotal_commands_processed:{}\r\ntotal_reads_processed:{}\r\ntotal_writes_processed:{}\r\n",
+            self.total_calls(),
+            self.total_reads(),
+            self.total_writes()
+        )
+    }
+
+    /// Total number of calls, across every command.
+    pub(crate) fn total_calls(&self) -> u64 {
+        self.commands
+            .values()
+            .map(|stat| stat.calls.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Render the `commandstats` section of `INFO`.
+    ///
+    /// Only commands which were called at least once are listed, sorted by
+    /// name.
+    pub(crate) fn info(&self) -> String {
+        let mut stats: Vec<_> = self
+            .commands
+            .iter()
+            .map(|(name, stat)| {
+                let calls = stat.calls.load(Ordering::Relaxed);
+                let usec = stat.usec.load(Ordering::Relaxed);
+                (name, calls, usec)
+            })
+            .filter(|(_, calls, _)| *calls > 0)
+            .collect();
+        stats.sort_unstable();
+
+        let mut info = String::from("# Commandstats\r\n");
+        for (name, calls, usec) in stats {
+            let usec_per_call = usec as f64 / calls as f64;
+            info.push_str(&format!(
+                "cmdstat_{}:calls={calls},usec={usec},usec_per_call={usec_per_call:.2}\r\n",
+                name.to_lowercase()
+            ));
+        }
+        info
+    }
+}
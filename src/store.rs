@@ -0,0 +1,160 @@
+//! An embeddable key/value store, usable without running the TCP server.
+use crate::db::{DbConfig, DbDropGuard};
+use bytes::Bytes;
+use std::time::Duration;
+
+/// An in-process key/value store.
+///
+/// [`Store`] exposes the same storage used by the server, including key
+/// expiration, without any networking involved.
+///
+/// A background task is spawned to purge expired keys, so a [`Store`] must be
+/// created from within a Tokio runtime. The task is stopped when the
+/// [`Store`] is dropped. [`Store::new_lazy`] creates a store without it.
+///
+/// # Examples
+///
+/// ```
+/// use loja::Store;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let store = Store::new();
+///
+///     store.set("name", "loja");
+///     store.set_with_ttl("session", "abc", Duration::from_secs(60));
+///
+///     assert_eq!(store.get("name").as_deref(), Some(&b"loja"[..]));
+///     assert_eq!(store.get("session").as_deref(), Some(&b"abc"[..]));
+///
+///     assert!(store.del("name"));
+///     assert!(!store.exists("name"));
+///     assert_eq!(store.keys(), vec!["session".to_string()]);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Store {
+    /// Handle to the database. Dropping it stops the purge task.
+    db: DbDropGuard,
+}
+
+impl Store {
+    /// Creates a new empty [`Store`] and starts its key expiration task.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn new() -> Self {
+        Self {
+            db: DbDropGuard::new(DbConfig::default()),
+        }
+    }
+
+    /// Creates a new empty [`Store`] without a key expiration task.
+    ///
+    /// Expired keys are only removed once accessed, which is enough for short
+    /// lived stores. As no task is spawned, no Tokio runtime is needed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use loja::Store;
+    /// use std::time::Duration;
+    ///
+    /// let store = Store::new_lazy();
+    /// store.set_with_ttl("session", "abc", Duration::from_millis(1));
+    /// std::thread::sleep(Duration::from_millis(5));
+    /// assert_eq!(store.get("session"), None);
+    /// ```
+    pub fn new_lazy() -> Self {
+        Self {
+            db: DbDropGuard::new(DbConfig {
+                active_expiration: false,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Get the value associated with `key`.
+    ///
+    /// Returns `None` if there is no value associated with the key, or if
+    /// the value has expired.
+    pub fn get(&self, key: &str) -> Option<Bytes> {
+        self.db.db().get(key).expect("the store only holds strings")
+    }
+
+    /// Set `key` to hold `value`, without an expiration.
+    ///
+    /// Any previous value, along with its TTL, is replaced.
+    pub fn set(&self, key: impl Into<String>, value: impl Into<Bytes>) {
+        self.db
+            .db()
+            .set(key.into(), value.into(), None)
+            .expect("the store has no memory budget");
+    }
+
+    /// Set `key` to hold `value`, expiring once `ttl` elapses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use loja::Store;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let store = Store::new();
+    ///     store.set_with_ttl("token", "secret", Duration::from_millis(10));
+    ///     assert!(store.exists("token"));
+    ///
+    ///     tokio::time::sleep(Duration::from_millis(20)).await;
+    ///     assert_eq!(store.get("token"), None);
+    /// }
+    /// ```
+    pub fn set_with_ttl(&self, key: impl Into<String>, value: impl Into<Bytes>, ttl: Duration) {
+        self.db
+            .db()
+            .set(key.into(), value.into(), Some(ttl))
+            .expect("the store has no memory budget");
+    }
+
+    /// Remove `key`, returning `true` if it held a value.
+    pub fn del(&self, key: &str) -> bool {
+        self.db.db().del(key)
+    }
+
+    /// Returns `true` if `key` holds a value.
+    pub fn exists(&self, key: &str) -> bool {
+        self.db.db().exists(key)
+    }
+
+    /// Returns the names of all keys holding a value, in no particular order.
+    pub fn keys(&self) -> Vec<String> {
+        self.db.db().keys()
+    }
+
+    /// Returns every key holding a string, with its value and remaining TTL,
+    /// in no particular order.
+    ///
+    /// Keys holding other types are left out, so this is not a snapshot of
+    /// the whole keyspace. The store is only locked while the entries are
+    /// cloned, so walking the returned snapshot does not block writers.
+    pub fn iter_string_snapshot(&self) -> Vec<(String, Bytes, Option<Duration>)> {
+        self.db.db().iter_string_snapshot()
+    }
+
+    /// Registers a callback invoked with the key name each time an expired
+    /// key is purged by the background task.
+    ///
+    /// Only one callback is kept, registering a new one replaces the previous.
+    pub fn on_expire(&self, f: impl Fn(&str) + Send + Sync + 'static) {
+        self.db.db().on_expire(f);
+    }
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,454 @@
+use bytes::Bytes;
+use loja::{server, Client, Frame};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::oneshot,
+};
+
+#[tokio::test]
+async fn test_ping_over_tcp() {
+    let (addr, handle) = server::test_harness().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_eq!(client.ping(None).await.unwrap(), Bytes::from("PONG"));
+    assert_eq!(
+        client.ping(Some(Bytes::from("hello"))).await.unwrap(),
+        Bytes::from("hello")
+    );
+
+    // Shutting down waits for the connection above to be closed.
+    drop(client);
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_latency_measures_ping_round_trip() {
+    let (addr, handle) = server::test_harness().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let latency = client.latency().await.unwrap();
+    assert!(latency > Duration::ZERO);
+    assert!(latency < Duration::from_secs(1), "latency is {latency:?}");
+
+    drop(client);
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_send_command_returns_raw_replies() {
+    let (addr, handle) = server::test_harness().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let args = ["SET", "foo", "bar"].map(Bytes::from);
+    assert_eq!(
+        client.send_command(&args).await.unwrap(),
+        Frame::SimpleString("OK".to_string())
+    );
+    let args = ["GET", "foo"].map(Bytes::from);
+    assert_eq!(
+        client.send_command(&args).await.unwrap(),
+        Frame::BulkString(Bytes::from("bar"))
+    );
+
+    let args = ["FROBNICATE", "foo"].map(Bytes::from);
+    let err = client.send_command(&args).await.unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("response error: ERR unknown command 'FROBNICATE'"),
+        "{err}"
+    );
+
+    // The connection is still usable after an error reply.
+    assert_eq!(client.ping(None).await.unwrap(), Bytes::from("PONG"));
+
+    drop(client);
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_info_reports_a_stable_run_id() {
+    let (addr, handle) = server::test_harness().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let run_id = |info: String| {
+        info.lines()
+            .find_map(|line| line.strip_prefix("run_id:"))
+            .map(str::to_string)
+            .unwrap()
+    };
+    let first = run_id(client.info(vec!["server".to_string()]).await.unwrap());
+    assert_eq!(first.len(), 40);
+    assert!(first.bytes().all(|b| b.is_ascii_hexdigit()), "{first}");
+    let second = run_id(client.info(vec![]).await.unwrap());
+    assert_eq!(first, second);
+
+    let id = "0123456789abcdef0123456789abcdef01234567";
+    let args = ["DEBUG", "SET-RUN-ID", id].map(Bytes::from);
+    assert_eq!(
+        client.send_command(&args).await.unwrap(),
+        Frame::SimpleString("OK".to_string())
+    );
+    let info = client.info(vec!["server".to_string()]).await.unwrap();
+    assert_eq!(run_id(info), id);
+
+    drop(client);
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_set_over_maxmemory_replies_oom() {
+    let config = server::ServerConfig {
+        maxmemory: Some(50),
+        maxmemory_policy: server::EvictionPolicy::NoEviction,
+        ..Default::default()
+    };
+    let (addr, handle) = server::test_harness_with_config(config).await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let value = Bytes::from("x".repeat(20));
+    client.set("a", value.clone()).await.unwrap();
+    client.set("b", value.clone()).await.unwrap();
+
+    let err = client.set("c", value.clone()).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "response error: OOM command not allowed when used memory > 'maxmemory'."
+    );
+    // The keys already stored are untouched, and the connection still works.
+    assert_eq!(client.get("a").await.unwrap(), Some(value.clone()));
+    assert_eq!(client.get("b").await.unwrap(), Some(value));
+    assert_eq!(client.get("c").await.unwrap(), None);
+
+    drop(client);
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_random_fields_and_members() {
+    let (addr, handle) = server::test_harness().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let fields = vec![(Bytes::from("a"), Bytes::from("1"))];
+    client.hset("hash", fields.clone()).await.unwrap();
+    client
+        .zadd("zset", vec![(2.5, Bytes::from("m"))])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        client.hrandfield("hash").await.unwrap(),
+        Some(Bytes::from("a"))
+    );
+    assert_eq!(client.hrandfield("missing").await.unwrap(), None);
+    assert_eq!(client.hrandfield_count("hash", 3).await.unwrap().len(), 1);
+    assert_eq!(
+        client.hrandfield_with_values("hash", -2).await.unwrap(),
+        [fields.clone(), fields].concat()
+    );
+
+    assert_eq!(
+        client.zrandmember("zset").await.unwrap(),
+        Some(Bytes::from("m"))
+    );
+    assert_eq!(client.zrandmember_count("zset", -2).await.unwrap().len(), 2);
+    assert_eq!(
+        client.zrandmember_with_scores("zset", 1).await.unwrap(),
+        vec![(Bytes::from("m"), 2.5)]
+    );
+
+    drop(client);
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_expired_hash_field_disappears() {
+    let (addr, handle) = server::test_harness().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let fields = vec![
+        (Bytes::from("a"), Bytes::from("1")),
+        (Bytes::from("b"), Bytes::from("2")),
+    ];
+    client.hset("hash", fields).await.unwrap();
+
+    let expire = vec![Bytes::from("a"), Bytes::from("missing")];
+    assert_eq!(
+        client.hexpire("hash", 1, None, expire).await.unwrap(),
+        vec![1, -2]
+    );
+    let ttl = vec![Bytes::from("a"), Bytes::from("b")];
+    assert_eq!(client.httl("hash", ttl).await.unwrap(), vec![1, -1]);
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(client.hget("hash", Bytes::from("a")).await.unwrap(), None);
+    assert_eq!(
+        client.hget("hash", Bytes::from("b")).await.unwrap(),
+        Some(Bytes::from("2"))
+    );
+    assert_eq!(client.hkeys("hash").await.unwrap(), vec![Bytes::from("b")]);
+    assert_eq!(
+        client
+            .hpersist("hash", vec![Bytes::from("a")])
+            .await
+            .unwrap(),
+        vec![-2]
+    );
+
+    drop(client);
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_run_multi_shares_the_db() {
+    let mut listeners = vec![];
+    let mut addrs = vec![];
+    for _ in 0..2 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        addrs.push(listener.local_addr().unwrap());
+        listeners.push(listener);
+    }
+    let (notify, shutdown) = oneshot::channel::<()>();
+    let server = tokio::spawn(server::run_multi(
+        listeners,
+        server::ServerConfig::default(),
+        shutdown,
+    ));
+
+    let mut first = Client::connect(addrs[0]).await.unwrap();
+    let mut second = Client::connect(addrs[1]).await.unwrap();
+    first.set("foo", Bytes::from("bar")).await.unwrap();
+    assert_eq!(second.get("foo").await.unwrap(), Some(Bytes::from("bar")));
+
+    // Shutting down waits for the connections on both listeners.
+    drop((first, second));
+    notify.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pipeline_replies_in_order() {
+    let (addr, handle) = server::test_harness().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let responses = client
+        .pipeline()
+        .set("foo", Bytes::from("bar"))
+        .lpush("foo", vec![Bytes::from("baz")])
+        .get("foo")
+        .execute()
+        .await
+        .unwrap();
+    assert_eq!(responses.len(), 3);
+    assert_eq!(responses[0], Frame::SimpleString("OK".to_string()));
+    // The error is returned at its position, the batch goes on.
+    assert!(matches!(&responses[1], Frame::SimpleError(msg) if msg.starts_with("WRONGTYPE")));
+    assert_eq!(responses[2], Frame::BulkString(Bytes::from("bar")));
+
+    // The connection is still usable after the batch.
+    assert_eq!(client.get("foo").await.unwrap(), Some(Bytes::from("bar")));
+
+    drop(client);
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_transaction_applies_atomically() {
+    let (addr, handle) = server::test_harness().await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let results = client
+        .transaction()
+        .set("foo", Bytes::from("1"))
+        .set("bar", Bytes::from("2"))
+        .exec()
+        .await
+        .unwrap();
+    let ok = Frame::SimpleString("OK".to_string());
+    assert_eq!(results, vec![ok.clone(), ok]);
+    assert_eq!(client.get("foo").await.unwrap(), Some(Bytes::from("1")));
+    assert_eq!(client.get("bar").await.unwrap(), Some(Bytes::from("2")));
+
+    // Pushing no values fails to be queued, so nothing is applied.
+    let err = client
+        .transaction()
+        .set("foo", Bytes::from("3"))
+        .lpush("list", vec![])
+        .exec()
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Transaction discarded because of previous errors."
+    );
+    assert_eq!(client.get("foo").await.unwrap(), Some(Bytes::from("1")));
+
+    drop(client);
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_shutdown_ends_subscriptions() {
+    let (addr, handle) = server::test_harness().await;
+
+    let mut subscriber = TcpStream::connect(addr).await.unwrap();
+    subscriber
+        .write_all(b"*2\r\n$9\r\nSUBSCRIBE\r\n$3\r\nfoo\r\n")
+        .await
+        .unwrap();
+    let confirmation = b"*3\r\n$9\r\nsubscribe\r\n$3\r\nfoo\r\n:1\r\n";
+    let mut buf = vec![0; confirmation.len()];
+    subscriber.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, confirmation);
+
+    // The subscribed connection is kept open, yet shutting down does not wait
+    // for the client to leave.
+    tokio::time::timeout(Duration::from_secs(1), handle.shutdown())
+        .await
+        .expect("shutdown waited on the subscriber");
+    assert_eq!(subscriber.read(&mut buf).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_subscribe_without_channels_is_an_arity_error() {
+    let (addr, handle) = server::test_harness().await;
+
+    let mut client = TcpStream::connect(addr).await.unwrap();
+    client
+        .write_all(b"*1\r\n$9\r\nsubscribe\r\n")
+        .await
+        .unwrap();
+    let expected = b"-ERR wrong number of arguments for 'subscribe' command\r\n";
+    let mut buf = vec![0; expected.len()];
+    client.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, expected);
+
+    // The connection is not left in the subscribed state.
+    client.write_all(b"*1\r\n$4\r\nPING\r\n").await.unwrap();
+    let mut buf = vec![0; 7];
+    client.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, b"+PONG\r\n");
+
+    drop(client);
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_publish_without_message_is_an_arity_error() {
+    let (addr, handle) = server::test_harness().await;
+
+    let mut client = TcpStream::connect(addr).await.unwrap();
+    client
+        .write_all(b"*2\r\n$7\r\nPUBLISH\r\n$3\r\nfoo\r\n")
+        .await
+        .unwrap();
+    let expected = b"-ERR wrong number of arguments for 'publish' command\r\n";
+    let mut buf = vec![0; expected.len()];
+    client.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, expected);
+
+    client
+        .write_all(b"*3\r\n$7\r\nPUBLISH\r\n$3\r\nfoo\r\n$3\r\nbar\r\n")
+        .await
+        .unwrap();
+    let mut buf = vec![0; 4];
+    client.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, b":0\r\n");
+
+    drop(client);
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_monitor_receives_commands_of_other_connections() {
+    let (addr, handle) = server::test_harness().await;
+
+    let mut monitor = TcpStream::connect(addr).await.unwrap();
+    monitor.write_all(b"*1\r\n$7\r\nMONITOR\r\n").await.unwrap();
+    let mut buf = vec![0; 5];
+    monitor.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, b"+OK\r\n");
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("foo", Bytes::from("bar")).await.unwrap();
+
+    let mut line = vec![];
+    while !line.ends_with(b"\r\n") {
+        let mut byte = [0];
+        monitor.read_exact(&mut byte).await.unwrap();
+        line.push(byte[0]);
+    }
+    let line = String::from_utf8(line).unwrap();
+    assert!(line.starts_with('+'), "{line}");
+    assert!(line.contains("[0 127.0.0.1:"), "{line}");
+    assert!(line.ends_with("] \"set\" \"foo\" \"bar\"\r\n"), "{line}");
+
+    drop(client);
+    drop(monitor);
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_bind_with_reuse_addr_rebinds_right_away() {
+    let options = server::BindOptions {
+        reuse_addr: true,
+        ..Default::default()
+    };
+    let listener = server::bind("127.0.0.1:0".parse().unwrap(), options).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Closing the accepted end first leaves it in `TIME_WAIT`, which keeps
+    // the port busy for a while without `SO_REUSEADDR`.
+    let client = TcpStream::connect(addr).await.unwrap();
+    let (accepted, _) = listener.accept().await.unwrap();
+    drop(accepted);
+    drop(client);
+    drop(listener);
+
+    let listener = server::bind(addr, options).unwrap();
+    assert_eq!(listener.local_addr().unwrap(), addr);
+}
+
+#[tokio::test]
+async fn test_admin_handle_reports_metrics() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let admin = server::AdminHandle::new();
+    let (notify, shutdown) = oneshot::channel::<()>();
+    let server = tokio::spawn(server::run_with_admin(
+        vec![listener],
+        server::ServerConfig::default(),
+        admin.clone(),
+        shutdown,
+    ));
+
+    // Connections are accepted and closed in the background, wait for the
+    // server to catch up.
+    let wait_for_connections = |expected| {
+        let admin = admin.clone();
+        async move {
+            while admin.metrics().active_connections != expected {
+                tokio::task::yield_now().await;
+            }
+        }
+    };
+
+    let mut client = Client::connect(addr).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(1), wait_for_connections(1))
+        .await
+        .unwrap();
+    client.set("foo", Bytes::from("bar")).await.unwrap();
+    client.get("foo").await.unwrap();
+    let metrics = admin.metrics();
+    assert_eq!(metrics.commands_processed, 2);
+    assert_eq!(metrics.keys, 1);
+
+    drop(client);
+    tokio::time::timeout(Duration::from_secs(1), wait_for_connections(0))
+        .await
+        .unwrap();
+
+    notify.send(()).unwrap();
+    server.await.unwrap();
+}